@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use promptforge::{chats, vars, ChatTemplate, Role::Human, Role::System};
+
+fn benchmark_arena_render(c: &mut Criterion) {
+    let template = ChatTemplate::from_messages(chats!(
+        System = "You are a {tone} assistant for {company}. Answer in {language}.",
+        Human = "Customer {customer} asks: {question}",
+    ))
+    .unwrap();
+    let variables = vars!(
+        tone = "friendly",
+        company = "Acme",
+        language = "English",
+        customer = "Ada",
+        question = "Where is my order and when will it arrive?"
+    );
+
+    c.bench_function("format_messages", |b| {
+        b.iter(|| black_box(template.format_messages(black_box(&variables))))
+    });
+
+    c.bench_function("format_messages_arena", |b| {
+        b.iter(|| black_box(template.format_messages_arena(black_box(&variables))))
+    });
+}
+
+criterion_group!(benches, benchmark_arena_render);
+criterion_main!(benches);
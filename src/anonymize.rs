@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use messageforge::BaseMessage;
+
+use crate::{
+    assertions::declared_role,
+    braces::{scan, BraceKind},
+    message_like::ArcMessageEnumExt,
+    ChatTemplate, FewShotChatTemplate, FewShotTemplate, MessageLike, Templatable, Template,
+    TemplateError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anonymizer {
+    pub seed: u64,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new(0x5EED)
+    }
+}
+
+impl Anonymizer {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn text(&self, text: &str) -> String {
+        let mut state = text
+            .bytes()
+            .fold(self.seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+            | 1;
+        let mut next = |range: u8| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % range as u64) as u8
+        };
+
+        text.chars()
+            .map(|c| match c {
+                c if c.is_ascii_uppercase() => (b'A' + next(26)) as char,
+                c if c.is_ascii_digit() => (b'0' + next(10)) as char,
+                c if c.is_alphabetic() => (b'a' + next(26)) as char,
+                c => c,
+            })
+            .collect()
+    }
+
+    pub fn template(&self, template: &Template) -> Template {
+        let mut template = template.clone();
+        template.map_partials(|value| self.text(value));
+        template
+    }
+
+    pub fn example(&self, template: &Template) -> Result<Template, TemplateError> {
+        if !template.partial_vars().is_empty() {
+            return Ok(self.template(template));
+        }
+
+        let source = template.template();
+        let anonymized: String = scan(source)
+            .iter()
+            .map(|token| match token.kind {
+                BraceKind::Text => self.text(token.literal(source)),
+                _ => source[token.span.clone()].to_string(),
+            })
+            .collect();
+
+        template.with_source(&anonymized)
+    }
+
+    pub fn few_shot(
+        &self,
+        few_shot: &FewShotTemplate<Template>,
+    ) -> Result<FewShotTemplate<Template>, TemplateError> {
+        few_shot.try_map(
+            |example| self.example(example),
+            |other| Ok(self.template(other)),
+        )
+    }
+
+    pub fn chat_template(&self, template: &ChatTemplate) -> Result<ChatTemplate, TemplateError> {
+        self.map_chat(template, false)
+    }
+
+    fn map_chat(
+        &self,
+        template: &ChatTemplate,
+        examples: bool,
+    ) -> Result<ChatTemplate, TemplateError> {
+        let messages = template
+            .messages
+            .iter()
+            .map(|message_like| {
+                Ok(match message_like {
+                    MessageLike::BaseMessage(message) if examples => {
+                        let role = declared_role(message).ok_or(TemplateError::InvalidRoleError)?;
+                        let message = role
+                            .to_message(&self.text(message.content()))
+                            .map_err(|_| TemplateError::InvalidRoleError)?;
+                        MessageLike::BaseMessage(Arc::new(message.unwrap_enum()))
+                    }
+                    MessageLike::RolePromptTemplate(role, prompt) if examples => {
+                        MessageLike::role_prompt_template(*role, self.example(prompt)?)
+                    }
+                    MessageLike::RolePromptTemplate(role, prompt) => {
+                        MessageLike::role_prompt_template(*role, self.template(prompt))
+                    }
+                    MessageLike::FewShotPrompt(few_shot) => {
+                        MessageLike::few_shot_prompt(FewShotChatTemplate::new(
+                            self.few_shot(few_shot.few_shot_template())?,
+                            self.map_chat(few_shot.example_prompt(), true)?,
+                        ))
+                    }
+                    other => other.clone(),
+                })
+            })
+            .collect::<Result<_, TemplateError>>()?;
+
+        Ok(ChatTemplate {
+            meta: template.meta.window(0..template.messages.len()),
+            messages,
+        })
+    }
+}
+
+impl ChatTemplate {
+    pub fn anonymized(&self) -> Result<ChatTemplate, TemplateError> {
+        Anonymizer::default().chat_template(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars, Formattable, PromptTestCase,
+        Role::{Ai, Human, System},
+    };
+
+    #[test]
+    fn test_anonymize_text_preserves_shape() {
+        let anonymizer = Anonymizer::new(7);
+        let original = "Order #4521 for Alice Smith, ships 2024-05-01.";
+        let anonymized = anonymizer.text(original);
+
+        assert_ne!(anonymized, original);
+        assert_eq!(anonymized.len(), original.len());
+        assert_eq!(anonymized, anonymizer.text(original));
+        for (a, b) in original.chars().zip(anonymized.chars()) {
+            assert_eq!(a.is_ascii_uppercase(), b.is_ascii_uppercase());
+            assert_eq!(a.is_ascii_lowercase(), b.is_ascii_lowercase());
+            assert_eq!(a.is_ascii_digit(), b.is_ascii_digit());
+            if !a.is_alphanumeric() {
+                assert_eq!(a, b);
+            }
+        }
+        assert_ne!(anonymized, Anonymizer::new(8).text(original));
+    }
+
+    #[test]
+    fn test_anonymize_few_shot_examples() {
+        let few_shot = FewShotTemplate::from_example_sets(
+            &Template::new("Q: {q}\nA: {a}").unwrap(),
+            vec![vars!(q = "Where is Alice's order 4521?", a = "Shipped.")],
+        );
+        let few_shot = FewShotTemplate::with_options(
+            vec![
+                few_shot.examples()[0].clone(),
+                Template::new("Customer Bob Jones asked about {topic}.").unwrap(),
+            ],
+            Some(
+                Template::new("Examples for {name}:")
+                    .unwrap()
+                    .with_partial("name", "Acme"),
+            ),
+            None,
+            "\n\n",
+        );
+
+        let anonymized = Anonymizer::default().few_shot(&few_shot).unwrap();
+        let bound = &anonymized.examples()[0];
+        assert_eq!(bound.template(), "Q: {q}\nA: {a}");
+        let q = &bound.partial_vars()["q"];
+        assert_eq!(q.len(), "Where is Alice's order 4521?".len());
+        assert!(!q.contains("Alice"));
+
+        let literal = &anonymized.examples()[1];
+        assert!(!literal.template().contains("Bob"));
+        assert!(literal.template().ends_with(" {topic}."));
+        assert_eq!(literal.input_variables(), vec!["topic"]);
+
+        let prefix = anonymized.prefix().unwrap();
+        assert_eq!(prefix.template(), "Examples for {name}:");
+        assert_ne!(prefix.partial_vars()["name"], "Acme");
+        assert!(anonymized.format(&vars!(topic = "refunds")).is_ok());
+    }
+
+    #[test]
+    fn test_anonymize_chat_template() {
+        let example_prompt = ChatTemplate::from_messages(chats!(
+            Human = "My card 4111 was charged twice.",
+            Ai = "Sorry {customer}, refunded.",
+        ))
+        .unwrap();
+        let few_shot = FewShotChatTemplate::new(
+            FewShotTemplate::new(vec![Template::new("Ticket from Carol: {issue}").unwrap()]),
+            example_prompt,
+        );
+        let mut chat = ChatTemplate::from_messages(chats!(
+            System = "You are a support agent for {company}.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        chat.messages
+            .insert(1, MessageLike::few_shot_prompt(few_shot));
+        chat = chat.with_tests(vec![PromptTestCase::default()]);
+
+        let anonymized = chat.anonymized().unwrap();
+        assert!(anonymized.tests().is_empty());
+        assert_eq!(anonymized.messages.len(), 3);
+
+        let MessageLike::RolePromptTemplate(_, system) = &anonymized.messages[0] else {
+            panic!("expected a templated system message");
+        };
+        assert_eq!(system.template(), "You are a support agent for {company}.");
+
+        let MessageLike::FewShotPrompt(few_shot) = &anonymized.messages[1] else {
+            panic!("expected a few-shot prompt");
+        };
+        assert!(!few_shot.examples()[0].template().contains("Carol"));
+        let example_messages = &few_shot.example_prompt().messages;
+        let MessageLike::BaseMessage(human) = &example_messages[0] else {
+            panic!("expected a literal example message");
+        };
+        assert!(!human.content().contains("4111"));
+        assert_eq!(
+            human.content().len(),
+            "My card 4111 was charged twice.".len()
+        );
+        let MessageLike::RolePromptTemplate(_, ai) = &example_messages[1] else {
+            panic!("expected a templated example message");
+        };
+        assert!(ai.template().contains(" {customer}, "));
+        assert!(!ai.template().contains("refunded"));
+    }
+}
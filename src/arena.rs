@@ -0,0 +1,179 @@
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+use bumpalo::{collections::String as ArenaString, Bump};
+use messageforge::MessageEnum;
+
+use crate::{
+    braces::{scan, BraceKind},
+    conditionals::{is_truthy, Branches},
+    filters::{apply_filters, split_filters},
+    ChatTemplate, MessageLike, Templatable, Template, TemplateError, TemplateFormat, Variables,
+};
+
+thread_local! {
+    static RENDER_ARENA: RefCell<Bump> = RefCell::new(Bump::new());
+}
+
+pub fn with_render_arena<R>(render: impl FnOnce(&Bump) -> R) -> R {
+    RENDER_ARENA.with(|arena| match arena.try_borrow_mut() {
+        Ok(mut arena) => {
+            arena.reset();
+            render(&arena)
+        }
+        Err(_) => render(&Bump::new()),
+    })
+}
+
+impl Template {
+    pub fn format_in(
+        &self,
+        arena: &Bump,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<String, TemplateError> {
+        if !self.is_validated() || self.template_format() != TemplateFormat::FmtString {
+            return self.format(variables);
+        }
+
+        let lookup = |var: &str| {
+            variables
+                .get(var)
+                .copied()
+                .or_else(|| self.partial_vars().get(var).map(String::as_str))
+        };
+        let source = self.template();
+        let mut result = ArenaString::with_capacity_in(source.len(), arena);
+        let mut branches = Branches::default();
+
+        for token in scan(source) {
+            if token.kind == BraceKind::Single
+                && branches.step(token.inner(source), |var| is_truthy(lookup(var)))
+            {
+                continue;
+            }
+            if !branches.is_rendering() {
+                continue;
+            }
+
+            let (var, filters) = match token.kind {
+                BraceKind::Single => split_filters(token.inner(source)),
+                _ => {
+                    result.push_str(token.literal(source));
+                    continue;
+                }
+            };
+
+            if !self.substitutes(var) {
+                result.push_str(token.literal(source));
+                continue;
+            }
+
+            match lookup(var) {
+                Some(value) if filters.is_empty() => result.push_str(value),
+                Some(value) => result.push_str(&apply_filters(&filters, value)?),
+                None => return Err(TemplateError::MissingVariable(var.to_string())),
+            }
+        }
+
+        Ok(result.as_str().to_string())
+    }
+
+    pub fn format_arena(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<String, TemplateError> {
+        with_render_arena(|arena| self.format_in(arena, &variables.as_map()))
+    }
+}
+
+impl ChatTemplate {
+    pub fn format_messages_arena(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.with_render_variables(variables, |variables| {
+            with_render_arena(|arena| {
+                let mut results = Vec::with_capacity(self.messages.len());
+
+                for message_like in &self.messages {
+                    match message_like {
+                        MessageLike::RolePromptTemplate(role, template) if !self.meta.strict => {
+                            let message =
+                                role.to_message(&template.format_in(arena, variables)?)?;
+                            if let Some(limits) = &self.meta.limits {
+                                limits.check_message(&message)?;
+                            }
+                            results.push(message);
+                        }
+                        _ => results.extend(
+                            self.render_groups(
+                                std::slice::from_ref(message_like),
+                                variables,
+                                None,
+                            )?
+                            .into_iter()
+                            .flatten(),
+                        ),
+                    }
+                }
+
+                self.finish_render(results, None)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, Placeholder, System},
+    };
+
+    #[test]
+    fn test_format_arena_matches_format() {
+        let mut template = Template::new("Dear {name|trim}, your {item} ships {when}.").unwrap();
+        template.partial("when", "today");
+
+        for variables in [
+            vars!(name = " ada ", item = "laptop"),
+            vars!(name = "grace", item = "desk", when = "tomorrow"),
+        ] {
+            assert_eq!(
+                template.format_arena(&variables).unwrap(),
+                template.format(&variables).unwrap()
+            );
+        }
+        assert!(matches!(
+            template.format_arena(&vars!(name = "ada")),
+            Err(TemplateError::MissingVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_messages_arena_matches_format_messages() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You are a {tone} assistant.",
+            Placeholder = "{history|optional}",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let variables = vars!(tone = "calm", question = "Why is the sky blue?");
+
+        for _ in 0..3 {
+            assert_eq!(
+                template.format_messages_arena(&variables).unwrap(),
+                template.format_messages(&variables).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_arena_use_falls_back_to_fresh_arena() {
+        let nested = with_render_arena(|outer| {
+            let inner = with_render_arena(|inner| *inner.alloc(2) + 1);
+            *outer.alloc(inner)
+        });
+        assert_eq!(nested, 3);
+    }
+}
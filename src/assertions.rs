@@ -0,0 +1,350 @@
+use std::{fmt, sync::Arc};
+
+use messageforge::{BaseMessage, MessageEnum};
+use regex::Regex;
+
+use crate::{
+    braces::unresolved_placeholders, role::ROLE_KWARG, token_counter::estimate_tokens,
+    ChatTemplate, Role,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssertionFailure {
+    MissingText(String),
+    UnexpectedText(String),
+    PatternMismatch(String),
+    InvalidPattern(String, String),
+    PlaceholderLeft {
+        message_index: usize,
+        placeholder: String,
+    },
+    TooManyTokens {
+        limit: usize,
+        actual: usize,
+    },
+    RoleOrder {
+        expected: Vec<Role>,
+        actual: Vec<Option<Role>>,
+    },
+}
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssertionFailure::MissingText(text) => {
+                write!(f, "expected output to contain {:?}", text)
+            }
+            AssertionFailure::UnexpectedText(text) => {
+                write!(f, "expected output not to contain {:?}", text)
+            }
+            AssertionFailure::PatternMismatch(pattern) => {
+                write!(f, "expected output to match /{}/", pattern)
+            }
+            AssertionFailure::InvalidPattern(pattern, err) => {
+                write!(f, "invalid pattern /{}/: {}", pattern, err)
+            }
+            AssertionFailure::PlaceholderLeft {
+                message_index,
+                placeholder,
+            } => write!(
+                f,
+                "message {} still contains placeholder {}",
+                message_index, placeholder
+            ),
+            AssertionFailure::TooManyTokens { limit, actual } => {
+                write!(f, "expected at most {} tokens, estimated {}", limit, actual)
+            }
+            AssertionFailure::RoleOrder { expected, actual } => {
+                let actual: Vec<&str> = actual
+                    .iter()
+                    .map(|role| role.as_ref().map_or("unknown", Role::as_str))
+                    .collect();
+                let expected: Vec<&str> = expected.iter().map(Role::as_str).collect();
+                write!(f, "expected role order {:?}, got {:?}", expected, actual)
+            }
+        }
+    }
+}
+
+pub fn message_role(message: &MessageEnum) -> Option<Role> {
+    Role::try_from(message.message_type().as_str()).ok()
+}
+
+pub(crate) fn declared_role(message: &MessageEnum) -> Option<Role> {
+    match (
+        message_role(message),
+        message.additional_kwargs().get(ROLE_KWARG),
+    ) {
+        (Some(Role::System), Some(role)) if role == Role::Developer.as_str() => {
+            Some(Role::Developer)
+        }
+        (role, _) => role,
+    }
+}
+
+pub fn assert_contains(messages: &[Arc<MessageEnum>], text: &str) -> Result<(), AssertionFailure> {
+    if ChatTemplate::transcript(messages).contains(text) {
+        Ok(())
+    } else {
+        Err(AssertionFailure::MissingText(text.to_string()))
+    }
+}
+
+pub fn assert_not_contains(
+    messages: &[Arc<MessageEnum>],
+    text: &str,
+) -> Result<(), AssertionFailure> {
+    if ChatTemplate::transcript(messages).contains(text) {
+        Err(AssertionFailure::UnexpectedText(text.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_matches(
+    messages: &[Arc<MessageEnum>],
+    pattern: &str,
+) -> Result<(), AssertionFailure> {
+    let re = Regex::new(pattern)
+        .map_err(|e| AssertionFailure::InvalidPattern(pattern.to_string(), e.to_string()))?;
+
+    if re.is_match(&ChatTemplate::transcript(messages)) {
+        Ok(())
+    } else {
+        Err(AssertionFailure::PatternMismatch(pattern.to_string()))
+    }
+}
+
+pub fn assert_no_placeholder_left(messages: &[Arc<MessageEnum>]) -> Result<(), AssertionFailure> {
+    for (message_index, message) in messages.iter().enumerate() {
+        let content = message.content();
+        if let Some(span) = unresolved_placeholders(content).into_iter().next() {
+            return Err(AssertionFailure::PlaceholderLeft {
+                message_index,
+                placeholder: content[span].to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn assert_max_tokens(
+    messages: &[Arc<MessageEnum>],
+    limit: usize,
+) -> Result<(), AssertionFailure> {
+    let actual = messages
+        .iter()
+        .map(|message| estimate_tokens(message.content()))
+        .sum();
+
+    if actual <= limit {
+        Ok(())
+    } else {
+        Err(AssertionFailure::TooManyTokens { limit, actual })
+    }
+}
+
+pub fn assert_role_order(
+    messages: &[Arc<MessageEnum>],
+    expected: &[Role],
+) -> Result<(), AssertionFailure> {
+    let actual: Vec<Option<Role>> = messages.iter().map(|m| message_role(m)).collect();
+
+    if actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(expected)
+            .all(|(actual, expected)| actual.as_ref() == Some(expected))
+    {
+        Ok(())
+    } else {
+        Err(AssertionFailure::RoleOrder {
+            expected: expected.to_vec(),
+            actual,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PromptAssertions<'a> {
+    messages: &'a [Arc<MessageEnum>],
+    failures: Vec<AssertionFailure>,
+}
+
+impl<'a> PromptAssertions<'a> {
+    pub fn new(messages: &'a [Arc<MessageEnum>]) -> Self {
+        Self {
+            messages,
+            failures: Vec::new(),
+        }
+    }
+
+    fn check(mut self, result: Result<(), AssertionFailure>) -> Self {
+        if let Err(failure) = result {
+            self.failures.push(failure);
+        }
+        self
+    }
+
+    pub fn contains(self, text: &str) -> Self {
+        let result = assert_contains(self.messages, text);
+        self.check(result)
+    }
+
+    pub fn not_contains(self, text: &str) -> Self {
+        let result = assert_not_contains(self.messages, text);
+        self.check(result)
+    }
+
+    pub fn matches(self, pattern: &str) -> Self {
+        let result = assert_matches(self.messages, pattern);
+        self.check(result)
+    }
+
+    pub fn no_placeholder_left(self) -> Self {
+        let result = assert_no_placeholder_left(self.messages);
+        self.check(result)
+    }
+
+    pub fn max_tokens(self, limit: usize) -> Self {
+        let result = assert_max_tokens(self.messages, limit);
+        self.check(result)
+    }
+
+    pub fn role_order(self, expected: &[Role]) -> Self {
+        let result = assert_role_order(self.messages, expected);
+        self.check(result)
+    }
+
+    pub fn failures(&self) -> &[AssertionFailure] {
+        &self.failures
+    }
+
+    pub fn finish(self) -> Result<(), Vec<AssertionFailure>> {
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(self.failures)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Ai, Human, System},
+    };
+
+    fn rendered() -> Vec<Arc<MessageEnum>> {
+        ChatTemplate::from_messages(chats!(
+            System = "You are {name}.",
+            Human = "Hello!",
+            Ai = "Hi, how can I help?",
+        ))
+        .unwrap()
+        .format_messages(&vars!(name = "Ava"))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_assert_contains() {
+        let messages = rendered();
+        assert!(assert_contains(&messages, "You are Ava.").is_ok());
+        assert!(assert_contains(&messages, "human: Hello!").is_ok());
+        assert_eq!(
+            assert_contains(&messages, "Goodbye"),
+            Err(AssertionFailure::MissingText("Goodbye".to_string()))
+        );
+        assert!(assert_not_contains(&messages, "Goodbye").is_ok());
+    }
+
+    #[test]
+    fn test_assert_matches() {
+        let messages = rendered();
+        assert!(assert_matches(&messages, r"(?m)^ai: Hi").is_ok());
+        assert!(matches!(
+            assert_matches(&messages, "^Hi"),
+            Err(AssertionFailure::PatternMismatch(_))
+        ));
+        assert!(matches!(
+            assert_matches(&messages, "("),
+            Err(AssertionFailure::InvalidPattern(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_assert_no_placeholder_left() {
+        assert!(assert_no_placeholder_left(&rendered()).is_ok());
+
+        let messages = vec![
+            Human.to_message("fine").unwrap(),
+            Human.to_message("Hello {{ name }}").unwrap(),
+        ];
+        assert_eq!(
+            assert_no_placeholder_left(&messages),
+            Err(AssertionFailure::PlaceholderLeft {
+                message_index: 1,
+                placeholder: "{{ name }}".to_string(),
+            })
+        );
+
+        let json = vec![Human
+            .to_message("Data: {\"a\": 1}, then {topic|trim}.")
+            .unwrap()];
+        assert_eq!(
+            assert_no_placeholder_left(&json),
+            Err(AssertionFailure::PlaceholderLeft {
+                message_index: 0,
+                placeholder: "{topic|trim}".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_assert_max_tokens() {
+        let messages = rendered();
+        assert!(assert_max_tokens(&messages, 100).is_ok());
+        assert_eq!(
+            assert_max_tokens(&messages, 2),
+            Err(AssertionFailure::TooManyTokens {
+                limit: 2,
+                actual: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_assert_role_order() {
+        let messages = rendered();
+        assert!(assert_role_order(&messages, &[System, Human, Ai]).is_ok());
+
+        let failure = assert_role_order(&messages, &[System, Ai]).unwrap_err();
+        assert_eq!(
+            failure.to_string(),
+            "expected role order [\"system\", \"ai\"], got [\"system\", \"human\", \"ai\"]"
+        );
+    }
+
+    #[test]
+    fn test_prompt_assertions_collects_failures() {
+        let messages = rendered();
+
+        assert!(PromptAssertions::new(&messages)
+            .contains("Ava")
+            .no_placeholder_left()
+            .max_tokens(50)
+            .role_order(&[System, Human, Ai])
+            .finish()
+            .is_ok());
+
+        let failures = PromptAssertions::new(&messages)
+            .contains("Bob")
+            .not_contains("Ava")
+            .max_tokens(1)
+            .finish()
+            .unwrap_err();
+        assert_eq!(failures.len(), 3);
+    }
+}
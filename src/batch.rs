@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "async")]
+use futures::{Stream, StreamExt};
+
+use crate::{ChatTemplate, RenderOutput, TemplateError};
+
+pub type Row = HashMap<String, String>;
+
+fn borrow_row(row: &Row) -> HashMap<&str, &str> {
+    row.iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect()
+}
+
+impl ChatTemplate {
+    pub fn render_batch<'a, I>(
+        &'a self,
+        rows: I,
+    ) -> impl Iterator<Item = Result<RenderOutput, TemplateError>> + 'a
+    where
+        I: IntoIterator<Item = Row>,
+        I::IntoIter: 'a,
+    {
+        rows.into_iter()
+            .map(move |row| self.render(&borrow_row(&row)))
+    }
+
+    #[cfg(feature = "async")]
+    pub fn render_stream<'a, S>(
+        &'a self,
+        rows: S,
+        max_in_flight: usize,
+    ) -> impl Stream<Item = Result<RenderOutput, TemplateError>> + 'a
+    where
+        S: Stream<Item = Row> + 'a,
+    {
+        rows.map(move |row| async move { self.render(&borrow_row(&row)) })
+            .buffered(max_in_flight.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{chats, Role::Human};
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(Human = "Summarize ticket {id}.")).unwrap()
+    }
+
+    fn row(id: usize) -> Row {
+        Row::from([("id".to_string(), id.to_string())])
+    }
+
+    #[test]
+    fn test_render_batch_is_lazy_and_ordered() {
+        let template = template();
+        let mut outputs = template.render_batch((0..).map(row));
+
+        let first = outputs.next().unwrap().unwrap();
+        assert_eq!(first.messages[0].content(), "Summarize ticket 0.");
+        let third = outputs.nth(1).unwrap().unwrap();
+        assert_eq!(third.messages[0].content(), "Summarize ticket 2.");
+
+        let errors = template.render_batch(vec![Row::new()]);
+        assert_eq!(errors.filter(Result::is_err).count(), 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_render_stream_bounds_in_flight_rows() {
+        use futures::stream;
+        use std::{cell::Cell, rc::Rc};
+
+        let template = template();
+        let pulled = Rc::new(Cell::new(0));
+        let counter = pulled.clone();
+        let rows = stream::iter((0..1_000_000).map(move |id| {
+            counter.set(counter.get() + 1);
+            row(id)
+        }));
+
+        let mut outputs = Box::pin(template.render_stream(rows, 4));
+        for id in 0..10 {
+            let output = outputs.next().await.unwrap().unwrap();
+            assert_eq!(
+                output.messages[0].content(),
+                format!("Summarize ticket {}.", id)
+            );
+        }
+        assert!(pulled.get() <= 14, "pulled {} rows", pulled.get());
+    }
+}
@@ -1,162 +1,379 @@
-use crate::is_even::IsEven;
-use regex::Regex;
+use std::ops::Range;
+
+use crate::{
+    filters::split_filters,
+    mustache_expr::{parse_mustache_expr, MustacheTag},
+    placeholder::is_valid_identifier,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceKind {
+    Text,
+    Escaped,
+    Single,
+    Double,
+    Open,
+    Close,
+    Stray,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BraceToken {
+    pub kind: BraceKind,
+    pub span: Range<usize>,
+}
+
+impl BraceToken {
+    pub fn is_slot(&self) -> bool {
+        matches!(self.kind, BraceKind::Single | BraceKind::Double)
+    }
+
+    pub fn inner<'a>(&self, source: &'a str) -> &'a str {
+        match self.kind {
+            BraceKind::Single => &source[self.span.start + 1..self.span.end - 1],
+            BraceKind::Double => &source[self.span.start + 2..self.span.end - 2],
+            _ => &source[self.span.clone()],
+        }
+    }
+
+    pub fn literal<'a>(&self, source: &'a str) -> &'a str {
+        match self.kind {
+            BraceKind::Escaped => &source[self.span.start + 1..self.span.end],
+            _ => &source[self.span.clone()],
+        }
+    }
+}
 
-pub fn has_multiple_words_between_braces(s: &str) -> bool {
-    let re = Regex::new(r"\{\{?\s*([^}]+)\s*\}?\}").unwrap();
+struct OpenBrace {
+    start: usize,
+    double: bool,
+    emitted: usize,
+}
+
+pub fn scan(template: &str) -> Vec<BraceToken> {
+    let bytes = template.as_bytes();
+    let mut tokens = Vec::new();
+    let mut stack: Vec<OpenBrace> = Vec::new();
+    let mut i = 0;
+
+    let push = |tokens: &mut Vec<BraceToken>, kind, span| tokens.push(BraceToken { kind, span });
+
+    while i < bytes.len() {
+        if (i == 0 || bytes[i - 1] == b'\n') && line_opens_fence(&template[i..]) {
+            let end = fence_end(template, i);
+            scan_fence(template, i..end, &mut tokens);
+            i = end;
+            continue;
+        }
+
+        match bytes[i] {
+            b'\\' if matches!(bytes.get(i + 1), Some(b'{' | b'}')) => {
+                push(&mut tokens, BraceKind::Escaped, i..i + 2);
+                i += 2;
+            }
+            b'{' | b'}' if is_quoted(bytes, i) => i += 1,
+            b'{' => {
+                let double = bytes.get(i + 1) == Some(&b'{');
+                stack.push(OpenBrace {
+                    start: i,
+                    double,
+                    emitted: tokens.len(),
+                });
+                i += if double { 2 } else { 1 };
+            }
+            b'}' => match stack.pop() {
+                None => {
+                    push(&mut tokens, BraceKind::Stray, i..i + 1);
+                    i += 1;
+                }
+                Some(open) if open.double && bytes.get(i + 1) == Some(&b'}') => {
+                    close(&mut tokens, open, i + 2, BraceKind::Double);
+                    i += 2;
+                }
+                Some(open) => {
+                    let open = if open.double {
+                        push(&mut tokens, BraceKind::Stray, open.start..open.start + 1);
+                        OpenBrace {
+                            start: open.start + 1,
+                            double: false,
+                            emitted: tokens.len(),
+                        }
+                    } else {
+                        open
+                    };
+                    close(&mut tokens, open, i + 1, BraceKind::Single);
+                    i += 1;
+                }
+            },
+            _ => i += 1,
+        }
+    }
+
+    for open in stack {
+        let width = if open.double { 2 } else { 1 };
+        for start in open.start..open.start + width {
+            push(&mut tokens, BraceKind::Stray, start..start + 1);
+        }
+    }
 
-    if let Some(captures) = re.captures(s) {
-        let content = &captures[1].trim();
-        let words: Vec<&str> = content.split_whitespace().collect();
-        return words.len() > 1;
+    tokens.sort_by_key(|token| token.span.start);
+    fill_text(template, tokens)
+}
+
+fn close(tokens: &mut Vec<BraceToken>, open: OpenBrace, end: usize, kind: BraceKind) {
+    if tokens.len() == open.emitted {
+        tokens.push(BraceToken {
+            kind,
+            span: open.start..end,
+        });
+        return;
     }
 
-    false
+    let width = if kind == BraceKind::Double { 2 } else { 1 };
+    tokens.push(BraceToken {
+        kind: BraceKind::Open,
+        span: open.start..open.start + width,
+    });
+    tokens.push(BraceToken {
+        kind: BraceKind::Close,
+        span: end - width..end,
+    });
 }
 
-pub fn count_left_braces(s: &str) -> usize {
-    s.matches("{").count()
+fn is_quoted(bytes: &[u8], i: usize) -> bool {
+    i > 0 && matches!(bytes[i - 1], b'\'' | b'"' | b'`') && bytes.get(i + 1) == Some(&bytes[i - 1])
 }
 
-pub fn count_right_braces(s: &str) -> usize {
-    s.matches("}").count()
+fn line_opens_fence(rest: &str) -> bool {
+    rest.trim_start_matches([' ', '\t']).starts_with("```")
 }
 
-pub fn has_even_left_braces(s: &str) -> bool {
-    count_left_braces(s).is_even()
+fn fence_end(template: &str, start: usize) -> usize {
+    let mut offset = match template[start..].find('\n') {
+        Some(newline) => start + newline + 1,
+        None => return template.len(),
+    };
+
+    while offset < template.len() {
+        let line_end = template[offset..]
+            .find('\n')
+            .map_or(template.len(), |newline| offset + newline + 1);
+        if line_opens_fence(&template[offset..line_end]) {
+            return line_end;
+        }
+        offset = line_end;
+    }
+
+    template.len()
 }
 
-pub fn has_even_right_braces(s: &str) -> bool {
-    count_right_braces(s).is_even()
+fn scan_fence(template: &str, fence: Range<usize>, tokens: &mut Vec<BraceToken>) {
+    let bytes = template.as_bytes();
+    let mut i = fence.start;
+
+    while i < fence.end {
+        match bytes[i] {
+            b'\\' if matches!(bytes.get(i + 1), Some(b'{' | b'}')) => {
+                tokens.push(BraceToken {
+                    kind: BraceKind::Escaped,
+                    span: i..i + 2,
+                });
+                i += 2;
+            }
+            b'{' => match fenced_slot(&template[..fence.end], i) {
+                Some(token) => {
+                    i = token.span.end;
+                    tokens.push(token);
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
 }
 
-pub fn has_left_brace(s: &str) -> bool {
-    count_left_braces(s) > 0
+fn fenced_slot(template: &str, start: usize) -> Option<BraceToken> {
+    let double = template[start..].starts_with("{{");
+    let (kind, open, close) = if double {
+        (BraceKind::Double, "{{", "}}")
+    } else {
+        (BraceKind::Single, "{", "}")
+    };
+
+    let inner_start = start + open.len();
+    let inner_end = inner_start + template[inner_start..].find(close)?;
+    let inner = &template[inner_start..inner_end];
+    let valid = !inner.contains(['{', '}', '\n'])
+        && if double {
+            is_valid_identifier(inner.trim())
+        } else {
+            is_valid_identifier(split_filters(inner).0)
+        };
+
+    valid.then(|| BraceToken {
+        kind,
+        span: start..inner_end + close.len(),
+    })
 }
 
-pub fn has_right_brace(s: &str) -> bool {
-    count_right_braces(s) > 0
+fn fill_text(template: &str, tokens: Vec<BraceToken>) -> Vec<BraceToken> {
+    let mut filled = Vec::with_capacity(tokens.len() * 2 + 1);
+    let mut last = 0;
+
+    for token in tokens {
+        if token.span.start > last {
+            filled.push(BraceToken {
+                kind: BraceKind::Text,
+                span: last..token.span.start,
+            });
+        }
+        last = token.span.end;
+        filled.push(token);
+    }
+
+    if last < template.len() {
+        filled.push(BraceToken {
+            kind: BraceKind::Text,
+            span: last..template.len(),
+        });
+    }
+
+    filled
 }
 
-pub fn has_consecutive_left_braces(s: &str) -> bool {
-    s.contains("{{")
+fn has_kind(s: &str, kind: BraceKind) -> bool {
+    scan(s).iter().any(|token| token.kind == kind)
 }
 
-pub fn has_consecutive_right_braces(s: &str) -> bool {
-    s.contains("}}")
+pub fn has_stray_braces(s: &str) -> bool {
+    has_kind(s, BraceKind::Stray)
 }
 
 pub fn has_only_single_braces(s: &str) -> bool {
-    has_left_brace(s)
-        && has_right_brace(s)
-        && !has_consecutive_left_braces(s)
-        && !has_consecutive_right_braces(s)
+    has_kind(s, BraceKind::Single) && !has_kind(s, BraceKind::Double) && !has_stray_braces(s)
 }
 
 pub fn has_only_double_braces(s: &str) -> bool {
-    has_consecutive_left_braces(s)
-        && has_consecutive_right_braces(s)
-        && has_even_left_braces(s)
-        && has_even_right_braces(s)
+    has_kind(s, BraceKind::Double) && !has_kind(s, BraceKind::Single) && !has_stray_braces(s)
 }
 
 pub fn has_no_braces(s: &str) -> bool {
-    !has_left_brace(s) && !has_right_brace(s)
+    !scan(s)
+        .iter()
+        .any(|token| !matches!(token.kind, BraceKind::Text | BraceKind::Escaped))
+}
+
+pub fn unresolved_placeholders(s: &str) -> Vec<Range<usize>> {
+    scan(s)
+        .into_iter()
+        .filter(|token| match token.kind {
+            BraceKind::Single => is_valid_identifier(split_filters(token.inner(s)).0),
+            BraceKind::Double => parse_mustache_expr(token.inner(s))
+                .is_some_and(|expr| expr.tag == MustacheTag::Variable),
+            _ => false,
+        })
+        .map(|token| token.span)
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_has_multiple_words_between_braces() {
-        assert!(has_multiple_words_between_braces("{one two}"));
-        assert!(has_multiple_words_between_braces("{ one two three }"));
-        assert!(has_multiple_words_between_braces(
-            "{one two} and {three four}"
-        ));
-
-        assert!(!has_multiple_words_between_braces("{ one }"));
-
-        assert!(has_multiple_words_between_braces("{{one two}}"));
-        assert!(has_multiple_words_between_braces("{{ one two three }}"));
-        assert!(has_multiple_words_between_braces(
-            "{{one two}} and {{three four}}"
-        ));
-
-        assert!(!has_multiple_words_between_braces("{{ one }}"));
+    fn kinds(s: &str) -> Vec<BraceKind> {
+        scan(s).into_iter().map(|token| token.kind).collect()
     }
 
     #[test]
-    fn test_count_left_braces() {
-        assert_eq!(count_left_braces("hello {world"), 1);
-        assert_eq!(count_left_braces("hello world}"), 0);
-        assert_eq!(count_left_braces("hello"), 0);
-        assert_eq!(count_left_braces("hello {big} {{world}}"), 3);
-        assert_eq!(count_left_braces("hello {{world}}"), 2);
+    fn test_scan_covers_source() {
+        let template = "Hi {name}, see {{doc}} and {\"a\": {b}} \\{x\\} }";
+        let tokens = scan(template);
+        let rebuilt: String = tokens.iter().map(|t| &template[t.span.clone()]).collect();
+        assert_eq!(rebuilt, template);
     }
 
     #[test]
-    fn test_count_right_braces() {
-        assert_eq!(count_right_braces("hello {world"), 0);
-        assert_eq!(count_right_braces("hello world}"), 1);
-        assert_eq!(count_right_braces("hello"), 0);
-        assert_eq!(count_right_braces("hello {big} {{world}}"), 3);
-        assert_eq!(count_right_braces("hello {{world}}"), 2);
+    fn test_scan_slots() {
+        use BraceKind::*;
+
+        assert_eq!(kinds("Hi {name}!"), vec![Text, Single, Text]);
+        assert_eq!(kinds("{{ name }}"), vec![Double]);
+        assert_eq!(kinds("{a}{{b}}"), vec![Single, Double]);
+
+        let tokens = scan("Hi {{ name }}!");
+        assert_eq!(tokens[1].span, 3..13);
+        assert_eq!(tokens[1].inner("Hi {{ name }}!"), " name ");
     }
 
     #[test]
-    fn test_has_even_left_braces() {
-        assert!(has_even_left_braces("hello {world} {world}"));
-        assert!(has_even_left_braces("hello world}"));
-        assert!(!has_even_left_braces("hello {world"));
-        assert!(!has_even_left_braces("hello {world}"));
-        assert!(!has_even_left_braces("hello {world} {world} {world}"));
-        assert!(!has_even_left_braces("hello {world} {{world}}"));
+    fn test_scan_nested_braces() {
+        use BraceKind::*;
+
+        let template = r#"Return {"name": {name}}"#;
+        assert_eq!(kinds(template), vec![Text, Open, Text, Single, Close]);
+        assert_eq!(scan(template)[3].inner(template), "name");
+
+        assert_eq!(kinds("{x {{y}} }"), vec![Open, Text, Double, Text, Close]);
     }
 
     #[test]
-    fn test_has_even_right_braces() {
-        assert!(has_even_right_braces("hello {world} {world}"));
-        assert!(has_even_right_braces("hello {world"));
-        assert!(!has_even_right_braces("hello {world}"));
-        assert!(!has_even_right_braces("hello {world} {world} {world}"));
-        assert!(!has_even_right_braces("hello {world} {{world}}"));
+    fn test_scan_stray_braces() {
+        use BraceKind::*;
+
+        assert_eq!(kinds("a { b"), vec![Text, Stray, Text]);
+        assert_eq!(kinds("a } b"), vec![Text, Stray, Text]);
+        assert_eq!(kinds("{{var}"), vec![Stray, Single]);
+        assert_eq!(kinds("{var}}"), vec![Single, Stray]);
+        assert_eq!(kinds("{{var"), vec![Stray, Stray, Text]);
     }
 
     #[test]
-    fn test_has_left_brace() {
-        assert!(has_left_brace("hello {world}"));
-        assert!(has_left_brace("hello {world"));
-
-        assert!(!has_left_brace("hello"));
-        assert!(!has_left_brace("hello world}"));
+    fn test_scan_escapes() {
+        use BraceKind::*;
+
+        let template = r"Use \{ and \} for {name}";
+        assert_eq!(
+            kinds(template),
+            vec![Text, Escaped, Text, Escaped, Text, Single]
+        );
+        assert_eq!(scan(template)[1].literal(template), "{");
     }
 
     #[test]
-    fn test_has_right_brace() {
-        assert!(has_right_brace("hello world}"));
-        assert!(has_right_brace("hello {world}"));
+    fn test_scan_ignores_quoted_braces() {
+        use BraceKind::*;
 
-        assert!(!has_right_brace("hello"));
-        assert!(!has_right_brace("hello {world"));
+        assert_eq!(kinds("Open with '{' and close with \"}\"."), vec![Text]);
+        assert_eq!(kinds("Say '{name}'"), vec![Text, Single, Text]);
     }
 
     #[test]
-    fn test_has_consecutive_left_braces() {
-        assert!(has_consecutive_left_braces("hello {{world}"));
-        assert!(has_consecutive_left_braces("hello {{world"));
+    fn test_scan_ignores_code_fences() {
+        use BraceKind::*;
 
-        assert!(!has_consecutive_left_braces("hello"));
-        assert!(!has_consecutive_left_braces("hello {world}"));
+        let template = "Fix {lang} code:\n```rust\nfn main() { }\n```\nThanks {name}";
+        assert_eq!(kinds(template), vec![Text, Single, Text, Single]);
+
+        assert_eq!(kinds("```\nfn f() {"), vec![Text]);
     }
 
     #[test]
-    fn test_has_consecutive_right_braces() {
-        assert!(has_consecutive_right_braces("hello world}}"));
-        assert!(has_consecutive_right_braces("hello {world}}"));
-
-        assert!(!has_consecutive_right_braces("hello"));
-        assert!(!has_consecutive_right_braces("hello {world}"));
+    fn test_scan_keeps_identifier_slots_in_code_fences() {
+        use BraceKind::*;
+
+        let template = "```\n{snippet}\n```\nHi {name}";
+        assert_eq!(kinds(template), vec![Text, Single, Text, Single]);
+        assert_eq!(scan(template)[1].inner(template), "snippet");
+        assert_eq!(crate::extract_variables(template), vec!["snippet", "name"]);
+
+        let template = "```js\nif (ok) { render({{ body }}, {opts|trim}) }\n```";
+        let slots: Vec<&str> = scan(template)
+            .iter()
+            .filter(|token| token.is_slot())
+            .map(|token| token.inner(template))
+            .collect();
+        assert_eq!(slots, vec![" body ", "opts|trim"]);
+        assert_eq!(kinds("```\nfn f() { {x y} }\n```"), vec![Text]);
     }
 
     #[test]
@@ -193,4 +410,15 @@ mod tests {
         assert!(!has_no_braces("hello {{world}}"));
         assert!(!has_no_braces("hello {{world}} {{world}}"));
     }
+
+    #[test]
+    fn test_unresolved_placeholders() {
+        let s = "Hi {name}, see {{ doc.title }}; {\"a\": 1} and {not a var} \\{escaped\\}";
+        let spans: Vec<&str> = unresolved_placeholders(s)
+            .into_iter()
+            .map(|span| &s[span])
+            .collect();
+        assert_eq!(spans, vec!["{name}", "{{ doc.title }}"]);
+        assert!(unresolved_placeholders("All {{#if x}}set{{/if}}.").is_empty());
+    }
 }
@@ -0,0 +1,249 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, MessageLike, TemplateError};
+
+#[derive(Debug, Clone)]
+pub struct Branch {
+    name: String,
+    prefix: Arc<ChatTemplate>,
+    suffix: Vec<MessageLike>,
+}
+
+impl Branch {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn prefix(&self) -> &ChatTemplate {
+        &self.prefix
+    }
+
+    pub fn suffix(&self) -> &[MessageLike] {
+        &self.suffix
+    }
+
+    pub fn push(&mut self, message: MessageLike) -> &mut Self {
+        self.suffix.push(message);
+        self
+    }
+
+    pub fn extend<I: IntoIterator<Item = MessageLike>>(&mut self, messages: I) -> &mut Self {
+        self.suffix.extend(messages);
+        self
+    }
+
+    pub fn shares_prefix_with(&self, other: &Branch) -> bool {
+        Arc::ptr_eq(&self.prefix, &other.prefix)
+    }
+
+    pub fn to_template(&self) -> ChatTemplate {
+        let mut template = (*self.prefix).clone();
+        template.messages.extend(self.suffix.iter().cloned());
+        template
+    }
+
+    pub fn format_messages(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.to_template().format_messages(variables)
+    }
+
+    pub fn fork(&self) -> Fork {
+        if self.suffix.is_empty() {
+            Fork::from_arc(Arc::clone(&self.prefix))
+        } else {
+            Fork::from_arc(Arc::new(self.to_template()))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fork {
+    base: Arc<ChatTemplate>,
+    branches: BTreeMap<String, Branch>,
+}
+
+impl Fork {
+    fn from_arc(base: Arc<ChatTemplate>) -> Self {
+        Self {
+            base,
+            branches: BTreeMap::new(),
+        }
+    }
+
+    pub fn base(&self) -> &ChatTemplate {
+        &self.base
+    }
+
+    pub fn branch(&mut self, name: impl Into<String>) -> &mut Branch {
+        let name = name.into();
+        let base = &self.base;
+        self.branches.entry(name.clone()).or_insert_with(|| Branch {
+            name,
+            prefix: Arc::clone(base),
+            suffix: Vec::new(),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Branch> {
+        self.branches.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Branch> {
+        self.branches.remove(name)
+    }
+
+    pub fn branches(&self) -> impl Iterator<Item = &Branch> {
+        self.branches.values()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.branches.keys().map(String::as_str).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.branches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.branches.is_empty()
+    }
+
+    pub fn merge(&self, name: &str, selected: &[usize]) -> Result<ChatTemplate, TemplateError> {
+        let branch = self.get(name).ok_or_else(|| {
+            TemplateError::MalformedTemplate(format!("Unknown branch '{}'", name))
+        })?;
+
+        let mut template = (*self.base).clone();
+        for &index in selected {
+            let message = branch.suffix.get(index).ok_or_else(|| {
+                TemplateError::MalformedTemplate(format!(
+                    "Branch '{}' has no message at index {}",
+                    name, index
+                ))
+            })?;
+            template.messages.push(message.clone());
+        }
+        Ok(template)
+    }
+}
+
+impl ChatTemplate {
+    pub fn fork(&self) -> Fork {
+        Fork::from_arc(Arc::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::{AiMessage, BaseMessage};
+
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, System},
+        Template,
+    };
+
+    fn thought(text: &str) -> MessageLike {
+        MessageLike::base_message(AiMessage::new(text).into())
+    }
+
+    fn contents(template: &ChatTemplate) -> Vec<String> {
+        template
+            .format_messages(&vars!(problem = "2 + 2"))
+            .unwrap()
+            .iter()
+            .map(|message| message.content().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_branches_share_prefix_and_diverge() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "Think step by step.",
+            Human = "Solve {problem}.",
+        ))
+        .unwrap();
+
+        let mut fork = template.fork();
+        fork.branch("count").push(thought("Count up from 2."));
+        fork.branch("add")
+            .push(thought("Add directly."))
+            .push(MessageLike::role_prompt_template(
+                Human,
+                Template::new("Check {problem} again.").unwrap(),
+            ));
+
+        assert_eq!(fork.names(), vec!["add", "count"]);
+        let add = fork.get("add").unwrap();
+        let count = fork.get("count").unwrap();
+        assert!(add.shares_prefix_with(count));
+        assert_eq!(add.suffix().len(), 2);
+        assert_eq!(count.suffix().len(), 1);
+        assert_eq!(fork.base().messages.len(), 2);
+
+        let rendered = add
+            .format_messages(&vars!(problem = "2 + 2"))
+            .unwrap()
+            .iter()
+            .map(|message| message.content().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rendered,
+            vec![
+                "Think step by step.",
+                "Solve 2 + 2.",
+                "Add directly.",
+                "Check 2 + 2 again."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_selected_messages() {
+        let template = ChatTemplate::from_messages(chats!(Human = "Solve {problem}.")).unwrap();
+        let mut fork = template.fork();
+        fork.branch("a")
+            .extend([thought("Guess 5."), thought("Actually 4.")]);
+
+        let merged = fork.merge("a", &[1]).unwrap();
+        assert_eq!(contents(&merged), vec!["Solve 2 + 2.", "Actually 4."]);
+
+        assert!(matches!(
+            fork.merge("a", &[2]),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+        assert!(matches!(
+            fork.merge("missing", &[]),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+    }
+
+    #[test]
+    fn test_nested_fork_extends_prefix() {
+        let template = ChatTemplate::from_messages(chats!(Human = "Solve {problem}.")).unwrap();
+        let mut fork = template.fork();
+        fork.branch("a").push(thought("Try 4."));
+
+        let mut nested = fork.get("a").unwrap().fork();
+        nested.branch("verify").push(thought("4 is right."));
+        nested.branch("refute").push(thought("4 is wrong."));
+
+        let verify = nested.get("verify").unwrap();
+        assert!(verify.shares_prefix_with(nested.get("refute").unwrap()));
+        assert_eq!(
+            contents(&verify.to_template()),
+            vec!["Solve 2 + 2.", "Try 4.", "4 is right."]
+        );
+
+        let empty = fork.branch("b").fork();
+        assert_eq!(empty.base().messages.len(), 1);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, sync::Arc};
+
+use messageforge::{BaseMessage, MessageEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assertions::{declared_role, message_role},
+    truncate_tokens, ChatTemplate, MessageLike, Role, TemplateError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sacrifice {
+    pub entry: usize,
+    pub priority: Priority,
+    pub tokens: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetedRender {
+    pub messages: Vec<Arc<MessageEnum>>,
+    pub tokens: usize,
+    pub sacrificed: Vec<Sacrifice>,
+}
+
+impl ChatTemplate {
+    pub fn with_priority(mut self, entry: usize, priority: Priority) -> Self {
+        self.meta.priorities.insert(entry, priority);
+        self
+    }
+
+    pub fn priority(&self, entry: usize) -> Option<Priority> {
+        if let Some(priority) = self.meta.priorities.get(&entry) {
+            return Some(*priority);
+        }
+
+        let priority = match self.messages.get(entry)? {
+            MessageLike::FewShotPrompt(_) => Priority::Low,
+            MessageLike::Placeholder(_) => Priority::Medium,
+            MessageLike::RolePromptTemplate(Role::System | Role::Developer, _) => {
+                Priority::Critical
+            }
+            MessageLike::BaseMessage(message) if message_role(message) == Some(Role::System) => {
+                Priority::Critical
+            }
+            _ => Priority::High,
+        };
+        Some(priority)
+    }
+
+    pub fn render_within_budget(
+        &self,
+        variables: &HashMap<&str, &str>,
+        max_tokens: usize,
+    ) -> Result<BudgetedRender, TemplateError> {
+        let mut groups = self.with_render_variables(variables, |variables| {
+            self.render_groups(&self.messages, variables, None)
+        })?;
+        let counter = self.counter();
+        let priorities: Vec<Priority> = (0..groups.len())
+            .map(|entry| self.priority(entry).unwrap_or(Priority::High))
+            .collect();
+
+        let mut total: usize = groups
+            .iter()
+            .flatten()
+            .map(|message| counter.count(message.content()))
+            .sum();
+        let mut sacrificed = Vec::new();
+
+        while total > max_tokens {
+            let entry = (0..groups.len())
+                .filter(|&entry| !groups[entry].is_empty() && priorities[entry] < Priority::Critical)
+                .min_by_key(|&entry| (priorities[entry], entry))
+                .ok_or_else(|| {
+                    TemplateError::LimitExceeded(format!(
+                        "prompt needs {} tokens after dropping every non-critical message; the budget is {} tokens",
+                        total, max_tokens
+                    ))
+                })?;
+
+            let truncatable = matches!(
+                self.messages[entry],
+                MessageLike::BaseMessage(_) | MessageLike::RolePromptTemplate(..)
+            );
+            let message = groups[entry].remove(0);
+            let tokens = counter.count(message.content());
+            let overflow = total - max_tokens;
+
+            let truncated = match declared_role(&message) {
+                Some(role) if truncatable && tokens > overflow => {
+                    let kept = truncate_tokens(message.content(), tokens - overflow, counter);
+                    Some(role.to_message(kept)?)
+                }
+                _ => None,
+            };
+
+            let removed = match truncated {
+                Some(truncated) => {
+                    let removed = tokens.saturating_sub(counter.count(truncated.content()));
+                    groups[entry].insert(0, truncated);
+                    removed
+                }
+                None => tokens,
+            };
+
+            total -= removed;
+            sacrificed.push(Sacrifice {
+                entry,
+                priority: priorities[entry],
+                tokens: removed,
+                truncated: !groups[entry].is_empty(),
+            });
+        }
+
+        let messages = self.finish_render(groups.into_iter().flatten().collect(), None)?;
+        Ok(BudgetedRender {
+            messages,
+            tokens: total,
+            sacrificed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, examples, vars, FewShotChatTemplate, FewShotTemplate,
+        Role::{Ai, FewShotPrompt, Human, Placeholder, System},
+    };
+
+    fn history() -> String {
+        serde_json::json!([
+            { "role": "human", "content": "First question about the invoice." },
+            { "role": "ai", "content": "Here is the first answer in detail." },
+            { "role": "human", "content": "A follow-up question." },
+        ])
+        .to_string()
+    }
+
+    fn template() -> ChatTemplate {
+        let few_shot = FewShotChatTemplate::new(
+            FewShotTemplate::new(examples!((
+                "{input}: Can I get a refund?",
+                "{output}: Refunds take five days."
+            ))),
+            ChatTemplate::from_messages(chats!(Human = "{input}", Ai = "{output}")).unwrap(),
+        );
+
+        ChatTemplate::from_messages(chats!(
+            System = "You are a billing assistant.".to_string(),
+            FewShotPrompt = few_shot,
+            Placeholder = "{history}".to_string(),
+            Human = "{question}".to_string(),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_default_priorities() {
+        let template = template();
+        let priorities: Vec<Option<Priority>> = (0..5).map(|i| template.priority(i)).collect();
+        assert_eq!(
+            priorities,
+            vec![
+                Some(Priority::Critical),
+                Some(Priority::Low),
+                Some(Priority::Medium),
+                Some(Priority::High),
+                None
+            ]
+        );
+        assert_eq!(
+            template.with_priority(3, Priority::Critical).priority(3),
+            Some(Priority::Critical)
+        );
+    }
+
+    #[test]
+    fn test_budget_drops_lowest_priority_first() {
+        let template = template();
+        let history = history();
+        let variables = vars!(history = history.as_str(), question = "Where is my refund?");
+
+        let full = template.render_within_budget(&variables, 1000).unwrap();
+        assert!(full.sacrificed.is_empty());
+
+        let budgeted = template
+            .render_within_budget(&variables, full.tokens - 5)
+            .unwrap();
+        assert!(budgeted.tokens <= full.tokens - 5);
+        assert_eq!(budgeted.sacrificed[0].entry, 1);
+        assert_eq!(budgeted.sacrificed[0].priority, Priority::Low);
+        assert_eq!(
+            budgeted.messages.last().unwrap().content(),
+            "Where is my refund?"
+        );
+
+        let tight = template.render_within_budget(&variables, 20).unwrap();
+        let entries: Vec<usize> = tight.sacrificed.iter().map(|s| s.entry).collect();
+        assert!(entries.contains(&2));
+        assert_eq!(tight.messages[0].content(), "You are a billing assistant.");
+        assert!(tight.tokens <= 20);
+    }
+
+    #[test]
+    fn test_budget_truncates_when_partial_removal_suffices() {
+        let template =
+            ChatTemplate::from_messages(chats!(System = "Be brief.", Human = "{doc}",)).unwrap();
+        let doc = "word ".repeat(40);
+        let result = template
+            .render_within_budget(&vars!(doc = doc.as_str()), 30)
+            .unwrap();
+
+        assert_eq!(result.sacrificed.len(), 1);
+        assert!(result.sacrificed[0].truncated);
+        assert_eq!(result.messages.len(), 2);
+        assert!(result.tokens <= 30);
+    }
+
+    #[test]
+    fn test_budget_uses_template_token_counter() {
+        let template = ChatTemplate::from_messages(chats!(System = "Be brief.", Human = "{doc}",))
+            .unwrap()
+            .with_token_counter(|text: &str| text.split_whitespace().count());
+        let doc = "word ".repeat(40);
+        let result = template
+            .render_within_budget(&vars!(doc = doc.as_str()), 12)
+            .unwrap();
+
+        assert_eq!(result.tokens, 12);
+        assert_eq!(result.sacrificed[0].tokens, 30);
+        assert_eq!(result.messages[1].content().split_whitespace().count(), 10);
+    }
+
+    #[test]
+    fn test_budget_fails_when_critical_messages_exceed_it() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You are a very thorough assistant.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let result = template.render_within_budget(&vars!(question = "Hi?"), 3);
+        assert!(matches!(result, Err(TemplateError::LimitExceeded(_))));
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::{
+    fs_store::atomic_write, semantic_search::embedding_text, ChatTemplate, Orphans, PartialLibrary,
+    PromptSet, RetentionPolicy, TemplateError,
+};
+
+const MANIFEST_PATH: &str = "manifest.json";
+const OBJECTS_DIR: &str = "objects";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub library_version: String,
+    pub prompts: BTreeMap<String, String>,
+    #[serde(default)]
+    pub partials: BTreeMap<String, BTreeMap<u32, String>>,
+}
+
+impl BundleManifest {
+    pub const FORMAT_VERSION: u32 = 2;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptBundle {
+    pub prompts: PromptSet,
+    pub partials: PartialLibrary,
+}
+
+fn object_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn bundle_error(action: &str, e: impl std::fmt::Display) -> TemplateError {
+    TemplateError::MalformedTemplate(format!("Failed to {} bundle: {}", action, e))
+}
+
+impl PromptBundle {
+    pub fn new(prompts: PromptSet, partials: PartialLibrary) -> Self {
+        Self { prompts, partials }
+    }
+
+    fn partial_roots(&self) -> Vec<String> {
+        self.prompts
+            .names()
+            .into_iter()
+            .filter_map(|name| self.prompts.get(name).map(embedding_text))
+            .collect()
+    }
+
+    pub fn orphans(&self, policy: RetentionPolicy) -> Orphans {
+        self.partials.orphans(self.partial_roots(), policy)
+    }
+
+    pub fn prune(&mut self, policy: RetentionPolicy) -> Orphans {
+        let roots = self.partial_roots();
+        self.partials.prune(roots, policy)
+    }
+
+    pub fn manifest(&self) -> Result<BundleManifest, TemplateError> {
+        Ok(self.objects()?.0)
+    }
+
+    fn objects(&self) -> Result<(BundleManifest, BTreeMap<String, Vec<u8>>), TemplateError> {
+        let mut manifest = BundleManifest {
+            format_version: BundleManifest::FORMAT_VERSION,
+            library_version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Default::default()
+        };
+        let mut objects = BTreeMap::new();
+
+        for name in self.prompts.names() {
+            let template = self.prompts.get(name).expect("name comes from the set");
+            let content = serde_json::to_vec(template).map_err(|e| bundle_error("export", e))?;
+            let hash = object_hash(&content);
+            manifest.prompts.insert(name.to_string(), hash.clone());
+            objects.insert(hash, content);
+        }
+
+        for name in self.partials.names() {
+            for version in self.partials.versions(name) {
+                let content = self
+                    .partials
+                    .get_version(name, version)
+                    .expect("version comes from the library")
+                    .as_bytes()
+                    .to_vec();
+                let hash = object_hash(&content);
+                manifest
+                    .partials
+                    .entry(name.to_string())
+                    .or_default()
+                    .insert(version, hash.clone());
+                objects.insert(hash, content);
+            }
+        }
+
+        Ok((manifest, objects))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, TemplateError> {
+        let (manifest, objects) = self.objects()?;
+        let manifest =
+            serde_json::to_vec_pretty(&manifest).map_err(|e| bundle_error("export", e))?;
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut append = |path: &str, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, data)
+                .map_err(|e| bundle_error("export", e))
+        };
+
+        append(MANIFEST_PATH, &manifest)?;
+        for (hash, content) in &objects {
+            append(&format!("{}/{}", OBJECTS_DIR, hash), content)?;
+        }
+
+        builder.into_inner().map_err(|e| bundle_error("export", e))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TemplateError> {
+        let mut files = HashMap::new();
+        let mut archive = tar::Archive::new(bytes);
+        for entry in archive.entries().map_err(|e| bundle_error("import", e))? {
+            let mut entry = entry.map_err(|e| bundle_error("import", e))?;
+            let path = entry
+                .path()
+                .map_err(|e| bundle_error("import", e))?
+                .to_string_lossy()
+                .into_owned();
+            let mut content = Vec::new();
+            entry
+                .read_to_end(&mut content)
+                .map_err(|e| bundle_error("import", e))?;
+            files.insert(path, content);
+        }
+
+        let manifest: BundleManifest = files
+            .get(MANIFEST_PATH)
+            .ok_or_else(|| bundle_error("import", "missing manifest.json"))
+            .and_then(|m| serde_json::from_slice(m).map_err(|e| bundle_error("import", e)))?;
+
+        if manifest.format_version != BundleManifest::FORMAT_VERSION {
+            return Err(bundle_error(
+                "import",
+                format!("unsupported format version {}", manifest.format_version),
+            ));
+        }
+
+        let object = |hash: &str| -> Result<&[u8], TemplateError> {
+            let content = files
+                .get(&format!("{}/{}", OBJECTS_DIR, hash))
+                .ok_or_else(|| bundle_error("import", format!("missing object {}", hash)))?;
+            if object_hash(content) != hash {
+                return Err(bundle_error(
+                    "import",
+                    format!("content hash mismatch for object {}", hash),
+                ));
+            }
+            Ok(content)
+        };
+
+        let mut prompts = PromptSet::new();
+        for (name, hash) in &manifest.prompts {
+            let template: ChatTemplate =
+                serde_json::from_slice(object(hash)?).map_err(|e| bundle_error("import", e))?;
+            prompts.insert(name.clone(), template);
+        }
+
+        let mut partials = PartialLibrary::new();
+        for (name, versions) in &manifest.partials {
+            for (version, hash) in versions {
+                let content =
+                    std::str::from_utf8(object(hash)?).map_err(|e| bundle_error("import", e))?;
+                partials.register_version(name.clone(), *version, content);
+            }
+        }
+
+        Ok(Self { prompts, partials })
+    }
+
+    pub async fn export<P: AsRef<Path>>(&self, path: P) -> Result<BundleManifest, TemplateError> {
+        let path = path.as_ref();
+        let manifest = self.manifest()?;
+        let bytes = self.to_bytes()?;
+
+        atomic_write(path, &bytes)
+            .await
+            .map_err(|e| bundle_error("write", e))?;
+
+        Ok(manifest)
+    }
+
+    pub async fn import<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
+        let bytes = fs::read(path).await.map_err(|e| bundle_error("read", e))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl PromptSet {
+    pub async fn export_bundle<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<BundleManifest, TemplateError> {
+        PromptBundle::new(self.clone(), PartialLibrary::new())
+            .export(path)
+            .await
+    }
+
+    pub async fn import_bundle<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
+        Ok(PromptBundle::import(path).await?.prompts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chats, vars, Role::Human, Role::System};
+
+    fn bundle() -> PromptBundle {
+        let mut prompts = PromptSet::new();
+        prompts.insert(
+            "support/greeting",
+            ChatTemplate::from_messages(chats!(
+                System = "You are a support agent.",
+                Human = "Hello, I am {name}.",
+            ))
+            .unwrap(),
+        );
+        prompts.insert(
+            "echo",
+            ChatTemplate::from_messages(chats!(Human = "{text}")).unwrap(),
+        );
+
+        let mut partials = PartialLibrary::new();
+        partials.register("tone", "Be kind.");
+        partials.register("tone", "Be kind and brief.");
+
+        PromptBundle::new(prompts, partials)
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let original = bundle();
+        let restored = PromptBundle::from_bytes(&original.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(restored.prompts.names(), vec!["echo", "support/greeting"]);
+        assert_eq!(
+            restored
+                .prompts
+                .get("support/greeting")
+                .unwrap()
+                .format(&vars!(name = "Ada"))
+                .unwrap(),
+            "system: You are a support agent.\nhuman: Hello, I am Ada."
+        );
+        assert_eq!(restored.partials.versions("tone"), vec![1, 2]);
+        assert_eq!(restored.partials.get("tone"), Some("Be kind and brief."));
+        assert_eq!(restored.manifest().unwrap(), original.manifest().unwrap());
+    }
+
+    #[test]
+    fn test_bundle_bytes_are_deterministic() {
+        assert_eq!(bundle().to_bytes().unwrap(), bundle().to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_manifest_is_content_addressed() {
+        let mut prompts = PromptSet::new();
+        let template = ChatTemplate::from_messages(chats!(Human = "{text}")).unwrap();
+        prompts.insert("a", template.clone());
+        prompts.insert("b", template);
+
+        let manifest = PromptBundle::new(prompts, PartialLibrary::new())
+            .manifest()
+            .unwrap();
+        assert_eq!(manifest.prompts["a"], manifest.prompts["b"]);
+        assert_eq!(manifest.format_version, BundleManifest::FORMAT_VERSION);
+    }
+
+    #[test]
+    fn test_objects_are_named_by_sha256() {
+        let manifest = bundle().manifest().unwrap();
+        assert_eq!(
+            manifest.partials["tone"][&1],
+            "5499befb38dbf3fcc08107141cb2a9e7f42a6aae403361dc3601b8296d7967ea"
+        );
+        assert!(manifest
+            .prompts
+            .values()
+            .all(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())));
+    }
+
+    #[test]
+    fn test_import_rejects_tampered_objects() {
+        let original = bundle();
+        let manifest = original.manifest().unwrap();
+        let hash = &manifest.prompts["echo"];
+
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in [
+            (
+                MANIFEST_PATH.to_string(),
+                serde_json::to_vec(&manifest).unwrap(),
+            ),
+            (format!("{}/{}", OBJECTS_DIR, hash), b"{}".to_vec()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, data.as_slice())
+                .unwrap();
+        }
+
+        let result = PromptBundle::from_bytes(&builder.into_inner().unwrap());
+        assert!(
+            matches!(result, Err(TemplateError::MalformedTemplate(msg)) if msg.contains("mismatch"))
+        );
+    }
+
+    #[test]
+    fn test_bundle_prune_keeps_partials_used_by_prompts() {
+        let mut bundle = bundle();
+        bundle.prompts.insert(
+            "support/tone",
+            ChatTemplate::from_messages(chats!(System = "{{> tone}}")).unwrap(),
+        );
+        bundle.partials.register("unused", "Nobody includes this.");
+
+        let orphans = bundle.prune(RetentionPolicy::keep_versions(1));
+        assert_eq!(orphans.partials, vec!["unused"]);
+        assert_eq!(orphans.versions, vec![("tone".to_string(), 1)]);
+        assert_eq!(bundle.partials.names(), vec!["tone"]);
+        assert!(bundle.orphans(RetentionPolicy::default()).is_empty());
+    }
+
+    #[test]
+    fn test_import_rejects_missing_manifest() {
+        let result = PromptBundle::from_bytes(&tar::Builder::new(Vec::new()).into_inner().unwrap());
+        assert!(matches!(result, Err(TemplateError::MalformedTemplate(_))));
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_files() {
+        let path =
+            std::env::temp_dir().join(format!("promptforge-bundle-{}.tar", std::process::id()));
+
+        let manifest = bundle().prompts.export_bundle(&path).await.unwrap();
+        assert_eq!(manifest.prompts.len(), 2);
+        assert!(manifest.partials.is_empty());
+
+        let prompts = PromptSet::import_bundle(&path).await.unwrap();
+        assert_eq!(prompts.names(), vec!["echo", "support/greeting"]);
+
+        fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -1,20 +1,85 @@
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, ops::Add, path::Path, sync::Arc};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+#[cfg(feature = "async")]
+use std::path::Path;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    ops::{Add, Range},
+    sync::Arc,
+    time::Duration,
+};
+#[cfg(feature = "async")]
 use tokio::fs;
 
 use messageforge::{BaseMessage, MessageEnum, MessageType};
 
 use crate::{
+    braces::unresolved_placeholders,
+    chat_template_view::ChatTemplateView,
+    embedded_tests::{PromptTestCase, PromptTestReport},
     extract_variables,
     few_shot_chat_template_config::MessageConfig,
+    history_repair::{HistoryRepair, RepairReport},
     message_like::{ArcMessageEnumExt, MessageLike},
-    FewShotChatTemplate, Formattable, MessagesPlaceholder, Role, Templatable, Template,
-    TemplateError, TemplateFormat,
+    normalize::{normalize_variables, Normalizer, VariableNormalizers, ALL_VARIABLES},
+    source_map::{whole_message, Segments, SourceMap, SourceOrigin},
+    template_format::{parse_toml, resolve_aliases},
+    var_value::{stringify_values, VarValue},
+    FewShotChatTemplate, FormatOptions, Formattable, Glossary, Lineage, MessagesPlaceholder,
+    ModelProfile, Priority, PromptPolicy, ReasoningHints, RenderLimits, RenderOutput, Role,
+    RolePolicy, Templatable, Template, TemplateError, TemplateFormat, TokenAnnotations,
+    TokenCounter, Variables,
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub type PartialRender = (Vec<Arc<MessageEnum>>, Vec<(usize, TemplateError)>);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChatTemplate {
     pub messages: Vec<MessageLike>,
+    #[serde(flatten)]
+    pub(crate) meta: ChatMetadata,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ChatMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tests: Vec<PromptTestCase>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) profile: Option<ModelProfile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) limits: Option<RenderLimits>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) normalizers: VariableNormalizers,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reasoning: Option<ReasoningHints>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) strict: bool,
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_priorities"
+    )]
+    pub(crate) priorities: BTreeMap<usize, Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) render_budget: Option<Duration>,
+    #[serde(default, skip_serializing_if = "RolePolicy::is_fail")]
+    pub(crate) role_policy: RolePolicy,
+    #[serde(skip)]
+    pub(crate) history_repair: Option<HistoryRepair>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) aliases: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub(crate) token_counter: Option<Arc<dyn TokenCounter>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) token_annotations: Option<TokenAnnotations>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) policy: Option<PromptPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) version: Option<String>,
+    #[serde(default, skip_serializing_if = "FormatOptions::is_default")]
+    pub(crate) format_options: FormatOptions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) glossary: Option<Glossary>,
 }
 
 impl ChatTemplate {
@@ -37,7 +102,9 @@ impl ChatTemplate {
                 _ => {
                     let prompt_template = Template::from_template(&template_str)?;
 
-                    if prompt_template.template_format() == TemplateFormat::PlainText {
+                    if prompt_template.template_format() == TemplateFormat::PlainText
+                        && role != Role::Developer
+                    {
                         let base_message = role
                             .to_message(&template_str)
                             .map_err(|_| TemplateError::InvalidRoleError)?;
@@ -49,70 +116,442 @@ impl ChatTemplate {
             }
         }
 
-        Ok(ChatTemplate { messages: result })
+        Self::check_variable_conflicts(&result)?;
+
+        Ok(ChatTemplate {
+            messages: result,
+            ..Default::default()
+        })
+    }
+
+    pub fn from_messages_unchecked<I>(messages: I) -> Result<Self, TemplateError>
+    where
+        I: IntoIterator<Item = (Role, String)>,
+    {
+        let mut result = Vec::new();
+
+        for (role, template_str) in messages {
+            match role {
+                Role::Placeholder => {
+                    let placeholder = MessagesPlaceholder::try_from(template_str)?;
+                    result.push(MessageLike::placeholder(placeholder));
+                }
+                Role::FewShotPrompt => {
+                    let few_shot_template = FewShotChatTemplate::try_from(template_str)?;
+                    result.push(MessageLike::few_shot_prompt(few_shot_template));
+                }
+                _ => result.push(MessageLike::role_prompt_template(
+                    role,
+                    Template::from_template_unchecked(&template_str),
+                )),
+            }
+        }
+
+        Ok(ChatTemplate {
+            messages: result,
+            ..Default::default()
+        })
+    }
+
+    pub fn is_validated(&self) -> bool {
+        self.messages.iter().all(|message_like| match message_like {
+            MessageLike::RolePromptTemplate(_, template) => template.is_validated(),
+            _ => true,
+        })
+    }
+
+    pub fn validate(&mut self) -> Result<&mut Self, TemplateError> {
+        for message_like in &mut self.messages {
+            if let MessageLike::RolePromptTemplate(_, template) = message_like {
+                if !template.is_validated() {
+                    Arc::make_mut(template).validate()?;
+                }
+            }
+        }
+
+        Self::check_variable_conflicts(&self.messages)?;
+        Ok(self)
+    }
+
+    pub(crate) fn referenced_variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+
+        for message in &self.messages {
+            let names = match message {
+                MessageLike::RolePromptTemplate(_, tmpl) => tmpl.input_variables(),
+                MessageLike::Placeholder(placeholder) => {
+                    vec![placeholder.variable_name().to_string()]
+                }
+                _ => vec![],
+            };
+
+            for name in names {
+                if !variables.contains(&name) {
+                    variables.push(name);
+                }
+            }
+        }
+
+        variables
+    }
+
+    fn check_variable_conflicts(messages: &[MessageLike]) -> Result<(), TemplateError> {
+        let placeholder_names: Vec<&str> = messages
+            .iter()
+            .filter_map(|message_like| match message_like {
+                MessageLike::Placeholder(placeholder) => Some(placeholder.variable_name()),
+                _ => None,
+            })
+            .collect();
+
+        for message_like in messages {
+            if let MessageLike::RolePromptTemplate(_, template) = message_like {
+                if let Some(conflict) = template
+                    .input_variables()
+                    .into_iter()
+                    .find(|var| placeholder_names.contains(&var.as_str()))
+                {
+                    return Err(TemplateError::ConflictingVariable(conflict));
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn invoke(
         &self,
-        variables: &HashMap<&str, &str>,
+        variables: &(impl Variables + ?Sized),
     ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
         self.format_messages(variables)
     }
 
+    pub fn invoke_with_messages(
+        &self,
+        variables: &(impl Variables + ?Sized),
+        messages: &HashMap<&str, Vec<MessageEnum>>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.format_messages_with_history(variables, messages)
+    }
+
     fn deserialize_placeholder_messages(
+        &self,
         messages_str: &str,
-        n_messages: usize,
+        placeholder: &MessagesPlaceholder,
     ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
-        let deserialized_messages: Vec<MessageEnum> =
-            serde_json::from_str(messages_str).map_err(|e| {
-                TemplateError::MalformedTemplate(format!(
-                    "Failed to deserialize placeholder: {}",
-                    e
-                ))
-            })?;
-
-        let limited_messages = if n_messages > 0 {
-            deserialized_messages.into_iter().take(n_messages).collect()
-        } else {
-            deserialized_messages
+        let malformed = |e: serde_json::Error| {
+            TemplateError::MalformedTemplate(format!("Failed to deserialize placeholder: {}", e))
+        };
+        let values: Vec<serde_json::Value> = match serde_json::from_str(messages_str) {
+            Ok(values) => values,
+            Err(e) => self
+                .repair_history(placeholder.variable_name(), messages_str)
+                .ok_or_else(|| malformed(e))?,
         };
 
-        Ok(limited_messages.into_iter().map(Arc::new).collect())
+        let mut deserialized_messages = Vec::with_capacity(values.len());
+        for value in values {
+            let role = value.get("role").and_then(|role| role.as_str());
+            match role {
+                Some(role) if MessageType::try_from(role).is_err() => {
+                    let content = value
+                        .get("content")
+                        .and_then(|content| content.as_str())
+                        .unwrap_or_default();
+                    if let Some(message) = self.meta.role_policy.resolve(role, content)? {
+                        deserialized_messages.push(message.unwrap_enum());
+                    }
+                }
+                _ => deserialized_messages.push(serde_json::from_value(value).map_err(malformed)?),
+            }
+        }
+
+        Ok(self.placeholder_messages(deserialized_messages, placeholder))
+    }
+
+    fn placeholder_messages(
+        &self,
+        messages: Vec<MessageEnum>,
+        placeholder: &MessagesPlaceholder,
+    ) -> Vec<Arc<MessageEnum>> {
+        placeholder
+            .trim_to_budget(messages, self.counter())
+            .into_iter()
+            .map(Arc::new)
+            .collect()
+    }
+
+    fn repair_history(&self, variable: &str, payload: &str) -> Option<Vec<serde_json::Value>> {
+        let history_repair = self.meta.history_repair.as_ref()?;
+        let repaired = history_repair.repair(payload)?;
+        let values = serde_json::from_str(&repaired).ok()?;
+
+        history_repair.report(RepairReport {
+            variable: variable.to_string(),
+            original: payload.to_string(),
+            repaired,
+        });
+        Some(values)
+    }
+
+    fn with_chat_format_options<'a>(&self, template: &'a Template) -> Cow<'a, Template> {
+        if self.meta.format_options.is_strict()
+            || template.format_options() == self.meta.format_options
+        {
+            Cow::Borrowed(template)
+        } else {
+            Cow::Owned(
+                template
+                    .clone()
+                    .with_format_options(self.meta.format_options),
+            )
+        }
+    }
+
+    fn fallback_messages(
+        &self,
+        fallback: &[(Role, String)],
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let mut messages = Vec::with_capacity(fallback.len());
+        for (role, content) in fallback {
+            messages.extend(self.role_message(*role, content)?);
+        }
+        Ok(messages)
+    }
+
+    fn role_message(
+        &self,
+        role: Role,
+        content: &str,
+    ) -> Result<Option<Arc<MessageEnum>>, TemplateError> {
+        match role.to_message(content) {
+            Ok(message) => Ok(Some(message)),
+            Err(_) => self.meta.role_policy.resolve(role.as_str(), content),
+        }
+    }
+
+    pub fn with_alias(mut self, alias: &str, canonical: &str) -> Self {
+        self.meta
+            .aliases
+            .insert(alias.to_string(), canonical.to_string());
+        self
+    }
+
+    pub fn with_role_policy(mut self, policy: RolePolicy) -> Self {
+        self.meta.role_policy = policy;
+        self
+    }
+
+    pub fn format(&self, variables: &(impl Variables + ?Sized)) -> Result<String, TemplateError> {
+        let formatted_messages = self.format_messages(variables)?;
+        Ok(self.profile_transcript(&formatted_messages))
     }
 
     pub fn format_messages(
         &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.format_slice(&self.messages, &variables.as_map())
+    }
+
+    pub fn format_messages_partial(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<PartialRender, TemplateError> {
+        self.with_render_variables(&variables.as_map(), |variables| {
+            let mut rendered = Vec::new();
+            let mut errors = Vec::new();
+
+            for (index, message_like) in self.messages.iter().enumerate() {
+                match self.render_groups(std::slice::from_ref(message_like), variables, None) {
+                    Ok(groups) => rendered.extend(groups.into_iter().flatten()),
+                    Err(e) => errors.push((index, e)),
+                }
+            }
+
+            Ok((self.finish_render(rendered, None)?, errors))
+        })
+    }
+
+    pub fn format_messages_values(
+        &self,
+        values: &HashMap<&str, VarValue>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let strings = stringify_values(values);
+        let strings: HashMap<&str, &str> = strings
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+
+        self.with_render_variables(&strings, |variables| {
+            let groups =
+                self.render_groups_with(&self.messages, variables, Some(values), None, None)?;
+            self.finish_render(groups.into_iter().flatten().collect(), None)
+        })
+    }
+
+    pub fn format_messages_with_history(
+        &self,
+        variables: &(impl Variables + ?Sized),
+        messages: &HashMap<&str, Vec<MessageEnum>>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.with_render_variables(&variables.as_map(), |variables| {
+            let groups =
+                self.render_groups_with(&self.messages, variables, None, Some(messages), None)?;
+            self.finish_render(groups.into_iter().flatten().collect(), None)
+        })
+    }
+
+    pub(crate) fn format_slice(
+        &self,
+        messages: &[MessageLike],
         variables: &HashMap<&str, &str>,
     ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
-        let mut results = Vec::new();
+        self.format_slice_mapped(messages, variables, None)
+    }
+
+    fn format_slice_mapped(
+        &self,
+        messages: &[MessageLike],
+        variables: &HashMap<&str, &str>,
+        source_map: Option<&mut SourceMap>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        if self.meta.render_budget.is_some() && source_map.is_none() {
+            return Ok(self.render_slice_timed(messages, variables)?.0);
+        }
+
+        self.with_render_variables(variables, |variables| {
+            self.render_messages(messages, variables, source_map)
+        })
+    }
+
+    pub(crate) fn with_render_variables<T>(
+        &self,
+        variables: &HashMap<&str, &str>,
+        render: impl FnOnce(&HashMap<&str, &str>) -> Result<T, TemplateError>,
+    ) -> Result<T, TemplateError> {
+        if let Some(limits) = &self.meta.limits {
+            limits.check_variables(variables)?;
+        }
+
+        let mut aliased: HashMap<&str, &str>;
+        let variables = if self.meta.aliases.is_empty() {
+            variables
+        } else {
+            aliased = variables.clone();
+            resolve_aliases(&self.meta.aliases, &mut aliased);
+            &aliased
+        };
+
+        if self.meta.normalizers.is_empty() {
+            return render(variables);
+        }
+
+        let normalized = normalize_variables(&self.meta.normalizers, variables)?;
+        let normalized: HashMap<&str, &str> = normalized
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        render(&normalized)
+    }
 
-        for message_like in &self.messages {
+    fn render_messages(
+        &self,
+        messages: &[MessageLike],
+        variables: &HashMap<&str, &str>,
+        mut source_map: Option<&mut SourceMap>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let groups = self.render_groups(messages, variables, source_map.as_deref_mut())?;
+        self.finish_render(groups.into_iter().flatten().collect(), source_map)
+    }
+
+    pub(crate) fn render_groups(
+        &self,
+        messages: &[MessageLike],
+        variables: &HashMap<&str, &str>,
+        source_map: Option<&mut SourceMap>,
+    ) -> Result<Vec<Vec<Arc<MessageEnum>>>, TemplateError> {
+        self.render_groups_with(messages, variables, None, None, source_map)
+    }
+
+    fn render_groups_with(
+        &self,
+        messages: &[MessageLike],
+        variables: &HashMap<&str, &str>,
+        values: Option<&HashMap<&str, VarValue>>,
+        history: Option<&HashMap<&str, Vec<MessageEnum>>>,
+        mut source_map: Option<&mut SourceMap>,
+    ) -> Result<Vec<Vec<Arc<MessageEnum>>>, TemplateError> {
+        let mut groups = Vec::with_capacity(messages.len());
+        let mut rendered = 0;
+
+        for (index, message_like) in messages.iter().enumerate() {
             let messages = match message_like {
-                MessageLike::BaseMessage(base_message) => vec![base_message.clone()],
+                MessageLike::BaseMessage(base_message) => {
+                    if self.meta.strict {
+                        check_unresolved(base_message.content(), &[])?;
+                    }
+                    vec![base_message.clone()]
+                }
 
                 MessageLike::RolePromptTemplate(role, template) => {
-                    let formatted_message = template.format(variables)?;
-                    let base_message = role
-                        .to_message(&formatted_message)
-                        .map_err(|_| TemplateError::InvalidRoleError)?;
-                    vec![base_message]
+                    let template = &self.with_chat_format_options(template);
+                    let structured = values.filter(|_| {
+                        matches!(
+                            template.template_format(),
+                            TemplateFormat::Mustache | TemplateFormat::Jinja2
+                        )
+                    });
+                    let formatted_message = if let Some(values) = structured {
+                        let formatted =
+                            template.format_values(&typed_variables(variables, values))?;
+                        if self.meta.strict {
+                            check_unresolved(&formatted, &[])?;
+                        }
+                        formatted
+                    } else if source_map.is_some() || self.meta.strict {
+                        let (formatted, segments) = template.format_with_source_map(variables)?;
+                        if self.meta.strict {
+                            check_unresolved(&formatted, &escaped_ranges(template, &segments))?;
+                        }
+                        if let Some(source_map) = source_map.as_deref_mut() {
+                            source_map.push_message(rendered, index, segments);
+                        }
+                        formatted
+                    } else {
+                        template.format(variables)?
+                    };
+                    self.role_message(*role, &formatted_message)?
+                        .into_iter()
+                        .collect()
+                }
+
+                MessageLike::Placeholder(placeholder)
+                    if placeholder.optional()
+                        && !self.meta.format_options.render_optional_placeholders =>
+                {
+                    Vec::new()
                 }
 
                 MessageLike::Placeholder(placeholder) => {
-                    if placeholder.optional() {
-                        vec![]
-                    } else {
-                        let messages_str =
-                            variables.get(placeholder.variable_name()).ok_or_else(|| {
-                                TemplateError::MissingVariable(
-                                    placeholder.variable_name().to_string(),
-                                )
-                            })?;
-
-                        Self::deserialize_placeholder_messages(
-                            messages_str,
-                            placeholder.n_messages(),
-                        )?
+                    let name = placeholder.variable_name();
+                    let typed = history.and_then(|history| history.get(name));
+                    match (typed, variables.get(name)) {
+                        (Some(messages), _) => {
+                            self.placeholder_messages(messages.clone(), placeholder)
+                        }
+                        (None, Some(messages_str)) => {
+                            self.deserialize_placeholder_messages(messages_str, placeholder)?
+                        }
+                        (None, None) => match placeholder.fallback() {
+                            Some(fallback) => self.fallback_messages(fallback)?,
+                            None if placeholder.optional()
+                                || !self.meta.format_options.is_strict() =>
+                            {
+                                vec![]
+                            }
+                            None => return Err(TemplateError::MissingVariable(name.to_string())),
+                        },
                     }
                 }
 
@@ -130,12 +569,206 @@ impl ChatTemplate {
                 }
             };
 
-            results.extend(messages);
+            if let Some(limits) = &self.meta.limits {
+                for message in &messages {
+                    limits.check_message(message)?;
+                }
+            }
+
+            if let Some(source_map) = source_map.as_deref_mut() {
+                let origin = match message_like {
+                    MessageLike::BaseMessage(_) => Some(SourceOrigin::Literal),
+                    MessageLike::Placeholder(placeholder) => Some(SourceOrigin::Variable {
+                        name: placeholder.variable_name().to_string(),
+                    }),
+                    MessageLike::FewShotPrompt(_) => Some(SourceOrigin::FewShot),
+                    MessageLike::RolePromptTemplate(..) => None,
+                };
+                if let Some(origin) = origin {
+                    for (offset, message) in messages.iter().enumerate() {
+                        source_map.push_message(
+                            rendered + offset,
+                            index,
+                            whole_message(message.content(), origin.clone()),
+                        );
+                    }
+                }
+            }
+
+            rendered += messages.len();
+            groups.push(messages);
+        }
+
+        Ok(groups)
+    }
+
+    pub(crate) fn finish_render(
+        &self,
+        results: Vec<Arc<MessageEnum>>,
+        source_map: Option<&mut SourceMap>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let results = match &self.meta.profile {
+            Some(profile) => {
+                let output = profile.apply(results.clone())?;
+                if let Some(source_map) = source_map {
+                    *source_map = std::mem::take(source_map).remap(&results, &output);
+                }
+                output
+            }
+            None => results,
+        };
+
+        if let Some(limits) = &self.meta.limits {
+            limits.check_total(&results)?;
         }
 
         Ok(results)
     }
 
+    pub fn with_normalizer(mut self, variable: impl Into<String>, normalizer: Normalizer) -> Self {
+        self.meta
+            .normalizers
+            .entry(variable.into())
+            .or_default()
+            .push(normalizer);
+        self
+    }
+
+    pub fn with_default_normalizer(self, normalizer: Normalizer) -> Self {
+        self.with_normalizer(ALL_VARIABLES, normalizer)
+    }
+
+    pub fn with_reasoning_hints(mut self, reasoning: ReasoningHints) -> Self {
+        self.meta.reasoning = Some(reasoning);
+        self
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.meta.version = Some(version.into());
+        self
+    }
+
+    pub fn with_format_options(mut self, options: FormatOptions) -> Self {
+        self.meta.format_options = options;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: PromptPolicy) -> Self {
+        self.meta.policy = Some(policy);
+        self
+    }
+
+    pub fn with_strict_placeholders(mut self) -> Self {
+        self.meta.strict = true;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: RenderLimits) -> Self {
+        self.meta.limits = Some(limits);
+        self
+    }
+
+    pub fn tests(&self) -> &[PromptTestCase] {
+        &self.meta.tests
+    }
+
+    pub fn profile(&self) -> Option<&ModelProfile> {
+        self.meta.profile.as_ref()
+    }
+
+    pub fn limits(&self) -> Option<RenderLimits> {
+        self.meta.limits
+    }
+
+    pub fn normalizers(&self) -> &VariableNormalizers {
+        &self.meta.normalizers
+    }
+
+    pub fn reasoning_hints(&self) -> Option<ReasoningHints> {
+        self.meta.reasoning
+    }
+
+    pub fn strict_placeholders(&self) -> bool {
+        self.meta.strict
+    }
+
+    pub fn priorities(&self) -> &BTreeMap<usize, Priority> {
+        &self.meta.priorities
+    }
+
+    pub fn render_budget(&self) -> Option<Duration> {
+        self.meta.render_budget
+    }
+
+    pub fn role_policy(&self) -> RolePolicy {
+        self.meta.role_policy
+    }
+
+    pub fn history_repair(&self) -> Option<&HistoryRepair> {
+        self.meta.history_repair.as_ref()
+    }
+
+    pub fn aliases(&self) -> &BTreeMap<String, String> {
+        &self.meta.aliases
+    }
+
+    pub fn token_annotations(&self) -> Option<&TokenAnnotations> {
+        self.meta.token_annotations.as_ref()
+    }
+
+    pub fn policy(&self) -> Option<&PromptPolicy> {
+        self.meta.policy.as_ref()
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.meta.version.as_deref()
+    }
+
+    pub fn format_options(&self) -> FormatOptions {
+        self.meta.format_options
+    }
+
+    pub fn glossary(&self) -> Option<&Glossary> {
+        self.meta.glossary.as_ref()
+    }
+
+    pub fn render(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<RenderOutput, TemplateError> {
+        Ok(RenderOutput::new(self.format_messages(variables)?))
+    }
+
+    pub fn render_with_source_map(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<RenderOutput, TemplateError> {
+        let mut source_map = SourceMap::default();
+        let messages =
+            self.format_slice_mapped(&self.messages, &variables.as_map(), Some(&mut source_map))?;
+        Ok(RenderOutput::new(messages).with_source_map(source_map))
+    }
+
+    pub fn render_with_lineage(
+        &self,
+        variables: &(impl Variables + ?Sized),
+        version: Option<&str>,
+    ) -> Result<RenderOutput, TemplateError> {
+        let variables = variables.as_map();
+        let lineage = Lineage::capture(self, &variables)?;
+        let lineage = match version {
+            Some(version) => lineage.with_version(version),
+            None => lineage,
+        };
+        Ok(self.render(&*variables)?.with_lineage(lineage))
+    }
+
+    pub fn specialize(&self, model_profile: ModelProfile) -> ChatTemplate {
+        let mut specialized = self.clone();
+        specialized.meta.profile = Some(model_profile);
+        specialized
+    }
+
     pub fn to_variables_map(&self) -> HashMap<&str, &str> {
         let mut variables = HashMap::new();
 
@@ -160,40 +793,190 @@ impl ChatTemplate {
         variables
     }
 
+    pub fn transcript(messages: &[Arc<MessageEnum>]) -> String {
+        messages
+            .iter()
+            .map(|message| {
+                let role_prefix = match message.message_type() {
+                    MessageType::Human => "human: ",
+                    MessageType::Ai => "ai: ",
+                    MessageType::System => "system: ",
+                    _ => "",
+                };
+                format!("{}{}", role_prefix, message.content())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub(crate) fn profile_transcript(&self, messages: &[Arc<MessageEnum>]) -> String {
+        match &self.meta.profile {
+            Some(profile) => profile.transcript(messages),
+            None => Self::transcript(messages),
+        }
+    }
+
+    pub fn window(&self, range: Range<usize>) -> Result<ChatTemplateView<'_>, TemplateError> {
+        ChatTemplateView::new(self, range)
+    }
+
+    pub fn split_at(
+        &self,
+        index: usize,
+    ) -> Result<(ChatTemplateView<'_>, ChatTemplateView<'_>), TemplateError> {
+        Ok((
+            self.window(0..index)?,
+            self.window(index..self.messages.len())?,
+        ))
+    }
+
+    pub fn with_tests(mut self, tests: Vec<PromptTestCase>) -> Self {
+        self.meta.tests = tests;
+        self
+    }
+
+    pub fn run_embedded_tests(&self) -> PromptTestReport {
+        let mut report = PromptTestReport::default();
+
+        for (index, test) in self.meta.tests.iter().enumerate() {
+            let reasons = match self.format_messages(&test.variables()) {
+                Ok(messages) => test.check(&messages),
+                Err(e) => vec![format!("render failed: {}", e)],
+            };
+            report.record(test.label(index), reasons);
+        }
+
+        report
+    }
+
+    #[cfg(feature = "async")]
     pub async fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
         let toml_content = fs::read_to_string(path).await.map_err(|e| {
             TemplateError::TomlDeserializationError(format!("Failed to read TOML file: {}", e))
         })?;
 
-        ChatTemplate::try_from(toml_content)
+        ChatTemplate::try_from(toml_content)
+    }
+}
+
+impl Formattable for ChatTemplate {
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError> {
+        ChatTemplate::format(self, variables)
+    }
+}
+
+fn check_unresolved(content: &str, escaped: &[Range<usize>]) -> Result<(), TemplateError> {
+    let unresolved = unresolved_placeholders(content).into_iter().find(|span| {
+        !escaped
+            .iter()
+            .any(|range| range.start < span.end && span.start < range.end)
+    });
+
+    match unresolved {
+        Some(span) => Err(TemplateError::UnresolvedPlaceholder(format!(
+            "'{}' was left in the rendered output",
+            &content[span]
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn escaped_ranges(template: &Template, segments: &Segments) -> Vec<Range<usize>> {
+    segments
+        .iter()
+        .filter_map(|(range, origin)| match origin {
+            SourceOrigin::Template { span }
+                if template.template()[span.clone()].starts_with('\\') =>
+            {
+                Some(range.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn typed_variables<'a>(
+    variables: &HashMap<&'a str, &str>,
+    values: &HashMap<&'a str, VarValue>,
+) -> HashMap<&'a str, VarValue> {
+    let mut typed: HashMap<&str, VarValue> = variables
+        .iter()
+        .map(|(name, value)| (*name, VarValue::from(*value)))
+        .collect();
+    for (name, value) in values {
+        if value.as_str().is_none() {
+            typed.insert(name, value.clone());
+        }
     }
+    typed
 }
 
-impl Formattable for ChatTemplate {
-    fn format(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError> {
-        let formatted_messages = self.format_messages(variables)?;
+fn deserialize_priorities<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<usize, Priority>, D::Error> {
+    BTreeMap::<String, Priority>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(entry, priority)| {
+            entry
+                .parse()
+                .map(|entry| (entry, priority))
+                .map_err(D::Error::custom)
+        })
+        .collect()
+}
 
-        let combined_result = formatted_messages
-            .iter()
-            .map(|message| {
-                let role_prefix = match message.message_type() {
-                    MessageType::Human => "human: ",
-                    MessageType::Ai => "ai: ",
-                    MessageType::System => "system: ",
-                    _ => "",
-                };
-                format!("{}{}", role_prefix, message.content())
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+impl ChatMetadata {
+    fn merge(&mut self, other: ChatMetadata, offset: usize) {
+        self.priorities.extend(
+            other
+                .priorities
+                .into_iter()
+                .map(|(entry, priority)| (entry + offset, priority)),
+        );
+        self.tests.extend(other.tests);
+        self.profile = self.profile.take().or(other.profile);
+        self.limits = self.limits.or(other.limits);
+        self.reasoning = self.reasoning.or(other.reasoning);
+        self.strict = self.strict || other.strict;
+        self.render_budget = self.render_budget.or(other.render_budget);
+        self.history_repair = self.history_repair.take().or(other.history_repair);
+        self.token_counter = self.token_counter.take().or(other.token_counter);
+        self.token_annotations = None;
+        self.policy = self.policy.take().or(other.policy);
+        self.version = self.version.take().or(other.version);
+        self.glossary = self.glossary.take().or(other.glossary);
+        if self.format_options.is_default() {
+            self.format_options = other.format_options;
+        }
+        if self.role_policy.is_fail() {
+            self.role_policy = other.role_policy;
+        }
+        for (variable, normalizers) in other.normalizers {
+            self.normalizers.entry(variable).or_insert(normalizers);
+        }
+        for (alias, canonical) in other.aliases {
+            self.aliases.entry(alias).or_insert(canonical);
+        }
+    }
 
-        Ok(combined_result)
+    pub(crate) fn window(&self, range: Range<usize>) -> ChatMetadata {
+        ChatMetadata {
+            tests: Vec::new(),
+            token_annotations: None,
+            priorities: self
+                .priorities
+                .range(range.clone())
+                .map(|(entry, priority)| (entry - range.start, *priority))
+                .collect(),
+            ..self.clone()
+        }
     }
 }
 
 impl Add for ChatTemplate {
     type Output = ChatTemplate;
     fn add(mut self, other: ChatTemplate) -> ChatTemplate {
+        self.meta.merge(other.meta, self.messages.len());
         self.messages.extend(other.messages);
         self
     }
@@ -208,7 +991,7 @@ impl TryFrom<String> for ChatTemplate {
                 TemplateError::MalformedTemplate(format!("Failed to parse JSON: {}", err))
             })
         } else {
-            toml::from_str(&value).map_err(|err| {
+            parse_toml(&value).map_err(|err| {
                 TemplateError::MalformedTemplate(format!("Failed to parse TOML: {}", err))
             })
         }
@@ -223,7 +1006,7 @@ impl TryFrom<Vec<MessageConfig>> for ChatTemplate {
             .into_iter()
             .map(|config| {
                 let role = Role::try_from(config.value.role.as_str())
-                    .map_err(|_| TemplateError::InvalidRoleError)?;
+                    .map_err(|_| TemplateError::UnknownRole(config.value.role.clone()))?;
                 let content = config.value.content;
 
                 Ok((role, content))
@@ -245,7 +1028,7 @@ mod tests {
     use super::*;
     use crate::message_like::MessageLike;
     use crate::Role::{Ai, FewShotPrompt, Human, Placeholder, System};
-    use crate::{chats, examples, vars, FewShotChatTemplate, FewShotTemplate};
+    use crate::{chats, examples, vars, FewShotChatTemplate, FewShotTemplate, PromptTestCase};
 
     #[test]
     fn test_from_messages_plaintext() {
@@ -411,6 +1194,667 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_invoke_with_typed_history() {
+        use messageforge::{AiMessage, HumanMessage};
+
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "This is a system message.",
+            Placeholder = "{history}",
+            Human = "How can I help you, {name}?"
+        ))
+        .unwrap();
+        let history = HashMap::from([(
+            "history",
+            vec![
+                HumanMessage::new("Hello, AI.").into(),
+                AiMessage::new("Hi, how can I assist you today?").into(),
+            ],
+        )]);
+
+        let result = chat_prompt
+            .invoke_with_messages(&vars!(name = "Bob"), &history)
+            .unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[1].content(), "Hello, AI.");
+        assert_eq!(result[2].message_type(), &MessageType::Ai);
+
+        let typed_wins = chat_prompt
+            .format_messages_with_history(&vars!(history = "not json", name = "Bob"), &history)
+            .unwrap();
+        assert_eq!(typed_wins.len(), 4);
+
+        assert!(matches!(
+            chat_prompt.format_messages_with_history(&vars!(name = "Bob"), &HashMap::new()),
+            Err(TemplateError::MissingVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_invoke_with_missing_placeholder_uses_default_message() {
+        let mut chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "This is a system message.",
+            Placeholder = "{history}",
+            Human = "How can I help you, {name}?"
+        ))
+        .unwrap();
+        chat_prompt.messages[1] = MessageLike::placeholder(
+            MessagesPlaceholder::new("history".to_string())
+                .with_default_message(System, "No prior conversation."),
+        );
+
+        let result = chat_prompt.invoke(&vars!(name = "Bob")).unwrap();
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].content(), "No prior conversation.");
+        assert_eq!(result[1].message_type(), &MessageType::System);
+        assert_eq!(result[2].content(), "How can I help you, Bob?");
+    }
+
+    #[test]
+    fn test_invoke_with_missing_placeholder_uses_empty_fallback() {
+        let chat_prompt = ChatTemplate {
+            messages: vec![
+                MessageLike::placeholder(
+                    MessagesPlaceholder::new("history".to_string()).with_fallback(vec![]),
+                ),
+                MessageLike::role_prompt_template(Human, Template::new("Hi {name}").unwrap()),
+            ],
+            ..Default::default()
+        };
+
+        let result = chat_prompt.invoke(&vars!(name = "Bob")).unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content(), "Hi Bob");
+    }
+
+    #[test]
+    fn test_invoke_with_provided_placeholder_ignores_fallback() {
+        let history_json = json!([{ "role": "human", "content": "Hello, AI." }]).to_string();
+        let chat_prompt = ChatTemplate {
+            messages: vec![MessageLike::placeholder(
+                MessagesPlaceholder::new("history".to_string())
+                    .with_default_message(System, "No prior conversation."),
+            )],
+            ..Default::default()
+        };
+
+        let result = chat_prompt
+            .invoke(&vars!(history = history_json.as_str()))
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content(), "Hello, AI.");
+    }
+
+    #[test]
+    fn test_invoke_with_invalid_fallback_role() {
+        let chat_prompt = ChatTemplate {
+            messages: vec![MessageLike::placeholder(
+                MessagesPlaceholder::new("history".to_string())
+                    .with_default_message(Role::Tool, "No prior conversation."),
+            )],
+            ..Default::default()
+        };
+
+        let err = chat_prompt.invoke(&vars!()).unwrap_err();
+        assert!(matches!(err, TemplateError::UnknownRole(role) if role == "tool"));
+    }
+
+    #[test]
+    fn test_role_policy_for_imported_history() {
+        let history_json = json!([
+            { "role": "human", "content": "Hello, AI." },
+            { "role": "narrator", "content": "The AI ponders." },
+            { "role": "ai", "content": "Hi there!" }
+        ])
+        .to_string();
+        let variables = vars!(history = history_json.as_str());
+        let chat_prompt = ChatTemplate::from_messages(chats!(Placeholder = "{history}")).unwrap();
+
+        let err = chat_prompt.invoke(&variables).unwrap_err();
+        assert!(matches!(err, TemplateError::UnknownRole(ref role) if role == "narrator"));
+        assert_eq!(err.to_string(), "Unknown role: 'narrator'");
+
+        let skipped = chat_prompt
+            .clone()
+            .with_role_policy(RolePolicy::Skip)
+            .invoke(&variables)
+            .unwrap();
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[1].content(), "Hi there!");
+
+        let substituted = chat_prompt
+            .with_role_policy(RolePolicy::Substitute(Human))
+            .invoke(&variables)
+            .unwrap();
+        assert_eq!(substituted.len(), 3);
+        assert_eq!(substituted[1].content(), "The AI ponders.");
+        assert_eq!(substituted[1].message_type(), &MessageType::Human);
+
+        let malformed = json!([{ "role": "human" }]).to_string();
+        let err = ChatTemplate::from_messages(chats!(Placeholder = "{history}"))
+            .unwrap()
+            .with_role_policy(RolePolicy::Skip)
+            .invoke(&vars!(history = malformed.as_str()))
+            .unwrap_err();
+        assert!(matches!(err, TemplateError::MalformedTemplate(_)));
+    }
+
+    #[test]
+    fn test_history_repair() {
+        use std::sync::Mutex;
+
+        let history = "[{'role': 'human', 'content': 'Hi, it\\'s me.'}] trailing";
+        let variables = vars!(history = history);
+        let chat_prompt = ChatTemplate::from_messages(chats!(Placeholder = "{history}")).unwrap();
+        assert!(matches!(
+            chat_prompt.invoke(&variables),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let repaired =
+            chat_prompt.with_history_repair(HistoryRepair::lenient().on_repaired(move |report| {
+                sink.lock().unwrap().push(report.clone());
+            }));
+
+        let messages = repaired.invoke(&variables).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content(), "Hi, it's me.");
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].variable, "history");
+        assert_eq!(
+            reports[0].repaired,
+            r#"[{"role": "human", "content": "Hi, it's me."}]"#
+        );
+        assert_eq!(reports[0].changed_span().start, 2);
+
+        let unrepairable = repaired.invoke(&vars!(history = "not json at all"));
+        assert!(matches!(
+            unrepairable,
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_messages_partial() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are {persona}.",
+            Placeholder = "{history}",
+            Human = "{question}",
+            Ai = "Noted.",
+        ))
+        .unwrap();
+
+        let (messages, errors) = chat_prompt
+            .format_messages_partial(&vars!(persona = "a pirate", history = "oops"))
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content(), "You are a pirate.");
+        assert_eq!(messages[1].content(), "Noted.");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, 1);
+        assert!(matches!(errors[0].1, TemplateError::MalformedTemplate(_)));
+        assert_eq!(errors[1].0, 2);
+        assert!(matches!(&errors[1].1, TemplateError::MissingVariable(_)));
+
+        let variables = vars!(persona = "a pirate", history = "[]", question = "Where?");
+        let (messages, errors) = chat_prompt.format_messages_partial(&variables).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(messages, chat_prompt.format_messages(&variables).unwrap());
+    }
+
+    #[test]
+    fn test_chat_template_aliases() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You help {user_name}.",
+            Placeholder = "{history}",
+        ))
+        .unwrap()
+        .with_alias("username", "user_name")
+        .with_alias("chat_history", "history");
+
+        let history = json!([{ "role": "human", "content": "Hi." }]).to_string();
+        let messages = chat_prompt
+            .format_messages(&vars!(username = "Ada", chat_history = history.as_str()))
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content(), "You help Ada.");
+        assert_eq!(messages[1].content(), "Hi.");
+
+        let merged = ChatTemplate::default().with_alias("username", "other") + chat_prompt;
+        assert_eq!(merged.aliases()["username"], "other");
+        assert_eq!(merged.aliases()["chat_history"], "history");
+    }
+
+    #[test]
+    fn test_role_policy_for_template_roles() {
+        let chat_prompt = ChatTemplate {
+            messages: vec![
+                MessageLike::role_prompt_template(Role::Tool, Template::new("{output}").unwrap()),
+                MessageLike::role_prompt_template(Human, Template::new("Hi {name}").unwrap()),
+            ],
+            ..Default::default()
+        };
+        let variables = vars!(output = "42", name = "Bob");
+
+        let err = chat_prompt.invoke(&variables).unwrap_err();
+        assert!(matches!(err, TemplateError::UnknownRole(role) if role == "tool"));
+
+        let chat_prompt = chat_prompt.with_role_policy(RolePolicy::Substitute(System));
+        let messages = chat_prompt.invoke(&variables).unwrap();
+        assert_eq!(messages[0].content(), "42");
+        assert_eq!(messages[0].message_type(), &MessageType::System);
+
+        let json = serde_json::to_value(&chat_prompt).unwrap();
+        assert_eq!(json["role_policy"], json!({ "substitute": "System" }));
+        let restored: ChatTemplate = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.role_policy(), RolePolicy::Substitute(System));
+        assert!(ChatTemplate::default().role_policy().is_fail());
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_format_messages_values() {
+        use crate::var_values;
+
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You help {{name}}.{{#if premium}} Priority support.{{/if}}",
+            Placeholder = "{history|optional}",
+            Human = "Order {order_id}: {question}",
+        ))
+        .unwrap()
+        .with_format_options(FormatOptions::new().render_optional_placeholders(true));
+
+        let values = var_values!(
+            name = "Ada",
+            premium = true,
+            order_id = 42,
+            question = "Where is it?",
+            history = json!([{ "role": "ai", "content": "Hello!" }]),
+        );
+        let messages = chat_prompt.format_messages_values(&values).unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].content(), "You help Ada. Priority support.");
+        assert_eq!(messages[1].content(), "Hello!");
+        assert_eq!(messages[2].content(), "Order 42: Where is it?");
+
+        let values = var_values!(name = "Bob", premium = false, order_id = 7, question = "?");
+        let messages = chat_prompt.format_messages_values(&values).unwrap();
+        assert_eq!(messages[0].content(), "You help Bob.");
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn test_invoke_with_optional_placeholder_missing() {
+        let chat_prompt = ChatTemplate {
+            messages: vec![
+                MessageLike::placeholder(MessagesPlaceholder::with_options(
+                    "history".to_string(),
+                    true,
+                    MessagesPlaceholder::DEFAULT_LIMIT,
+                )),
+                MessageLike::role_prompt_template(Human, Template::new("Hi {name}").unwrap()),
+            ],
+            ..Default::default()
+        };
+
+        let result = chat_prompt.invoke(&vars!(name = "Bob")).unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content(), "Hi Bob");
+    }
+
+    #[test]
+    fn test_invoke_with_optional_placeholder_provided() {
+        let history_json = json!([
+            { "role": "human", "content": "Hello, AI." },
+            { "role": "ai", "content": "Hi there!" },
+            { "role": "human", "content": "Still there?" }
+        ])
+        .to_string();
+        let chat_prompt = ChatTemplate {
+            messages: vec![MessageLike::placeholder(MessagesPlaceholder::with_options(
+                "history".to_string(),
+                true,
+                2,
+            ))],
+            ..Default::default()
+        };
+
+        let variables = vars!(history = history_json.as_str());
+        assert!(chat_prompt.invoke(&variables).unwrap().is_empty());
+
+        let chat_prompt = chat_prompt
+            .with_format_options(FormatOptions::new().render_optional_placeholders(true));
+        let result = chat_prompt.invoke(&variables).unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content(), "Hello, AI.");
+        assert_eq!(result[1].content(), "Hi there!");
+    }
+
+    #[test]
+    fn test_from_messages_conflicting_variable() {
+        let result = ChatTemplate::from_messages(chats!(
+            System = "Summarize {history}.",
+            Placeholder = "{history}",
+        ));
+
+        assert!(matches!(
+            result,
+            Err(TemplateError::ConflictingVariable(name)) if name == "history"
+        ));
+    }
+
+    #[test]
+    fn test_from_messages_conflict_detected_regardless_of_order() {
+        let result = ChatTemplate::from_messages(chats!(
+            Placeholder = "{history|optional}",
+            Human = "{{history}}",
+        ));
+
+        assert!(matches!(result, Err(TemplateError::ConflictingVariable(_))));
+    }
+
+    #[test]
+    fn test_from_messages_repeated_placeholder_is_not_a_conflict() {
+        let result = ChatTemplate::from_messages(chats!(
+            Placeholder = "{history}",
+            Human = "{question}",
+            Placeholder = "{history}",
+        ));
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_invoke_with_placeholder_options_from_chats() {
+        let history_json = json!([
+            { "role": "human", "content": "First." },
+            { "role": "ai", "content": "Second." },
+            { "role": "human", "content": "Third." }
+        ])
+        .to_string();
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are a helpful assistant.",
+            Placeholder = "{history|optional|limit:2|keep:last}",
+        ))
+        .unwrap()
+        .with_format_options(FormatOptions::new().render_optional_placeholders(true));
+
+        let result = chat_prompt
+            .invoke(&vars!(history = history_json.as_str()))
+            .unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].content(), "Second.");
+        assert_eq!(result[2].content(), "Third.");
+
+        let result = chat_prompt.invoke(&HashMap::new()).unwrap();
+        assert_eq!(result.len(), 1);
+    }
+
+    #[test]
+    fn test_placeholder_token_budget() {
+        let history_json = json!([
+            { "role": "human", "content": "one two three" },
+            { "role": "ai", "content": "four five" },
+            { "role": "human", "content": "six" }
+        ])
+        .to_string();
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are a helpful assistant.",
+            Placeholder = "{history|max_tokens:3|keep:last}",
+        ))
+        .unwrap()
+        .with_token_counter(|text: &str| text.split_whitespace().count());
+
+        let result = chat_prompt
+            .format_messages(&vars!(history = history_json.as_str()))
+            .unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].content(), "four five");
+        assert_eq!(result[2].content(), "six");
+
+        let mut heuristic = chat_prompt;
+        heuristic.meta.token_counter = None;
+        let result = heuristic
+            .format_messages(&vars!(history = history_json.as_str()))
+            .unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1].content(), "six");
+    }
+
+    #[test]
+    fn test_invoke_with_optional_placeholder_and_invalid_json() {
+        let chat_prompt = ChatTemplate {
+            messages: vec![MessageLike::placeholder(MessagesPlaceholder::with_options(
+                "history".to_string(),
+                true,
+                MessagesPlaceholder::DEFAULT_LIMIT,
+            ))],
+            ..Default::default()
+        }
+        .with_format_options(FormatOptions::new().render_optional_placeholders(true));
+
+        let result = chat_prompt.invoke(&vars!(history = "not json"));
+        assert!(matches!(result, Err(TemplateError::MalformedTemplate(_))));
+    }
+
+    #[test]
+    fn test_specialize_for_models() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are a {tone} assistant.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let variables = vars!(tone = "terse", question = "Why?");
+
+        assert_eq!(
+            chat_prompt
+                .specialize(ModelProfile::gpt())
+                .format(&variables)
+                .unwrap(),
+            "system: You are a terse assistant.\nhuman: Why?"
+        );
+        assert_eq!(
+            chat_prompt
+                .specialize(ModelProfile::claude())
+                .format(&variables)
+                .unwrap(),
+            "You are a terse assistant.\n\nHuman: Why?"
+        );
+
+        let llama = chat_prompt.specialize(ModelProfile::llama());
+        let messages = llama.format_messages(&variables).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content(), "You are a terse assistant.\n\nWhy?");
+        assert_eq!(
+            llama.format(&variables).unwrap(),
+            "[INST] You are a terse assistant.\n\nWhy?"
+        );
+
+        assert!(chat_prompt.profile().is_none());
+    }
+
+    #[test]
+    fn test_specialize_limits_placeholder_history() {
+        let history_json = json!([
+            { "role": "human", "content": "One." },
+            { "role": "ai", "content": "Two." },
+            { "role": "human", "content": "Three." }
+        ])
+        .to_string();
+        let chat_prompt =
+            ChatTemplate::from_messages(chats!(System = "Be brief.", Placeholder = "{history}",))
+                .unwrap()
+                .specialize(ModelProfile::new("small").with_max_messages(2));
+
+        let messages = chat_prompt
+            .format_messages(&vars!(history = history_json.as_str()))
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content(), "Be brief.");
+        assert_eq!(messages[1].content(), "Three.");
+    }
+
+    #[test]
+    fn test_render_with_lineage() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are a {tone} assistant.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let variables = vars!(tone = "terse", question = "Why?");
+
+        let plain = chat_prompt.render(&variables).unwrap();
+        assert!(plain.lineage.is_none());
+
+        let output = chat_prompt
+            .render_with_lineage(&variables, Some("2024-06-01"))
+            .unwrap();
+        assert_eq!(output.messages, plain.messages);
+
+        let lineage = output.lineage.unwrap();
+        assert_eq!(lineage.template_version.as_deref(), Some("2024-06-01"));
+        assert!(lineage.matches_inputs(&chat_prompt, &variables).unwrap());
+        assert!(!lineage
+            .matches_inputs(&chat_prompt.specialize(ModelProfile::claude()), &variables)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_render_limits() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "Summarize the document.",
+            Human = "{doc}",
+        ))
+        .unwrap();
+        let doc = "word ".repeat(100);
+        let variables = vars!(doc = doc.as_str());
+
+        assert!(chat_prompt.format_messages(&variables).is_ok());
+
+        for limits in [
+            RenderLimits::new().max_variable_bytes(100),
+            RenderLimits::new().max_message_bytes(100),
+            RenderLimits::new().max_total_bytes(510),
+        ] {
+            let result = chat_prompt
+                .clone()
+                .with_limits(limits)
+                .format_messages(&variables);
+            assert!(
+                matches!(result, Err(TemplateError::LimitExceeded(_))),
+                "{:?}",
+                limits
+            );
+        }
+
+        let generous = chat_prompt.with_limits(
+            RenderLimits::new()
+                .max_variable_bytes(500)
+                .max_message_bytes(500)
+                .max_total_bytes(523),
+        );
+        assert!(generous.format_messages(&variables).is_ok());
+    }
+
+    #[test]
+    fn test_normalizers_run_before_substitution() {
+        let chat_prompt =
+            ChatTemplate::from_messages(chats!(Human = "Summarize: {doc} ({source})",))
+                .unwrap()
+                .with_normalizer("doc", Normalizer::StripControl)
+                .with_normalizer("doc", Normalizer::CollapseWhitespace);
+
+        let messages = chat_prompt
+            .format_messages(&vars!(doc = "  first\u{7}\n\n  second  ", source = " web "))
+            .unwrap();
+        assert_eq!(messages[0].content(), "Summarize: first second ( web )");
+
+        let json = serde_json::to_string(&chat_prompt).unwrap();
+        assert!(json.contains(r#""normalizers":{"doc":["strip_control","collapse_whitespace"]}"#));
+    }
+
+    #[test]
+    fn test_default_normalizers_clean_windows_transcripts() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(Human = "Transcript:\n{transcript}",))
+            .unwrap()
+            .with_default_normalizer(Normalizer::StripBom)
+            .with_default_normalizer(Normalizer::NormalizeNewlines)
+            .with_default_normalizer(Normalizer::RejectControl);
+
+        let messages = chat_prompt
+            .format_messages(&vars!(transcript = "\u{feff}hi\r\nthere\r\n"))
+            .unwrap();
+        assert_eq!(messages[0].content(), "Transcript:\nhi\nthere\n");
+
+        assert!(matches!(
+            chat_prompt.format_messages(&vars!(transcript = "bad\u{1b}[0m")),
+            Err(TemplateError::InvalidVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_embedded_tests() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are a {tone} assistant.",
+            Human = "{question}",
+        ))
+        .unwrap()
+        .with_tests(vec![
+            PromptTestCase {
+                name: Some("friendly".to_string()),
+                variables: HashMap::from([
+                    ("tone".to_string(), "friendly".to_string()),
+                    ("question".to_string(), "Hi?".to_string()),
+                ]),
+                expect_contains: vec!["friendly assistant".to_string()],
+                expect_matches: vec![r"(?m)^human: Hi\?$".to_string()],
+                ..Default::default()
+            },
+            PromptTestCase {
+                variables: HashMap::from([("tone".to_string(), "terse".to_string())]),
+                ..Default::default()
+            },
+            PromptTestCase {
+                variables: HashMap::from([
+                    ("tone".to_string(), "rude".to_string()),
+                    ("question".to_string(), "Why?".to_string()),
+                ]),
+                expect_not_contains: vec!["rude".to_string()],
+                ..Default::default()
+            },
+        ]);
+
+        let report = chat_prompt.run_embedded_tests();
+
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].test, "test #2");
+        assert!(report.failures[0].reason.starts_with("render failed"));
+        assert_eq!(report.failures[1].test, "test #3");
+        assert_eq!(
+            report.failures[1].reason,
+            "expected output not to contain \"rude\""
+        );
+    }
+
+    #[test]
+    fn test_run_embedded_tests_without_tests() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(System = "Hello.")).unwrap();
+        let report = chat_prompt.run_embedded_tests();
+
+        assert!(report.is_success());
+        assert_eq!(report.passed, 0);
+    }
+
     #[test]
     fn test_empty_templates() {
         let templates = chats!();
@@ -538,6 +1982,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_merges_metadata() {
+        let first = ChatTemplate::from_messages(chats!(System = "Be brief.", Human = "{a}"))
+            .unwrap()
+            .with_priority(1, Priority::Low)
+            .with_version("v1");
+        let second = ChatTemplate::from_messages(chats!(Human = "{b}"))
+            .unwrap()
+            .with_priority(0, Priority::Critical)
+            .with_version("v2")
+            .with_strict_placeholders()
+            .with_alias("question", "b");
+
+        let combined = first + second;
+        assert_eq!(
+            combined.priorities().iter().collect::<Vec<_>>(),
+            vec![(&1, &Priority::Low), (&2, &Priority::Critical)]
+        );
+        assert_eq!(combined.version(), Some("v1"));
+        assert!(combined.strict_placeholders());
+        assert_eq!(combined.aliases()["question"], "b");
+
+        let json = serde_json::to_value(&combined).unwrap();
+        assert_eq!(json["version"], "v1");
+        assert_eq!(json["priorities"]["2"], json!("critical"));
+        let restored: ChatTemplate = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.priorities(), combined.priorities());
+        assert_eq!(restored.aliases(), combined.aliases());
+    }
+
     #[test]
     fn test_format_with_basic_messages() {
         let templates = chats!(
@@ -766,7 +2240,7 @@ human: Thanks, AI.";
 
     #[test]
     fn test_to_variables_map_with_empty_template() {
-        let chat_template = ChatTemplate { messages: vec![] };
+        let chat_template = ChatTemplate::default();
 
         let variables = chat_template.to_variables_map();
         let expected: HashMap<&str, &str> = HashMap::new();
@@ -865,6 +2339,7 @@ human: What is 4+4?";
         assert_eq!(chat_template.messages.len(), 2);
     }
 
+    #[cfg(feature = "toml")]
     #[test]
     fn test_chat_template_try_from_valid_toml() {
         let toml_data = r#"
@@ -921,4 +2396,102 @@ human: What is 4+4?";
             panic!("Expected TemplateError::MalformedTemplate");
         }
     }
+
+    #[test]
+    fn test_unchecked_chat_template_validates_later() {
+        let mut chat_prompt = ChatTemplate::from_messages_unchecked(chats!(
+            System = "You are {persona}.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        assert!(!chat_prompt.is_validated());
+
+        let variables = vars!(persona = "a pirate", question = "Where is the gold?");
+        let deferred = chat_prompt.format_messages(&variables).unwrap();
+
+        chat_prompt.validate().unwrap();
+        assert!(chat_prompt.is_validated());
+        assert_eq!(chat_prompt.format_messages(&variables).unwrap(), deferred);
+
+        let mut broken =
+            ChatTemplate::from_messages_unchecked(chats!(Human = "{question}}")).unwrap();
+        assert!(broken.validate().is_err());
+
+        let mut conflicting = ChatTemplate::from_messages_unchecked(chats!(
+            Human = "{history}",
+            Placeholder = "{history}",
+        ))
+        .unwrap();
+        assert!(matches!(
+            conflicting.validate(),
+            Err(TemplateError::ConflictingVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_strict_placeholders() {
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You are {persona}. Reply with \\{answer\\}.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let nested = vars!(persona = "a {tone} assistant", question = "Hi?");
+        let resolved = vars!(persona = "a helpful assistant", question = "Hi?");
+
+        assert!(chat_prompt.format_messages(&nested).is_ok());
+
+        let strict = chat_prompt.with_strict_placeholders();
+        let messages = strict.format_messages(&resolved).unwrap();
+        assert_eq!(
+            messages[0].content(),
+            "You are a helpful assistant. Reply with {answer}."
+        );
+        assert_eq!(
+            strict.format_messages(&nested).unwrap_err().to_string(),
+            "Unresolved placeholder: '{tone}' was left in the rendered output"
+        );
+
+        let mut literal = strict.clone();
+        literal.messages.push(MessageLike::BaseMessage(
+            Ai.to_message("Hello {{ user.name }}!").unwrap(),
+        ));
+        assert!(matches!(
+            literal.format_messages(&resolved),
+            Err(TemplateError::UnresolvedPlaceholder(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_options_apply_to_messages() {
+        use crate::MissingVariables;
+
+        let chat_prompt = ChatTemplate::from_messages(chats!(
+            System = "You support {product}.",
+            Placeholder = "{history}",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let variables = vars!(question = "Where is my order?");
+        assert!(matches!(
+            chat_prompt.format_messages(&variables),
+            Err(TemplateError::MissingVariable(_))
+        ));
+
+        let lenient = chat_prompt
+            .clone()
+            .with_format_options(FormatOptions::lenient());
+        let messages = lenient.format_messages(&variables).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content(), "You support .");
+
+        let keep = chat_prompt.with_format_options(
+            FormatOptions::new().missing_vars(MissingVariables::KeepPlaceholder),
+        );
+        let output = keep.render_with_source_map(&variables).unwrap();
+        assert_eq!(output.messages[0].content(), "You support {product}.");
+        assert_eq!(
+            serde_json::to_value(&keep).unwrap()["format_options"],
+            json!({ "missing_vars": "keep_placeholder" })
+        );
+    }
 }
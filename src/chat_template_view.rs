@@ -0,0 +1,150 @@
+use std::{ops::Range, sync::Arc};
+
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, Formattable, MessageLike, TemplateError, Variables};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChatTemplateView<'a> {
+    template: &'a ChatTemplate,
+    messages: &'a [MessageLike],
+    start: usize,
+}
+
+impl<'a> ChatTemplateView<'a> {
+    pub(crate) fn new(
+        template: &'a ChatTemplate,
+        range: Range<usize>,
+    ) -> Result<Self, TemplateError> {
+        let messages = template.messages.get(range.clone()).ok_or_else(|| {
+            TemplateError::MalformedTemplate(format!(
+                "Window {}..{} is out of bounds for a template with {} messages.",
+                range.start,
+                range.end,
+                template.messages.len()
+            ))
+        })?;
+
+        Ok(Self {
+            template,
+            messages,
+            start: range.start,
+        })
+    }
+
+    pub fn messages(&self) -> &'a [MessageLike] {
+        self.messages
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.messages.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn format_messages(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.template
+            .format_slice(self.messages, &variables.as_map())
+    }
+
+    pub fn to_template(&self) -> ChatTemplate {
+        ChatTemplate {
+            messages: self.messages.to_vec(),
+            meta: self.template.meta.window(self.range()),
+        }
+    }
+}
+
+impl Formattable for ChatTemplateView<'_> {
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError> {
+        let formatted_messages = self.format_messages(variables)?;
+        Ok(self.template.profile_transcript(&formatted_messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Ai, Human, System},
+    };
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(
+            System = "You are a support agent for {company}.",
+            Human = "Hi!",
+            Ai = "Hello! How can I help?",
+            Human = "{question}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_split_at_renders_prefix_and_suffix() {
+        let template = template();
+        let (prefix, suffix) = template.split_at(3).unwrap();
+        let variables = vars!(company = "Acme", question = "Where is my order?");
+
+        assert_eq!(prefix.len(), 3);
+        assert_eq!(suffix.range(), 3..4);
+
+        let mut combined = prefix.format_messages(&variables).unwrap();
+        combined.extend(suffix.format_messages(&variables).unwrap());
+        assert_eq!(combined, template.format_messages(&variables).unwrap());
+
+        assert_eq!(
+            suffix.format(&variables).unwrap(),
+            "human: Where is my order?"
+        );
+    }
+
+    #[test]
+    fn test_window_shares_messages() {
+        let template = template();
+        let window = template.window(1..3).unwrap();
+
+        assert!(std::ptr::eq(window.messages(), &template.messages[1..3]));
+        assert_eq!(
+            window.format(&vars!()).unwrap(),
+            "human: Hi!\nai: Hello! How can I help?"
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds_window() {
+        let template = template();
+        assert!(matches!(
+            template.window(2..9),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+        assert!(template.split_at(5).is_err());
+
+        let (all, empty) = template.split_at(4).unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_to_template_keeps_settings() {
+        let template = template().with_limits(crate::RenderLimits::new().max_total_bytes(10));
+        let owned = template.window(3..4).unwrap().to_template();
+
+        assert_eq!(owned.messages.len(), 1);
+        assert_eq!(owned.limits(), template.limits());
+        assert!(owned.format(&vars!(question = "Short?")).is_ok());
+        assert!(matches!(
+            owned.format(&vars!(question = "A much longer question?")),
+            Err(TemplateError::LimitExceeded(_))
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    braces::{scan, BraceKind},
+    placeholder::is_valid_identifier,
+    TemplateError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Directive<'a> {
+    If(&'a str),
+    Else,
+    End,
+}
+
+pub(crate) fn parse_directive(inner: &str, open_blocks: usize) -> Option<Directive<'_>> {
+    let inner = inner.trim();
+    if let Some(var) = inner
+        .strip_prefix("if")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+    {
+        let var = var.trim();
+        return is_valid_identifier(var).then_some(Directive::If(var));
+    }
+
+    match inner {
+        "else" if open_blocks > 0 => Some(Directive::Else),
+        "end" if open_blocks > 0 => Some(Directive::End),
+        _ => None,
+    }
+}
+
+pub(crate) fn check_conditionals(s: &str) -> Result<(), TemplateError> {
+    let mut open: Vec<(&str, bool)> = Vec::new();
+
+    for token in scan(s) {
+        if token.kind != BraceKind::Single {
+            continue;
+        }
+        match parse_directive(token.inner(s), open.len()) {
+            Some(Directive::If(var)) => open.push((var, false)),
+            Some(Directive::Else) => {
+                let (var, has_else) = open.last_mut().expect("else is only parsed inside a block");
+                if *has_else {
+                    return Err(TemplateError::MalformedTemplate(format!(
+                        "Duplicate '{{else}}' in '{{if {}}}' block",
+                        var
+                    )));
+                }
+                *has_else = true;
+            }
+            Some(Directive::End) => {
+                open.pop();
+            }
+            None => {}
+        }
+    }
+
+    match open.last() {
+        Some((var, _)) => Err(TemplateError::MalformedTemplate(format!(
+            "Unclosed '{{if {}}}' block",
+            var
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn is_truthy(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !value.is_empty())
+}
+
+struct Frame {
+    parent: bool,
+    condition: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Branches {
+    frames: Vec<Frame>,
+}
+
+impl Branches {
+    pub(crate) fn is_rendering(&self) -> bool {
+        self.frames
+            .last()
+            .map_or(true, |frame| frame.parent && frame.condition)
+    }
+
+    pub(crate) fn step(&mut self, inner: &str, truthy: impl Fn(&str) -> bool) -> bool {
+        match parse_directive(inner, self.frames.len()) {
+            Some(Directive::If(var)) => {
+                let parent = self.is_rendering();
+                self.frames.push(Frame {
+                    parent,
+                    condition: parent && truthy(var),
+                });
+            }
+            Some(Directive::Else) => {
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.condition = !frame.condition;
+                }
+            }
+            Some(Directive::End) => {
+                self.frames.pop();
+            }
+            None => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive() {
+        assert_eq!(
+            parse_directive("if premium", 0),
+            Some(Directive::If("premium"))
+        );
+        assert_eq!(
+            parse_directive(" if  premium ", 0),
+            Some(Directive::If("premium"))
+        );
+        assert_eq!(parse_directive("if", 0), None);
+        assert_eq!(parse_directive("iffy", 0), None);
+        assert_eq!(parse_directive("if 1x", 0), None);
+        assert_eq!(parse_directive("else", 0), None);
+        assert_eq!(parse_directive("end", 0), None);
+        assert_eq!(parse_directive("else", 1), Some(Directive::Else));
+        assert_eq!(parse_directive("end", 1), Some(Directive::End));
+    }
+
+    #[test]
+    fn test_check_conditionals() {
+        assert!(check_conditionals("{if a}x{else}y{end} {end}").is_ok());
+        assert!(check_conditionals("{if a}{if b}x{end}{end}").is_ok());
+        assert!(matches!(
+            check_conditionals("{if a}{if b}x{end}"),
+            Err(TemplateError::MalformedTemplate(msg)) if msg == "Unclosed '{if a}' block"
+        ));
+        assert!(matches!(
+            check_conditionals("{if a}x{else}y{else}z{end}"),
+            Err(TemplateError::MalformedTemplate(msg)) if msg.starts_with("Duplicate '{else}'")
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, TemplateError};
+
+const BUILTIN_CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4o-mini", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3-mini", 200_000),
+    ("claude-3-5-sonnet", 200_000),
+    ("claude-3-5-haiku", 200_000),
+    ("claude-3-opus", 200_000),
+    ("llama-3.1", 128_000),
+    ("llama-3", 8_192),
+    ("mistral-large", 128_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini-1.5-flash", 1_048_576),
+];
+
+lazy_static! {
+    static ref CONTEXT_WINDOWS: RwLock<ContextWindows> = RwLock::new(ContextWindows::default());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextWindows {
+    windows: BTreeMap<String, usize>,
+}
+
+impl Default for ContextWindows {
+    fn default() -> Self {
+        Self {
+            windows: BUILTIN_CONTEXT_WINDOWS
+                .iter()
+                .map(|(model, tokens)| (model.to_string(), *tokens))
+                .collect(),
+        }
+    }
+}
+
+impl ContextWindows {
+    pub fn empty() -> Self {
+        Self {
+            windows: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>, tokens: usize) -> Self {
+        self.insert(model, tokens);
+        self
+    }
+
+    pub fn insert(&mut self, model: impl Into<String>, tokens: usize) {
+        self.windows.insert(model.into(), tokens);
+    }
+
+    pub fn get(&self, model: &str) -> Option<usize> {
+        if let Some(tokens) = self.windows.get(model) {
+            return Some(*tokens);
+        }
+
+        self.windows
+            .iter()
+            .filter(|(name, _)| {
+                model
+                    .strip_prefix(name.as_str())
+                    .is_some_and(|rest| rest.starts_with(['-', ':', '@']))
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, tokens)| *tokens)
+    }
+}
+
+pub fn context_window(model: &str) -> Option<usize> {
+    CONTEXT_WINDOWS
+        .read()
+        .ok()
+        .and_then(|windows| windows.get(model))
+}
+
+pub fn set_context_window(model: impl Into<String>, tokens: usize) {
+    if let Ok(mut windows) = CONTEXT_WINDOWS.write() {
+        windows.insert(model, tokens);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextFit {
+    pub model: String,
+    pub context_window: usize,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl ContextFit {
+    pub fn fits(&self) -> bool {
+        self.prompt_tokens + self.completion_tokens <= self.context_window
+    }
+
+    pub fn remaining(&self) -> isize {
+        self.context_window as isize - (self.prompt_tokens + self.completion_tokens) as isize
+    }
+}
+
+impl ChatTemplate {
+    pub fn context_fit(
+        &self,
+        model: &str,
+        variables: &HashMap<&str, &str>,
+        completion_tokens: usize,
+    ) -> Result<ContextFit, TemplateError> {
+        let context_window = context_window(model).ok_or_else(|| {
+            TemplateError::UnsupportedFormat(format!(
+                "No context window is known for model '{}'",
+                model
+            ))
+        })?;
+        let prompt_tokens = self
+            .counter()
+            .count_messages(&self.format_messages(variables)?);
+
+        Ok(ContextFit {
+            model: model.to_string(),
+            context_window,
+            prompt_tokens,
+            completion_tokens,
+        })
+    }
+
+    pub fn fits_model(
+        &self,
+        model: &str,
+        variables: &HashMap<&str, &str>,
+        completion_tokens: usize,
+    ) -> Result<bool, TemplateError> {
+        Ok(self
+            .context_fit(model, variables, completion_tokens)?
+            .fits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, System},
+    };
+
+    #[test]
+    fn test_lookup_matches_exact_then_longest_prefix() {
+        let windows = ContextWindows::default();
+        assert_eq!(windows.get("gpt-4o-mini"), Some(128_000));
+        assert_eq!(windows.get("gpt-4-0613"), Some(8_192));
+        assert_eq!(windows.get("gpt-4o-2024-08-06"), Some(128_000));
+        assert_eq!(windows.get("claude-3-5-sonnet-20241022"), Some(200_000));
+        assert_eq!(windows.get("gpt-4oops"), None);
+        assert_eq!(ContextWindows::empty().get("gpt-4o"), None);
+        assert_eq!(
+            ContextWindows::empty()
+                .with_model("local", 4_096)
+                .get("local:q4"),
+            Some(4_096)
+        );
+    }
+
+    #[test]
+    fn test_fits_model() {
+        set_context_window("tiny-test-model", 40);
+        let template =
+            ChatTemplate::from_messages(chats!(System = "Be concise.", Human = "{question}"))
+                .unwrap();
+        let variables = vars!(question = "What is the capital of France?");
+
+        let fit = template
+            .context_fit("tiny-test-model", &variables, 10)
+            .unwrap();
+        assert_eq!(fit.prompt_tokens, 11);
+        assert_eq!(fit.remaining(), 19);
+        assert!(fit.fits());
+        assert!(!template
+            .fits_model("tiny-test-model", &variables, 30)
+            .unwrap());
+        assert!(template
+            .fits_model("gpt-4o-mini", &variables, 4_096)
+            .unwrap());
+
+        assert!(matches!(
+            template.fits_model("unknown-model", &variables, 10),
+            Err(TemplateError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, Role, Template, TemplateError};
+
+pub type MockResponder = Arc<dyn Fn(&[Arc<MessageEnum>]) -> String + Send + Sync>;
+
+#[derive(Clone)]
+pub enum ScriptTurn {
+    User(Box<Template>),
+    Assistant(String),
+    Mock(MockResponder),
+}
+
+impl fmt::Debug for ScriptTurn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptTurn::User(template) => f.debug_tuple("User").field(template).finish(),
+            ScriptTurn::Assistant(reply) => f.debug_tuple("Assistant").field(reply).finish(),
+            ScriptTurn::Mock(_) => f.write_str("Mock(..)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptStep {
+    pub turn: usize,
+    pub role: Role,
+    pub messages: Vec<Arc<MessageEnum>>,
+}
+
+impl ScriptStep {
+    pub fn last_message(&self) -> Option<&Arc<MessageEnum>> {
+        self.messages.last()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConversationScript {
+    preamble: ChatTemplate,
+    turns: Vec<ScriptTurn>,
+}
+
+impl ConversationScript {
+    pub fn new(preamble: ChatTemplate) -> Self {
+        Self {
+            preamble,
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn user(mut self, template: &str) -> Result<Self, TemplateError> {
+        self.turns
+            .push(ScriptTurn::User(Box::new(Template::new(template)?)));
+        Ok(self)
+    }
+
+    pub fn assistant(mut self, reply: impl Into<String>) -> Self {
+        self.turns.push(ScriptTurn::Assistant(reply.into()));
+        self
+    }
+
+    pub fn mock_assistant<F>(mut self, responder: F) -> Self
+    where
+        F: Fn(&[Arc<MessageEnum>]) -> String + Send + Sync + 'static,
+    {
+        self.turns.push(ScriptTurn::Mock(Arc::new(responder)));
+        self
+    }
+
+    pub fn turns(&self) -> &[ScriptTurn] {
+        &self.turns
+    }
+
+    pub fn run(&self, variables: &HashMap<&str, &str>) -> Result<Vec<ScriptStep>, TemplateError> {
+        let mut messages = self.preamble.format_messages(variables)?;
+        let mut steps = Vec::with_capacity(self.turns.len());
+
+        for (turn, script_turn) in self.turns.iter().enumerate() {
+            let (role, content) = match script_turn {
+                ScriptTurn::User(template) => (Role::Human, template.format(variables)?),
+                ScriptTurn::Assistant(reply) => (Role::Ai, reply.clone()),
+                ScriptTurn::Mock(responder) => (Role::Ai, responder(&messages)),
+            };
+
+            messages.push(role.to_message(&content)?);
+            steps.push(ScriptStep {
+                turn,
+                role,
+                messages: messages.clone(),
+            });
+        }
+
+        Ok(steps)
+    }
+
+    pub fn final_messages(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        match self.run(variables)?.pop() {
+            Some(step) => Ok(step.messages),
+            None => self.preamble.format_messages(variables),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chats, vars, Role::System};
+    use messageforge::BaseMessage;
+
+    fn script() -> ConversationScript {
+        let preamble =
+            ChatTemplate::from_messages(chats!(System = "You are a {persona} assistant.")).unwrap();
+
+        ConversationScript::new(preamble)
+            .user("Book a table for {party} people.")
+            .unwrap()
+            .assistant("Which evening works for you?")
+            .user("Friday at {time}.")
+            .unwrap()
+            .mock_assistant(|messages| format!("Confirmed after {} messages.", messages.len()))
+    }
+
+    #[test]
+    fn test_run_renders_state_at_each_step() {
+        let steps = script()
+            .run(&vars!(persona = "booking", party = "4", time = "7pm"))
+            .unwrap();
+
+        assert_eq!(steps.len(), 4);
+        let lengths: Vec<usize> = steps.iter().map(|step| step.messages.len()).collect();
+        assert_eq!(lengths, vec![2, 3, 4, 5]);
+
+        assert_eq!(steps[0].role, Role::Human);
+        assert_eq!(
+            steps[0].last_message().unwrap().content(),
+            "Book a table for 4 people."
+        );
+        assert_eq!(steps[2].last_message().unwrap().content(), "Friday at 7pm.");
+        assert_eq!(steps[3].role, Role::Ai);
+        assert_eq!(
+            steps[3].last_message().unwrap().content(),
+            "Confirmed after 4 messages."
+        );
+        assert_eq!(
+            steps[3].messages[0].content(),
+            "You are a booking assistant."
+        );
+    }
+
+    #[test]
+    fn test_run_is_deterministic() {
+        let variables = vars!(persona = "booking", party = "2", time = "8pm");
+        assert_eq!(
+            script().final_messages(&variables).unwrap(),
+            script().final_messages(&variables).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_missing_variable_fails_the_run() {
+        let result = script().run(&vars!(persona = "booking", party = "2"));
+        assert!(matches!(result, Err(TemplateError::MissingVariable(_))));
+    }
+
+    #[test]
+    fn test_default_script_has_no_preamble() {
+        let script = ConversationScript::default().assistant("Hello!");
+        let messages = script.final_messages(&vars!()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content(), "Hello!");
+    }
+}
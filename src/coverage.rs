@@ -0,0 +1,315 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    braces::{scan, BraceKind},
+    filters::split_filters,
+    mustache_expr::{parse_mustache_expr, MustacheExpr, MustacheTag},
+    placeholder::is_valid_identifier,
+    Templatable, Template,
+};
+
+const INVERTED_HELPERS: &[&str] = &["unless"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionCoverage {
+    pub label: String,
+    pub span: Range<usize>,
+    pub hits: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageFailure {
+    pub case: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub cases: usize,
+    pub variables: BTreeMap<String, usize>,
+    pub defaults: BTreeMap<String, usize>,
+    pub sections: Vec<SectionCoverage>,
+    pub failures: Vec<CoverageFailure>,
+}
+
+impl CoverageReport {
+    pub fn unexercised_variables(&self) -> Vec<&str> {
+        self.variables
+            .iter()
+            .filter(|(_, &hits)| hits == 0)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn triggered_defaults(&self) -> Vec<&str> {
+        self.defaults
+            .iter()
+            .filter(|(_, &hits)| hits > 0)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn uncovered_sections(&self) -> Vec<&SectionCoverage> {
+        self.sections
+            .iter()
+            .filter(|section| section.hits == 0)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+            && self.unexercised_variables().is_empty()
+            && self.uncovered_sections().is_empty()
+    }
+}
+
+impl Template {
+    pub fn coverage(&self, corpus: &[HashMap<&str, &str>]) -> CoverageReport {
+        let source = self.template();
+        let mut report = CoverageReport {
+            cases: corpus.len(),
+            variables: self
+                .input_variables()
+                .into_iter()
+                .map(|name| (name, 0))
+                .collect(),
+            defaults: self
+                .partial_vars()
+                .keys()
+                .map(|name| (name.clone(), 0))
+                .collect(),
+            sections: sections(source),
+            failures: Vec::new(),
+        };
+
+        for (case, vars) in corpus.iter().enumerate() {
+            if let Err(e) = self.format(vars) {
+                report.failures.push(CoverageFailure {
+                    case,
+                    error: e.to_string(),
+                });
+            }
+
+            let lookup = |name: &str| {
+                vars.get(name)
+                    .copied()
+                    .or_else(|| self.partial_vars().get(name).map(String::as_str))
+            };
+            let walk = walk(source, lookup);
+
+            for (section, rendered) in report.sections.iter_mut().zip(walk.sections) {
+                section.hits += usize::from(rendered);
+            }
+
+            for name in walk.variables {
+                if let Some(hits) = report.variables.get_mut(name) {
+                    *hits += 1;
+                }
+                if !vars.contains_key(name) {
+                    if let Some(hits) = report.defaults.get_mut(name) {
+                        *hits += 1;
+                    }
+                }
+            }
+        }
+
+        report
+    }
+}
+
+struct Walk<'a> {
+    sections: Vec<bool>,
+    variables: Vec<&'a str>,
+}
+
+struct Frame {
+    condition: bool,
+    parent: bool,
+    context: bool,
+}
+
+fn sections(source: &str) -> Vec<SectionCoverage> {
+    let mut sections = Vec::new();
+    let mut open = Vec::new();
+
+    for (slot, expr) in section_tags(source) {
+        let label = match expr.tag {
+            MustacheTag::Open => {
+                let label = source[slot.start + 2..slot.end - 2].trim().to_string();
+                open.push(label.clone());
+                label
+            }
+            MustacheTag::Else => match open.last() {
+                Some(parent) => format!("else ({})", parent),
+                None => "else".to_string(),
+            },
+            _ => {
+                open.pop();
+                continue;
+            }
+        };
+
+        sections.push(SectionCoverage {
+            label,
+            span: slot,
+            hits: 0,
+        });
+    }
+
+    sections
+}
+
+fn walk<'a, 'v>(source: &'a str, lookup: impl Fn(&str) -> Option<&'v str>) -> Walk<'a> {
+    let mut result = Walk {
+        sections: Vec::new(),
+        variables: Vec::new(),
+    };
+    let mut stack: Vec<Frame> = Vec::new();
+    let truthy = |name: &str| lookup(name).is_some_and(|value| !value.is_empty());
+
+    for slot in scan(source) {
+        let rendering = stack
+            .last()
+            .map_or(true, |frame| frame.parent && frame.condition);
+        let top_level = !stack.iter().any(|frame| frame.context);
+        let inner = slot.inner(source);
+
+        let expr = match slot.kind {
+            BraceKind::Single => {
+                let (name, _) = split_filters(inner);
+                if rendering && is_valid_identifier(name) {
+                    result.variables.push(name);
+                }
+                continue;
+            }
+            BraceKind::Double => match parse_mustache_expr(inner) {
+                Some(expr) => expr,
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        if rendering && top_level {
+            result.variables.extend(&expr.variables);
+        }
+
+        match expr.tag {
+            MustacheTag::Open => {
+                let subject = expr.variables.first().copied();
+                let inverted =
+                    inner.trim_start().starts_with('^') || INVERTED_HELPERS.contains(&expr.name);
+                let condition = match subject {
+                    Some(name) if top_level => truthy(name) != inverted,
+                    _ => true,
+                };
+                result.sections.push(rendering && condition);
+                stack.push(Frame {
+                    condition,
+                    parent: rendering,
+                    context: expr.changes_context(),
+                });
+            }
+            MustacheTag::Else => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.condition = !frame.condition;
+                    result.sections.push(frame.parent && frame.condition);
+                } else {
+                    result.sections.push(false);
+                }
+            }
+            MustacheTag::Close => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+fn section_tags(source: &str) -> impl Iterator<Item = (Range<usize>, MustacheExpr<'_>)> {
+    scan(source)
+        .into_iter()
+        .filter(|slot| slot.kind == BraceKind::Double)
+        .filter_map(move |slot| {
+            parse_mustache_expr(slot.inner(source))
+                .filter(|expr| {
+                    matches!(
+                        expr.tag,
+                        MustacheTag::Open | MustacheTag::Else | MustacheTag::Close
+                    )
+                })
+                .map(|expr| (slot.span, expr))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars;
+
+    #[test]
+    fn test_fmtstring_variable_coverage() {
+        let mut template = Template::new("Hi {name}, your {product} shipped.").unwrap();
+        template.partial("product", "order");
+
+        let report = template.coverage(&[
+            vars!(name = "Ada"),
+            vars!(name = "Grace", product = "laptop"),
+        ]);
+
+        assert_eq!(report.cases, 2);
+        assert_eq!(report.variables["name"], 2);
+        assert_eq!(report.variables["product"], 2);
+        assert_eq!(report.defaults["product"], 1);
+        assert_eq!(report.triggered_defaults(), vec!["product"]);
+        assert!(report.failures.is_empty());
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_failures_are_recorded_per_case() {
+        let template = Template::new("Hi {name}.").unwrap();
+        let report = template.coverage(&[vars!(name = "Ada"), vars!()]);
+
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].case, 1);
+        assert!(!report.is_complete());
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_mustache_sections_and_branches() {
+        let template = Template::new(
+            "{{#if urgent}}Reply now about {{topic}}.{{else}}Reply soon.{{/if}}{{#unless notes}} No notes.{{/unless}}",
+        )
+        .unwrap();
+
+        let report = template.coverage(&[
+            vars!(urgent = "", topic = "billing", notes = "n/a"),
+            vars!(urgent = "", topic = "refunds", notes = "call back"),
+        ]);
+
+        let labels: Vec<(&str, usize)> = report
+            .sections
+            .iter()
+            .map(|section| (section.label.as_str(), section.hits))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("#if urgent", 0),
+                ("else (#if urgent)", 2),
+                ("#unless notes", 0)
+            ]
+        );
+        assert_eq!(report.uncovered_sections().len(), 2);
+        assert_eq!(report.unexercised_variables(), vec!["topic"]);
+        assert_eq!(report.variables["urgent"], 2);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{ChatTemplate, MessageLike, Role, Template, TemplateError};
+
+pub const DRAFT_VARIABLE: &str = "draft";
+pub const CRITIQUE_VARIABLE: &str = "critique";
+
+#[derive(Debug, Clone)]
+pub struct CritiquePair {
+    pub critique: ChatTemplate,
+    pub revise: ChatTemplate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    critique_instructions: String,
+    revision_instructions: String,
+}
+
+impl Default for Reflection {
+    fn default() -> Self {
+        Self {
+            critique_instructions: "Critique the response above. List concrete errors, omissions \
+                                    and unclear passages. Do not rewrite it."
+                .to_string(),
+            revision_instructions: "Revise your response to address every point in the critique. \
+                                    Reply with the revised response only."
+                .to_string(),
+        }
+    }
+}
+
+impl Reflection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_critique_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.critique_instructions = instructions.into();
+        self
+    }
+
+    pub fn with_revision_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.revision_instructions = instructions.into();
+        self
+    }
+
+    pub fn derive(&self, task: &ChatTemplate) -> Result<CritiquePair, TemplateError> {
+        let variables = task.input_variables();
+        for reserved in [DRAFT_VARIABLE, CRITIQUE_VARIABLE] {
+            if variables.contains(reserved) {
+                return Err(TemplateError::MalformedTemplate(format!(
+                    "Task template already uses the reserved variable '{}'",
+                    reserved
+                )));
+            }
+        }
+
+        let mut critique = ChatTemplate {
+            messages: task.messages.clone(),
+            meta: task.meta.window(0..task.messages.len()),
+        };
+        critique.messages.push(draft_message()?);
+        let mut revise = critique.clone();
+
+        critique.messages.push(MessageLike::role_prompt_template(
+            Role::Human,
+            Template::new("{critique_instructions}")?
+                .with_partial("critique_instructions", &self.critique_instructions),
+        ));
+        revise.messages.push(MessageLike::role_prompt_template(
+            Role::Human,
+            Template::new(&format!(
+                "Critique:\n{{{}}}\n\n{{revision_instructions}}",
+                CRITIQUE_VARIABLE
+            ))?
+            .with_partial("revision_instructions", &self.revision_instructions),
+        ));
+
+        Ok(CritiquePair { critique, revise })
+    }
+}
+
+impl ChatTemplate {
+    pub fn critique_and_revise(&self) -> Result<CritiquePair, TemplateError> {
+        Reflection::default().derive(self)
+    }
+}
+
+fn draft_message() -> Result<MessageLike, TemplateError> {
+    Ok(MessageLike::role_prompt_template(
+        Role::Ai,
+        Template::new(&format!("{{{}}}", DRAFT_VARIABLE))?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, System},
+    };
+
+    fn task() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(
+            System = "You write release notes.",
+            Human = "Summarize the changes in {version}.",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_critique_and_revise_templates() {
+        let pair = task().critique_and_revise().unwrap();
+
+        let critique = pair
+            .critique
+            .format_messages(&vars!(version = "1.2", draft = "Fixed bugs."))
+            .unwrap();
+        assert_eq!(critique.len(), 4);
+        assert_eq!(critique[2].content(), "Fixed bugs.");
+        assert!(critique[3]
+            .content()
+            .starts_with("Critique the response above."));
+
+        let revise = pair
+            .revise
+            .format_messages(&vars!(
+                version = "1.2",
+                draft = "Fixed bugs.",
+                critique = "Too vague."
+            ))
+            .unwrap();
+        assert_eq!(revise.len(), 4);
+        assert!(revise[3].content().starts_with("Critique:\nToo vague.\n\n"));
+
+        let required = pair.revise.input_variables().required;
+        assert!(required.contains("draft") && required.contains("critique"));
+        assert!(!pair.critique.input_variables().contains("critique"));
+    }
+
+    #[test]
+    fn test_custom_instructions_keep_braces_literal() {
+        let pair = Reflection::new()
+            .with_critique_instructions("Check the {tone}.")
+            .with_revision_instructions("Keep {braces} as written.")
+            .derive(&task())
+            .unwrap();
+
+        let variables = vars!(version = "2.0", draft = "d", critique = "c");
+        let critique = pair.critique.format_messages(&variables).unwrap();
+        assert_eq!(critique[3].content(), "Check the {tone}.");
+        let revise = pair.revise.format_messages(&variables).unwrap();
+        assert!(revise[3].content().ends_with("Keep {braces} as written."));
+    }
+
+    #[test]
+    fn test_instructions_can_be_overridden_at_render_time() {
+        let pair = task().critique_and_revise().unwrap();
+        let critique = pair
+            .critique
+            .format_messages(&vars!(
+                version = "1.0",
+                draft = "d",
+                critique_instructions = "Only check spelling."
+            ))
+            .unwrap();
+        assert_eq!(critique[3].content(), "Only check spelling.");
+    }
+
+    #[test]
+    fn test_reserved_variables_are_rejected() {
+        let task = ChatTemplate::from_messages(chats!(Human = "Improve {draft}.")).unwrap();
+        assert!(matches!(
+            task.critique_and_revise(),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, MessageLike, RegisteredPrompt, Template};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Defaults {
+    values: Arc<BTreeMap<String, String>>,
+}
+
+impl Defaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        Arc::make_mut(&mut self.values).insert(name.into(), value.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        Arc::make_mut(&mut self.values).remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub(crate) fn or(&self, fallback: &Defaults) -> Defaults {
+        if fallback.is_empty() {
+            return self.clone();
+        }
+        let mut layered = fallback.clone();
+        for (name, value) in self.iter() {
+            layered.set(name, value);
+        }
+        layered
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Defaults {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut defaults = Defaults::new();
+        for (name, value) in iter {
+            defaults.set(name, value);
+        }
+        defaults
+    }
+}
+
+impl ChatTemplate {
+    pub fn with_defaults(mut self, defaults: Defaults) -> Self {
+        for template in self.message_templates_mut() {
+            template.set_defaults(defaults.clone());
+        }
+        self
+    }
+
+    pub(crate) fn layer_defaults(&mut self, fallback: &Defaults) {
+        for template in self.message_templates_mut() {
+            template.layer_defaults(fallback);
+        }
+    }
+
+    fn message_templates_mut(&mut self) -> impl Iterator<Item = &mut Template> {
+        self.messages
+            .iter_mut()
+            .filter_map(|message| match message {
+                MessageLike::RolePromptTemplate(_, template) => Some(Arc::make_mut(template)),
+                _ => None,
+            })
+    }
+}
+
+impl RegisteredPrompt {
+    pub(crate) fn layer_defaults(&mut self, fallback: &Defaults) {
+        if fallback.is_empty() {
+            return;
+        }
+        match self {
+            RegisteredPrompt::Text(template) => Arc::make_mut(template).layer_defaults(fallback),
+            RegisteredPrompt::Chat(template) => Arc::make_mut(template).layer_defaults(fallback),
+            RegisteredPrompt::Persona(_) => {}
+        }
+    }
+}
+
+pub(crate) fn apply_defaults<'a>(
+    defaults: &'a Defaults,
+    variables: &mut HashMap<&'a str, &'a str>,
+) {
+    for (name, value) in defaults.iter() {
+        variables.entry(name).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chats, vars, ChatTemplate, PromptRegistry, Role::System, Template};
+    use messageforge::BaseMessage;
+
+    #[test]
+    fn test_defaults_value() {
+        let mut defaults = Defaults::new().with("company", "Acme");
+        defaults.set("company", "Globex").set("tone", "formal");
+        assert_eq!(defaults.get("company"), Some("Globex"));
+        assert_eq!(defaults.len(), 2);
+        assert_eq!(defaults.remove("tone").as_deref(), Some("formal"));
+        assert!(!defaults.contains("tone"));
+
+        let base: Defaults = [("company", "Acme"), ("region", "EU")]
+            .into_iter()
+            .collect();
+        let layered = defaults.or(&base);
+        assert_eq!(
+            layered.iter().collect::<Vec<_>>(),
+            vec![("company", "Globex"), ("region", "EU")]
+        );
+        assert_eq!(
+            serde_json::to_string(&layered).unwrap(),
+            r#"{"company":"Globex","region":"EU"}"#
+        );
+    }
+
+    #[test]
+    fn test_defaults_have_lowest_precedence() {
+        let defaults = Defaults::new()
+            .with("company", "Acme")
+            .with("tone", "formal");
+
+        let tmpl = Template::new("{company} ({tone}): {question}")
+            .unwrap()
+            .with_partial("tone", "friendly")
+            .with_defaults(defaults.clone());
+        assert_eq!(
+            tmpl.format(&vars!(question = "Hi?")).unwrap(),
+            "Acme (friendly): Hi?"
+        );
+        assert_eq!(
+            tmpl.format(&vars!(question = "Hi?", company = "Initech"))
+                .unwrap(),
+            "Initech (friendly): Hi?"
+        );
+        assert!(Template::new("{company}")
+            .unwrap()
+            .format(&vars!())
+            .is_err());
+
+        let aliased = Template::new("{company}")
+            .unwrap()
+            .with_alias("org", "company")
+            .with_defaults(defaults.clone());
+        assert_eq!(aliased.format(&vars!(org = "Globex")).unwrap(), "Globex");
+
+        let chat = ChatTemplate::from_messages(chats!(System = "You work for {company}."))
+            .unwrap()
+            .with_defaults(defaults);
+        let messages = chat.format_messages(&vars!()).unwrap();
+        assert_eq!(messages[0].content(), "You work for Acme.");
+    }
+
+    #[test]
+    fn test_registry_defaults() {
+        let registry = PromptRegistry::new()
+            .with(
+                "greeting",
+                Template::new("{company} says hi to {name}.")
+                    .unwrap()
+                    .with_defaults(Defaults::new().with("name", "you")),
+            )
+            .unwrap()
+            .with_defaults(Defaults::new().with("company", "Acme").with("name", "all"))
+            .with(
+                "chat",
+                ChatTemplate::from_messages(chats!(System = "You work for {company}.")).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            registry.format("greeting", &vars!()).unwrap(),
+            "Acme says hi to you."
+        );
+        assert_eq!(
+            registry.format_messages("chat", &vars!()).unwrap()[0].content(),
+            "You work for Acme."
+        );
+        assert_eq!(registry.defaults().get("company"), Some("Acme"));
+    }
+
+    #[test]
+    fn test_registry_defaults_are_applied_at_lookup() {
+        let registry = PromptRegistry::new()
+            .with("greeting", Template::new("{company}: {name}").unwrap())
+            .unwrap()
+            .with_defaults(Defaults::new().with("company", "Acme").with("name", "all"))
+            .with_defaults(Defaults::new().with("company", "Globex"));
+
+        assert_eq!(
+            registry.format("greeting", &vars!(name = "Ada")).unwrap(),
+            "Globex: Ada"
+        );
+        assert!(registry.format("greeting", &vars!()).is_err());
+        assert_eq!(
+            registry
+                .template("greeting")
+                .unwrap()
+                .defaults()
+                .get("company"),
+            Some("Globex")
+        );
+
+        match registry.remove("greeting") {
+            Some(RegisteredPrompt::Text(template)) => assert!(template.defaults().is_empty()),
+            other => panic!("expected a text template, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::TemplateError;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, name: impl Into<String>) -> &mut Self {
+        self.edges.entry(name.into()).or_default();
+        self
+    }
+
+    pub fn add_edge(&mut self, from: impl Into<String>, to: impl Into<String>) -> &mut Self {
+        self.edges.entry(from.into()).or_default().insert(to.into());
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.edges.contains_key(name)
+    }
+
+    pub fn nodes(&self) -> Vec<&str> {
+        self.edges.keys().map(String::as_str).collect()
+    }
+
+    pub fn dependencies(&self, name: &str) -> Vec<&str> {
+        self.edges
+            .get(name)
+            .map(|deps| deps.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        self.edges
+            .iter()
+            .filter(|(_, deps)| deps.contains(name))
+            .map(|(node, _)| node.as_str())
+            .collect()
+    }
+
+    pub fn transitive_dependencies(&self, name: &str) -> Result<Vec<String>, TemplateError> {
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::new();
+        self.visit(name, &mut Vec::new(), &mut visited, &mut order)?;
+        order.pop();
+        Ok(order)
+    }
+
+    pub fn load_order(&self) -> Result<Vec<String>, TemplateError> {
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::new();
+        for name in self.edges.keys() {
+            self.visit(name, &mut Vec::new(), &mut visited, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        match self.load_order() {
+            Err(TemplateError::DependencyCycle(cycle)) => Some(cycle),
+            _ => None,
+        }
+    }
+
+    fn visit(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+        visited: &mut BTreeSet<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), TemplateError> {
+        if let Some(start) = stack.iter().position(|node| node == name) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(TemplateError::DependencyCycle(cycle));
+        }
+        if visited.contains(name) {
+            return Ok(());
+        }
+
+        stack.push(name.to_string());
+        for dependency in self.edges.get(name).into_iter().flatten() {
+            self.visit(dependency, stack, visited, order)?;
+        }
+        stack.pop();
+
+        visited.insert(name.to_string());
+        order.push(name.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        graph
+            .add_edge("support", "preamble")
+            .add_edge("support", "tone")
+            .add_edge("preamble", "tone")
+            .add_edge("preamble", "safety")
+            .add_node("unused");
+        graph
+    }
+
+    #[test]
+    fn test_direct_dependencies_and_dependents() {
+        let graph = graph();
+        assert_eq!(graph.dependencies("support"), vec!["preamble", "tone"]);
+        assert_eq!(graph.dependents("tone"), vec!["preamble", "support"]);
+        assert!(graph.dependencies("missing").is_empty());
+    }
+
+    #[test]
+    fn test_transitive_dependencies_are_topological() {
+        let graph = graph();
+        assert_eq!(
+            graph.transitive_dependencies("support").unwrap(),
+            vec!["safety", "tone", "preamble"]
+        );
+        assert_eq!(
+            graph.load_order().unwrap(),
+            vec!["safety", "tone", "preamble", "support", "unused"]
+        );
+        assert!(graph.find_cycle().is_none());
+    }
+
+    #[test]
+    fn test_cycle_reports_full_path() {
+        let mut graph = graph();
+        graph
+            .add_edge("safety", "root")
+            .add_edge("root", "preamble");
+
+        let err = graph.load_order().unwrap_err();
+        assert!(err.matches(&TemplateError::DependencyCycle(vec![
+            "preamble".into(),
+            "safety".into(),
+            "root".into(),
+            "preamble".into(),
+        ])));
+        assert_eq!(
+            err.to_string(),
+            "Dependency cycle: preamble -> safety -> root -> preamble"
+        );
+    }
+}
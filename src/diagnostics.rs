@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    braces::{scan, BraceKind},
+    filters::{split_filters, FILTER_NAMES},
+    mustache_expr::{parse_mustache_expr, MustacheTag},
+    placeholder::is_valid_identifier,
+    Templatable, Template, TemplateFormat,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(source: &str, start: usize, end: usize) -> Self {
+        let before = &source[..start];
+        let line = before.matches('\n').count();
+        let column = before
+            .rsplit('\n')
+            .next()
+            .map_or(0, |line| line.chars().count());
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: String,
+    pub message: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableHover {
+    pub name: String,
+    pub span: Span,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub insert_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+    pub format: Option<TemplateFormat>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub hovers: Vec<VariableHover>,
+    pub completions: Vec<CompletionItem>,
+}
+
+impl DiagnosticReport {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+pub type VariableSchema = BTreeMap<String, String>;
+
+pub fn diagnose(source: &str, schema: Option<&VariableSchema>) -> DiagnosticReport {
+    let mut diagnostics = Vec::new();
+    let mut hovers = Vec::new();
+    let mut used = BTreeSet::new();
+
+    let format = match Template::new(source) {
+        Ok(template) => Some(template.template_format()),
+        Err(e) => {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "invalid-template".to_string(),
+                message: e.to_string(),
+                span: unbalanced_brace_span(source)
+                    .unwrap_or_else(|| Span::new(source, 0, source.len())),
+            });
+            None
+        }
+    };
+
+    for slot in scan(source).into_iter().filter(|token| token.is_slot()) {
+        let span = Span::new(source, slot.span.start, slot.span.end);
+        let (mut name, filters) = split_filters(slot.inner(source));
+
+        if slot.kind == BraceKind::Double {
+            match parse_mustache_expr(slot.inner(source)) {
+                Some(expr) if expr.tag != MustacheTag::Variable => {
+                    used.extend(expr.variables.iter().map(|var| var.to_string()));
+                    continue;
+                }
+                Some(expr) => name = expr.variables.first().copied().unwrap_or(name),
+                None => {}
+            }
+        }
+
+        if !is_valid_identifier(name) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "invalid-variable".to_string(),
+                message: format!("'{}' is not a valid variable name.", name),
+                span,
+            });
+            continue;
+        }
+
+        for (filter, _) in filters {
+            if !FILTER_NAMES.contains(&filter) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "unknown-filter".to_string(),
+                    message: format!("Unknown filter '{}'.", filter),
+                    span,
+                });
+            }
+        }
+
+        let description = schema.and_then(|schema| schema.get(name).cloned());
+        if let Some(schema) = schema {
+            if !schema.contains_key(name) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "unknown-variable".to_string(),
+                    message: format!("Variable '{}' is not declared in the schema.", name),
+                    span,
+                });
+            }
+        }
+
+        used.insert(name.to_string());
+        hovers.push(VariableHover {
+            name: name.to_string(),
+            span,
+            description,
+        });
+    }
+
+    if let Some(schema) = schema {
+        for name in schema.keys().filter(|name| !used.contains(*name)) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Information,
+                code: "unused-variable".to_string(),
+                message: format!("Schema variable '{}' is not used.", name),
+                span: Span::new(source, 0, 0),
+            });
+        }
+    }
+
+    let completions = completions(format.as_ref(), schema, &used);
+
+    DiagnosticReport {
+        format,
+        diagnostics,
+        hovers,
+        completions,
+    }
+}
+
+fn completions(
+    format: Option<&TemplateFormat>,
+    schema: Option<&VariableSchema>,
+    used: &BTreeSet<String>,
+) -> Vec<CompletionItem> {
+    let candidates: Vec<(String, Option<String>)> = match schema {
+        Some(schema) => schema
+            .iter()
+            .map(|(name, description)| (name.clone(), Some(description.clone())))
+            .collect(),
+        None => used.iter().map(|name| (name.clone(), None)).collect(),
+    };
+
+    candidates
+        .into_iter()
+        .map(|(name, detail)| CompletionItem {
+            insert_text: match format {
+                Some(TemplateFormat::Mustache | TemplateFormat::Jinja2) => {
+                    format!("{{{{{}}}}}", name)
+                }
+                _ => format!("{{{}}}", name),
+            },
+            label: name,
+            detail,
+        })
+        .collect()
+}
+
+fn unbalanced_brace_span(source: &str) -> Option<Span> {
+    scan(source)
+        .into_iter()
+        .find(|token| token.kind == BraceKind::Stray)
+        .map(|token| Span::new(source, token.span.start, token.span.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> VariableSchema {
+        BTreeMap::from([
+            ("name".to_string(), "The customer's first name".to_string()),
+            ("order".to_string(), "Order identifier".to_string()),
+        ])
+    }
+
+    fn codes(report: &DiagnosticReport) -> Vec<&str> {
+        report.diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_clean_template() {
+        let report = diagnose("Hi {name}, order {order} shipped.", Some(&schema()));
+
+        assert_eq!(report.format, Some(TemplateFormat::FmtString));
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(report.hovers.len(), 2);
+        assert_eq!(report.hovers[0].name, "name");
+        assert_eq!(
+            report.hovers[0].description.as_deref(),
+            Some("The customer's first name")
+        );
+        assert_eq!(report.hovers[0].span.start, 3);
+        assert_eq!(report.hovers[0].span.end, 9);
+    }
+
+    #[test]
+    fn test_unknown_and_unused_variables() {
+        let report = diagnose("Hi {nmae}!", Some(&schema()));
+
+        assert_eq!(
+            codes(&report),
+            vec!["unknown-variable", "unused-variable", "unused-variable"]
+        );
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn test_unbalanced_braces() {
+        let report = diagnose("line one\nHi {name, welcome.", None);
+
+        assert!(report.has_errors());
+        assert_eq!(report.format, None);
+        let span = report.diagnostics[0].span;
+        assert_eq!((span.line, span.column), (1, 3));
+        assert_eq!(span.start, 12);
+    }
+
+    #[test]
+    fn test_invalid_variable_and_unknown_filter() {
+        let report = diagnose("{first name} {doc|shout}", None);
+
+        assert_eq!(
+            codes(&report),
+            vec!["invalid-template", "invalid-variable", "unknown-filter"]
+        );
+    }
+
+    #[test]
+    fn test_mustache_helpers_and_sections() {
+        let report = diagnose(
+            "{{#if name}}Hi {{upper name}}{{/if}}, order {{order.id}}",
+            Some(&schema()),
+        );
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(report.hovers.len(), 1);
+        assert_eq!(report.hovers[0].name, "order");
+    }
+
+    #[test]
+    fn test_completions_follow_format() {
+        let report = diagnose("Hello {{name}}", Some(&schema()));
+        let inserts: Vec<&str> = report
+            .completions
+            .iter()
+            .map(|c| c.insert_text.as_str())
+            .collect();
+        assert_eq!(inserts, vec!["{{name}}", "{{order}}"]);
+
+        let report = diagnose("Hello {name}", None);
+        assert_eq!(report.completions.len(), 1);
+        assert_eq!(report.completions[0].insert_text, "{name}");
+        assert_eq!(report.completions[0].detail, None);
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = diagnose("Hi {nmae}", Some(&schema()));
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["format"], "FmtString");
+        assert_eq!(json["diagnostics"][0]["severity"], "warning");
+        assert_eq!(json["hovers"][0]["span"]["column"], 3);
+
+        let deserialized: DiagnosticReport = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, report);
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+
+use messageforge::BaseMessage;
+use serde::{Deserialize, Serialize};
+
+use crate::{assertions::message_role, ChatTemplate, MessageLike, Role, Templatable};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffMessage {
+    pub role: Option<Role>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MessageChange {
+    Added {
+        index: usize,
+        message: DiffMessage,
+    },
+    Removed {
+        index: usize,
+        message: DiffMessage,
+    },
+    Changed {
+        old_index: usize,
+        new_index: usize,
+        before: DiffMessage,
+        after: DiffMessage,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateDiff {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<(Option<String>, Option<String>)>,
+    pub messages: Vec<MessageChange>,
+    pub added_variables: BTreeSet<String>,
+    pub removed_variables: BTreeSet<String>,
+}
+
+impl TemplateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.version.is_none()
+            && self.messages.is_empty()
+            && self.added_variables.is_empty()
+            && self.removed_variables.is_empty()
+    }
+}
+
+impl ChatTemplate {
+    pub fn diff(&self, other: &ChatTemplate) -> TemplateDiff {
+        let before: Vec<DiffMessage> = self.messages.iter().map(diff_message).collect();
+        let after: Vec<DiffMessage> = other.messages.iter().map(diff_message).collect();
+
+        let old_variables: BTreeSet<String> = self.referenced_variables().into_iter().collect();
+        let new_variables: BTreeSet<String> = other.referenced_variables().into_iter().collect();
+
+        TemplateDiff {
+            version: (self.meta.version != other.meta.version)
+                .then(|| (self.meta.version.clone(), other.meta.version.clone())),
+            messages: message_changes(&before, &after),
+            added_variables: new_variables.difference(&old_variables).cloned().collect(),
+            removed_variables: old_variables.difference(&new_variables).cloned().collect(),
+        }
+    }
+}
+
+fn diff_message(message_like: &MessageLike) -> DiffMessage {
+    let (role, content) = match message_like {
+        MessageLike::BaseMessage(message) => (message_role(message), message.content().to_string()),
+        MessageLike::RolePromptTemplate(role, template) => {
+            (Some(*role), template.template().to_string())
+        }
+        MessageLike::Placeholder(placeholder) => (
+            Some(Role::Placeholder),
+            format!("{{{}}}", placeholder.variable_name()),
+        ),
+        MessageLike::FewShotPrompt(few_shot) => (
+            Some(Role::FewShotPrompt),
+            serde_json::to_string(few_shot).unwrap_or_default(),
+        ),
+    };
+
+    DiffMessage { role, content }
+}
+
+fn message_changes(before: &[DiffMessage], after: &[DiffMessage]) -> Vec<MessageChange> {
+    let mut lcs = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            pair_gap(&mut changes, &mut removed, &mut added, before, after);
+            i += 1;
+            j += 1;
+        } else if j < after.len() && (i == before.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    pair_gap(&mut changes, &mut removed, &mut added, before, after);
+
+    changes
+}
+
+fn pair_gap(
+    changes: &mut Vec<MessageChange>,
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    before: &[DiffMessage],
+    after: &[DiffMessage],
+) {
+    let mut added_iter = std::mem::take(added).into_iter().peekable();
+
+    for old_index in removed.drain(..) {
+        match added_iter.peek() {
+            Some(&new_index) if before[old_index].role == after[new_index].role => {
+                added_iter.next();
+                changes.push(MessageChange::Changed {
+                    old_index,
+                    new_index,
+                    before: before[old_index].clone(),
+                    after: after[new_index].clone(),
+                });
+            }
+            _ => changes.push(MessageChange::Removed {
+                index: old_index,
+                message: before[old_index].clone(),
+            }),
+        }
+    }
+
+    changes.extend(added_iter.map(|index| MessageChange::Added {
+        index,
+        message: after[index].clone(),
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats,
+        Role::{Human, Placeholder, System},
+    };
+
+    #[test]
+    fn test_diff_messages_and_variables() {
+        let before = ChatTemplate::from_messages(chats!(
+            System = "You are a support agent for {company}.",
+            Placeholder = "{history}",
+            Human = "{question}",
+        ))
+        .unwrap()
+        .with_version("1.0.0");
+        let after = ChatTemplate::from_messages(chats!(
+            System = "You are a friendly support agent for {company} in {region}.",
+            Human = "{question}",
+            Human = "Answer in {language}.",
+        ))
+        .unwrap()
+        .with_version("1.1.0");
+
+        let diff = before.diff(&after);
+
+        assert_eq!(
+            diff.version,
+            Some((Some("1.0.0".to_string()), Some("1.1.0".to_string())))
+        );
+        assert_eq!(
+            diff.messages,
+            vec![
+                MessageChange::Changed {
+                    old_index: 0,
+                    new_index: 0,
+                    before: DiffMessage {
+                        role: Some(System),
+                        content: "You are a support agent for {company}.".to_string(),
+                    },
+                    after: DiffMessage {
+                        role: Some(System),
+                        content: "You are a friendly support agent for {company} in {region}."
+                            .to_string(),
+                    },
+                },
+                MessageChange::Removed {
+                    index: 1,
+                    message: DiffMessage {
+                        role: Some(Placeholder),
+                        content: "{history}".to_string(),
+                    },
+                },
+                MessageChange::Added {
+                    index: 2,
+                    message: DiffMessage {
+                        role: Some(Human),
+                        content: "Answer in {language}.".to_string(),
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            diff.added_variables,
+            BTreeSet::from(["language".to_string(), "region".to_string()])
+        );
+        assert_eq!(
+            diff.removed_variables,
+            BTreeSet::from(["history".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_identical_templates_have_empty_diff() {
+        let template = ChatTemplate::from_messages(chats!(Human = "{question}")).unwrap();
+        assert!(template.diff(&template.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_serializes_to_json() {
+        let before = ChatTemplate::from_messages(chats!(Human = "Hi")).unwrap();
+        let after =
+            ChatTemplate::from_messages(chats!(Human = "Hi", System = "Be brief.")).unwrap();
+
+        let json = serde_json::to_value(before.diff(&after)).unwrap();
+        assert_eq!(json["messages"][0]["kind"], "added");
+        assert_eq!(json["messages"][0]["index"], 1);
+        assert!(json.get("version").is_none());
+    }
+}
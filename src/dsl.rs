@@ -0,0 +1,234 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{ChatTemplate, MessagesPlaceholder, Role, Template, TemplateError};
+
+lazy_static! {
+    static ref HEADER_RE: Regex =
+        Regex::new(r"^#([A-Za-z_]+)\s*(?:\(([^)]*)\))?\s*:(.*)$").unwrap();
+}
+
+struct DslMessage {
+    line: usize,
+    role: Role,
+    content: Vec<String>,
+}
+
+fn dsl_error(line: usize, msg: impl std::fmt::Display) -> TemplateError {
+    TemplateError::MalformedTemplate(format!("line {}: {}", line, msg))
+}
+
+pub(crate) fn dsl_role(name: &str) -> Option<Role> {
+    match name.to_lowercase().as_str() {
+        "system" => Some(Role::System),
+        "developer" => Some(Role::Developer),
+        "user" | "human" => Some(Role::Human),
+        "assistant" | "ai" => Some(Role::Ai),
+        "tool" => Some(Role::Tool),
+        "placeholder" => Some(Role::Placeholder),
+        _ => None,
+    }
+}
+
+fn placeholder_slot(line: usize, args: &str) -> Result<String, TemplateError> {
+    let mut args = args.split(',').map(str::trim).filter(|arg| !arg.is_empty());
+    let name = args
+        .next()
+        .ok_or_else(|| dsl_error(line, "placeholder needs a variable name"))?;
+
+    let mut slot = name.to_string();
+    for arg in args {
+        let option = match arg.split_once('=') {
+            Some((key, value)) => match key.trim() {
+                "n" | "limit" => format!("limit:{}", value.trim()),
+                "keep" => format!("keep:{}", value.trim()),
+                key => {
+                    return Err(dsl_error(
+                        line,
+                        format!("unknown placeholder option '{}'", key),
+                    ))
+                }
+            },
+            None if arg == "optional" => "optional".to_string(),
+            None => {
+                return Err(dsl_error(
+                    line,
+                    format!("unknown placeholder option '{}'", arg),
+                ))
+            }
+        };
+        slot.push('|');
+        slot.push_str(&option);
+    }
+
+    Ok(format!("{{{}}}", slot))
+}
+
+pub fn parse_dsl(source: &str) -> Result<Vec<(Role, String)>, TemplateError> {
+    let mut messages: Vec<DslMessage> = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+
+        if let Some(captures) = HEADER_RE.captures(raw) {
+            let name = &captures[1];
+            let role = dsl_role(name)
+                .ok_or_else(|| dsl_error(line, format!("unknown role '#{}'", name)))?;
+            let args = captures.get(2).map(|m| m.as_str());
+            let inline = captures[3].trim();
+
+            let content = match (role, args) {
+                (Role::Placeholder, Some(args)) => {
+                    if !inline.is_empty() {
+                        return Err(dsl_error(line, "placeholders cannot have content"));
+                    }
+                    vec![placeholder_slot(line, args)?]
+                }
+                (Role::Placeholder, None) => {
+                    return Err(dsl_error(line, "expected '#placeholder(name, ...):'"))
+                }
+                (_, Some(_)) => {
+                    return Err(dsl_error(
+                        line,
+                        format!("'#{}' does not take arguments", name),
+                    ))
+                }
+                (_, None) if inline.is_empty() => Vec::new(),
+                (_, None) => vec![inline.to_string()],
+            };
+
+            messages.push(DslMessage {
+                line,
+                role,
+                content,
+            });
+            continue;
+        }
+
+        let text = raw
+            .strip_prefix('\\')
+            .filter(|rest| rest.starts_with('#'))
+            .unwrap_or(raw);
+        match messages.last_mut() {
+            Some(message) if message.role == Role::Placeholder => {
+                if !text.trim().is_empty() {
+                    return Err(dsl_error(line, "placeholders cannot have content"));
+                }
+            }
+            Some(message) => message.content.push(text.to_string()),
+            None if text.trim().is_empty() => {}
+            None => return Err(dsl_error(line, "expected a role header such as '#system:'")),
+        }
+    }
+
+    messages
+        .into_iter()
+        .map(|message| {
+            let content = message.content.join("\n").trim_matches('\n').to_string();
+            if content.trim().is_empty() {
+                return Err(dsl_error(message.line, "message is empty"));
+            }
+
+            match message.role {
+                Role::Placeholder => MessagesPlaceholder::try_from(content.clone()).map(|_| ()),
+                _ => Template::from_template(&content).map(|_| ()),
+            }
+            .map_err(|e| dsl_error(message.line, e))?;
+
+            Ok((message.role, content))
+        })
+        .collect()
+}
+
+impl ChatTemplate {
+    pub fn from_dsl(source: &str) -> Result<Self, TemplateError> {
+        ChatTemplate::from_messages(parse_dsl(source)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{vars, MessageLike, TrimStrategy};
+
+    const SUPPORT_CHAT: &str = "\
+#system:
+You are a support agent for {company}.
+\\#1 rule: be kind.
+
+#placeholder(history, n=5, optional, keep=last):
+#user: {question}
+";
+
+    #[test]
+    fn test_from_dsl() {
+        let template = ChatTemplate::from_dsl(SUPPORT_CHAT).unwrap();
+        assert_eq!(template.messages.len(), 3);
+
+        match &template.messages[1] {
+            MessageLike::Placeholder(placeholder) => {
+                assert_eq!(placeholder.variable_name(), "history");
+                assert_eq!(placeholder.n_messages(), 5);
+                assert!(placeholder.optional());
+                assert_eq!(placeholder.trim_strategy(), TrimStrategy::KeepLast);
+            }
+            other => panic!("Expected a placeholder, got {:?}", other),
+        }
+
+        let messages = template
+            .format_messages(&vars!(company = "Acme", question = "Where is my order?"))
+            .unwrap();
+        assert_eq!(
+            messages[0].content(),
+            "You are a support agent for Acme.\n#1 rule: be kind."
+        );
+        assert_eq!(messages[1].content(), "Where is my order?");
+    }
+
+    #[test]
+    fn test_dsl_role_aliases() {
+        let messages =
+            parse_dsl("#developer: Be terse.\n#human: Hi\n#assistant:\nHello!\n#ai: Bye").unwrap();
+        let roles: Vec<Role> = messages.iter().map(|(role, _)| *role).collect();
+        assert_eq!(
+            roles,
+            vec![Role::Developer, Role::Human, Role::Ai, Role::Ai]
+        );
+        assert_eq!(messages[2].1, "Hello!");
+    }
+
+    #[test]
+    fn test_dsl_errors_include_line_numbers() {
+        let cases = [
+            ("Hello\n#user: hi", "line 1: expected a role header"),
+            (
+                "#system: ok\n#narrator: hi",
+                "line 2: unknown role '#narrator'",
+            ),
+            ("#user:\n\n#system: ok", "line 1: message is empty"),
+            (
+                "#placeholder(history, depth=2):",
+                "line 1: unknown placeholder option 'depth'",
+            ),
+            (
+                "#placeholder(history):\nstray",
+                "line 2: placeholders cannot have content",
+            ),
+            ("#system: ok\n#user: {name}}", "line 2: "),
+        ];
+
+        for (source, expected) in cases {
+            match ChatTemplate::from_dsl(source) {
+                Err(TemplateError::MalformedTemplate(msg)) => {
+                    assert!(msg.starts_with(expected), "{:?} -> {}", source, msg)
+                }
+                other => panic!(
+                    "Expected MalformedTemplate for {:?}, got {:?}",
+                    source, other
+                ),
+            }
+        }
+    }
+}
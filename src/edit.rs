@@ -0,0 +1,243 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    placeholder::is_valid_identifier, Templatable, Template, TemplateError, TemplateFormat,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TemplateEdit {
+    Replace { range: Range<usize>, text: String },
+    Insert { at: usize, text: String },
+    WrapInVariable { range: Range<usize>, name: String },
+}
+
+impl TemplateEdit {
+    pub fn replace(range: Range<usize>, text: impl Into<String>) -> Self {
+        TemplateEdit::Replace {
+            range,
+            text: text.into(),
+        }
+    }
+
+    pub fn insert(at: usize, text: impl Into<String>) -> Self {
+        TemplateEdit::Insert {
+            at,
+            text: text.into(),
+        }
+    }
+
+    pub fn wrap_in_variable(range: Range<usize>, name: impl Into<String>) -> Self {
+        TemplateEdit::WrapInVariable {
+            range,
+            name: name.into(),
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            TemplateEdit::Replace { range, .. } | TemplateEdit::WrapInVariable { range, .. } => {
+                range.clone()
+            }
+            TemplateEdit::Insert { at, .. } => *at..*at,
+        }
+    }
+
+    fn replacement(&self, format: &TemplateFormat) -> Result<String, TemplateError> {
+        match self {
+            TemplateEdit::Replace { text, .. } | TemplateEdit::Insert { text, .. } => {
+                Ok(text.clone())
+            }
+            TemplateEdit::WrapInVariable { name, .. } => {
+                if !is_valid_identifier(name) {
+                    return Err(TemplateError::MalformedTemplate(format!(
+                        "Invalid variable name '{}'.",
+                        name
+                    )));
+                }
+                Ok(match format {
+                    TemplateFormat::Mustache | TemplateFormat::Jinja2 => {
+                        format!("{{{{{}}}}}", name)
+                    }
+                    _ => format!("{{{}}}", name),
+                })
+            }
+        }
+    }
+
+    fn check_range(&self, source: &str) -> Result<(), TemplateError> {
+        let range = self.range();
+        let in_bounds = range.start <= range.end
+            && range.end <= source.len()
+            && source.is_char_boundary(range.start)
+            && source.is_char_boundary(range.end);
+
+        if in_bounds {
+            Ok(())
+        } else {
+            Err(TemplateError::MalformedTemplate(format!(
+                "Edit range {}..{} is not valid for a template of {} bytes.",
+                range.start,
+                range.end,
+                source.len()
+            )))
+        }
+    }
+}
+
+impl Template {
+    pub fn apply_edit(&self, edit: &TemplateEdit) -> Result<Template, TemplateError> {
+        self.apply_edits(std::slice::from_ref(edit))
+    }
+
+    pub fn apply_edits(&self, edits: &[TemplateEdit]) -> Result<Template, TemplateError> {
+        let source = self.template();
+        let format = self.template_format();
+
+        let mut ordered: Vec<&TemplateEdit> = edits.iter().collect();
+        ordered.sort_by_key(|edit| (edit.range().start, edit.range().end));
+        for pair in ordered.windows(2) {
+            if pair[0].range().end > pair[1].range().start {
+                return Err(TemplateError::MalformedTemplate(format!(
+                    "Edits {:?} and {:?} overlap.",
+                    pair[0].range(),
+                    pair[1].range()
+                )));
+            }
+        }
+
+        let mut edited = source.to_string();
+        for edit in ordered.into_iter().rev() {
+            edit.check_range(source)?;
+            edited.replace_range(edit.range(), &edit.replacement(&format)?);
+        }
+
+        let mut template = Template::new(&edited)?;
+        for (var, value) in self.partial_vars() {
+            template.partial(var, value);
+        }
+        Ok(template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars;
+
+    #[test]
+    fn test_replace_and_insert() {
+        let template = Template::new("Hello, {name}!").unwrap();
+
+        let edited = template
+            .apply_edit(&TemplateEdit::replace(0..5, "Welcome"))
+            .unwrap();
+        assert_eq!(edited.template(), "Welcome, {name}!");
+
+        let edited = template
+            .apply_edit(&TemplateEdit::insert(13, " from {city}"))
+            .unwrap();
+        assert_eq!(edited.template(), "Hello, {name} from {city}!");
+        assert_eq!(edited.input_variables(), vec!["name", "city"]);
+    }
+
+    #[test]
+    fn test_wrap_plain_text_in_variable_switches_format() {
+        let template = Template::new("Translate this into French.").unwrap();
+        assert_eq!(template.template_format(), TemplateFormat::PlainText);
+
+        let edited = template
+            .apply_edit(&TemplateEdit::wrap_in_variable(20..26, "language"))
+            .unwrap();
+        assert_eq!(edited.template(), "Translate this into {language}.");
+        assert_eq!(edited.template_format(), TemplateFormat::FmtString);
+        assert_eq!(
+            edited.format(&vars!(language = "German")).unwrap(),
+            "Translate this into German."
+        );
+    }
+
+    #[test]
+    fn test_wrap_in_mustache_template_uses_double_braces() {
+        let template = Template::new("Hi {{name}}, welcome to Paris.").unwrap();
+        let edited = template
+            .apply_edit(&TemplateEdit::wrap_in_variable(24..29, "city"))
+            .unwrap();
+
+        assert_eq!(edited.template(), "Hi {{name}}, welcome to {{city}}.");
+        assert_eq!(edited.template_format(), TemplateFormat::Mustache);
+        assert_eq!(edited.input_variables(), vec!["name", "city"]);
+    }
+
+    #[test]
+    fn test_multiple_edits_use_original_offsets() {
+        let template = Template::new("Dear customer, your order shipped.").unwrap();
+        let edits = vec![
+            TemplateEdit::wrap_in_variable(20..25, "item"),
+            TemplateEdit::wrap_in_variable(5..13, "name"),
+            TemplateEdit::insert(34, " Thanks!"),
+        ];
+
+        let edited = template.apply_edits(&edits).unwrap();
+        assert_eq!(
+            edited.template(),
+            "Dear {name}, your {item} shipped. Thanks!"
+        );
+    }
+
+    #[test]
+    fn test_invalid_edits_fail() {
+        let template = Template::new("Héllo, {name}!").unwrap();
+
+        for edit in [
+            TemplateEdit::replace(0..100, "x"),
+            TemplateEdit::replace(2..3, "e"),
+            TemplateEdit::wrap_in_variable(0..1, "not valid"),
+        ] {
+            assert!(matches!(
+                template.apply_edit(&edit),
+                Err(TemplateError::MalformedTemplate(_))
+            ));
+        }
+
+        let overlapping = [
+            TemplateEdit::replace(0..5, "a"),
+            TemplateEdit::replace(3..7, "b"),
+        ];
+        assert!(template.apply_edits(&overlapping).is_err());
+    }
+
+    #[test]
+    fn test_edit_that_breaks_template_is_rejected() {
+        let template = Template::new("Hello, {name}!").unwrap();
+        let result = template.apply_edit(&TemplateEdit::insert(0, "{{greeting}} "));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_edits_preserve_partials() {
+        let mut template = Template::new("{greeting}, {name}!").unwrap();
+        template.partial("greeting", "Hi");
+
+        let edited = template
+            .apply_edit(&TemplateEdit::insert(19, " Bye."))
+            .unwrap();
+        assert_eq!(
+            edited.format(&vars!(name = "Ada")).unwrap(),
+            "Hi, Ada! Bye."
+        );
+    }
+
+    #[test]
+    fn test_edit_serialization() {
+        let edit = TemplateEdit::wrap_in_variable(1..4, "x");
+        let json = serde_json::to_string(&edit).unwrap();
+        assert_eq!(
+            json,
+            r#"{"op":"wrap_in_variable","range":{"start":1,"end":4},"name":"x"}"#
+        );
+        assert_eq!(serde_json::from_str::<TemplateEdit>(&json).unwrap(), edit);
+    }
+}
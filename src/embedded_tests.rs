@@ -0,0 +1,182 @@
+use std::{collections::HashMap, sync::Arc};
+
+use messageforge::MessageEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{assertions::PromptAssertions, Role};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptTestCase {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect_contains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect_not_contains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect_matches: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect_role_order: Vec<Role>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+    #[serde(default)]
+    pub allow_placeholders: bool,
+}
+
+impl PromptTestCase {
+    pub fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("test #{}", index + 1))
+    }
+
+    pub fn variables(&self) -> HashMap<&str, &str> {
+        self.variables
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+
+    pub fn check(&self, messages: &[Arc<MessageEnum>]) -> Vec<String> {
+        let mut assertions = PromptAssertions::new(messages);
+
+        for expected in &self.expect_contains {
+            assertions = assertions.contains(expected);
+        }
+        for unexpected in &self.expect_not_contains {
+            assertions = assertions.not_contains(unexpected);
+        }
+        for pattern in &self.expect_matches {
+            assertions = assertions.matches(pattern);
+        }
+        if !self.expect_role_order.is_empty() {
+            assertions = assertions.role_order(&self.expect_role_order);
+        }
+        if let Some(limit) = self.max_tokens {
+            assertions = assertions.max_tokens(limit);
+        }
+        if !self.allow_placeholders {
+            assertions = assertions.no_placeholder_left();
+        }
+
+        assertions
+            .failures()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTestFailure {
+    pub test: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptTestReport {
+    pub passed: usize,
+    pub failures: Vec<PromptTestFailure>,
+}
+
+impl PromptTestReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn record(&mut self, test: String, reasons: Vec<String>) {
+        if reasons.is_empty() {
+            self.passed += 1;
+        } else {
+            self.failures
+                .extend(reasons.into_iter().map(|reason| PromptTestFailure {
+                    test: test.clone(),
+                    reason,
+                }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn human(content: &str) -> Vec<Arc<MessageEnum>> {
+        vec![Role::Human.to_message(content).unwrap()]
+    }
+
+    #[test]
+    fn test_check_passes() {
+        let case = PromptTestCase {
+            expect_contains: vec!["Hello".to_string()],
+            expect_not_contains: vec!["{name}".to_string()],
+            expect_matches: vec![r"^human: Hello, \w+!$".to_string()],
+            expect_role_order: vec![Role::Human],
+            max_tokens: Some(10),
+            ..Default::default()
+        };
+
+        assert!(case.check(&human("Hello, Ada!")).is_empty());
+    }
+
+    #[test]
+    fn test_check_placeholders() {
+        let case = PromptTestCase::default();
+        assert_eq!(
+            case.check(&human("Hello, {name}!")),
+            vec!["message 0 still contains placeholder {name}"]
+        );
+
+        let case = PromptTestCase {
+            allow_placeholders: true,
+            ..Default::default()
+        };
+        assert!(case.check(&human("Hello, {name}!")).is_empty());
+    }
+
+    #[test]
+    fn test_check_collects_all_failures() {
+        let case = PromptTestCase {
+            expect_contains: vec!["Goodbye".to_string()],
+            expect_not_contains: vec!["Hello".to_string()],
+            expect_matches: vec!["^bye".to_string(), "(".to_string()],
+            ..Default::default()
+        };
+
+        let failures = case.check(&human("Hello"));
+        assert_eq!(failures.len(), 4);
+        assert_eq!(failures[0], "expected output to contain \"Goodbye\"");
+        assert_eq!(failures[1], "expected output not to contain \"Hello\"");
+        assert_eq!(failures[2], "expected output to match /^bye/");
+        assert!(failures[3].starts_with("invalid pattern /(/"));
+    }
+
+    #[test]
+    fn test_label() {
+        let unnamed = PromptTestCase::default();
+        assert_eq!(unnamed.label(1), "test #2");
+
+        let named = PromptTestCase {
+            name: Some("greets user".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(named.label(0), "greets user");
+    }
+
+    #[test]
+    fn test_report_record() {
+        let mut report = PromptTestReport::default();
+        report.record("a".to_string(), vec![]);
+        assert!(report.is_success());
+
+        report.record(
+            "b".to_string(),
+            vec!["bad".to_string(), "worse".to_string()],
+        );
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failures.len(), 2);
+        assert!(!report.is_success());
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+#[cfg(feature = "async")]
+use std::sync::Mutex;
+
+#[cfg(feature = "async")]
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    Stream,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "async")]
+use crate::PromptSet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PromptEvent {
+    Created { name: String, revision: u64 },
+    Updated { name: String, revision: u64 },
+    Deleted { name: String },
+    RolledBack { name: String },
+}
+
+impl PromptEvent {
+    pub fn name(&self) -> &str {
+        match self {
+            PromptEvent::Created { name, .. }
+            | PromptEvent::Updated { name, .. }
+            | PromptEvent::Deleted { name }
+            | PromptEvent::RolledBack { name } => name,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EventBus {
+    #[cfg(feature = "async")]
+    subscribers: Mutex<Vec<UnboundedSender<PromptEvent>>>,
+}
+
+impl EventBus {
+    #[cfg(feature = "async")]
+    pub(crate) fn publish(&self, event: PromptEvent) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+        }
+    }
+
+    #[cfg(not(feature = "async"))]
+    pub(crate) fn publish(&self, _event: PromptEvent) {}
+
+    #[cfg(feature = "async")]
+    fn subscribe(&self) -> UnboundedReceiver<PromptEvent> {
+        let (sender, receiver) = unbounded();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    #[cfg(feature = "async")]
+    fn subscribers(&self) -> usize {
+        self.subscribers.lock().map_or(0, |s| s.len())
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn subscribers(&self) -> usize {
+        0
+    }
+}
+
+impl Clone for EventBus {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.subscribers())
+            .finish()
+    }
+}
+
+#[cfg(feature = "async")]
+impl PromptSet {
+    pub fn subscribe(&self) -> impl Stream<Item = PromptEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use futures::{FutureExt, StreamExt};
+
+    use super::*;
+    use crate::{chats, ChatTemplate, Role::Human};
+
+    fn prompt(content: &str) -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(Human = content)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_lifecycle_events() {
+        let mut prompts = PromptSet::new();
+        prompts.insert("before", prompt("Not observed."));
+
+        let events = prompts.subscribe();
+        prompts.insert("greeting", prompt("Hello."));
+        prompts.put("greeting", prompt("Hi."), Some(1)).unwrap();
+        prompts
+            .start_rollout("greeting", prompt("Hey."), 10)
+            .unwrap();
+        prompts.rollback("greeting");
+        prompts.remove("greeting");
+        prompts.remove("missing");
+        drop(prompts);
+
+        let events: Vec<PromptEvent> = events.collect().await;
+        assert_eq!(
+            events,
+            vec![
+                PromptEvent::Created {
+                    name: "greeting".into(),
+                    revision: 1
+                },
+                PromptEvent::Updated {
+                    name: "greeting".into(),
+                    revision: 2
+                },
+                PromptEvent::RolledBack {
+                    name: "greeting".into()
+                },
+                PromptEvent::Deleted {
+                    name: "greeting".into()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_subscribers_are_pruned() {
+        let mut prompts = PromptSet::new();
+        let kept = prompts.subscribe();
+        drop(prompts.subscribe());
+
+        prompts.insert("a", prompt("A."));
+        assert_eq!(prompts.events.subscribers(), 1);
+
+        let mut kept = Box::pin(kept);
+        assert_eq!(kept.next().now_or_never().flatten().unwrap().name(), "a");
+
+        let cloned = prompts.clone();
+        prompts.insert("b", prompt("B."));
+        assert_eq!(cloned.events.subscribers(), 0);
+    }
+}
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{semantic_search::cosine_similarity, Embedder, HeuristicCounter, TokenCounter};
+
+pub trait ExampleSelector: fmt::Debug + Send + Sync {
+    fn select(&self, examples: &[String], variables: &HashMap<&str, &str>) -> Vec<usize>;
+}
+
+fn input_text(variables: &HashMap<&str, &str>) -> String {
+    let mut entries: Vec<_> = variables.iter().collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone)]
+pub struct LengthBasedSelector {
+    pub max_tokens: usize,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl LengthBasedSelector {
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            counter: Arc::new(HeuristicCounter),
+        }
+    }
+
+    pub fn with_counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+}
+
+impl ExampleSelector for LengthBasedSelector {
+    fn select(&self, examples: &[String], variables: &HashMap<&str, &str>) -> Vec<usize> {
+        let mut remaining = self
+            .max_tokens
+            .saturating_sub(self.counter.count(&input_text(variables)));
+        let mut selected = Vec::new();
+
+        for (index, example) in examples.iter().enumerate() {
+            let tokens = self.counter.count(example);
+            if tokens > remaining {
+                break;
+            }
+            remaining -= tokens;
+            selected.push(index);
+        }
+
+        selected
+    }
+}
+
+#[derive(Clone)]
+pub struct SemanticSelector {
+    embedder: Arc<dyn Embedder>,
+    k: usize,
+}
+
+impl SemanticSelector {
+    pub fn new(embedder: impl Embedder + 'static, k: usize) -> Self {
+        Self {
+            embedder: Arc::new(embedder),
+            k,
+        }
+    }
+}
+
+impl fmt::Debug for SemanticSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemanticSelector")
+            .field("k", &self.k)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExampleSelector for SemanticSelector {
+    fn select(&self, examples: &[String], variables: &HashMap<&str, &str>) -> Vec<usize> {
+        let query = self.embedder.embed(&input_text(variables));
+        let mut scored: Vec<(usize, f32)> = examples
+            .iter()
+            .enumerate()
+            .map(|(index, example)| {
+                (
+                    index,
+                    cosine_similarity(&query, &self.embedder.embed(example)),
+                )
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored
+            .into_iter()
+            .take(self.k)
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomSelector {
+    pub k: usize,
+    pub seed: u64,
+}
+
+impl RandomSelector {
+    pub fn new(k: usize, seed: u64) -> Self {
+        Self { k, seed }
+    }
+}
+
+impl ExampleSelector for RandomSelector {
+    fn select(&self, examples: &[String], _variables: &HashMap<&str, &str>) -> Vec<usize> {
+        let mut state = self.seed | 1;
+        let mut indices: Vec<usize> = (0..examples.len()).collect();
+
+        for i in (1..indices.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            indices.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+
+        indices.truncate(self.k);
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars;
+
+    fn examples() -> Vec<String> {
+        ["happy -> sad", "tall -> short", "energetic -> lethargic"]
+            .iter()
+            .map(|example| example.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_length_based_selector() {
+        let selector = LengthBasedSelector::new(8);
+        assert_eq!(selector.select(&examples(), &vars!()), vec![0, 1]);
+        assert_eq!(
+            selector.select(&examples(), &vars!(adjective = "very very big")),
+            vec![0]
+        );
+        assert!(LengthBasedSelector::new(0)
+            .select(&examples(), &vars!())
+            .is_empty());
+
+        let by_words =
+            LengthBasedSelector::new(6).with_counter(|text: &str| text.split_whitespace().count());
+        assert_eq!(by_words.select(&examples(), &vars!()), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_semantic_selector() {
+        let embedder = |text: &str| {
+            vec![
+                text.matches("tall").count() as f32,
+                text.matches("happy").count() as f32,
+            ]
+        };
+        let selector = SemanticSelector::new(embedder, 1);
+        assert_eq!(
+            selector.select(&examples(), &vars!(adjective = "tall")),
+            vec![1]
+        );
+        assert_eq!(
+            selector.select(&examples(), &vars!(adjective = "happy")),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn test_random_selector_is_seeded() {
+        let selector = RandomSelector::new(2, 7);
+        let first = selector.select(&examples(), &vars!());
+        assert_eq!(first.len(), 2);
+        assert_eq!(first, selector.select(&examples(), &vars!()));
+        assert!(first.iter().all(|index| *index < 3));
+        assert_ne!(first[0], first[1]);
+        assert!(RandomSelector::new(5, 1).select(&[], &vars!()).is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use messageforge::BaseMessage;
+
+use crate::{
+    assertions::message_role, ChatTemplate, MessageLike, MessagesPlaceholder, Templatable,
+    TrimStrategy,
+};
+
+impl ChatTemplate {
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "This chat template renders {} message entries in order:",
+            self.messages.len()
+        );
+
+        for (index, message_like) in self.messages.iter().enumerate() {
+            let _ = write!(out, "\n{}. ", index + 1);
+            match message_like {
+                MessageLike::BaseMessage(message) => {
+                    let role = message_role(message).map_or_else(
+                        || message.message_type().as_str().to_string(),
+                        |r| r.to_string(),
+                    );
+                    let _ = writeln!(
+                        out,
+                        "{} message (literal, {} chars). Always rendered as written.",
+                        role,
+                        message.content().chars().count()
+                    );
+                }
+                MessageLike::RolePromptTemplate(role, template) => {
+                    let _ = writeln!(
+                        out,
+                        "{} message ({} template).",
+                        role,
+                        template.template_format().as_str()
+                    );
+                    let variables = template.input_variables();
+                    if variables.is_empty() {
+                        let _ = writeln!(out, "   Needs no variables.");
+                    } else {
+                        let _ = writeln!(out, "   Requires: {}.", variables.join(", "));
+                        let _ = writeln!(
+                            out,
+                            "   If any of these is missing, rendering fails with a MissingVariable error."
+                        );
+                    }
+                }
+                MessageLike::Placeholder(placeholder) => explain_placeholder(
+                    &mut out,
+                    placeholder,
+                    self.meta.format_options.render_optional_placeholders,
+                ),
+                MessageLike::FewShotPrompt(few_shot) => {
+                    let _ = writeln!(
+                        out,
+                        "Few-shot examples ({} examples, separated by {:?}).",
+                        few_shot.examples().len(),
+                        few_shot.example_separator()
+                    );
+                    let _ = writeln!(
+                        out,
+                        "   Rendered from stored examples; needs no runtime variables."
+                    );
+                }
+            }
+        }
+
+        let variables = self.required_variables();
+        let _ = write!(out, "\nRequired variables: ");
+        if variables.is_empty() {
+            let _ = writeln!(out, "none.");
+        } else {
+            let _ = writeln!(out, "{}.", variables.join(", "));
+        }
+
+        if !self.meta.normalizers.is_empty() {
+            let _ = writeln!(out, "Normalized before substitution:");
+            for (variable, normalizers) in &self.meta.normalizers {
+                let _ = writeln!(out, "  - {}: {:?}", variable, normalizers);
+            }
+        }
+
+        if let Some(profile) = &self.meta.profile {
+            let _ = writeln!(
+                out,
+                "Model profile '{}' is applied after rendering (system messages: {:?}).",
+                profile.name, profile.system_handling
+            );
+        }
+
+        if self.meta.limits.is_some() {
+            let _ = writeln!(
+                out,
+                "Render limits are enforced; oversized input fails with a LimitExceeded error."
+            );
+        }
+
+        if self.meta.strict {
+            let _ = writeln!(
+                out,
+                "Strict placeholders are on; leftover {{...}} in the output fails with an UnresolvedPlaceholder error."
+            );
+        }
+
+        out
+    }
+
+    pub(crate) fn required_variables(&self) -> Vec<String> {
+        let mut variables: Vec<String> = Vec::new();
+        for message_like in &self.messages {
+            let names = match message_like {
+                MessageLike::RolePromptTemplate(_, template) => template.input_variables(),
+                MessageLike::Placeholder(placeholder)
+                    if !placeholder.optional() && placeholder.fallback().is_none() =>
+                {
+                    vec![placeholder.variable_name().to_string()]
+                }
+                _ => Vec::new(),
+            };
+            for name in names {
+                if !variables.contains(&name) {
+                    variables.push(name);
+                }
+            }
+        }
+        variables
+    }
+}
+
+fn explain_placeholder(out: &mut String, placeholder: &MessagesPlaceholder, render_optional: bool) {
+    let _ = writeln!(
+        out,
+        "Messages placeholder '{}'.",
+        placeholder.variable_name()
+    );
+    if placeholder.optional() && !render_optional {
+        let _ = writeln!(
+            out,
+            "   Optional, so it renders nothing even when '{}' is provided; set FormatOptions::render_optional_placeholders to expand it.",
+            placeholder.variable_name()
+        );
+        return;
+    }
+    let kept = match placeholder.trim_strategy() {
+        TrimStrategy::KeepFirst => "first",
+        TrimStrategy::KeepLast => "last",
+    };
+    let _ = writeln!(
+        out,
+        "   Expands to the messages in '{}', keeping the {} {}.",
+        placeholder.variable_name(),
+        kept,
+        placeholder.n_messages()
+    );
+
+    let missing = match (placeholder.fallback(), placeholder.optional()) {
+        (Some(fallback), _) => format!("renders {} fallback message(s)", fallback.len()),
+        (None, true) => "renders nothing (optional)".to_string(),
+        (None, false) => "fails with a MissingVariable error".to_string(),
+    };
+    let _ = writeln!(out, "   If the variable is missing, it {}.", missing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, FormatOptions, ModelProfile, RenderLimits,
+        Role::{Human, Placeholder, System},
+    };
+
+    #[test]
+    fn test_explain_walkthrough() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You are {persona}.",
+            Placeholder = "{history|optional|limit:5|keep:last}",
+            Human = "{question}",
+        ))
+        .unwrap();
+
+        let explanation = template.explain();
+        assert!(explanation.starts_with("This chat template renders 3 message entries in order:"));
+        assert!(
+            explanation.contains("1. system message (FmtString template).\n   Requires: persona.")
+        );
+        assert!(explanation.contains("2. Messages placeholder 'history'."));
+        assert!(explanation.contains("renders nothing even when 'history' is provided"));
+        assert!(explanation.contains("Required variables: persona, question."));
+        assert!(!explanation.contains("Model profile"));
+
+        let explanation = template
+            .with_format_options(FormatOptions::new().render_optional_placeholders(true))
+            .explain();
+        assert!(explanation.contains("keeping the last 5."));
+        assert!(explanation.contains("If the variable is missing, it renders nothing (optional)."));
+    }
+
+    #[test]
+    fn test_explain_mentions_profile_and_limits() {
+        let template = ChatTemplate::from_messages(chats!(System = "Be brief."))
+            .unwrap()
+            .specialize(ModelProfile::llama())
+            .with_limits(RenderLimits::new().max_total_bytes(100));
+
+        let explanation = template.explain();
+        assert!(explanation
+            .contains("1. system message (literal, 9 chars). Always rendered as written."));
+        assert!(explanation.contains("Required variables: none."));
+        assert!(explanation.contains("Model profile 'llama'"));
+        assert!(explanation.contains("LimitExceeded"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::cmp::Ordering;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use serde_json::Value;
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => match (a, b) {
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => (a == b).then_some(Ordering::Equal),
+        },
+    }
+}
+
+fn to_json(result: f64) -> Option<Value> {
+    if result.fract() == 0.0 && result.abs() < i64::MAX as f64 {
+        return Some(Value::from(result as i64));
+    }
+    serde_json::Number::from_f64(result).map(Value::Number)
+}
+
+fn params<'a>(
+    name: &'static str,
+    h: &'a Helper<'_>,
+) -> Result<(&'a Value, &'a Value), RenderError> {
+    let param = |index| {
+        h.param(index)
+            .map(|param| param.value())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, index))
+    };
+    Ok((param(0)?, param(1)?))
+}
+
+#[derive(Clone, Copy)]
+struct ComparisonHelper {
+    name: &'static str,
+    op: fn(Option<Ordering>) -> bool,
+}
+
+impl HelperDef for ComparisonHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let (a, b) = params(self.name, h)?;
+        Ok(ScopedJson::Derived(Value::Bool((self.op)(compare(a, b)))))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ArithmeticHelper {
+    name: &'static str,
+    op: fn(f64, f64) -> Option<f64>,
+}
+
+impl HelperDef for ArithmeticHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let (a, b) = params(self.name, h)?;
+        let (Some(a), Some(b)) = (number(a), number(b)) else {
+            return Err(RenderErrorReason::Other(format!(
+                "Helper '{}' expects numeric arguments",
+                self.name
+            ))
+            .into());
+        };
+
+        (self.op)(a, b)
+            .and_then(to_json)
+            .map(ScopedJson::Derived)
+            .ok_or_else(|| {
+                RenderErrorReason::Other(format!(
+                    "Helper '{}' has no result for {} and {}",
+                    self.name, a, b
+                ))
+                .into()
+            })
+    }
+}
+
+const COMPARISONS: &[ComparisonHelper] = &[
+    ComparisonHelper {
+        name: "eq",
+        op: |ord| ord == Some(Ordering::Equal),
+    },
+    ComparisonHelper {
+        name: "ne",
+        op: |ord| ord != Some(Ordering::Equal),
+    },
+    ComparisonHelper {
+        name: "gt",
+        op: |ord| ord == Some(Ordering::Greater),
+    },
+    ComparisonHelper {
+        name: "gte",
+        op: |ord| matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+    },
+    ComparisonHelper {
+        name: "lt",
+        op: |ord| ord == Some(Ordering::Less),
+    },
+    ComparisonHelper {
+        name: "lte",
+        op: |ord| matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+    },
+];
+
+const ARITHMETIC: &[ArithmeticHelper] = &[
+    ArithmeticHelper {
+        name: "add",
+        op: |a, b| Some(a + b),
+    },
+    ArithmeticHelper {
+        name: "sub",
+        op: |a, b| Some(a - b),
+    },
+    ArithmeticHelper {
+        name: "mul",
+        op: |a, b| Some(a * b),
+    },
+    ArithmeticHelper {
+        name: "div",
+        op: |a, b| (b != 0.0).then(|| a / b),
+    },
+    ArithmeticHelper {
+        name: "mod",
+        op: |a, b| (b != 0.0).then(|| a % b),
+    },
+];
+
+pub(crate) fn register_expression_helpers(handlebars: &mut Handlebars<'_>) {
+    for helper in COMPARISONS {
+        handlebars.register_helper(helper.name, Box::new(*helper));
+    }
+    for helper in ARITHMETIC {
+        handlebars.register_helper(helper.name, Box::new(*helper));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{var_values, vars, Templatable, Template, TemplateError};
+
+    #[test]
+    fn test_arithmetic_helpers() {
+        let template = Template::new(
+            "{{add count 1}} {{sub count 2}} {{mul count 3}} {{div count 4}} {{mod count 4}}",
+        )
+        .unwrap();
+        assert_eq!(template.input_variables(), vec!["count"]);
+        assert_eq!(
+            template.format(&vars!(count = "10")).unwrap(),
+            "11 8 30 2.5 2"
+        );
+        assert!(matches!(
+            template.format(&vars!(count = "ten")),
+            Err(TemplateError::RuntimeError(_))
+        ));
+        assert!(matches!(
+            Template::new("{{div count 0}}")
+                .unwrap()
+                .format(&vars!(count = "1")),
+            Err(TemplateError::RuntimeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_comparison_helpers() {
+        let template =
+            Template::new("{{#if (gt count 5)}}many{{else if (eq count 0)}}none{{else}}few{{/if}}")
+                .unwrap();
+        assert_eq!(template.input_variables(), vec!["count"]);
+        assert_eq!(template.format(&vars!(count = "10")).unwrap(), "many");
+        assert_eq!(template.format(&vars!(count = "0")).unwrap(), "none");
+        assert_eq!(template.format(&vars!(count = "3")).unwrap(), "few");
+
+        let threshold =
+            Template::new("{{#if (and (gte score min) (lt score max))}}pass{{else}}fail{{/if}}")
+                .unwrap();
+        assert_eq!(
+            threshold
+                .format(&vars!(score = "10", min = "9", max = "100"))
+                .unwrap(),
+            "pass"
+        );
+        assert_eq!(
+            threshold
+                .format_values(&var_values!(score = 8, min = 9, max = 100))
+                .unwrap(),
+            "fail"
+        );
+        assert_eq!(
+            Template::new("{{ne tier \"free\"}}")
+                .unwrap()
+                .format(&vars!(tier = "pro"))
+                .unwrap(),
+            "true"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, TemplateError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    Delay(Duration),
+    Missing,
+    Replace(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    faults: HashMap<String, Vec<Fault>>,
+}
+
+impl FaultInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inject(mut self, variable: impl Into<String>, fault: Fault) -> Self {
+        self.faults.entry(variable.into()).or_default().push(fault);
+        self
+    }
+
+    pub fn delay(self, variable: impl Into<String>, duration: Duration) -> Self {
+        self.inject(variable, Fault::Delay(duration))
+    }
+
+    pub fn missing(self, variable: impl Into<String>) -> Self {
+        self.inject(variable, Fault::Missing)
+    }
+
+    pub fn replace(self, variable: impl Into<String>, value: impl Into<String>) -> Self {
+        self.inject(variable, Fault::Replace(value.into()))
+    }
+
+    pub fn faults(&self, variable: &str) -> &[Fault] {
+        self.faults.get(variable).map_or(&[], Vec::as_slice)
+    }
+
+    pub async fn format_messages(
+        &self,
+        template: &ChatTemplate,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let mut faulted = variables.clone();
+
+        for variable in template.referenced_variables() {
+            for fault in self.faults(&variable) {
+                match fault {
+                    Fault::Delay(duration) => tokio::time::sleep(*duration).await,
+                    Fault::Missing => {
+                        faulted.remove(variable.as_str());
+                    }
+                    Fault::Replace(value) => {
+                        if let Some(key) = variables.keys().find(|k| **k == variable) {
+                            faulted.insert(key, value.as_str());
+                        }
+                    }
+                }
+            }
+        }
+
+        template.format_messages(&faulted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, Placeholder, System},
+    };
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(
+            System = "You help {name}.",
+            Placeholder = "{history}",
+            Human = "{question}",
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_without_faults_renders_normally() {
+        let variables = vars!(name = "Ann", history = "[]", question = "Hi?");
+        let messages = FaultInjector::new()
+            .format_messages(&template(), &variables)
+            .await
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content(), "Hi?");
+    }
+
+    #[tokio::test]
+    async fn test_missing_fault() {
+        let variables = vars!(name = "Ann", history = "[]", question = "Hi?");
+        let err = FaultInjector::new()
+            .missing("question")
+            .format_messages(&template(), &variables)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, TemplateError::MissingVariable(_)));
+    }
+
+    #[tokio::test]
+    async fn test_replace_fault() {
+        let variables = vars!(name = "Ann", history = "[]", question = "Hi?");
+        let err = FaultInjector::new()
+            .replace("history", "{not json")
+            .format_messages(&template(), &variables)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, TemplateError::MalformedTemplate(_)));
+    }
+
+    #[tokio::test]
+    async fn test_delay_fault() {
+        let variables = vars!(name = "Ann", history = "[]", question = "Hi?");
+        let injector = FaultInjector::new().delay("name", Duration::from_millis(30));
+
+        let start = Instant::now();
+        injector
+            .format_messages(&template(), &variables)
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        let result = tokio::time::timeout(
+            Duration::from_millis(5),
+            injector.format_messages(&template(), &variables),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_faults_on_unreferenced_variables_are_ignored() {
+        let variables = vars!(name = "Ann", history = "[]", question = "Hi?");
+        let injector = FaultInjector::new().missing("unused");
+
+        assert_eq!(injector.faults("unused"), &[Fault::Missing]);
+        assert!(injector
+            .format_messages(&template(), &variables)
+            .await
+            .is_ok());
+    }
+}
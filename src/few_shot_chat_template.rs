@@ -1,10 +1,15 @@
-use std::{collections::HashMap, fmt, path::Path, sync::Arc};
+#[cfg(feature = "async")]
+use std::path::Path;
+use std::{collections::HashMap, fmt, sync::Arc};
 
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "async")]
 use tokio::fs;
 
 use crate::{
-    ChatTemplate, FewShotChatTemplateConfig, FewShotTemplate, Formattable, Template, TemplateError,
+    few_shot_template::BudgetedExamples, template_format::parse_toml, ChatTemplate,
+    FewShotChatTemplateConfig, FewShotTemplate, Formattable, Template, TemplateError, TokenCounter,
+    Variables,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,10 +31,24 @@ impl FewShotChatTemplate {
         self.format(&variables)
     }
 
+    pub fn format_examples_within_budget(
+        &self,
+        max_tokens: usize,
+        counter: &dyn TokenCounter,
+    ) -> Result<BudgetedExamples, TemplateError> {
+        let variables = self.example_prompt.to_variables_map();
+        self.examples
+            .format_within_budget(&variables, max_tokens, counter)
+    }
+
     pub fn examples(&self) -> &[Template] {
         self.examples.examples()
     }
 
+    pub(crate) fn few_shot_template(&self) -> &FewShotTemplate<Template> {
+        &self.examples
+    }
+
     pub fn example_prompt(&self) -> &ChatTemplate {
         &self.example_prompt
     }
@@ -74,7 +93,7 @@ impl FewShotChatTemplate {
     }
 
     fn try_from_toml(value: &str) -> Result<Self, TemplateError> {
-        let toml_parsed: HashMap<String, String> = toml::from_str(value).map_err(|e| {
+        let toml_parsed: HashMap<String, String> = parse_toml(value).map_err(|e| {
             TemplateError::MalformedTemplate(format!("Failed to parse TOML: {}", e))
         })?;
 
@@ -91,12 +110,13 @@ impl FewShotChatTemplate {
         Ok(FewShotChatTemplate::new(examples, example_prompt))
     }
 
+    #[cfg(feature = "async")]
     pub async fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
         let toml_content = fs::read_to_string(path).await.map_err(|e| {
             TemplateError::TomlDeserializationError(format!("Failed to read TOML file: {}", e))
         })?;
 
-        let config: FewShotChatTemplateConfig = toml::from_str(&toml_content).map_err(|e| {
+        let config: FewShotChatTemplateConfig = parse_toml(&toml_content).map_err(|e| {
             TemplateError::MalformedTemplate(format!("Failed to parse TOML: {}", e))
         })?;
 
@@ -105,7 +125,7 @@ impl FewShotChatTemplate {
 }
 
 impl Formattable for FewShotChatTemplate {
-    fn format(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError> {
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError> {
         let examples = self.examples.format(variables)?;
         if examples.is_empty() {
             Ok(String::new())
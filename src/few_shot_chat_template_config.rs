@@ -54,6 +54,7 @@ mod tests {
     use super::*;
     use std::convert::TryInto;
 
+    #[cfg(feature = "toml")]
     #[test]
     fn test_few_shot_chat_template_config_deserialization() {
         let toml_str = r#"
@@ -146,6 +147,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "toml")]
     #[test]
     fn test_invalid_toml_deserialization() {
         let invalid_toml_str = r#"
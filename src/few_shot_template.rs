@@ -1,11 +1,15 @@
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "async")]
 use tokio::fs;
 
-use crate::template_format::TemplateError;
-use crate::{Formattable, Templatable, Template};
+use crate::template_format::{parse_toml, TemplateError};
+use crate::{ExampleSelector, Formattable, Templatable, Template, TokenCounter, Variables};
 use std::collections::HashMap;
+use std::hash::Hash;
+#[cfg(feature = "async")]
 use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FewShotTemplate<T: Templatable + Formattable> {
@@ -15,6 +19,8 @@ pub struct FewShotTemplate<T: Templatable + Formattable> {
     prefix: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     suffix: Option<T>,
+    #[serde(skip)]
+    selector: Option<Arc<dyn ExampleSelector>>,
 }
 
 impl<T> Default for FewShotTemplate<T>
@@ -27,6 +33,7 @@ where
             example_separator: Self::DEFAULT_EXAMPLE_SEPARATOR.to_string(),
             prefix: None,
             suffix: None,
+            selector: None,
         }
     }
 }
@@ -55,6 +62,7 @@ where
             example_separator: example_separator.into(),
             prefix,
             suffix,
+            selector: None,
         }
     }
 
@@ -78,6 +86,61 @@ where
         self.suffix.as_ref()
     }
 
+    pub fn selector(&self) -> Option<&dyn ExampleSelector> {
+        self.selector.as_deref()
+    }
+
+    pub fn with_selector(mut self, selector: impl ExampleSelector + 'static) -> Self {
+        self.selector = Some(Arc::new(selector));
+        self
+    }
+
+    pub fn order_by_key<K, F>(mut self, key: F) -> Self
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.examples.sort_by_key(key);
+        self
+    }
+
+    pub fn balance_by_label<L, F>(mut self, label: F, max_per_label: Option<usize>) -> Self
+    where
+        L: Eq + Hash,
+        F: Fn(&T) -> L,
+    {
+        let mut label_order: HashMap<L, usize> = HashMap::new();
+        let mut groups: Vec<Vec<T>> = Vec::new();
+
+        for example in self.examples.drain(..) {
+            let next_index = label_order.len();
+            let index = *label_order.entry(label(&example)).or_insert(next_index);
+            if index == groups.len() {
+                groups.push(Vec::new());
+            }
+            groups[index].push(example);
+        }
+
+        let rounds = groups
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or_default()
+            .min(max_per_label.unwrap_or(usize::MAX));
+        let mut groups: Vec<_> = groups.into_iter().map(Vec::into_iter).collect();
+
+        for _ in 0..rounds {
+            for group in groups.iter_mut() {
+                if let Some(example) = group.next() {
+                    self.examples.push(example);
+                }
+            }
+        }
+
+        self
+    }
+
+    #[cfg(feature = "async")]
     pub async fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
         let toml_content = fs::read_to_string(path).await.map_err(|e| {
             TemplateError::TomlDeserializationError(format!("Failed to read TOML file: {}", e))
@@ -87,8 +150,80 @@ where
     }
 }
 
-impl Formattable for FewShotTemplate<Template> {
-    fn format(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetedExamples {
+    pub text: String,
+    pub examples_used: usize,
+    pub tokens: usize,
+}
+
+impl FewShotTemplate<Template> {
+    pub(crate) fn try_map(
+        &self,
+        example: impl Fn(&Template) -> Result<Template, TemplateError>,
+        other: impl Fn(&Template) -> Result<Template, TemplateError>,
+    ) -> Result<Self, TemplateError> {
+        Ok(Self {
+            examples: self
+                .examples
+                .iter()
+                .map(example)
+                .collect::<Result<_, _>>()?,
+            example_separator: self.example_separator.clone(),
+            prefix: self.prefix.as_ref().map(&other).transpose()?,
+            suffix: self.suffix.as_ref().map(&other).transpose()?,
+            selector: self.selector.clone(),
+        })
+    }
+
+    pub fn from_example_sets<'a, I>(example: &Template, example_sets: I) -> Self
+    where
+        I: IntoIterator<Item = HashMap<&'a str, &'a str>>,
+    {
+        let examples = example_sets
+            .into_iter()
+            .map(|example_set| {
+                example_set
+                    .into_iter()
+                    .fold(example.clone(), |template, (name, value)| {
+                        template.with_partial(name, value)
+                    })
+            })
+            .collect();
+
+        Self::new(examples)
+    }
+
+    pub fn format_within_budget(
+        &self,
+        variables: &HashMap<&str, &str>,
+        max_tokens: usize,
+        counter: &dyn TokenCounter,
+    ) -> Result<BudgetedExamples, TemplateError> {
+        let (prefix_str, formatted_examples, suffix_str) = self.format_parts(variables)?;
+
+        let mut text = self.join_parts(&prefix_str, &[], &suffix_str);
+        let mut examples_used = 0;
+        for count in 1..=formatted_examples.len() {
+            let candidate = self.join_parts(&prefix_str, &formatted_examples[..count], &suffix_str);
+            if counter.count(&candidate) > max_tokens {
+                break;
+            }
+            text = candidate;
+            examples_used = count;
+        }
+
+        Ok(BudgetedExamples {
+            tokens: counter.count(&text),
+            text,
+            examples_used,
+        })
+    }
+
+    fn format_parts(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<(String, Vec<String>, String), TemplateError> {
         let prefix_str = if let Some(ref prefix_template) = self.prefix {
             prefix_template.format(variables)?
         } else {
@@ -102,7 +237,13 @@ impl Formattable for FewShotTemplate<Template> {
             formatted_examples.push(formatted_example);
         }
 
-        let examples_str = formatted_examples.join(&self.example_separator);
+        if let Some(selector) = &self.selector {
+            formatted_examples = selector
+                .select(&formatted_examples, variables)
+                .into_iter()
+                .filter_map(|index| formatted_examples.get(index).cloned())
+                .collect();
+        }
 
         let suffix_str = if let Some(ref suffix_template) = self.suffix {
             suffix_template.format(variables)?
@@ -110,21 +251,38 @@ impl Formattable for FewShotTemplate<Template> {
             String::new()
         };
 
+        Ok((prefix_str, formatted_examples, suffix_str))
+    }
+
+    fn join_parts(
+        &self,
+        prefix_str: &str,
+        formatted_examples: &[String],
+        suffix_str: &str,
+    ) -> String {
+        let examples_str = formatted_examples.join(&self.example_separator);
+
         let mut result_parts = Vec::new();
 
         if !prefix_str.is_empty() {
             result_parts.push(prefix_str);
         }
         if !examples_str.is_empty() {
-            result_parts.push(examples_str);
+            result_parts.push(&examples_str);
         }
         if !suffix_str.is_empty() {
             result_parts.push(suffix_str);
         }
 
-        let result = result_parts.join(&self.example_separator);
+        result_parts.join(&self.example_separator)
+    }
+}
 
-        Ok(result)
+impl Formattable for FewShotTemplate<Template> {
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError> {
+        let (prefix_str, formatted_examples, suffix_str) =
+            self.format_parts(&variables.as_map())?;
+        Ok(self.join_parts(&prefix_str, &formatted_examples, &suffix_str))
     }
 }
 
@@ -137,6 +295,7 @@ where
     example_separator: String,
     prefix: Option<T>,
     suffix: Option<T>,
+    selector: Option<Arc<dyn ExampleSelector>>,
 }
 
 impl<T> Default for FewShotTemplateBuilder<T>
@@ -149,6 +308,7 @@ where
             suffix: None,
             example_separator: FewShotTemplate::<T>::DEFAULT_EXAMPLE_SEPARATOR.to_string(),
             examples: Vec::new(),
+            selector: None,
         }
     }
 }
@@ -189,12 +349,18 @@ where
         self
     }
 
+    pub fn selector(mut self, selector: impl ExampleSelector + 'static) -> Self {
+        self.selector = Some(Arc::new(selector));
+        self
+    }
+
     pub fn build(self) -> FewShotTemplate<T> {
         FewShotTemplate {
             examples: self.examples,
             example_separator: self.example_separator,
             prefix: self.prefix,
             suffix: self.suffix,
+            selector: self.selector,
         }
     }
 }
@@ -211,7 +377,7 @@ where
                 TemplateError::MalformedTemplate(format!("JSON deserialization error: {}", e))
             })
         } else {
-            toml::from_str(&value).map_err(|e| {
+            parse_toml(&value).map_err(|e| {
                 TemplateError::MalformedTemplate(format!("TOML deserialization error: {}", e))
             })
         }
@@ -223,7 +389,7 @@ mod tests {
     use super::*;
     use crate::template_format::TemplateError;
     use crate::vars;
-    use crate::Template;
+    use crate::{HeuristicCounter, Template};
 
     #[test]
     fn test_few_shot_template_with_prefix_suffix_and_examples() {
@@ -637,6 +803,99 @@ Question: Who was the father of Mary Ball Washington?
         assert_eq!(formatted_output_trimmed, expected_output_trimmed);
     }
 
+    fn labeled_examples() -> Vec<Template> {
+        [
+            "positive: great product",
+            "positive: love it",
+            "positive: works perfectly",
+            "negative: broke in a day",
+            "neutral: it is a box",
+            "negative: waste of money",
+        ]
+        .iter()
+        .map(|text| Template::new(text).unwrap())
+        .collect()
+    }
+
+    fn label_of(example: &Template) -> String {
+        example.template().split(':').next().unwrap().to_string()
+    }
+
+    fn texts(few_shot_template: &FewShotTemplate<Template>) -> Vec<&str> {
+        few_shot_template
+            .examples()
+            .iter()
+            .map(|example| example.template())
+            .collect()
+    }
+
+    #[test]
+    fn test_few_shot_template_balance_by_label() {
+        let few_shot_template =
+            FewShotTemplate::new(labeled_examples()).balance_by_label(label_of, None);
+
+        assert_eq!(
+            texts(&few_shot_template),
+            vec![
+                "positive: great product",
+                "negative: broke in a day",
+                "neutral: it is a box",
+                "positive: love it",
+                "negative: waste of money",
+                "positive: works perfectly",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_few_shot_template_balance_by_label_with_limit() {
+        let few_shot_template =
+            FewShotTemplate::new(labeled_examples()).balance_by_label(label_of, Some(1));
+
+        assert_eq!(
+            texts(&few_shot_template),
+            vec![
+                "positive: great product",
+                "negative: broke in a day",
+                "neutral: it is a box",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_few_shot_template_order_by_key() {
+        let few_shot_template = FewShotTemplate::new(labeled_examples())
+            .order_by_key(|example| example.template().len());
+
+        assert_eq!(
+            texts(&few_shot_template),
+            vec![
+                "positive: love it",
+                "neutral: it is a box",
+                "positive: great product",
+                "negative: broke in a day",
+                "negative: waste of money",
+                "positive: works perfectly",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_few_shot_template_balance_then_order_is_stable() {
+        let few_shot_template = FewShotTemplate::new(labeled_examples())
+            .balance_by_label(label_of, Some(1))
+            .order_by_key(label_of);
+
+        assert_eq!(
+            texts(&few_shot_template),
+            vec![
+                "negative: broke in a day",
+                "neutral: it is a box",
+                "positive: great product",
+            ]
+        );
+    }
+
     #[test]
     fn test_serialize_few_shot_template() {
         let prefix_template = Template::new("This is the prefix. Topic: {topic}").unwrap();
@@ -808,4 +1067,81 @@ Question: Who was the father of Mary Ball Washington?
             }
         }
     }
+
+    #[test]
+    fn test_format_within_budget_uses_as_many_examples_as_fit() {
+        let few_shot_template = FewShotTemplate::builder()
+            .prefix(Template::new("Classify {kind}:").unwrap())
+            .example(Template::new("Q: Is the sky blue?\nA: yes").unwrap())
+            .example(Template::new("Q: Is fire cold?\nA: no").unwrap())
+            .example(Template::new("Q: Do fish swim?\nA: yes").unwrap())
+            .build();
+        let variables = vars!(kind = "facts");
+
+        let all = few_shot_template
+            .format_within_budget(&variables, 1000, &HeuristicCounter)
+            .unwrap();
+        assert_eq!(all.examples_used, 3);
+        assert_eq!(all.text, few_shot_template.format(&variables).unwrap());
+
+        let two = few_shot_template
+            .format_within_budget(&variables, all.tokens - 1, &HeuristicCounter)
+            .unwrap();
+        assert_eq!(two.examples_used, 2);
+        assert!(two.text.ends_with("A: no"));
+        assert!(two.tokens < all.tokens);
+
+        let none = few_shot_template
+            .format_within_budget(&variables, 1, &HeuristicCounter)
+            .unwrap();
+        assert_eq!(none.examples_used, 0);
+        assert_eq!(none.text, "Classify facts:");
+
+        let words = |text: &str| text.split_whitespace().count();
+        let by_words = few_shot_template
+            .format_within_budget(&variables, 9, &words)
+            .unwrap();
+        assert_eq!(by_words.examples_used, 1);
+        assert_eq!(by_words.tokens, 9);
+    }
+
+    #[test]
+    fn test_from_example_sets_with_selector() {
+        let example = Template::new("Input: {input}\nOutput: {output}").unwrap();
+        let few_shot_template = FewShotTemplate::from_example_sets(
+            &example,
+            vec![
+                vars!(input = "happy", output = "sad"),
+                vars!(input = "tall", output = "short"),
+                vars!(input = "sunny", output = "gloomy"),
+            ],
+        );
+        assert_eq!(few_shot_template.examples().len(), 3);
+        assert!(few_shot_template.selector().is_none());
+
+        let variables = vars!(adjective = "big");
+        assert_eq!(
+            few_shot_template.format(&variables).unwrap(),
+            "Input: happy\nOutput: sad\n\nInput: tall\nOutput: short\n\nInput: sunny\nOutput: gloomy"
+        );
+
+        let embedder = |text: &str| vec![text.contains("tall") as u8 as f32, 1.0];
+        let semantic = few_shot_template
+            .clone()
+            .with_selector(crate::SemanticSelector::new(embedder, 1));
+        assert_eq!(
+            semantic.format(&vars!(adjective = "tall")).unwrap(),
+            "Input: tall\nOutput: short"
+        );
+
+        let limited = FewShotTemplate::builder()
+            .prefix(Template::new("Give the antonym of {adjective}.").unwrap())
+            .examples(few_shot_template.examples().to_vec())
+            .selector(crate::LengthBasedSelector::new(14))
+            .build();
+        assert_eq!(
+            limited.format(&variables).unwrap(),
+            "Give the antonym of big.\n\nInput: happy\nOutput: sad\n\nInput: tall\nOutput: short"
+        );
+    }
 }
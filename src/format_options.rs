@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingVariables {
+    #[default]
+    Error,
+    Empty,
+    KeepPlaceholder,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FormatOptions {
+    #[serde(default)]
+    pub missing_vars: MissingVariables,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub render_optional_placeholders: bool,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lenient() -> Self {
+        Self::new().missing_vars(MissingVariables::Empty)
+    }
+
+    pub fn missing_vars(mut self, missing_vars: MissingVariables) -> Self {
+        self.missing_vars = missing_vars;
+        self
+    }
+
+    pub fn render_optional_placeholders(mut self, render: bool) -> Self {
+        self.render_optional_placeholders = render;
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.missing_vars == MissingVariables::Error
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_options_serde() {
+        let options = FormatOptions::new().missing_vars(MissingVariables::KeepPlaceholder);
+        let json = serde_json::to_value(options).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "missing_vars": "keep_placeholder" })
+        );
+
+        let restored: FormatOptions = serde_json::from_str("{}").unwrap();
+        assert!(restored.is_strict());
+        assert_eq!(
+            FormatOptions::lenient().missing_vars,
+            MissingVariables::Empty
+        );
+        assert!(restored.is_default());
+
+        let options = FormatOptions::new().render_optional_placeholders(true);
+        assert!(options.is_strict());
+        assert!(!options.is_default());
+        assert_eq!(
+            serde_json::to_value(options).unwrap(),
+            serde_json::json!({ "missing_vars": "error", "render_optional_placeholders": true })
+        );
+    }
+}
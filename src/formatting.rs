@@ -1,8 +1,8 @@
+use crate::prompt_vars::Variables;
 use crate::template_format::{TemplateError, TemplateFormat};
-use std::collections::HashMap;
 
 pub trait Formattable {
-    fn format(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError>;
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError>;
 }
 
 pub trait Templatable: Formattable {
@@ -0,0 +1,533 @@
+use std::{
+    ffi::OsString,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{
+    lineage::fingerprint, prompt_cache::file_version, ChatTemplate, MessageLike, PromptCache,
+    PromptSet, Templatable, Template, TemplateError,
+};
+
+const PROMPT_EXTENSION: &str = "json";
+const TMP_SUFFIX: &str = ".tmp";
+const LOCK_SUFFIX: &str = ".lock";
+const JOURNAL_FILE: &str = "journal.log";
+
+fn store_error(action: &str, e: impl std::fmt::Display) -> TemplateError {
+    TemplateError::MalformedTemplate(format!("Failed to {} prompt store: {}", action, e))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+async fn sync_dir(path: &Path) {
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = fs::File::open(dir).await {
+            let _ = dir.sync_all().await;
+        }
+    }
+}
+
+fn reparse(template: &ChatTemplate) -> Result<(), TemplateError> {
+    for message_like in &template.messages {
+        if let MessageLike::RolePromptTemplate(_, template) = message_like {
+            Template::new(template.template())?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn atomic_write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = with_suffix(path, TMP_SUFFIX);
+    let mut file = fs::File::create(&tmp).await?;
+    file.write_all(bytes).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    if let Err(e) = fs::rename(&tmp, path).await {
+        let _ = fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    sync_dir(path).await;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub valid: Vec<String>,
+    pub corrupt: Vec<(String, String)>,
+    pub leftovers: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.corrupt.is_empty() && self.leftovers.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FsPromptStore {
+    root: PathBuf,
+    journal: bool,
+}
+
+impl FsPromptStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            journal: false,
+        }
+    }
+
+    pub fn with_journal(mut self) -> Self {
+        self.journal = true;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path_for(&self, name: &str) -> Result<PathBuf, TemplateError> {
+        let relative = Path::new(name);
+        let valid = !name.is_empty()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !valid {
+            return Err(TemplateError::MalformedTemplate(format!(
+                "Invalid prompt name '{}'",
+                name
+            )));
+        }
+
+        let mut path = self.root.join(relative).into_os_string();
+        path.push(".");
+        path.push(PROMPT_EXTENSION);
+        Ok(PathBuf::from(path))
+    }
+
+    async fn journal(&self, entry: &str, name: &str) -> Result<(), TemplateError> {
+        if !self.journal {
+            return Ok(());
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join(JOURNAL_FILE))
+            .await
+            .map_err(|e| store_error("journal", e))?;
+        file.write_all(format!("{}\t{}\n", entry, name).as_bytes())
+            .await
+            .map_err(|e| store_error("journal", e))?;
+        file.sync_all().await.map_err(|e| store_error("journal", e))
+    }
+
+    pub async fn put(&self, name: &str, template: &ChatTemplate) -> Result<(), TemplateError> {
+        let path = self.path_for(name)?;
+        let content = serde_json::to_vec_pretty(template).map_err(|e| store_error("write", e))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(|e| store_error("write", e))?;
+        }
+
+        self.journal("begin", name).await?;
+        PromptCache::global().invalidate_name(&path.to_string_lossy());
+        atomic_write(&path, &content)
+            .await
+            .map_err(|e| store_error("write", e))?;
+        self.journal("commit", name).await
+    }
+
+    pub async fn revision(&self, name: &str) -> Result<Option<String>, TemplateError> {
+        match fs::read(self.path_for(name)?).await {
+            Ok(content) => Ok(Some(fingerprint(&content))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(store_error("read", e)),
+        }
+    }
+
+    pub async fn put_if(
+        &self,
+        name: &str,
+        template: &ChatTemplate,
+        expected: Option<&str>,
+    ) -> Result<String, TemplateError> {
+        let path = self.path_for(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(|e| store_error("write", e))?;
+        }
+
+        let lock = with_suffix(&path, LOCK_SUFFIX);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock)
+            .await
+        {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(TemplateError::VersionConflict(format!(
+                    "'{}' is being written by another editor",
+                    name
+                )))
+            }
+            Err(e) => return Err(store_error("lock", e)),
+        }
+
+        let result = async {
+            let actual = self.revision(name).await?;
+            if actual.as_deref() != expected {
+                return Err(TemplateError::VersionConflict(format!(
+                    "'{}' expected {} but found {}",
+                    name,
+                    expected.unwrap_or("absent"),
+                    actual.as_deref().unwrap_or("absent")
+                )));
+            }
+            self.put(name, template).await?;
+            Ok(self.revision(name).await?.unwrap_or_default())
+        }
+        .await;
+
+        let _ = fs::remove_file(&lock).await;
+        result
+    }
+
+    pub async fn get(&self, name: &str) -> Result<ChatTemplate, TemplateError> {
+        let path = self.path_for(name)?;
+        let version = fs::metadata(&path)
+            .await
+            .and_then(|metadata| file_version(&metadata))
+            .map_err(|e| store_error("read", e))?;
+        let key = path.to_string_lossy();
+        if let Some(template) = PromptCache::global().get(&key, &version) {
+            return Ok((*template).clone());
+        }
+
+        let content = fs::read(&path).await.map_err(|e| store_error("read", e))?;
+        let template: ChatTemplate =
+            serde_json::from_slice(&content).map_err(|e| store_error("read", e))?;
+        Ok((*PromptCache::global().insert(key, version, template)).clone())
+    }
+
+    pub async fn remove(&self, name: &str) -> Result<(), TemplateError> {
+        let path = self.path_for(name)?;
+        self.journal("begin", name).await?;
+        PromptCache::global().invalidate_name(&path.to_string_lossy());
+        fs::remove_file(&path)
+            .await
+            .map_err(|e| store_error("remove", e))?;
+        sync_dir(&path).await;
+        self.journal("commit", name).await
+    }
+
+    async fn files(&self) -> Result<Vec<PathBuf>, TemplateError> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(store_error("list", e)),
+            };
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| store_error("list", e))?
+            {
+                let path = entry.path();
+                if entry
+                    .file_type()
+                    .await
+                    .map_err(|e| store_error("list", e))?
+                    .is_dir()
+                {
+                    pending.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    fn name_for(&self, path: &Path) -> Option<String> {
+        if path.extension()? != PROMPT_EXTENSION {
+            return None;
+        }
+        let relative = path.strip_prefix(&self.root).ok()?.with_extension("");
+        let parts: Vec<&str> = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<_>>()?;
+        Some(parts.join("/"))
+    }
+
+    pub async fn names(&self) -> Result<Vec<String>, TemplateError> {
+        Ok(self
+            .files()
+            .await?
+            .iter()
+            .filter_map(|path| self.name_for(path))
+            .collect())
+    }
+
+    pub async fn verify(&self) -> Result<VerifyReport, TemplateError> {
+        let mut report = VerifyReport::default();
+
+        for path in self.files().await? {
+            if path.to_string_lossy().ends_with(TMP_SUFFIX) {
+                report.leftovers.push(path);
+                continue;
+            }
+            let Some(name) = self.name_for(&path) else {
+                continue;
+            };
+            match self
+                .get(&name)
+                .await
+                .and_then(|template| reparse(&template))
+            {
+                Ok(()) => report.valid.push(name),
+                Err(e) => report.corrupt.push((name, e.to_string())),
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub async fn recover(&self) -> Result<Vec<String>, TemplateError> {
+        let journal = self.root.join(JOURNAL_FILE);
+        let content = match fs::read_to_string(&journal).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(store_error("recover", e)),
+        };
+
+        let mut open: Vec<String> = Vec::new();
+        for line in content.lines() {
+            match line.split_once('\t') {
+                Some(("begin", name)) => open.push(name.to_string()),
+                Some(("commit", name)) => open.retain(|pending| pending != name),
+                _ => {}
+            }
+        }
+
+        for path in self.files().await? {
+            let path_str = path.to_string_lossy();
+            if path_str.ends_with(TMP_SUFFIX) || path_str.ends_with(LOCK_SUFFIX) {
+                fs::remove_file(&path)
+                    .await
+                    .map_err(|e| store_error("recover", e))?;
+            }
+        }
+
+        if !content.is_empty() {
+            atomic_write(&journal, b"")
+                .await
+                .map_err(|e| store_error("recover", e))?;
+        }
+
+        open.sort();
+        open.dedup();
+        Ok(open)
+    }
+
+    pub async fn save_set(&self, prompts: &PromptSet) -> Result<(), TemplateError> {
+        for name in prompts.names() {
+            let template = prompts.get(name).expect("name comes from the set");
+            self.put(name, template).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn load_set(&self) -> Result<PromptSet, TemplateError> {
+        let mut prompts = PromptSet::new();
+        for name in self.names().await? {
+            let template = self.get(&name).await?;
+            prompts.insert(name, template);
+        }
+        Ok(prompts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats,
+        Role::{Human, System},
+    };
+
+    fn temp_root(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("promptforge-store-{}-{}", test, std::process::id()))
+    }
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(
+            System = "You process refunds.",
+            Human = "Refund order {order_id}.",
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_put_get_and_names() {
+        let root = temp_root("put");
+        let store = FsPromptStore::new(&root);
+
+        store.put("billing/refunds", &template()).await.unwrap();
+        store.put("greeting", &template()).await.unwrap();
+
+        assert_eq!(
+            store.names().await.unwrap(),
+            vec!["billing/refunds", "greeting"]
+        );
+        assert_eq!(
+            store.get("billing/refunds").await.unwrap().messages.len(),
+            2
+        );
+        assert!(store.put("../escape", &template()).await.is_err());
+
+        let prompts = store.load_set().await.unwrap();
+        assert_eq!(prompts.names(), vec!["billing/refunds", "greeting"]);
+
+        store.remove("greeting").await.unwrap();
+        assert_eq!(store.names().await.unwrap(), vec!["billing/refunds"]);
+
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_is_cached_until_written() {
+        let root = temp_root("cache");
+        let store = FsPromptStore::new(&root);
+        store.put("greeting", &template()).await.unwrap();
+
+        let path = root.join("greeting.json");
+        let key = path.to_string_lossy();
+        let version = file_version(&fs::metadata(&path).await.unwrap()).unwrap();
+        assert!(PromptCache::global().get(&key, &version).is_none());
+
+        store.get("greeting").await.unwrap();
+        assert!(PromptCache::global().get(&key, &version).is_some());
+
+        store.put("greeting", &template()).await.unwrap();
+        assert!(PromptCache::global().get(&key, &version).is_none());
+
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_corrupt_files() {
+        let root = temp_root("verify");
+        let store = FsPromptStore::new(&root);
+        store.put("good", &template()).await.unwrap();
+        store.put("edited", &template()).await.unwrap();
+        let edited = fs::read_to_string(root.join("edited.json")).await.unwrap();
+        fs::write(
+            root.join("edited.json"),
+            edited.replace("{order_id}", "{order_id"),
+        )
+        .await
+        .unwrap();
+        fs::write(root.join("broken.json"), b"{\"messages\": [")
+            .await
+            .unwrap();
+        fs::write(root.join("good.json.tmp"), b"partial")
+            .await
+            .unwrap();
+
+        let report = store.verify().await.unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.valid, vec!["good"]);
+        let corrupt: Vec<&str> = report
+            .corrupt
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(corrupt, vec!["broken", "edited"]);
+        assert_eq!(report.leftovers, vec![root.join("good.json.tmp")]);
+
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_journal_recovery() {
+        let root = temp_root("journal");
+        let store = FsPromptStore::new(&root).with_journal();
+        store.put("committed", &template()).await.unwrap();
+
+        fs::write(root.join("interrupted.json.tmp"), b"{")
+            .await
+            .unwrap();
+        let mut journal = fs::OpenOptions::new()
+            .append(true)
+            .open(root.join(JOURNAL_FILE))
+            .await
+            .unwrap();
+        journal.write_all(b"begin\tinterrupted\n").await.unwrap();
+
+        assert_eq!(store.recover().await.unwrap(), vec!["interrupted"]);
+        let report = store.verify().await.unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.valid, vec!["committed"]);
+        assert!(store.recover().await.unwrap().is_empty());
+
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_put_if_detects_concurrent_edits() {
+        let root = temp_root("cas");
+        let store = FsPromptStore::new(&root);
+
+        let first = store.put_if("refunds", &template(), None).await.unwrap();
+        assert_eq!(
+            store.revision("refunds").await.unwrap(),
+            Some(first.clone())
+        );
+
+        let edited = template().with_strict_placeholders();
+        let second = store
+            .put_if("refunds", &edited, Some(&first))
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+
+        let stale = store.put_if("refunds", &template(), Some(&first)).await;
+        assert!(matches!(stale, Err(TemplateError::VersionConflict(_))));
+        let created = store.put_if("refunds", &template(), None).await;
+        assert!(matches!(created, Err(TemplateError::VersionConflict(_))));
+
+        fs::write(root.join("refunds.json.lock"), b"")
+            .await
+            .unwrap();
+        let locked = store.put_if("refunds", &template(), Some(&second)).await;
+        assert!(
+            matches!(locked, Err(TemplateError::VersionConflict(msg)) if msg.contains("another editor"))
+        );
+        assert!(store.recover().await.unwrap().is_empty());
+        store
+            .put_if("refunds", &template(), Some(&second))
+            .await
+            .unwrap();
+
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+use messageforge::{BaseMessage, MessageEnum};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, TemplateError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlossaryViolation {
+    MissingTerm(String),
+    BannedTerm { message_index: usize, term: String },
+}
+
+impl fmt::Display for GlossaryViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlossaryViolation::MissingTerm(term) => {
+                write!(f, "required term {:?} does not appear", term)
+            }
+            GlossaryViolation::BannedTerm {
+                message_index,
+                term,
+            } => write!(f, "message {} uses banned term {:?}", message_index, term),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Glossary {
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub required: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub banned: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub case_sensitive: bool,
+}
+
+impl Glossary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn require(mut self, term: impl Into<String>) -> Self {
+        self.required.insert(term.into());
+        self
+    }
+
+    pub fn ban(mut self, term: impl Into<String>) -> Self {
+        self.banned.insert(term.into());
+        self
+    }
+
+    pub fn case_sensitive(mut self) -> Self {
+        self.case_sensitive = true;
+        self
+    }
+
+    pub fn check(&self, messages: &[Arc<MessageEnum>]) -> Vec<GlossaryViolation> {
+        let contents: Vec<&str> = messages.iter().map(|message| message.content()).collect();
+        self.check_contents(&contents)
+    }
+
+    pub fn check_text(&self, text: &str) -> Vec<GlossaryViolation> {
+        self.check_contents(&[text])
+    }
+
+    fn check_contents(&self, contents: &[&str]) -> Vec<GlossaryViolation> {
+        let mut violations: Vec<GlossaryViolation> = self
+            .required
+            .iter()
+            .filter(|term| !contents.iter().any(|content| self.mentions(content, term)))
+            .map(|term| GlossaryViolation::MissingTerm(term.clone()))
+            .collect();
+
+        for (message_index, content) in contents.iter().enumerate() {
+            violations.extend(
+                self.banned
+                    .iter()
+                    .filter(|term| self.mentions(content, term))
+                    .map(|term| GlossaryViolation::BannedTerm {
+                        message_index,
+                        term: term.clone(),
+                    }),
+            );
+        }
+        violations
+    }
+
+    fn mentions(&self, text: &str, term: &str) -> bool {
+        let Ok(re) = RegexBuilder::new(&regex::escape(term))
+            .case_insensitive(!self.case_sensitive)
+            .build()
+        else {
+            return false;
+        };
+
+        let mentioned = re.find_iter(text).any(|found| {
+            let before = text[..found.start()].chars().next_back();
+            let after = text[found.end()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        });
+        mentioned
+    }
+}
+
+impl ChatTemplate {
+    pub fn with_glossary(mut self, glossary: Glossary) -> Self {
+        self.meta.glossary = Some(glossary);
+        self
+    }
+
+    pub fn check_terminology(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<GlossaryViolation>, TemplateError> {
+        let Some(glossary) = &self.meta.glossary else {
+            return Ok(Vec::new());
+        };
+        Ok(glossary.check(&self.format_messages(variables)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, System},
+    };
+
+    fn glossary() -> Glossary {
+        Glossary::new()
+            .require("Acme Cloud")
+            .ban("cheap")
+            .ban("guarantee")
+    }
+
+    #[test]
+    fn test_check_text() {
+        let glossary = glossary();
+        assert!(glossary
+            .check_text("Try ACME cloud today, an affordable option.")
+            .is_empty());
+        assert_eq!(
+            glossary.check_text("We guarantee a cheaper price."),
+            vec![
+                GlossaryViolation::MissingTerm("Acme Cloud".to_string()),
+                GlossaryViolation::BannedTerm {
+                    message_index: 0,
+                    term: "guarantee".to_string()
+                },
+            ]
+        );
+
+        let strict = Glossary::new().require("Acme Cloud").case_sensitive();
+        assert_eq!(strict.check_text("acme cloud").len(), 1);
+    }
+
+    #[test]
+    fn test_check_terminology_per_template() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You sell Acme Cloud.",
+            Human = "{question}",
+        ))
+        .unwrap()
+        .with_glossary(glossary());
+
+        assert!(template
+            .check_terminology(&vars!(question = "What does it cost?"))
+            .unwrap()
+            .is_empty());
+
+        let violations = template
+            .check_terminology(&vars!(question = "Is it cheap?"))
+            .unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].to_string(),
+            "message 1 uses banned term \"cheap\""
+        );
+
+        let json = serde_json::to_value(&template).unwrap();
+        assert_eq!(json["glossary"]["banned"][0], "cheap");
+        assert!(ChatTemplate::default()
+            .check_terminology(&vars!())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use messageforge::{BaseMessage, MessageEnum};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "jinja")]
+use serde_json::Value;
+
+use crate::{assertions::message_role, Role, TemplateError};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HfMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl HfMessage {
+    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: content.into(),
+        }
+    }
+}
+
+pub fn hf_role(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::Developer => "developer",
+        Role::Human => "user",
+        Role::Ai => "assistant",
+        Role::Tool => "tool",
+        Role::Placeholder => "placeholder",
+        Role::FewShotPrompt => "fewshotprompt",
+    }
+}
+
+pub fn to_hf_messages(messages: &[Arc<MessageEnum>]) -> Vec<HfMessage> {
+    messages
+        .iter()
+        .map(|message| {
+            let role = message_role(message).map_or_else(
+                || message.message_type().as_str().to_lowercase(),
+                |role| hf_role(role).to_string(),
+            );
+            HfMessage::new(role, message.content())
+        })
+        .collect()
+}
+
+pub fn from_hf_messages(messages: &[HfMessage]) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+    messages
+        .iter()
+        .map(|message| {
+            let role = match message.role.as_str() {
+                "user" => Role::Human,
+                "assistant" => Role::Ai,
+                other => Role::try_from(other)?,
+            };
+            Ok(role.to_message(&message.content)?)
+        })
+        .collect()
+}
+
+#[cfg(feature = "jinja")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HfChatTemplate {
+    source: String,
+    bos_token: String,
+    eos_token: String,
+}
+
+#[cfg(feature = "jinja")]
+impl HfChatTemplate {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            bos_token: String::new(),
+            eos_token: String::new(),
+        }
+    }
+
+    pub fn from_tokenizer_config(config: &str) -> Result<Self, TemplateError> {
+        let config: Value = serde_json::from_str(config).map_err(|e| {
+            TemplateError::MalformedTemplate(format!("Failed to parse tokenizer config: {}", e))
+        })?;
+
+        let source = match config.get("chat_template") {
+            Some(Value::String(source)) => source.clone(),
+            Some(Value::Array(templates)) => templates
+                .iter()
+                .find(|t| t.get("name").and_then(Value::as_str) == Some("default"))
+                .and_then(|t| t.get("template"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    TemplateError::MalformedTemplate(
+                        "Tokenizer config has no default chat_template.".to_string(),
+                    )
+                })?,
+            _ => {
+                return Err(TemplateError::MalformedTemplate(
+                    "Tokenizer config has no chat_template.".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self::new(source)
+            .with_bos_token(Self::special_token(&config, "bos_token"))
+            .with_eos_token(Self::special_token(&config, "eos_token")))
+    }
+
+    fn special_token(config: &Value, key: &str) -> String {
+        match config.get(key) {
+            Some(Value::String(token)) => token.clone(),
+            Some(token) => token
+                .get("content")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            None => String::new(),
+        }
+    }
+
+    pub fn with_bos_token(mut self, bos_token: impl Into<String>) -> Self {
+        self.bos_token = bos_token.into();
+        self
+    }
+
+    pub fn with_eos_token(mut self, eos_token: impl Into<String>) -> Self {
+        self.eos_token = eos_token.into();
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn apply(
+        &self,
+        messages: &[Arc<MessageEnum>],
+        add_generation_prompt: bool,
+    ) -> Result<String, TemplateError> {
+        self.apply_hf(&to_hf_messages(messages), add_generation_prompt)
+    }
+
+    pub fn apply_hf(
+        &self,
+        messages: &[HfMessage],
+        add_generation_prompt: bool,
+    ) -> Result<String, TemplateError> {
+        let to_template_error =
+            |e: minijinja::Error| TemplateError::MalformedTemplate(format!("Jinja error: {}", e));
+
+        let mut env = minijinja::Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.add_function("raise_exception", |message: String| -> Result<(), _> {
+            Err(minijinja::Error::new(
+                minijinja::ErrorKind::InvalidOperation,
+                message,
+            ))
+        });
+
+        let template = env
+            .template_from_str(&self.source)
+            .map_err(to_template_error)?;
+
+        template
+            .render(minijinja::context! {
+                messages => messages,
+                add_generation_prompt => add_generation_prompt,
+                bos_token => &self.bos_token,
+                eos_token => &self.eos_token,
+            })
+            .map_err(to_template_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Arc<MessageEnum>> {
+        vec![
+            Role::System.to_message("Be concise.").unwrap(),
+            Role::Human.to_message("Hi.").unwrap(),
+            Role::Ai.to_message("Hello!").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_to_hf_messages() {
+        let messages = to_hf_messages(&conversation());
+
+        assert_eq!(
+            messages,
+            vec![
+                HfMessage::new("system", "Be concise."),
+                HfMessage::new("user", "Hi."),
+                HfMessage::new("assistant", "Hello!"),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&messages[1]).unwrap(),
+            r#"{"role":"user","content":"Hi."}"#
+        );
+    }
+
+    #[test]
+    fn test_from_hf_messages_roundtrip() {
+        let messages = from_hf_messages(&to_hf_messages(&conversation())).unwrap();
+        assert_eq!(messages, conversation());
+
+        let result = from_hf_messages(&[HfMessage::new("narrator", "Once upon a time")]);
+        assert!(matches!(result, Err(TemplateError::InvalidRoleError)));
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_apply_chatml_template() {
+        let template = HfChatTemplate::new(
+            "{% for message in messages %}{{'<|im_start|>' + message['role'] + '\n' + message['content'] + '<|im_end|>' + '\n'}}{% endfor %}{% if add_generation_prompt %}{{ '<|im_start|>assistant\n' }}{% endif %}",
+        );
+
+        let rendered = template.apply(&conversation()[..2], true).unwrap();
+        assert_eq!(
+            rendered,
+            "<|im_start|>system\nBe concise.<|im_end|>\n<|im_start|>user\nHi.<|im_end|>\n<|im_start|>assistant\n"
+        );
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_apply_uses_trim_blocks_and_special_tokens() {
+        let template = HfChatTemplate::new(
+            "{% for message in messages %}\n{% if message['role'] == 'user' %}\n{{ '<|user|>\n' + message['content'] + eos_token }}\n{% elif message['role'] == 'assistant' %}\n{{ '<|assistant|>\n' + message['content'] + eos_token }}\n{% endif %}\n{% endfor %}",
+        )
+        .with_eos_token("</s>");
+
+        let rendered = template.apply(&conversation()[1..], false).unwrap();
+        assert_eq!(rendered, "<|user|>\nHi.</s>\n<|assistant|>\nHello!</s>\n");
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_raise_exception() {
+        let template = HfChatTemplate::new(
+            "{% if messages[0]['role'] == 'system' %}{{ raise_exception('System role not supported') }}{% endif %}",
+        );
+
+        let result = template.apply(&conversation(), false);
+        assert!(
+            matches!(result, Err(TemplateError::MalformedTemplate(msg)) if msg.contains("System role not supported"))
+        );
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_from_tokenizer_config() {
+        let config = r#"{
+            "bos_token": {"content": "<s>", "lstrip": false},
+            "eos_token": "</s>",
+            "chat_template": "{{ bos_token }}{% for message in messages %}[{{ message['role'] }}] {{ message['content'] }}{{ eos_token }}{% endfor %}"
+        }"#;
+
+        let template = HfChatTemplate::from_tokenizer_config(config).unwrap();
+        assert_eq!(
+            template.apply(&conversation()[1..2], false).unwrap(),
+            "<s>[user] Hi.</s>"
+        );
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_from_tokenizer_config_named_templates() {
+        let config = r#"{
+            "chat_template": [
+                {"name": "tool_use", "template": "tools"},
+                {"name": "default", "template": "default"}
+            ]
+        }"#;
+        let template = HfChatTemplate::from_tokenizer_config(config).unwrap();
+        assert_eq!(template.source(), "default");
+
+        let result = HfChatTemplate::from_tokenizer_config(r#"{"eos_token": "</s>"}"#);
+        assert!(matches!(result, Err(TemplateError::MalformedTemplate(_))));
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::SystemTime;
+
+use crate::{ChatTemplate, PromptSet};
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct PromptRevision {
+    pub revision: u64,
+    pub recorded_at: SystemTime,
+    pub template: Option<ChatTemplate>,
+}
+
+impl PromptRevision {
+    pub fn is_deleted(&self) -> bool {
+        self.template.is_none()
+    }
+}
+
+impl PromptSet {
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = Some(limit.max(1));
+        for revisions in self.history.values_mut() {
+            trim_history(revisions, limit.max(1));
+        }
+        self
+    }
+
+    pub fn history_limit(&self) -> usize {
+        self.history_limit.unwrap_or(DEFAULT_HISTORY_LIMIT)
+    }
+
+    pub fn history(&self, name: &str) -> &[PromptRevision] {
+        self.history.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn record(&mut self, name: &str, revision: PromptRevision) {
+        let limit = self.history_limit();
+        let revisions = self.history.entry(name.to_string()).or_default();
+        revisions.push(revision);
+        trim_history(revisions, limit);
+    }
+
+    pub fn get_as_of(&self, name: &str, at: SystemTime) -> Option<&ChatTemplate> {
+        self.history(name)
+            .iter()
+            .filter(|entry| entry.recorded_at <= at)
+            .max_by_key(|entry| (entry.recorded_at, entry.revision))
+            .and_then(|entry| entry.template.as_ref())
+    }
+}
+
+fn trim_history(revisions: &mut Vec<PromptRevision>, limit: usize) {
+    if revisions.len() > limit {
+        revisions.drain(..revisions.len() - limit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{chats, vars, Role::Human};
+
+    fn prompt(content: &str) -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(Human = content)).unwrap()
+    }
+
+    fn rendered(template: Option<&ChatTemplate>) -> Option<String> {
+        template.map(|t| {
+            t.format_messages(&vars!()).unwrap()[0]
+                .content()
+                .to_string()
+        })
+    }
+
+    #[test]
+    fn test_get_as_of() {
+        let start = SystemTime::now() - Duration::from_secs(3600);
+        let at = |minutes: u64| start + Duration::from_secs(minutes * 60);
+
+        let mut prompts = PromptSet::new();
+        prompts.insert_at("greeting", prompt("Hello."), at(0));
+        prompts.insert_at("greeting", prompt("Hi there."), at(10));
+        prompts.insert_at("other", prompt("Unrelated."), at(5));
+
+        assert!(prompts
+            .get_as_of("greeting", start - Duration::from_secs(1))
+            .is_none());
+        assert_eq!(
+            rendered(prompts.get_as_of("greeting", at(0))).as_deref(),
+            Some("Hello.")
+        );
+        assert_eq!(
+            rendered(prompts.get_as_of("greeting", at(9))).as_deref(),
+            Some("Hello.")
+        );
+        assert_eq!(
+            rendered(prompts.get_as_of("greeting", at(30))).as_deref(),
+            Some("Hi there.")
+        );
+
+        prompts.remove("greeting");
+        assert!(prompts.get_as_of("greeting", SystemTime::now()).is_none());
+        assert_eq!(
+            rendered(prompts.get_as_of("greeting", at(30))).as_deref(),
+            Some("Hi there.")
+        );
+
+        let history = prompts.history("greeting");
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[1].revision, 2);
+        assert!(history[2].is_deleted());
+        assert!(prompts.history("missing").is_empty());
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let start = SystemTime::now() - Duration::from_secs(3600);
+        let at = |minutes: u64| start + Duration::from_secs(minutes * 60);
+
+        let mut prompts = PromptSet::new();
+        assert_eq!(prompts.history_limit(), DEFAULT_HISTORY_LIMIT);
+        for minute in 0..5 {
+            prompts.insert_at("greeting", prompt(&format!("v{}", minute)), at(minute));
+        }
+
+        let mut prompts = prompts.with_history_limit(2);
+        assert_eq!(prompts.history("greeting").len(), 2);
+        assert_eq!(prompts.history("greeting")[0].revision, 4);
+        assert!(prompts.get_as_of("greeting", at(1)).is_none());
+        assert_eq!(
+            rendered(prompts.get_as_of("greeting", at(3))).as_deref(),
+            Some("v3")
+        );
+
+        prompts.insert_at("greeting", prompt("v5"), at(5));
+        let revisions: Vec<u64> = prompts
+            .history("greeting")
+            .iter()
+            .map(|entry| entry.revision)
+            .collect();
+        assert_eq!(revisions, vec![5, 6]);
+        assert_eq!(prompts.revision("greeting"), Some(6));
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fmt, ops::Range, sync::Arc};
+
+use crate::ChatTemplate;
+
+type RepairFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+type RepairObserver = dyn Fn(&RepairReport) + Send + Sync;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    pub variable: String,
+    pub original: String,
+    pub repaired: String,
+}
+
+impl RepairReport {
+    pub fn changed_span(&self) -> Range<usize> {
+        let original = self.original.as_bytes();
+        let repaired = self.repaired.as_bytes();
+
+        let prefix = original
+            .iter()
+            .zip(repaired)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = original[prefix..]
+            .iter()
+            .rev()
+            .zip(repaired[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        prefix..original.len() - suffix
+    }
+}
+
+#[derive(Clone)]
+pub struct HistoryRepair {
+    repair: Arc<RepairFn>,
+    observer: Option<Arc<RepairObserver>>,
+}
+
+impl HistoryRepair {
+    pub fn new(repair: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self {
+            repair: Arc::new(repair),
+            observer: None,
+        }
+    }
+
+    pub fn lenient() -> Self {
+        Self::new(lenient)
+    }
+
+    pub fn on_repaired(mut self, observer: impl Fn(&RepairReport) + Send + Sync + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    pub(crate) fn repair(&self, payload: &str) -> Option<String> {
+        (self.repair)(payload).filter(|repaired| repaired != payload)
+    }
+
+    pub(crate) fn report(&self, report: RepairReport) {
+        if let Some(observer) = &self.observer {
+            observer(&report);
+        }
+    }
+}
+
+impl fmt::Debug for HistoryRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HistoryRepair")
+            .field("observed", &self.observer.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChatTemplate {
+    pub fn with_history_repair(mut self, repair: HistoryRepair) -> Self {
+        self.meta.history_repair = Some(repair);
+        self
+    }
+}
+
+pub fn strip_trailing_garbage(payload: &str) -> Option<String> {
+    let start = payload.find(['[', '{'])?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, c) in payload[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    let end = start + offset + 1;
+                    return Some(payload[start..end].to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+pub fn fix_single_quotes(payload: &str) -> Option<String> {
+    let mut repaired = String::with_capacity(payload.len());
+    let mut quote = None;
+    let mut escaped = false;
+
+    for c in payload.chars() {
+        match quote {
+            Some(open) if escaped => {
+                escaped = false;
+                if open == '\'' && c == '\'' {
+                    repaired.pop();
+                }
+                repaired.push(c);
+            }
+            Some(_) if c == '\\' => {
+                escaped = true;
+                repaired.push(c);
+            }
+            Some(open) if c == open => {
+                quote = None;
+                repaired.push('"');
+            }
+            Some('\'') if c == '"' => repaired.push_str("\\\""),
+            Some(_) => repaired.push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                repaired.push('"');
+            }
+            None => repaired.push(c),
+        }
+    }
+
+    Some(repaired)
+}
+
+pub fn lenient(payload: &str) -> Option<String> {
+    let unquoted = fix_single_quotes(payload)?;
+    strip_trailing_garbage(&unquoted).or(Some(unquoted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_trailing_garbage() {
+        assert_eq!(
+            strip_trailing_garbage(r#"[{"role": "ai", "content": "a]b"}]  <EOF>"#).as_deref(),
+            Some(r#"[{"role": "ai", "content": "a]b"}]"#)
+        );
+        assert_eq!(strip_trailing_garbage(r#"[{"role": "ai""#), None);
+        assert_eq!(strip_trailing_garbage("no json"), None);
+    }
+
+    #[test]
+    fn test_fix_single_quotes() {
+        assert_eq!(
+            fix_single_quotes(r#"[{'role': 'human', 'content': 'say "hi", it\'s fine'}]"#)
+                .as_deref(),
+            Some(r#"[{"role": "human", "content": "say \"hi\", it's fine"}]"#)
+        );
+        assert_eq!(
+            fix_single_quotes(r#"[{"content": "it's"}]"#).as_deref(),
+            Some(r#"[{"content": "it's"}]"#)
+        );
+    }
+
+    #[test]
+    fn test_changed_span() {
+        let report = RepairReport {
+            variable: "history".to_string(),
+            original: "[1, 2]garbage".to_string(),
+            repaired: "[1, 2]".to_string(),
+        };
+        assert_eq!(report.changed_span(), 6..13);
+        assert_eq!(&report.original[report.changed_span()], "garbage");
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, MessageLike, Templatable, Template};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputVariables {
+    pub required: BTreeSet<String>,
+    pub optional: BTreeSet<String>,
+}
+
+impl InputVariables {
+    pub fn all(&self) -> BTreeSet<&str> {
+        self.required
+            .iter()
+            .chain(&self.optional)
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.required.contains(name) || self.optional.contains(name)
+    }
+
+    pub fn is_required(&self, name: &str) -> bool {
+        self.required.contains(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.optional.is_empty()
+    }
+
+    fn add(&mut self, name: String, required: bool) {
+        if required {
+            self.optional.remove(&name);
+            self.required.insert(name);
+        } else if !self.required.contains(&name) {
+            self.optional.insert(name);
+        }
+    }
+
+    fn merge(&mut self, other: InputVariables) {
+        for name in other.required {
+            self.add(name, true);
+        }
+        for name in other.optional {
+            self.add(name, false);
+        }
+    }
+}
+
+impl Template {
+    pub fn variable_requirements(&self) -> InputVariables {
+        let mut variables = InputVariables::default();
+
+        for name in self.input_variables() {
+            let has_fallback =
+                self.partial_vars().contains_key(&name) || self.defaults().contains(&name);
+            variables.add(name, !has_fallback);
+        }
+        for name in self.bound_variables() {
+            variables.add(name.clone(), false);
+        }
+
+        variables
+    }
+}
+
+impl ChatTemplate {
+    pub fn input_variables(&self) -> InputVariables {
+        let mut variables = InputVariables::default();
+
+        for message_like in &self.messages {
+            match message_like {
+                MessageLike::RolePromptTemplate(_, template) => {
+                    variables.merge(template.variable_requirements())
+                }
+                MessageLike::Placeholder(placeholder) => variables.add(
+                    placeholder.variable_name().to_string(),
+                    !placeholder.optional() && placeholder.fallback().is_none(),
+                ),
+                MessageLike::BaseMessage(_) | MessageLike::FewShotPrompt(_) => {}
+            }
+        }
+
+        variables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats,
+        Role::{Human, Placeholder, System},
+    };
+
+    fn names(set: &BTreeSet<String>) -> Vec<&str> {
+        set.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_template_partials_are_optional() {
+        let template = Template::new("Hi {name}, welcome to {company}.")
+            .unwrap()
+            .with_partial("company", "Acme");
+
+        let variables = template.variable_requirements();
+        assert_eq!(names(&variables.required), vec!["name"]);
+        assert_eq!(names(&variables.optional), vec!["company"]);
+        assert!(variables.is_required("name"));
+        assert!(!variables.is_required("company"));
+    }
+
+    #[test]
+    fn test_chat_template_input_variables() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You support {product}.",
+            Placeholder = "{history|optional}",
+            Placeholder = "{context}",
+            Human = "{question}",
+        ))
+        .unwrap();
+
+        let variables = template.input_variables();
+        assert_eq!(
+            names(&variables.required),
+            vec!["context", "product", "question"]
+        );
+        assert_eq!(names(&variables.optional), vec!["history"]);
+        assert_eq!(variables.all().len(), 4);
+    }
+
+    #[test]
+    fn test_required_use_wins_over_optional_use() {
+        let mut template = ChatTemplate::from_messages(chats!(Human = "Ask {team}.")).unwrap();
+        template.messages.insert(
+            0,
+            MessageLike::role_prompt_template(
+                System,
+                Template::new("You work for {team}.")
+                    .unwrap()
+                    .with_partial("team", "support"),
+            ),
+        );
+
+        let variables = template.input_variables();
+        assert_eq!(names(&variables.required), vec!["team"]);
+        assert!(variables.optional.is_empty());
+    }
+}
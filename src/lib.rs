@@ -1,12 +1,28 @@
-pub mod braces;
+pub const MSRV: &str = env!("CARGO_PKG_RUST_VERSION");
 
-pub mod is_even;
-pub use is_even::IsEven;
+mod braces;
+pub use braces::{scan, BraceKind, BraceToken};
 
-pub mod placeholder;
-pub use placeholder::extract_placeholder_variable;
+mod mustache_expr;
+
+#[cfg(feature = "mustache")]
+mod expression_helpers;
+
+mod conditionals;
+
+mod macros;
+
+mod placeholder;
+pub(crate) use placeholder::extract_placeholder_variable;
 pub use placeholder::extract_variables;
-pub use placeholder::is_valid_identifier;
+
+pub mod filters;
+
+pub mod normalize;
+pub use normalize::Normalizer;
+
+pub mod truncate;
+pub use truncate::{truncate_chars, truncate_graphemes, truncate_tokens};
 
 pub mod template_format;
 pub use template_format::merge_vars;
@@ -15,14 +31,71 @@ pub use template_format::TemplateFormat;
 
 pub mod vars;
 
+pub mod prompt_vars;
+pub use prompt_vars::{PromptVars, Variables};
+
+pub mod defaults;
+pub use defaults::Defaults;
+
+pub mod var_value;
+pub use var_value::VarValue;
+
 pub mod formatting;
 pub use formatting::{Formattable, Templatable};
 
+pub mod format_options;
+pub use format_options::{FormatOptions, MissingVariables};
+
 pub mod template;
 pub use template::Template;
 
+pub mod input_variables;
+pub use input_variables::InputVariables;
+
+pub mod sections;
+pub use sections::Section;
+
+pub mod edit;
+pub use edit::TemplateEdit;
+
+pub mod coverage;
+pub use coverage::CoverageReport;
+
+pub mod diagnostics;
+pub use diagnostics::{diagnose, DiagnosticReport};
+
+pub mod assertions;
+pub use assertions::{AssertionFailure, PromptAssertions};
+
+pub mod embedded_tests;
+pub use embedded_tests::{PromptTestCase, PromptTestReport};
+
 pub mod chat_template;
-pub use chat_template::ChatTemplate;
+pub use chat_template::{ChatTemplate, PartialRender};
+
+#[cfg(feature = "arena")]
+pub mod arena;
+
+pub mod dsl;
+
+#[cfg(feature = "yaml")]
+pub mod markdown;
+#[cfg(feature = "yaml")]
+pub use markdown::{FrontMatter, MarkdownPrompt};
+
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+pub mod chat_template_view;
+pub use chat_template_view::ChatTemplateView;
+
+pub mod diff;
+pub use diff::{DiffMessage, MessageChange, TemplateDiff};
+
+pub mod explain;
+
+pub mod summary;
+pub use summary::ChatTemplateSummary;
 
 pub mod message_like;
 pub use message_like::ArcMessageEnumExt;
@@ -30,14 +103,25 @@ pub use message_like::MessageLike;
 
 pub mod chats;
 
+pub mod branch;
+pub use branch::{Branch, Fork};
+
+pub mod conversation_script;
+pub use conversation_script::ConversationScript;
+
 pub mod role;
-pub use role::Role;
+pub use role::{Role, RolePolicy};
 
 pub mod messages_placeholder;
-pub use messages_placeholder::MessagesPlaceholder;
+pub use messages_placeholder::{MessagesPlaceholder, TrimStrategy};
+
+pub mod example_selector;
+pub use example_selector::{
+    ExampleSelector, LengthBasedSelector, RandomSelector, SemanticSelector,
+};
 
 pub mod few_shot_template;
-pub use few_shot_template::FewShotTemplate;
+pub use few_shot_template::{BudgetedExamples, FewShotTemplate};
 
 pub mod few_shot_chat_template;
 pub use few_shot_chat_template::FewShotChatTemplate;
@@ -46,3 +130,140 @@ pub mod examples;
 
 pub mod few_shot_chat_template_config;
 pub use few_shot_chat_template_config::FewShotChatTemplateConfig;
+
+pub mod hf_chat_template;
+#[cfg(feature = "jinja")]
+pub use hf_chat_template::HfChatTemplate;
+
+pub mod model_profile;
+pub use model_profile::ModelProfile;
+
+pub mod limits;
+pub use limits::RenderLimits;
+
+pub mod budget;
+pub use budget::{BudgetedRender, Priority, Sacrifice};
+
+pub mod token_counter;
+#[cfg(feature = "tiktoken")]
+pub use token_counter::BpeCounter;
+pub use token_counter::{HeuristicCounter, TokenCounter};
+
+pub mod anonymize;
+pub use anonymize::Anonymizer;
+
+pub mod token_annotations;
+pub use token_annotations::TokenAnnotations;
+
+pub mod context_window;
+pub use context_window::{context_window, set_context_window, ContextFit, ContextWindows};
+
+pub mod lineage;
+pub use lineage::Lineage;
+
+pub mod prompt_response;
+pub use prompt_response::{PromptResponsePair, ResponseParser};
+
+pub mod reasoning;
+pub use reasoning::{ReasoningEffort, ReasoningHints, Verbosity};
+
+pub mod render_budget;
+pub use render_budget::{on_slow_render, RenderStage, RenderTimings, SlowRender, SlowRenderHandle};
+
+pub mod pipeline;
+pub use pipeline::PipelineTemplate;
+
+pub mod render_output;
+pub use render_output::RenderOutput;
+
+pub mod batch;
+
+pub mod render_queue;
+pub use render_queue::{QueuedRender, RateLimits, RenderQueue};
+
+pub mod source_map;
+pub use source_map::{SourceMap, SourceOrigin};
+
+pub mod sweep;
+pub use sweep::ParamSweep;
+
+pub mod mutate;
+pub use mutate::{Mutant, Mutation};
+
+pub mod critique;
+pub use critique::{CritiquePair, Reflection};
+
+pub mod self_consistency;
+pub use self_consistency::{majority_vote, SampledRender, SelfConsistency};
+
+pub mod scenario;
+pub use scenario::Scenario;
+
+#[cfg(feature = "async")]
+pub mod resolve;
+#[cfg(feature = "async")]
+pub use resolve::Resolvers;
+
+#[cfg(feature = "async")]
+pub mod fs_store;
+#[cfg(feature = "async")]
+pub use fs_store::{FsPromptStore, VerifyReport};
+
+pub mod events;
+pub use events::PromptEvent;
+
+pub mod history_repair;
+pub use history_repair::{HistoryRepair, RepairReport};
+
+pub mod history;
+pub use history::PromptRevision;
+
+pub mod prompt_set;
+pub use prompt_set::{PromptMetadata, PromptSet};
+
+pub mod prompt_registry;
+pub use prompt_registry::{PromptRegistry, RegisteredPrompt};
+
+pub mod persona;
+pub use persona::Persona;
+
+pub mod glossary;
+pub use glossary::{Glossary, GlossaryViolation};
+
+pub mod policy;
+pub use policy::{DataClassification, PolicyChecker, PromptPolicy};
+
+#[cfg(feature = "notify")]
+pub mod prompt_loader;
+#[cfg(feature = "notify")]
+pub use prompt_loader::{PromptLoader, PromptWatcher};
+
+pub mod rollout;
+pub use rollout::Rollout;
+
+pub mod semantic_search;
+pub use semantic_search::{Embedder, SemanticMatch};
+
+pub mod prompt_cache;
+pub use prompt_cache::{PromptCache, RenderMemo};
+
+pub mod openai;
+pub use openai::{OpenAiMessage, OpenAiRequest};
+
+pub mod dependency_graph;
+pub use dependency_graph::DependencyGraph;
+
+pub mod partial_library;
+pub use partial_library::{Orphans, PartialLibrary, RetentionPolicy};
+
+#[cfg(feature = "bundle")]
+pub mod bundle;
+#[cfg(feature = "bundle")]
+pub use bundle::{BundleManifest, PromptBundle};
+
+#[cfg(all(feature = "async", any(test, feature = "testing")))]
+pub mod fault_injection;
+#[cfg(all(feature = "async", any(test, feature = "testing")))]
+pub use fault_injection::{Fault, FaultInjector};
+
+pub mod prelude;
@@ -0,0 +1,140 @@
+use std::{collections::HashMap, sync::Arc};
+
+use messageforge::{BaseMessage, MessageEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::TemplateError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_variable_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_bytes: Option<usize>,
+}
+
+impl RenderLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_variable_bytes(mut self, max: usize) -> Self {
+        self.max_variable_bytes = Some(max);
+        self
+    }
+
+    pub fn max_message_bytes(mut self, max: usize) -> Self {
+        self.max_message_bytes = Some(max);
+        self
+    }
+
+    pub fn max_total_bytes(mut self, max: usize) -> Self {
+        self.max_total_bytes = Some(max);
+        self
+    }
+
+    pub fn check_variables(&self, variables: &HashMap<&str, &str>) -> Result<(), TemplateError> {
+        let Some(max) = self.max_variable_bytes else {
+            return Ok(());
+        };
+
+        for (name, value) in variables {
+            if value.len() > max {
+                return Err(TemplateError::LimitExceeded(format!(
+                    "variable '{}' is {} bytes; the limit is {} bytes",
+                    name,
+                    value.len(),
+                    max
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_message(&self, message: &MessageEnum) -> Result<(), TemplateError> {
+        match self.max_message_bytes {
+            Some(max) if message.content().len() > max => {
+                Err(TemplateError::LimitExceeded(format!(
+                    "rendered {} message is {} bytes; the limit is {} bytes",
+                    message.message_type().as_str(),
+                    message.content().len(),
+                    max
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_total(&self, messages: &[Arc<MessageEnum>]) -> Result<(), TemplateError> {
+        let Some(max) = self.max_total_bytes else {
+            return Ok(());
+        };
+
+        let total: usize = messages.iter().map(|message| message.content().len()).sum();
+        if total > max {
+            return Err(TemplateError::LimitExceeded(format!(
+                "rendered prompt is {} bytes; the limit is {} bytes",
+                total, max
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vars, Role};
+
+    #[test]
+    fn test_default_limits_allow_everything() {
+        let limits = RenderLimits::default();
+        let big = "x".repeat(10_000);
+        let message = Role::Human.to_message(&big).unwrap();
+
+        assert!(limits.check_variables(&vars!(doc = big.as_str())).is_ok());
+        assert!(limits.check_message(&message).is_ok());
+        assert!(limits.check_total(&[message]).is_ok());
+    }
+
+    #[test]
+    fn test_variable_limit() {
+        let limits = RenderLimits::new().max_variable_bytes(4);
+
+        assert!(limits.check_variables(&vars!(doc = "four")).is_ok());
+        let result = limits.check_variables(&vars!(doc = "fives"));
+        assert!(matches!(
+            result,
+            Err(TemplateError::LimitExceeded(msg)) if msg.contains("'doc'") && msg.contains("5 bytes")
+        ));
+    }
+
+    #[test]
+    fn test_message_and_total_limits() {
+        let limits = RenderLimits::new().max_message_bytes(5).max_total_bytes(8);
+        let short = Role::Human.to_message("Hi!").unwrap();
+        let long = Role::Ai.to_message("Hello there").unwrap();
+
+        assert!(limits.check_message(&short).is_ok());
+        assert!(matches!(
+            limits.check_message(&long),
+            Err(TemplateError::LimitExceeded(_))
+        ));
+
+        assert!(limits.check_total(&[short.clone(), short.clone()]).is_ok());
+        assert!(matches!(
+            limits.check_total(&[short.clone(), short.clone(), short]),
+            Err(TemplateError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_limits_serialization() {
+        let limits = RenderLimits::new().max_total_bytes(1024);
+        let json = serde_json::to_string(&limits).unwrap();
+        assert_eq!(json, r#"{"max_total_bytes":1024}"#);
+        assert_eq!(serde_json::from_str::<RenderLimits>(&json).unwrap(), limits);
+    }
+}
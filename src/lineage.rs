@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::TemplateError;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+pub fn fingerprint(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(bytes))
+}
+
+pub fn template_fingerprint<T: Serialize>(template: &T) -> Result<String, TemplateError> {
+    let serialized = serde_json::to_vec(template).map_err(|e| {
+        TemplateError::MalformedTemplate(format!("Failed to serialize template: {}", e))
+    })?;
+    Ok(fingerprint(&serialized))
+}
+
+pub fn variables_fingerprint(variables: &HashMap<&str, &str>) -> String {
+    let mut entries: Vec<_> = variables.iter().collect();
+    entries.sort();
+
+    let mut bytes = Vec::new();
+    for (key, value) in entries {
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.push(0);
+    }
+    fingerprint(&bytes)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lineage {
+    pub template_fingerprint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_version: Option<String>,
+    pub variables_hash: String,
+    pub library_version: String,
+    pub timestamp: u64,
+}
+
+impl Lineage {
+    pub fn capture<T: Serialize>(
+        template: &T,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Self, TemplateError> {
+        Ok(Self {
+            template_fingerprint: template_fingerprint(template)?,
+            template_version: None,
+            variables_hash: variables_fingerprint(variables),
+            library_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.template_version = Some(version.into());
+        self
+    }
+
+    pub fn matches_inputs<T: Serialize>(
+        &self,
+        template: &T,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<bool, TemplateError> {
+        Ok(self.template_fingerprint == template_fingerprint(template)?
+            && self.variables_hash == variables_fingerprint(variables))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vars, Template};
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        assert_eq!(fingerprint(b""), "cbf29ce484222325");
+        assert_eq!(fingerprint(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(fingerprint(b"ab"), fingerprint(b"ba"));
+    }
+
+    #[test]
+    fn test_variables_fingerprint_ignores_insertion_order() {
+        let first = vars!(a = "1", b = "2");
+        let mut second = HashMap::new();
+        second.insert("b", "2");
+        second.insert("a", "1");
+
+        assert_eq!(
+            variables_fingerprint(&first),
+            variables_fingerprint(&second)
+        );
+        assert_ne!(
+            variables_fingerprint(&first),
+            variables_fingerprint(&vars!(a = "1", b = "3"))
+        );
+        assert_ne!(
+            variables_fingerprint(&vars!(a = "1b")),
+            variables_fingerprint(&vars!(a1 = "b"))
+        );
+    }
+
+    #[test]
+    fn test_capture_and_match() {
+        let template = Template::new("Hello, {name}!").unwrap();
+        let variables = vars!(name = "Ada");
+
+        let lineage = Lineage::capture(&template, &variables)
+            .unwrap()
+            .with_version("v2");
+
+        assert_eq!(lineage.template_version.as_deref(), Some("v2"));
+        assert_eq!(lineage.library_version, env!("CARGO_PKG_VERSION"));
+        assert!(lineage.timestamp > 0);
+        assert!(lineage.matches_inputs(&template, &variables).unwrap());
+        assert!(!lineage
+            .matches_inputs(&template, &vars!(name = "Grace"))
+            .unwrap());
+        assert!(!lineage
+            .matches_inputs(&Template::new("Hi, {name}!").unwrap(), &variables)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_lineage_serialization() {
+        let lineage = Lineage::capture(&Template::new("{x}").unwrap(), &vars!(x = "1")).unwrap();
+        let json = serde_json::to_string(&lineage).unwrap();
+        assert!(!json.contains("template_version"));
+
+        let deserialized: Lineage = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, lineage);
+    }
+}
@@ -0,0 +1,260 @@
+#[cfg(feature = "mustache")]
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::{
+    braces::{scan, BraceKind},
+    mustache_expr::{parse_mustache_expr, split_params, MustacheTag},
+    placeholder::is_valid_identifier,
+    TemplateError,
+};
+
+pub(crate) const MACRO_BLOCK: &str = "macro";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MacroDef<'a> {
+    pub name: &'a str,
+    pub params: Vec<&'a str>,
+}
+
+pub(crate) fn parse_macro_header(expr: &str) -> Option<MacroDef<'_>> {
+    let rest = expr.trim().strip_prefix(MACRO_BLOCK)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let (name, params) = match rest.trim().split_once('(') {
+        Some((name, params)) => (name.trim(), params.trim_end().strip_suffix(')')?),
+        None => (rest.trim(), ""),
+    };
+    let params: Vec<&str> = params
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .collect();
+
+    let valid = is_valid_identifier(name) && params.iter().all(|param| is_valid_identifier(param));
+    valid.then_some(MacroDef { name, params })
+}
+
+pub(crate) fn macro_definitions(source: &str) -> Vec<MacroDef<'_>> {
+    scan(source)
+        .into_iter()
+        .filter(|token| token.kind == BraceKind::Double)
+        .filter_map(|token| parse_macro_header(token.inner(source)))
+        .collect()
+}
+
+fn invocation<'a>(inner: &'a str, macros: &[MacroDef<'a>]) -> Option<(usize, Vec<&'a str>)> {
+    let expr = parse_mustache_expr(inner)?;
+    if !matches!(expr.tag, MustacheTag::Variable | MustacheTag::Helper) {
+        return None;
+    }
+    let index = macros.iter().position(|def| def.name == expr.name)?;
+    let args = split_params(inner.trim())?.split_off(1);
+    Some((index, args))
+}
+
+pub(crate) fn check_macros(source: &str) -> Result<(), TemplateError> {
+    let macros = macro_definitions(source);
+    let mut names = HashSet::new();
+    let mut open = false;
+
+    for token in scan(source) {
+        if token.kind != BraceKind::Double {
+            continue;
+        }
+        let inner = token.inner(source);
+
+        if let Some(def) = parse_macro_header(inner) {
+            if open {
+                return Err(TemplateError::MalformedTemplate(format!(
+                    "Macro '{}' cannot be defined inside another macro",
+                    def.name
+                )));
+            }
+            if !names.insert(def.name) {
+                return Err(TemplateError::MalformedTemplate(format!(
+                    "Macro '{}' is defined more than once",
+                    def.name
+                )));
+            }
+            open = true;
+            continue;
+        }
+        if parse_mustache_expr(inner)
+            .is_some_and(|expr| expr.tag == MustacheTag::Close && expr.name == MACRO_BLOCK)
+        {
+            open = false;
+            continue;
+        }
+
+        let Some((index, args)) = invocation(inner, &macros) else {
+            continue;
+        };
+        let def = &macros[index];
+        let mut bound = HashSet::new();
+        for (position, arg) in args.iter().enumerate() {
+            let param = match arg.split_once('=') {
+                Some((key, _)) if is_valid_identifier(key) => {
+                    def.params.iter().find(|p| **p == key)
+                }
+                _ => def.params.get(position),
+            };
+            match param {
+                Some(param) if bound.insert(*param) => {}
+                _ => {
+                    return Err(TemplateError::MalformedTemplate(format!(
+                        "Invalid argument '{}' for macro '{}'",
+                        arg, def.name
+                    )))
+                }
+            }
+        }
+        if bound.len() != def.params.len() {
+            return Err(TemplateError::MalformedTemplate(format!(
+                "Macro '{}' expects {} argument(s), got {}",
+                def.name,
+                def.params.len(),
+                bound.len()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "mustache")]
+pub(crate) fn expand_macros(source: &str) -> Cow<'_, str> {
+    let macros = macro_definitions(source);
+    if macros.is_empty() {
+        return Cow::Borrowed(source);
+    }
+
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for token in scan(source) {
+        if token.kind != BraceKind::Double {
+            continue;
+        }
+        let inner = token.inner(source);
+
+        let replacement = if let Some(def) = parse_macro_header(inner) {
+            format!("{{{{#*inline \"{}\"}}}}", def.name)
+        } else if parse_mustache_expr(inner)
+            .is_some_and(|expr| expr.tag == MustacheTag::Close && expr.name == MACRO_BLOCK)
+        {
+            "{{/inline}}".to_string()
+        } else if let Some((index, args)) = invocation(inner, &macros) {
+            let def = &macros[index];
+            let mut call = format!("{{{{> {}", def.name);
+            for (position, arg) in args.iter().enumerate() {
+                call.push(' ');
+                match arg.split_once('=') {
+                    Some((key, _)) if is_valid_identifier(key) => call.push_str(arg),
+                    _ => {
+                        call.push_str(def.params[position]);
+                        call.push('=');
+                        call.push_str(arg);
+                    }
+                }
+            }
+            call.push_str("}}");
+            call
+        } else {
+            continue;
+        };
+
+        result.push_str(&source[last..token.span.start]);
+        result.push_str(&replacement);
+        last = token.span.end;
+    }
+
+    result.push_str(&source[last..]);
+    Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Templatable, Template, TemplateFormat};
+
+    const WARNINGS: &str = "{{macro warn(topic, level)}}[{{level}}] Never discuss {{topic}} with {{customer}}.\n{{/macro}}\
+                            {{warn \"pricing\" \"high\"}}{{warn topic=subject level=\"low\"}}";
+
+    #[test]
+    fn test_parse_macro_header() {
+        let def = parse_macro_header("macro warn(topic, level)").unwrap();
+        assert_eq!(def.name, "warn");
+        assert_eq!(def.params, vec!["topic", "level"]);
+        assert_eq!(
+            parse_macro_header(" macro footer ").unwrap().params,
+            Vec::<&str>::new()
+        );
+        assert_eq!(parse_macro_header("macro footer()").unwrap().name, "footer");
+        assert_eq!(parse_macro_header("macro warn(topic"), None);
+        assert_eq!(parse_macro_header("macro 1warn(topic)"), None);
+        assert_eq!(parse_macro_header("macroname"), None);
+        assert_eq!(parse_macro_header("macro warn(a b)"), None);
+    }
+
+    #[test]
+    fn test_macro_variables() {
+        let template = Template::new(WARNINGS).unwrap();
+        assert_eq!(template.template_format(), TemplateFormat::Mustache);
+        assert_eq!(template.input_variables(), vec!["customer", "subject"]);
+    }
+
+    #[test]
+    fn test_check_macros() {
+        assert!(check_macros(WARNINGS).is_ok());
+        let err = |source: &str| match check_macros(source) {
+            Err(TemplateError::MalformedTemplate(msg)) => msg,
+            other => panic!("expected an error, got {:?}", other.map(|_| ())),
+        };
+        assert_eq!(
+            err("{{macro a(x)}}{{x}}{{/macro}}{{a}}"),
+            "Macro 'a' expects 1 argument(s), got 0"
+        );
+        assert_eq!(
+            err("{{macro a(x)}}{{x}}{{/macro}}{{a 1 2}}"),
+            "Invalid argument '2' for macro 'a'"
+        );
+        assert_eq!(
+            err("{{macro a(x)}}{{x}}{{/macro}}{{a y=1}}"),
+            "Invalid argument 'y=1' for macro 'a'"
+        );
+        assert_eq!(
+            err("{{macro a}}x{{/macro}}{{macro a}}y{{/macro}}"),
+            "Macro 'a' is defined more than once"
+        );
+        assert_eq!(
+            err("{{macro a}}{{macro b}}y{{/macro}}{{/macro}}"),
+            "Macro 'b' cannot be defined inside another macro"
+        );
+        assert!(matches!(
+            Template::new("{{macro a(x)}}{{x}}{{/macro}}{{a}}"),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_render_macros() {
+        use crate::vars;
+
+        let template = Template::new(WARNINGS).unwrap();
+        assert_eq!(
+            template
+                .format(&vars!(customer = "Dana", subject = "refunds"))
+                .unwrap(),
+            "[high] Never discuss pricing with Dana.\n[low] Never discuss refunds with Dana.\n"
+        );
+        assert_eq!(
+            expand_macros("{{macro a(x)}}{{x}}{{/macro}}{{a 1}}"),
+            "{{#*inline \"a\"}}{{x}}{{/inline}}{{> a x=1}}"
+        );
+        assert!(matches!(expand_macros("{{a 1}}"), Cow::Borrowed(_)));
+    }
+}
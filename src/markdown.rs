@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{diagnostics::VariableSchema, dsl::dsl_role, ChatTemplate, Role, TemplateError};
+
+lazy_static! {
+    static ref HEADING_RE: Regex = Regex::new(r"^#{1,6}\s+(\S.*?)\s*#*\s*$").unwrap();
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrontMatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: VariableSchema,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarkdownPrompt {
+    pub front_matter: FrontMatter,
+    pub template: ChatTemplate,
+}
+
+struct Section {
+    line: usize,
+    role: Role,
+    content: Vec<String>,
+}
+
+fn markdown_error(line: usize, msg: impl std::fmt::Display) -> TemplateError {
+    TemplateError::MalformedTemplate(format!("line {}: {}", line, msg))
+}
+
+fn split_front_matter(source: &str) -> Result<(&str, &str, usize), TemplateError> {
+    let mut lines = source.split_inclusive('\n');
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok(("", source, 0));
+    }
+
+    let start = source.find('\n').map_or(source.len(), |i| i + 1);
+    let mut offset = start;
+    for (index, line) in lines.enumerate() {
+        if line.trim_end() == "---" {
+            let body = &source[offset + line.len()..];
+            return Ok((&source[start..offset], body, index + 2));
+        }
+        offset += line.len();
+    }
+
+    Err(markdown_error(
+        1,
+        "front matter is missing its closing '---'",
+    ))
+}
+
+fn parse_sections(body: &str, first_line: usize) -> Result<Vec<(Role, String)>, TemplateError> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut in_fence = false;
+
+    for (index, raw) in body.lines().enumerate() {
+        let line = first_line + index;
+
+        if raw.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+
+        let role = match HEADING_RE.captures(raw) {
+            Some(captures) if !in_fence => dsl_role(&captures[1]),
+            _ => None,
+        };
+
+        match (role, sections.last_mut()) {
+            (Some(role), _) => sections.push(Section {
+                line,
+                role,
+                content: Vec::new(),
+            }),
+            (None, Some(section)) => section.content.push(raw.to_string()),
+            (None, None) if raw.trim().is_empty() || HEADING_RE.is_match(raw) => {}
+            (None, None) => {
+                return Err(markdown_error(
+                    line,
+                    "expected a role heading such as '## System' before any content",
+                ))
+            }
+        }
+    }
+
+    sections
+        .into_iter()
+        .map(|section| {
+            let content = section.content.join("\n").trim().to_string();
+            if content.is_empty() {
+                return Err(markdown_error(section.line, "section is empty"));
+            }
+            Ok((section.role, content))
+        })
+        .collect()
+}
+
+impl MarkdownPrompt {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let (yaml, body, body_offset) = split_front_matter(source)?;
+        let front_matter: FrontMatter = if yaml.trim().is_empty() {
+            FrontMatter::default()
+        } else {
+            serde_yaml::from_str(yaml)?
+        };
+
+        let sections = parse_sections(body, body_offset + 1)?;
+        if sections.is_empty() {
+            return Err(TemplateError::MalformedTemplate(
+                "Markdown prompt has no role sections".to_string(),
+            ));
+        }
+
+        let mut template = ChatTemplate::from_messages(sections)?;
+        if front_matter.strict {
+            template = template.with_strict_placeholders();
+        }
+
+        if !front_matter.variables.is_empty() {
+            let undeclared: Vec<String> = template
+                .required_variables()
+                .into_iter()
+                .filter(|name| !front_matter.variables.contains_key(name))
+                .collect();
+            if !undeclared.is_empty() {
+                return Err(TemplateError::MalformedTemplate(format!(
+                    "variables not declared in front matter: {}",
+                    undeclared.join(", ")
+                )));
+            }
+        }
+
+        Ok(Self {
+            front_matter,
+            template,
+        })
+    }
+}
+
+impl ChatTemplate {
+    pub fn from_markdown(source: &str) -> Result<Self, TemplateError> {
+        MarkdownPrompt::parse(source).map(|prompt| prompt.template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{vars, MessageLike};
+
+    const REFUND_PROMPT: &str = "---
+name: billing/refunds
+description: Handles refund requests.
+tags: [billing, support]
+variables:
+  company: Company the agent works for
+  order_id: Order being refunded
+---
+
+# Refund assistant
+
+## System
+
+You work in billing at **{company}**.
+
+### Rules
+- Be polite.
+
+```md
+## User
+This heading is part of an example, not a new section.
+```
+
+## Placeholder
+
+{history|optional}
+
+## User
+
+Please refund order {order_id}.
+";
+
+    #[test]
+    fn test_from_markdown() {
+        let prompt = MarkdownPrompt::parse(REFUND_PROMPT).unwrap();
+        assert_eq!(prompt.front_matter.name.as_deref(), Some("billing/refunds"));
+        assert_eq!(prompt.front_matter.tags, vec!["billing", "support"]);
+        assert_eq!(
+            prompt.front_matter.variables["order_id"],
+            "Order being refunded"
+        );
+
+        let template = prompt.template;
+        assert_eq!(template.messages.len(), 3);
+        assert!(matches!(template.messages[1], MessageLike::Placeholder(_)));
+
+        let messages = template
+            .format_messages(&vars!(company = "Acme", order_id = "42"))
+            .unwrap();
+        assert!(messages[0]
+            .content()
+            .starts_with("You work in billing at **Acme**."));
+        assert!(messages[0].content().contains("### Rules\n- Be polite."));
+        assert!(messages[0].content().contains("## User\nThis heading"));
+        assert_eq!(messages[1].content(), "Please refund order 42.");
+    }
+
+    #[test]
+    fn test_from_markdown_without_front_matter() {
+        let template =
+            ChatTemplate::from_markdown("## System\nBe brief.\n## Assistant\nOk.").unwrap();
+        let messages = template.format_messages(&vars!()).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content(), "Ok.");
+    }
+
+    #[test]
+    fn test_from_markdown_strict_front_matter() {
+        let template =
+            ChatTemplate::from_markdown("---\nstrict: true\n---\n## User\n{question}").unwrap();
+        assert!(template.strict_placeholders());
+    }
+
+    #[test]
+    fn test_from_markdown_errors() {
+        let cases = [
+            (
+                "---\nname: x\n## User\nhi",
+                "line 1: front matter is missing",
+            ),
+            (
+                "---\n---\nHello\n## User\nhi",
+                "line 3: expected a role heading",
+            ),
+            ("## System\n\n## User\nhi", "line 1: section is empty"),
+            (
+                "---\nvariables:\n  name: Who\n---\n## User\n{name} {age}",
+                "variables not declared in front matter: age",
+            ),
+            ("# Title only", "Markdown prompt has no role sections"),
+        ];
+
+        for (source, expected) in cases {
+            match ChatTemplate::from_markdown(source) {
+                Err(TemplateError::MalformedTemplate(msg)) => {
+                    assert!(msg.starts_with(expected), "{:?} -> {}", source, msg)
+                }
+                other => panic!(
+                    "Expected MalformedTemplate for {:?}, got {:?}",
+                    source, other
+                ),
+            }
+        }
+
+        assert!(matches!(
+            ChatTemplate::from_markdown("---\ntags: [unterminated\n---\n## User\nhi"),
+            Err(TemplateError::YamlDeserializationError(_))
+        ));
+    }
+}
@@ -1,12 +1,98 @@
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::{extract_placeholder_variable, TemplateError};
+use messageforge::MessageEnum;
+
+use crate::{
+    extract_placeholder_variable, filters::split_filters, placeholder::is_valid_identifier, Role,
+    TemplateError, TokenCounter,
+};
+
+lazy_static! {
+    static ref PLACEHOLDER_SLOT_RE: Regex = Regex::new(r"\{{1,2}([^}]+)\}{1,2}").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimStrategy {
+    #[default]
+    KeepFirst,
+    KeepLast,
+}
+
+impl TrimStrategy {
+    pub fn apply<T>(&self, messages: Vec<T>, n_messages: usize) -> Vec<T> {
+        if n_messages == 0 || messages.len() <= n_messages {
+            return messages;
+        }
+
+        match self {
+            TrimStrategy::KeepFirst => messages.into_iter().take(n_messages).collect(),
+            TrimStrategy::KeepLast => {
+                let skip = messages.len() - n_messages;
+                messages.into_iter().skip(skip).collect()
+            }
+        }
+    }
+
+    pub fn apply_budget<T>(
+        &self,
+        messages: Vec<T>,
+        max_tokens: usize,
+        tokens: impl Fn(&T) -> usize,
+    ) -> Vec<T> {
+        let mut remaining = max_tokens;
+        let fits = |message: &T| {
+            let cost = tokens(message);
+            let fits = cost <= remaining;
+            if fits {
+                remaining -= cost;
+            }
+            fits
+        };
+
+        match self {
+            TrimStrategy::KeepFirst => messages.into_iter().take_while(fits).collect(),
+            TrimStrategy::KeepLast => {
+                let mut kept: Vec<T> = messages.into_iter().rev().take_while(fits).collect();
+                kept.reverse();
+                kept
+            }
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == TrimStrategy::default()
+    }
+}
+
+impl TryFrom<&str> for TrimStrategy {
+    type Error = TemplateError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "first" => Ok(TrimStrategy::KeepFirst),
+            "last" => Ok(TrimStrategy::KeepLast),
+            _ => Err(TemplateError::MalformedTemplate(format!(
+                "Unknown trim strategy '{}'; expected 'first' or 'last'.",
+                s
+            ))),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MessagesPlaceholder {
     variable_name: String,
     optional: bool,
     n_messages: usize,
+    #[serde(default, skip_serializing_if = "TrimStrategy::is_default")]
+    trim_strategy: TrimStrategy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fallback: Option<Vec<(Role, String)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
 }
 
 impl MessagesPlaceholder {
@@ -25,9 +111,35 @@ impl MessagesPlaceholder {
             } else {
                 n_messages
             },
+            trim_strategy: TrimStrategy::default(),
+            fallback: None,
+            max_tokens: None,
         }
     }
 
+    pub fn builder() -> MessagesPlaceholderBuilder {
+        MessagesPlaceholderBuilder::new()
+    }
+
+    pub fn with_trim_strategy(mut self, trim_strategy: TrimStrategy) -> Self {
+        self.trim_strategy = trim_strategy;
+        self
+    }
+
+    pub fn with_fallback(mut self, messages: Vec<(Role, String)>) -> Self {
+        self.fallback = Some(messages);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_default_message(self, role: Role, content: impl Into<String>) -> Self {
+        self.with_fallback(vec![(role, content.into())])
+    }
+
     pub fn variable_name(&self) -> &str {
         &self.variable_name
     }
@@ -39,14 +151,156 @@ impl MessagesPlaceholder {
     pub fn n_messages(&self) -> usize {
         self.n_messages
     }
+
+    pub fn trim_strategy(&self) -> TrimStrategy {
+        self.trim_strategy
+    }
+
+    pub fn fallback(&self) -> Option<&[(Role, String)]> {
+        self.fallback.as_deref()
+    }
+
+    pub fn max_tokens(&self) -> Option<usize> {
+        self.max_tokens
+    }
+
+    pub fn trim<T>(&self, messages: Vec<T>) -> Vec<T> {
+        self.trim_strategy.apply(messages, self.n_messages)
+    }
+
+    pub fn trim_to_budget<C: TokenCounter + ?Sized>(
+        &self,
+        messages: Vec<MessageEnum>,
+        counter: &C,
+    ) -> Vec<MessageEnum> {
+        let messages = self.trim(messages);
+        match self.max_tokens {
+            Some(max_tokens) => self
+                .trim_strategy
+                .apply_budget(messages, max_tokens, |message| {
+                    counter.count_message(message)
+                }),
+            None => messages,
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, TemplateError> {
+        let variable_name = extract_placeholder_variable(s)?;
+        let mut builder = Self::builder().variable_name(variable_name.as_str());
+
+        let slot = PLACEHOLDER_SLOT_RE
+            .captures_iter(s)
+            .map(|cap| cap.get(1).unwrap().as_str())
+            .find(|expr| split_filters(expr).0 == variable_name)
+            .unwrap_or_default();
+
+        let (_, options) = split_filters(slot);
+        for (option, arg) in options {
+            builder = match (option, arg) {
+                ("optional", None) => builder.optional(true),
+                ("limit", Some(limit)) => builder.n_messages(limit.parse().map_err(|_| {
+                    TemplateError::MalformedTemplate(format!(
+                        "Invalid placeholder limit '{}'.",
+                        limit
+                    ))
+                })?),
+                ("max_tokens", Some(max_tokens)) => {
+                    builder.max_tokens(max_tokens.parse().map_err(|_| {
+                        TemplateError::MalformedTemplate(format!(
+                            "Invalid placeholder token budget '{}'.",
+                            max_tokens
+                        ))
+                    })?)
+                }
+                ("keep", Some(strategy)) => {
+                    builder.trim_strategy(TrimStrategy::try_from(strategy)?)
+                }
+                _ => {
+                    return Err(TemplateError::MalformedTemplate(format!(
+                        "Unknown placeholder option '{}'.",
+                        option
+                    )))
+                }
+            };
+        }
+
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessagesPlaceholderBuilder {
+    variable_name: String,
+    optional: bool,
+    n_messages: usize,
+    trim_strategy: TrimStrategy,
+    fallback: Option<Vec<(Role, String)>>,
+    max_tokens: Option<usize>,
+}
+
+impl MessagesPlaceholderBuilder {
+    pub fn new() -> Self {
+        Self {
+            n_messages: MessagesPlaceholder::DEFAULT_LIMIT,
+            ..Default::default()
+        }
+    }
+
+    pub fn variable_name(mut self, variable_name: impl Into<String>) -> Self {
+        self.variable_name = variable_name.into();
+        self
+    }
+
+    pub fn optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    pub fn n_messages(mut self, n_messages: usize) -> Self {
+        self.n_messages = n_messages;
+        self
+    }
+
+    pub fn trim_strategy(mut self, trim_strategy: TrimStrategy) -> Self {
+        self.trim_strategy = trim_strategy;
+        self
+    }
+
+    pub fn fallback(mut self, messages: Vec<(Role, String)>) -> Self {
+        self.fallback = Some(messages);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn build(self) -> Result<MessagesPlaceholder, TemplateError> {
+        if !is_valid_identifier(&self.variable_name) {
+            return Err(TemplateError::MalformedTemplate(format!(
+                "Invalid placeholder variable name '{}'.",
+                self.variable_name
+            )));
+        }
+
+        let mut placeholder =
+            MessagesPlaceholder::with_options(self.variable_name, self.optional, self.n_messages)
+                .with_trim_strategy(self.trim_strategy);
+        placeholder.max_tokens = self.max_tokens;
+
+        Ok(match self.fallback {
+            Some(fallback) => placeholder.with_fallback(fallback),
+            None => placeholder,
+        })
+    }
 }
 
 impl TryFrom<&str> for MessagesPlaceholder {
     type Error = TemplateError;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        let placeholder_variable = extract_placeholder_variable(s)?;
-        Ok(MessagesPlaceholder::new(placeholder_variable))
+        MessagesPlaceholder::parse(s)
     }
 }
 
@@ -54,8 +308,7 @@ impl TryFrom<String> for MessagesPlaceholder {
     type Error = TemplateError;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        let placeholder_variable = extract_placeholder_variable(&s)?;
-        Ok(MessagesPlaceholder::new(placeholder_variable))
+        MessagesPlaceholder::parse(&s)
     }
 }
 
@@ -183,4 +436,191 @@ mod tests {
         assert!(placeholder.optional());
         assert_eq!(placeholder.n_messages(), 50);
     }
+
+    #[test]
+    fn test_tryfrom_optional_marker() {
+        let placeholder = MessagesPlaceholder::try_from("{history|optional}").unwrap();
+
+        assert_eq!(placeholder.variable_name(), "history");
+        assert!(placeholder.optional());
+        assert_eq!(placeholder.n_messages(), MessagesPlaceholder::DEFAULT_LIMIT);
+        assert_eq!(placeholder.trim_strategy(), TrimStrategy::KeepFirst);
+    }
+
+    #[test]
+    fn test_tryfrom_limit_and_keep_options() {
+        let placeholder =
+            MessagesPlaceholder::try_from("{{ history | limit:10 | keep:last }}".to_string())
+                .unwrap();
+
+        assert_eq!(placeholder.variable_name(), "history");
+        assert!(!placeholder.optional());
+        assert_eq!(placeholder.n_messages(), 10);
+        assert_eq!(placeholder.trim_strategy(), TrimStrategy::KeepLast);
+    }
+
+    #[test]
+    fn test_tryfrom_invalid_options_should_fail() {
+        for template in [
+            "{history|required}",
+            "{history|limit:ten}",
+            "{history|keep:middle}",
+            "{history|optional:yes}",
+        ] {
+            assert!(
+                matches!(
+                    MessagesPlaceholder::try_from(template),
+                    Err(TemplateError::MalformedTemplate(_))
+                ),
+                "{template}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder() {
+        let placeholder = MessagesPlaceholder::builder()
+            .variable_name("history")
+            .optional(true)
+            .n_messages(5)
+            .trim_strategy(TrimStrategy::KeepLast)
+            .fallback(vec![(Role::System, "No history.".to_string())])
+            .build()
+            .unwrap();
+
+        assert_eq!(placeholder.variable_name(), "history");
+        assert!(placeholder.optional());
+        assert_eq!(placeholder.n_messages(), 5);
+        assert_eq!(placeholder.trim_strategy(), TrimStrategy::KeepLast);
+        assert_eq!(placeholder.fallback().map(|f| f.len()), Some(1));
+    }
+
+    #[test]
+    fn test_builder_defaults_match_new() {
+        let placeholder = MessagesPlaceholder::builder()
+            .variable_name("history")
+            .build()
+            .unwrap();
+        assert_eq!(placeholder, MessagesPlaceholder::new("history".to_string()));
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_variable_name() {
+        assert!(MessagesPlaceholder::builder().build().is_err());
+        assert!(MessagesPlaceholder::builder()
+            .variable_name("chat history")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_trim_strategy_apply() {
+        let messages = vec![1, 2, 3, 4];
+
+        assert_eq!(
+            TrimStrategy::KeepFirst.apply(messages.clone(), 2),
+            vec![1, 2]
+        );
+        assert_eq!(
+            TrimStrategy::KeepLast.apply(messages.clone(), 2),
+            vec![3, 4]
+        );
+        assert_eq!(TrimStrategy::KeepLast.apply(messages.clone(), 10), messages);
+    }
+
+    #[test]
+    fn test_trim_strategy_apply_budget() {
+        let messages = vec![3, 1, 4, 1, 5];
+        let cost = |n: &usize| *n;
+
+        assert_eq!(
+            TrimStrategy::KeepFirst.apply_budget(messages.clone(), 8, cost),
+            vec![3, 1, 4]
+        );
+        assert_eq!(
+            TrimStrategy::KeepLast.apply_budget(messages.clone(), 8, cost),
+            vec![1, 5]
+        );
+        assert!(TrimStrategy::KeepLast
+            .apply_budget(messages.clone(), 4, cost)
+            .is_empty());
+        assert_eq!(
+            TrimStrategy::KeepFirst.apply_budget(messages.clone(), 14, cost),
+            messages
+        );
+    }
+
+    #[test]
+    fn test_max_tokens_option() {
+        let placeholder =
+            MessagesPlaceholder::try_from("{history|max_tokens:500|keep:last}").unwrap();
+        assert_eq!(placeholder.max_tokens(), Some(500));
+        assert_eq!(placeholder.trim_strategy(), TrimStrategy::KeepLast);
+        assert_eq!(
+            MessagesPlaceholder::new("history".to_string()).max_tokens(),
+            None
+        );
+        assert!(matches!(
+            MessagesPlaceholder::try_from("{history|max_tokens:lots}"),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+
+        let json = serde_json::to_string(&placeholder).unwrap();
+        assert!(json.contains(r#""max_tokens":500"#));
+        let deserialized: MessagesPlaceholder = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, placeholder);
+        assert!(
+            !serde_json::to_string(&MessagesPlaceholder::new("h".to_string()))
+                .unwrap()
+                .contains("max_tokens")
+        );
+    }
+
+    #[test]
+    fn test_trim_strategy_serialization() {
+        let placeholder = MessagesPlaceholder::new("history".to_string());
+        let json = serde_json::to_string(&placeholder).unwrap();
+        assert!(!json.contains("trim_strategy"));
+
+        let placeholder = placeholder.with_trim_strategy(TrimStrategy::KeepLast);
+        let json = serde_json::to_string(&placeholder).unwrap();
+        assert!(json.contains(r#""trim_strategy":"keep_last""#));
+        let deserialized: MessagesPlaceholder = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, placeholder);
+    }
+
+    #[test]
+    fn test_messages_placeholder_without_fallback() {
+        let placeholder = MessagesPlaceholder::new("history".to_string());
+        assert!(placeholder.fallback().is_none());
+    }
+
+    #[test]
+    fn test_messages_placeholder_with_empty_fallback() {
+        let placeholder = MessagesPlaceholder::new("history".to_string()).with_fallback(vec![]);
+        assert_eq!(placeholder.fallback(), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_messages_placeholder_with_default_message() {
+        let placeholder = MessagesPlaceholder::new("history".to_string())
+            .with_default_message(Role::System, "No prior conversation.");
+
+        assert_eq!(
+            placeholder.fallback(),
+            Some(&[(Role::System, "No prior conversation.".to_string())][..])
+        );
+    }
+
+    #[test]
+    fn test_messages_placeholder_fallback_serialization() {
+        let placeholder = MessagesPlaceholder::new("history".to_string());
+        let json = serde_json::to_string(&placeholder).unwrap();
+        assert!(!json.contains("fallback"));
+
+        let placeholder = placeholder.with_default_message(Role::Ai, "Nothing yet.");
+        let json = serde_json::to_string(&placeholder).unwrap();
+        let deserialized: MessagesPlaceholder = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, placeholder);
+    }
 }
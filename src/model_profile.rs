@@ -0,0 +1,339 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use messageforge::{BaseMessage, MessageEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::{assertions::message_role, Role, TemplateError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemHandling {
+    #[default]
+    Keep,
+    MergeIntoFirstHuman,
+    AsHuman,
+    Drop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptFormat {
+    pub system: String,
+    pub human: String,
+    pub ai: String,
+    pub separator: String,
+}
+
+impl Default for TranscriptFormat {
+    fn default() -> Self {
+        Self {
+            system: "system: ".to_string(),
+            human: "human: ".to_string(),
+            ai: "ai: ".to_string(),
+            separator: "\n".to_string(),
+        }
+    }
+}
+
+impl TranscriptFormat {
+    pub fn render(&self, messages: &[Arc<MessageEnum>]) -> String {
+        messages
+            .iter()
+            .map(|message| {
+                let role_prefix = match message_role(message) {
+                    Some(Role::System) => self.system.as_str(),
+                    Some(Role::Human) => self.human.as_str(),
+                    Some(Role::Ai) => self.ai.as_str(),
+                    _ => "",
+                };
+                format!("{}{}", role_prefix, message.content())
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelProfile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub role_map: BTreeMap<Role, Role>,
+    #[serde(default)]
+    pub system_handling: SystemHandling,
+    #[serde(default)]
+    pub transcript: TranscriptFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages: Option<usize>,
+}
+
+impl ModelProfile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn gpt() -> Self {
+        Self::new("gpt")
+    }
+
+    pub fn claude() -> Self {
+        Self::new("claude").with_transcript(TranscriptFormat {
+            system: String::new(),
+            human: "Human: ".to_string(),
+            ai: "Assistant: ".to_string(),
+            separator: "\n\n".to_string(),
+        })
+    }
+
+    pub fn llama() -> Self {
+        Self::new("llama")
+            .with_system_handling(SystemHandling::MergeIntoFirstHuman)
+            .with_transcript(TranscriptFormat {
+                system: String::new(),
+                human: "[INST] ".to_string(),
+                ai: "[/INST] ".to_string(),
+                separator: "\n".to_string(),
+            })
+    }
+
+    pub fn with_role(mut self, from: Role, to: Role) -> Self {
+        self.role_map.insert(from, to);
+        self
+    }
+
+    pub fn with_system_handling(mut self, system_handling: SystemHandling) -> Self {
+        self.system_handling = system_handling;
+        self
+    }
+
+    pub fn with_transcript(mut self, transcript: TranscriptFormat) -> Self {
+        self.transcript = transcript;
+        self
+    }
+
+    pub fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = Some(max_messages);
+        self
+    }
+
+    pub fn apply(
+        &self,
+        messages: Vec<Arc<MessageEnum>>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let messages = self.handle_system(messages)?;
+        let messages = self.map_roles(messages)?;
+        Ok(self.limit(messages))
+    }
+
+    pub fn transcript(&self, messages: &[Arc<MessageEnum>]) -> String {
+        self.transcript.render(messages)
+    }
+
+    fn handle_system(
+        &self,
+        messages: Vec<Arc<MessageEnum>>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let is_system = |message: &Arc<MessageEnum>| message_role(message) == Some(Role::System);
+
+        match self.system_handling {
+            SystemHandling::Keep => Ok(messages),
+            SystemHandling::Drop => Ok(messages.into_iter().filter(|m| !is_system(m)).collect()),
+            SystemHandling::AsHuman => messages
+                .into_iter()
+                .map(|message| {
+                    if is_system(&message) {
+                        Ok(Role::Human.to_message(message.content())?)
+                    } else {
+                        Ok(message)
+                    }
+                })
+                .collect(),
+            SystemHandling::MergeIntoFirstHuman => {
+                let (system, mut rest): (Vec<_>, Vec<_>) =
+                    messages.into_iter().partition(|m| is_system(m));
+                if system.is_empty() {
+                    return Ok(rest);
+                }
+
+                let system_text = system
+                    .iter()
+                    .map(|message| message.content())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                match rest
+                    .iter()
+                    .position(|message| message_role(message) == Some(Role::Human))
+                {
+                    Some(index) => {
+                        let merged = format!("{}\n\n{}", system_text, rest[index].content());
+                        rest[index] = Role::Human.to_message(&merged)?;
+                    }
+                    None => rest.insert(0, Role::Human.to_message(&system_text)?),
+                }
+                Ok(rest)
+            }
+        }
+    }
+
+    fn map_roles(
+        &self,
+        messages: Vec<Arc<MessageEnum>>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        if self.role_map.is_empty() {
+            return Ok(messages);
+        }
+
+        messages
+            .into_iter()
+            .map(
+                |message| match message_role(&message).and_then(|role| self.role_map.get(&role)) {
+                    Some(target) => Ok(target.to_message(message.content())?),
+                    None => Ok(message),
+                },
+            )
+            .collect()
+    }
+
+    fn limit(&self, messages: Vec<Arc<MessageEnum>>) -> Vec<Arc<MessageEnum>> {
+        let max_messages = match self.max_messages {
+            Some(max_messages) if messages.len() > max_messages => max_messages,
+            _ => return messages,
+        };
+
+        let is_system = |message: &Arc<MessageEnum>| message_role(message) == Some(Role::System);
+        let system_count = messages.iter().filter(|m| is_system(m)).count();
+        let mut budget = max_messages.saturating_sub(system_count);
+
+        let mut keep = vec![false; messages.len()];
+        for (index, message) in messages.iter().enumerate().rev() {
+            if is_system(message) {
+                keep[index] = true;
+            } else if budget > 0 {
+                keep[index] = true;
+                budget -= 1;
+            }
+        }
+
+        messages
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(message, keep)| keep.then_some(message))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Arc<MessageEnum>> {
+        vec![
+            Role::System.to_message("Be concise.").unwrap(),
+            Role::Human.to_message("Hi.").unwrap(),
+            Role::Ai.to_message("Hello!").unwrap(),
+            Role::Human.to_message("What is Rust?").unwrap(),
+        ]
+    }
+
+    fn roles(messages: &[Arc<MessageEnum>]) -> Vec<Role> {
+        messages.iter().filter_map(|m| message_role(m)).collect()
+    }
+
+    #[test]
+    fn test_default_profile_is_identity() {
+        let messages = ModelProfile::gpt().apply(conversation()).unwrap();
+        assert_eq!(messages, conversation());
+    }
+
+    #[test]
+    fn test_merge_system_into_first_human() {
+        let messages = ModelProfile::new("local")
+            .with_system_handling(SystemHandling::MergeIntoFirstHuman)
+            .apply(conversation())
+            .unwrap();
+
+        assert_eq!(roles(&messages), vec![Role::Human, Role::Ai, Role::Human]);
+        assert_eq!(messages[0].content(), "Be concise.\n\nHi.");
+    }
+
+    #[test]
+    fn test_merge_system_without_human_inserts_one() {
+        let messages = ModelProfile::new("local")
+            .with_system_handling(SystemHandling::MergeIntoFirstHuman)
+            .apply(vec![Role::System.to_message("Be concise.").unwrap()])
+            .unwrap();
+
+        assert_eq!(roles(&messages), vec![Role::Human]);
+        assert_eq!(messages[0].content(), "Be concise.");
+    }
+
+    #[test]
+    fn test_system_as_human_and_drop() {
+        let messages = ModelProfile::new("m")
+            .with_system_handling(SystemHandling::AsHuman)
+            .apply(conversation())
+            .unwrap();
+        assert_eq!(messages[0].content(), "Be concise.");
+        assert_eq!(message_role(&messages[0]), Some(Role::Human));
+
+        let messages = ModelProfile::new("m")
+            .with_system_handling(SystemHandling::Drop)
+            .apply(conversation())
+            .unwrap();
+        assert_eq!(roles(&messages), vec![Role::Human, Role::Ai, Role::Human]);
+    }
+
+    #[test]
+    fn test_role_map() {
+        let messages = ModelProfile::new("m")
+            .with_role(Role::Ai, Role::Human)
+            .apply(conversation())
+            .unwrap();
+
+        assert_eq!(
+            roles(&messages),
+            vec![Role::System, Role::Human, Role::Human, Role::Human]
+        );
+        assert_eq!(messages[2].content(), "Hello!");
+    }
+
+    #[test]
+    fn test_max_messages_keeps_system_and_latest() {
+        let messages = ModelProfile::new("m")
+            .with_max_messages(3)
+            .apply(conversation())
+            .unwrap();
+
+        assert_eq!(roles(&messages), vec![Role::System, Role::Ai, Role::Human]);
+        assert_eq!(messages[2].content(), "What is Rust?");
+    }
+
+    #[test]
+    fn test_transcript_formats() {
+        let messages = conversation();
+
+        assert_eq!(
+            ModelProfile::claude().transcript(&messages[1..3]),
+            "Human: Hi.\n\nAssistant: Hello!"
+        );
+        assert_eq!(
+            ModelProfile::gpt().transcript(&messages[..2]),
+            "system: Be concise.\nhuman: Hi."
+        );
+    }
+
+    #[test]
+    fn test_profile_serialization_roundtrip() {
+        let profile = ModelProfile::llama()
+            .with_role(Role::Ai, Role::Human)
+            .with_max_messages(8);
+        let json = serde_json::to_string(&profile).unwrap();
+        let deserialized: ModelProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, profile);
+
+        let minimal: ModelProfile = serde_json::from_str(r#"{"name":"plain"}"#).unwrap();
+        assert_eq!(minimal, ModelProfile::new("plain"));
+    }
+}
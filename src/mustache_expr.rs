@@ -0,0 +1,311 @@
+use crate::{
+    filters::split_filters,
+    macros::{parse_macro_header, MACRO_BLOCK},
+    placeholder::is_valid_identifier,
+};
+
+const BLOCK_HELPERS: &[&str] = &["if", "unless", "each", "with"];
+const CONTEXT_HELPERS: &[&str] = &["each", "with"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MustacheTag {
+    Variable,
+    Helper,
+    Open,
+    Close,
+    Else,
+    Comment,
+    Partial,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MustacheExpr<'a> {
+    pub tag: MustacheTag,
+    pub name: &'a str,
+    pub variables: Vec<&'a str>,
+}
+
+impl MustacheExpr<'_> {
+    pub fn changes_context(&self) -> bool {
+        CONTEXT_HELPERS.contains(&self.name)
+    }
+}
+
+pub fn parse_mustache_expr(expr: &str) -> Option<MustacheExpr<'_>> {
+    let expr = expr.trim();
+
+    if let Some(comment) = expr.strip_prefix('!') {
+        return Some(MustacheExpr {
+            tag: MustacheTag::Comment,
+            name: comment.trim(),
+            variables: Vec::new(),
+        });
+    }
+
+    if let Some(partial) = expr.strip_prefix('>') {
+        let name = partial.trim();
+        return (!name.is_empty()).then_some(MustacheExpr {
+            tag: MustacheTag::Partial,
+            name,
+            variables: Vec::new(),
+        });
+    }
+
+    if parse_macro_header(expr).is_some() {
+        return Some(MustacheExpr {
+            tag: MustacheTag::Open,
+            name: MACRO_BLOCK,
+            variables: Vec::new(),
+        });
+    }
+
+    if let Some(name) = expr.strip_prefix('/') {
+        let name = name.trim();
+        return path_root(name).map(|_| MustacheExpr {
+            tag: MustacheTag::Close,
+            name,
+            variables: Vec::new(),
+        });
+    }
+
+    if let Some(block) = expr.strip_prefix(['#', '^']) {
+        let params = split_params(strip_block_params(block)?)?;
+        let (&name, args) = params.split_first()?;
+        let variables = if args.is_empty() {
+            if BLOCK_HELPERS.contains(&name) {
+                return None;
+            }
+            path_root(name)?.into_iter().collect()
+        } else {
+            is_valid_identifier(name).then_some(())?;
+            param_variables(args)?
+        };
+        return Some(MustacheExpr {
+            tag: MustacheTag::Open,
+            name,
+            variables,
+        });
+    }
+
+    let (filtered, _) = split_filters(expr);
+    if filtered.len() < expr.len() {
+        return path_root(filtered).map(|root| MustacheExpr {
+            tag: MustacheTag::Variable,
+            name: filtered,
+            variables: root.into_iter().collect(),
+        });
+    }
+
+    let params = split_params(expr)?;
+    let (&name, args) = params.split_first()?;
+
+    if name == "else" {
+        return Some(MustacheExpr {
+            tag: MustacheTag::Else,
+            name,
+            variables: param_variables(args.get(1..).unwrap_or_default())?,
+        });
+    }
+
+    if args.is_empty() {
+        return path_root(name).map(|root| MustacheExpr {
+            tag: MustacheTag::Variable,
+            name,
+            variables: root.into_iter().collect(),
+        });
+    }
+
+    is_valid_identifier(name).then_some(())?;
+    Some(MustacheExpr {
+        tag: MustacheTag::Helper,
+        name,
+        variables: param_variables(args)?,
+    })
+}
+
+fn strip_block_params(block: &str) -> Option<&str> {
+    let Some((head, params)) = block.split_once(" as |") else {
+        return Some(block);
+    };
+    let names = params.trim_end().strip_suffix('|')?;
+    let valid = !names.trim().is_empty() && names.split_whitespace().all(is_valid_identifier);
+
+    valid.then_some(head)
+}
+
+pub(crate) fn split_params(expr: &str) -> Option<Vec<&str>> {
+    let mut params = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    let mut depth = 0usize;
+
+    for (index, c) in expr.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                start.get_or_insert(index);
+            }
+            (None, '(') => {
+                depth += 1;
+                start.get_or_insert(index);
+            }
+            (None, ')') => {
+                depth = depth.checked_sub(1)?;
+            }
+            (None, c) if c.is_whitespace() && depth == 0 => {
+                if let Some(begin) = start.take() {
+                    params.push(&expr[begin..index]);
+                }
+            }
+            (None, _) => {
+                start.get_or_insert(index);
+            }
+        }
+    }
+
+    if quote.is_some() || depth > 0 {
+        return None;
+    }
+    params.extend(start.map(|begin| &expr[begin..]));
+
+    Some(params)
+}
+
+fn param_variables<'a>(params: &[&'a str]) -> Option<Vec<&'a str>> {
+    let mut variables = Vec::new();
+
+    for param in params {
+        let value = match param.split_once('=') {
+            Some((key, value)) if is_valid_identifier(key) => value,
+            _ => param,
+        };
+
+        if is_literal(value) {
+            continue;
+        }
+
+        if let Some(subexpr) = value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+            let params = split_params(subexpr)?;
+            let (&helper, args) = params.split_first()?;
+            is_valid_identifier(helper).then_some(())?;
+            variables.extend(param_variables(args)?);
+            continue;
+        }
+
+        variables.extend(path_root(value)?);
+    }
+
+    Some(variables)
+}
+
+fn is_literal(param: &str) -> bool {
+    let quoted = param.len() >= 2
+        && ((param.starts_with('"') && param.ends_with('"'))
+            || (param.starts_with('\'') && param.ends_with('\'')));
+
+    quoted
+        || matches!(param, "true" | "false" | "null" | "undefined")
+        || param.parse::<f64>().is_ok()
+}
+
+fn path_root(path: &str) -> Option<Option<&str>> {
+    if path == "this" || path == "." {
+        return Some(None);
+    }
+
+    let (local, path) = match path
+        .strip_prefix("this.")
+        .or_else(|| path.strip_prefix("../"))
+        .or_else(|| path.strip_prefix('@'))
+    {
+        Some(rest) => (true, rest),
+        None => (false, path),
+    };
+
+    let valid = path
+        .split(['.', '/'])
+        .all(|segment| is_valid_identifier(segment) || segment == "..");
+    if !valid {
+        return None;
+    }
+
+    let root = path.split(['.', '/']).next()?;
+    Some((!local && root != "..").then_some(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(expr: &str) -> Option<MustacheTag> {
+        parse_mustache_expr(expr).map(|expr| expr.tag)
+    }
+
+    fn variables(expr: &str) -> Vec<&str> {
+        parse_mustache_expr(expr).unwrap().variables
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(tag("name"), Some(MustacheTag::Variable));
+        assert_eq!(tag(" user.name "), Some(MustacheTag::Variable));
+        assert_eq!(tag("upper name"), Some(MustacheTag::Helper));
+        assert_eq!(tag("#if ready"), Some(MustacheTag::Open));
+        assert_eq!(tag("^items"), Some(MustacheTag::Open));
+        assert_eq!(tag("/if"), Some(MustacheTag::Close));
+        assert_eq!(tag("else"), Some(MustacheTag::Else));
+        assert_eq!(
+            tag("! a comment, with punctuation"),
+            Some(MustacheTag::Comment)
+        );
+        assert_eq!(tag("> footer"), Some(MustacheTag::Partial));
+    }
+
+    #[test]
+    fn test_parse_variables() {
+        assert_eq!(variables("name"), vec!["name"]);
+        assert_eq!(variables("user.name"), vec!["user"]);
+        assert_eq!(variables("doc|trim"), vec!["doc"]);
+        assert_eq!(variables("upper name"), vec!["name"]);
+        assert_eq!(
+            variables("join items \", \" limit=max"),
+            vec!["items", "max"]
+        );
+        assert_eq!(variables("truncate text 20 ellipsis=true"), vec!["text"]);
+        assert_eq!(variables("#each items"), vec!["items"]);
+        assert_eq!(variables("#each users as |user index|"), vec!["users"]);
+        assert_eq!(variables("#section"), vec!["section"]);
+        assert_eq!(variables("this.name"), Vec::<&str>::new());
+        assert_eq!(variables("@index"), Vec::<&str>::new());
+        assert_eq!(variables("upper ../title"), Vec::<&str>::new());
+        assert_eq!(variables("/each"), Vec::<&str>::new());
+        assert_eq!(variables("add count 1"), vec!["count"]);
+        assert_eq!(variables("#if (gt count 5)"), vec!["count"]);
+        assert_eq!(
+            variables("#if (and (gte score min) (lt score \"1 0\"))"),
+            vec!["score", "min", "score"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_expressions() {
+        assert_eq!(tag("hello, world!"), None);
+        assert_eq!(tag("var!invalid"), None);
+        assert_eq!(tag("upper \"unterminated"), None);
+        assert_eq!(tag("123 name"), None);
+        assert_eq!(tag("#if"), None);
+        assert_eq!(tag("#each users as |bad name!|"), None);
+        assert_eq!(tag("#each users as ||"), None);
+        assert_eq!(tag("#"), None);
+        assert_eq!(tag("/"), None);
+        assert_eq!(tag(">"), None);
+        assert_eq!(tag("doc | trim"), Some(MustacheTag::Variable));
+        assert_eq!(tag("two words | trim"), None);
+        assert_eq!(tag("#if (gt count 5"), None);
+        assert_eq!(tag("#if gt count 5)"), None);
+        assert_eq!(tag("#if ()"), None);
+        assert_eq!(tag("#if (1x count)"), None);
+    }
+}
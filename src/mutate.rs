@@ -0,0 +1,361 @@
+use std::collections::{BTreeMap, HashSet};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    sweep::splitmix64, FewShotTemplate, Formattable, Templatable, Template, TemplateError,
+};
+
+lazy_static! {
+    static ref WORD_RE: Regex = Regex::new(r"\{[^}]*\}+|[A-Za-z][A-Za-z'-]*").unwrap();
+}
+
+pub const SECTION_SEPARATOR: &str = "\n\n";
+
+#[derive(Debug, Clone)]
+pub struct Mutant<T> {
+    pub label: String,
+    pub value: T,
+}
+
+impl<T> Mutant<T> {
+    pub fn new(label: impl Into<String>, value: T) -> Self {
+        Self {
+            label: label.into(),
+            value,
+        }
+    }
+}
+
+pub trait Mutation<T> {
+    fn mutate(&self, input: &T) -> Result<Vec<Mutant<T>>, TemplateError>;
+
+    fn then<M>(self, next: M) -> Chain<Self, M>
+    where
+        Self: Sized,
+        M: Mutation<T>,
+    {
+        Chain {
+            first: self,
+            second: next,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<T, A, B> Mutation<T> for Chain<A, B>
+where
+    A: Mutation<T>,
+    B: Mutation<T>,
+{
+    fn mutate(&self, input: &T) -> Result<Vec<Mutant<T>>, TemplateError> {
+        let mut results = Vec::new();
+        for first in self.first.mutate(input)? {
+            for second in self.second.mutate(&first.value)? {
+                results.push(Mutant::new(
+                    format!("{}+{}", first.label, second.label),
+                    second.value,
+                ));
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReorderExamples {
+    pub count: usize,
+    pub seed: u64,
+}
+
+impl ReorderExamples {
+    pub const MAX_ATTEMPTS: usize = 1000;
+
+    pub fn new(count: usize, seed: u64) -> Self {
+        Self { count, seed }
+    }
+}
+
+impl<T> Mutation<FewShotTemplate<T>> for ReorderExamples
+where
+    T: Templatable
+        + Formattable
+        + Clone
+        + DeserializeOwned
+        + TryFrom<String, Error = TemplateError>,
+{
+    fn mutate(
+        &self,
+        input: &FewShotTemplate<T>,
+    ) -> Result<Vec<Mutant<FewShotTemplate<T>>>, TemplateError> {
+        let n = input.examples().len();
+        let identity: Vec<usize> = (0..n).collect();
+        let mut seen = HashSet::from([identity.clone()]);
+        let mut state = self.seed;
+        let mut results = Vec::new();
+
+        for _ in 0..Self::MAX_ATTEMPTS {
+            if results.len() >= self.count {
+                break;
+            }
+
+            let mut order = identity.clone();
+            for i in (1..n).rev() {
+                let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+                order.swap(i, j);
+            }
+            if !seen.insert(order.clone()) {
+                continue;
+            }
+
+            let examples = order.iter().map(|&i| input.examples()[i].clone()).collect();
+            let label = format!(
+                "reorder:{}",
+                order
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join("-")
+            );
+            results.push(Mutant::new(
+                label,
+                FewShotTemplate::with_options(
+                    examples,
+                    input.prefix().cloned(),
+                    input.suffix().cloned(),
+                    input.example_separator(),
+                ),
+            ));
+        }
+
+        Ok(results)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SwapSynonyms {
+    thesaurus: BTreeMap<String, Vec<String>>,
+}
+
+impl SwapSynonyms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_thesaurus(thesaurus: BTreeMap<String, Vec<String>>) -> Self {
+        Self { thesaurus }
+    }
+
+    pub fn synonyms<I, S>(mut self, word: impl Into<String>, alternatives: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.thesaurus
+            .entry(word.into())
+            .or_default()
+            .extend(alternatives.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl Mutation<Template> for SwapSynonyms {
+    fn mutate(&self, input: &Template) -> Result<Vec<Mutant<Template>>, TemplateError> {
+        let source = input.template();
+        let mut results = Vec::new();
+
+        for (word, alternatives) in &self.thesaurus {
+            let occurrences: Vec<_> = WORD_RE
+                .find_iter(source)
+                .filter(|m| m.as_str() == word)
+                .collect();
+            if occurrences.is_empty() {
+                continue;
+            }
+
+            for alternative in alternatives {
+                let mut swapped = String::with_capacity(source.len());
+                let mut last = 0;
+                for occurrence in &occurrences {
+                    swapped.push_str(&source[last..occurrence.start()]);
+                    swapped.push_str(alternative);
+                    last = occurrence.end();
+                }
+                swapped.push_str(&source[last..]);
+
+                results.push(Mutant::new(
+                    format!("synonym:{}->{}", word, alternative),
+                    Template::new(&swapped)?,
+                ));
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DropSections {
+    sections: Option<Vec<usize>>,
+}
+
+impl DropSections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn only<I: IntoIterator<Item = usize>>(sections: I) -> Self {
+        Self {
+            sections: Some(sections.into_iter().collect()),
+        }
+    }
+}
+
+impl Mutation<Template> for DropSections {
+    fn mutate(&self, input: &Template) -> Result<Vec<Mutant<Template>>, TemplateError> {
+        let sections: Vec<&str> = input.template().split(SECTION_SEPARATOR).collect();
+        if sections.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let candidates: Vec<usize> = match &self.sections {
+            Some(indices) => indices
+                .iter()
+                .copied()
+                .filter(|&i| i < sections.len())
+                .collect(),
+            None => (0..sections.len()).collect(),
+        };
+
+        candidates
+            .into_iter()
+            .map(|dropped| {
+                let remaining = sections
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != dropped)
+                    .map(|(_, section)| *section)
+                    .collect::<Vec<_>>()
+                    .join(SECTION_SEPARATOR);
+                Ok(Mutant::new(
+                    format!("drop:{}", dropped),
+                    Template::new(&remaining)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars;
+
+    fn labels<T>(mutants: &[Mutant<T>]) -> Vec<&str> {
+        mutants.iter().map(|m| m.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_swap_synonyms() {
+        let template =
+            Template::new("Write a short summary of {short_doc}. Keep it short.").unwrap();
+        let mutation = SwapSynonyms::new().synonyms("short", ["brief", "concise"]);
+
+        let mutants = mutation.mutate(&template).unwrap();
+        assert_eq!(
+            labels(&mutants),
+            vec!["synonym:short->brief", "synonym:short->concise"]
+        );
+        assert_eq!(
+            mutants[0].value.template(),
+            "Write a brief summary of {short_doc}. Keep it brief."
+        );
+        assert_eq!(mutants[0].value.input_variables(), vec!["short_doc"]);
+    }
+
+    #[test]
+    fn test_swap_synonyms_ignores_missing_words() {
+        let template = Template::new("Hello there.").unwrap();
+        let mutation = SwapSynonyms::new().synonyms("short", ["brief"]);
+        assert!(mutation.mutate(&template).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_drop_sections() {
+        let template =
+            Template::new("You are a helper.\n\nBe polite.\n\nAnswer: {question}").unwrap();
+
+        let mutants = DropSections::new().mutate(&template).unwrap();
+        assert_eq!(labels(&mutants), vec!["drop:0", "drop:1", "drop:2"]);
+        assert_eq!(
+            mutants[1].value.template(),
+            "You are a helper.\n\nAnswer: {question}"
+        );
+
+        let mutants = DropSections::only([1, 7]).mutate(&template).unwrap();
+        assert_eq!(labels(&mutants), vec!["drop:1"]);
+    }
+
+    #[test]
+    fn test_drop_sections_keeps_single_section() {
+        let template = Template::new("Only {one} section.").unwrap();
+        assert!(DropSections::new().mutate(&template).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reorder_examples() {
+        let few_shot = FewShotTemplate::new(vec![
+            Template::new("Q: 1").unwrap(),
+            Template::new("Q: 2").unwrap(),
+            Template::new("Q: 3").unwrap(),
+        ]);
+
+        let mutants = ReorderExamples::new(10, 7).mutate(&few_shot).unwrap();
+        assert_eq!(mutants.len(), 5);
+
+        let unique: HashSet<&str> = labels(&mutants).into_iter().collect();
+        assert_eq!(unique.len(), 5);
+        assert!(!unique.contains("reorder:0-1-2"));
+
+        let again = ReorderExamples::new(10, 7).mutate(&few_shot).unwrap();
+        assert_eq!(labels(&again), labels(&mutants));
+
+        let first = &mutants[0];
+        let order: Vec<usize> = first.label["reorder:".len()..]
+            .split('-')
+            .map(|i| i.parse().unwrap())
+            .collect();
+        assert_eq!(
+            first.value.examples()[0].template(),
+            few_shot.examples()[order[0]].template()
+        );
+    }
+
+    #[test]
+    fn test_chained_mutations() {
+        let template = Template::new("Be short.\n\nSummarize {doc}.").unwrap();
+        let mutation = SwapSynonyms::new()
+            .synonyms("Summarize", ["Condense", "Recap"])
+            .then(DropSections::only([0]));
+
+        let mutants = mutation.mutate(&template).unwrap();
+        assert_eq!(
+            labels(&mutants),
+            vec![
+                "synonym:Summarize->Condense+drop:0",
+                "synonym:Summarize->Recap+drop:0"
+            ]
+        );
+        assert_eq!(
+            mutants[1].value.format(&vars!(doc = "the memo")).unwrap(),
+            "Recap the memo."
+        );
+    }
+}
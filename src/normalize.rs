@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::TemplateError;
+
+pub const ALL_VARIABLES: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalizer {
+    Trim,
+    CollapseWhitespace,
+    Nfc,
+    StripControl,
+    NormalizeNewlines,
+    StripBom,
+    RejectControl,
+}
+
+fn is_disallowed_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\t' | '\r')
+}
+
+impl Normalizer {
+    pub fn try_apply(&self, value: &str) -> Result<String, TemplateError> {
+        match self {
+            Normalizer::RejectControl => match value.chars().find(|c| is_disallowed_control(*c)) {
+                Some(c) => Err(TemplateError::InvalidVariable(format!(
+                    "value contains control character U+{:04X}",
+                    u32::from(c)
+                ))),
+                None => Ok(value.to_string()),
+            },
+            _ => Ok(self.apply(value)),
+        }
+    }
+
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Normalizer::Trim => value.trim().to_string(),
+            Normalizer::CollapseWhitespace => {
+                value.split_whitespace().collect::<Vec<_>>().join(" ")
+            }
+            Normalizer::Nfc => value.nfc().collect(),
+            Normalizer::StripControl => value
+                .chars()
+                .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+                .collect(),
+            Normalizer::NormalizeNewlines => value.replace("\r\n", "\n").replace('\r', "\n"),
+            Normalizer::StripBom => value.replace('\u{feff}', ""),
+            Normalizer::RejectControl => value.to_string(),
+        }
+    }
+}
+
+pub type VariableNormalizers = BTreeMap<String, Vec<Normalizer>>;
+
+pub fn normalize(value: &str, normalizers: &[Normalizer]) -> String {
+    normalizers
+        .iter()
+        .fold(value.to_string(), |acc, normalizer| normalizer.apply(&acc))
+}
+
+pub fn try_normalize(value: &str, normalizers: &[Normalizer]) -> Result<String, TemplateError> {
+    normalizers
+        .iter()
+        .try_fold(value.to_string(), |acc, normalizer| {
+            normalizer.try_apply(&acc)
+        })
+}
+
+pub fn normalize_variables<'a>(
+    normalizers: &VariableNormalizers,
+    variables: &HashMap<&'a str, &str>,
+) -> Result<HashMap<&'a str, String>, TemplateError> {
+    let shared = normalizers
+        .get(ALL_VARIABLES)
+        .map_or(&[][..], Vec::as_slice);
+
+    variables
+        .iter()
+        .map(|(name, value)| {
+            let pipeline = normalizers.get(*name).map_or(&[][..], Vec::as_slice);
+            let value = try_normalize(value, shared)
+                .and_then(|value| try_normalize(&value, pipeline))
+                .map_err(|e| match e {
+                    TemplateError::InvalidVariable(msg) => {
+                        TemplateError::InvalidVariable(format!("'{}': {}", name, msg))
+                    }
+                    e => e,
+                })?;
+            Ok((*name, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars;
+
+    #[test]
+    fn test_individual_normalizers() {
+        assert_eq!(Normalizer::Trim.apply("  hi \n"), "hi");
+        assert_eq!(
+            Normalizer::CollapseWhitespace.apply(" a \n\n b\t c "),
+            "a b c"
+        );
+        assert_eq!(Normalizer::Nfc.apply("e\u{301}"), "\u{e9}");
+        assert_eq!(
+            Normalizer::StripControl.apply("a\u{0}b\u{7}c\nd\te\u{1b}"),
+            "abc\nd\te"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_runs_in_order() {
+        let pipeline = [Normalizer::StripControl, Normalizer::Trim, Normalizer::Nfc];
+        assert_eq!(normalize("\u{0} cafe\u{301} ", &pipeline), "caf\u{e9}");
+        assert_eq!(normalize("unchanged", &[]), "unchanged");
+    }
+
+    #[test]
+    fn test_normalize_variables_only_touches_declared() {
+        let normalizers =
+            VariableNormalizers::from([("doc".to_string(), vec![Normalizer::CollapseWhitespace])]);
+        let normalized =
+            normalize_variables(&normalizers, &vars!(doc = " a  b ", raw = " a  b ")).unwrap();
+
+        assert_eq!(normalized["doc"], "a b");
+        assert_eq!(normalized["raw"], " a  b ");
+    }
+
+    #[test]
+    fn test_normalizer_serialization() {
+        let json =
+            serde_json::to_string(&vec![Normalizer::CollapseWhitespace, Normalizer::Nfc]).unwrap();
+        assert_eq!(json, r#"["collapse_whitespace","nfc"]"#);
+    }
+
+    #[test]
+    fn test_transport_normalizers() {
+        assert_eq!(
+            Normalizer::NormalizeNewlines.apply("a\r\nb\rc\n"),
+            "a\nb\nc\n"
+        );
+        assert_eq!(Normalizer::StripBom.apply("\u{feff}hello"), "hello");
+        assert_eq!(
+            Normalizer::RejectControl.try_apply("line\r\n\tok").unwrap(),
+            "line\r\n\tok"
+        );
+        assert!(matches!(
+            Normalizer::RejectControl.try_apply("bell\u{7}"),
+            Err(TemplateError::InvalidVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_all_variables_pipeline_runs_first() {
+        let normalizers = VariableNormalizers::from([
+            (
+                ALL_VARIABLES.to_string(),
+                vec![Normalizer::StripBom, Normalizer::NormalizeNewlines],
+            ),
+            ("doc".to_string(), vec![Normalizer::Trim]),
+        ]);
+        let normalized =
+            normalize_variables(&normalizers, &vars!(doc = "\u{feff} a\r\n", raw = "x\r\ny"))
+                .unwrap();
+        assert_eq!(normalized["doc"], "a");
+        assert_eq!(normalized["raw"], "x\ny");
+
+        let strict = VariableNormalizers::from([(
+            ALL_VARIABLES.to_string(),
+            vec![Normalizer::RejectControl],
+        )]);
+        let err = normalize_variables(&strict, &vars!(doc = "a\u{0}b")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid variable: 'doc': value contains control character U+0000"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use messageforge::BaseMessage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assertions::declared_role,
+    hf_chat_template::hf_role,
+    reasoning::{ReasoningEffort, Verbosity},
+    ChatTemplate, Role, TemplateError, Variables,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenAiMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenAiRequest {
+    pub model: String,
+    pub messages: Vec<OpenAiMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<Verbosity>,
+}
+
+impl ChatTemplate {
+    pub fn to_openai_request(
+        &self,
+        model: impl Into<String>,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<OpenAiRequest, TemplateError> {
+        let messages = self
+            .format_messages(variables)?
+            .iter()
+            .map(|message| OpenAiMessage {
+                role: hf_role(declared_role(message).unwrap_or(Role::Human)).to_string(),
+                content: message.content().to_string(),
+            })
+            .collect();
+
+        let reasoning = self.meta.reasoning.unwrap_or_default();
+        Ok(OpenAiRequest {
+            model: model.into(),
+            messages,
+            reasoning_effort: reasoning.effort,
+            verbosity: reasoning.verbosity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats,
+        reasoning::ReasoningHints,
+        vars,
+        Role::{Developer, Human, Placeholder, System},
+    };
+
+    fn roles(request: &OpenAiRequest) -> Vec<&str> {
+        request
+            .messages
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_developer_role_is_preserved() {
+        let template = ChatTemplate::from_messages(chats!(
+            Developer = "Follow the style guide.",
+            Human = "Summarize {topic}.",
+        ))
+        .unwrap();
+
+        let request = template
+            .to_openai_request("gpt-4o", &vars!(topic = "the release"))
+            .unwrap();
+        assert_eq!(roles(&request), vec!["developer", "user"]);
+        assert_eq!(request.messages[0].content, "Follow the style guide.");
+        assert_eq!(request.messages[1].content, "Summarize the release.");
+    }
+
+    #[test]
+    fn test_reasoning_hints_are_emitted() {
+        let template = ChatTemplate::from_messages(chats!(
+            Developer = "Be terse.",
+            System = "Answer in English.",
+            Placeholder = "{history}",
+            Human = "{question}",
+        ))
+        .unwrap()
+        .with_reasoning_hints(
+            ReasoningHints::new()
+                .effort(ReasoningEffort::Low)
+                .verbosity(Verbosity::Low),
+        );
+        let history = serde_json::json!([
+            { "role": "system", "content": "Earlier instructions." },
+            { "role": "ai", "content": "Understood." },
+        ])
+        .to_string();
+
+        let request = template
+            .to_openai_request("o3", &vars!(history = history.as_str(), question = "Why?"))
+            .unwrap();
+        assert_eq!(
+            roles(&request),
+            vec!["developer", "system", "system", "assistant", "user"]
+        );
+
+        let payload = serde_json::to_value(&request).unwrap();
+        assert_eq!(payload["model"], "o3");
+        assert_eq!(payload["reasoning_effort"], "low");
+        assert_eq!(payload["verbosity"], "low");
+    }
+
+    #[test]
+    fn test_plain_system_messages_without_hints() {
+        let template = ChatTemplate::from_messages(chats!(System = "Be terse.")).unwrap();
+        let request = template.to_openai_request("gpt-4o", &vars!()).unwrap();
+
+        assert_eq!(roles(&request), vec!["system"]);
+        let payload = serde_json::to_string(&request).unwrap();
+        assert!(!payload.contains("reasoning_effort"));
+    }
+}
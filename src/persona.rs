@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, MessageLike, Templatable, Template};
+
+pub const PERSONA_VARIABLE: &str = "persona";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+}
+
+impl Persona {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_style(mut self, style: impl Into<String>) -> Self {
+        self.style = Some(style.into());
+        self
+    }
+
+    pub fn with_constraint(mut self, constraint: impl Into<String>) -> Self {
+        self.constraints.push(constraint.into());
+        self
+    }
+
+    pub fn with_example(mut self, phrase: impl Into<String>) -> Self {
+        self.examples.push(phrase.into());
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut block = format!("You are {}.", self.name);
+        if let Some(style) = &self.style {
+            block.push_str(&format!("\nStyle: {}", style));
+        }
+        if !self.constraints.is_empty() {
+            block.push_str("\nConstraints:");
+            for constraint in &self.constraints {
+                block.push_str(&format!("\n- {}", constraint));
+            }
+        }
+        if !self.examples.is_empty() {
+            block.push_str("\nExample phrases:");
+            for phrase in &self.examples {
+                block.push_str(&format!("\n- \"{}\"", phrase));
+            }
+        }
+        block
+    }
+}
+
+impl Template {
+    pub fn with_persona(self, persona: &Persona) -> Self {
+        if uses_persona(&self) {
+            self.with_partial(PERSONA_VARIABLE, &persona.render())
+        } else {
+            self
+        }
+    }
+}
+
+impl ChatTemplate {
+    pub fn with_persona(mut self, persona: &Persona) -> Self {
+        let block = persona.render();
+        for message in &mut self.messages {
+            if let MessageLike::RolePromptTemplate(_, template) = message {
+                if uses_persona(template) {
+                    *template =
+                        Arc::new((**template).clone().with_partial(PERSONA_VARIABLE, &block));
+                }
+            }
+        }
+        self
+    }
+}
+
+fn uses_persona(template: &Template) -> bool {
+    template
+        .input_variables()
+        .iter()
+        .any(|var| var == PERSONA_VARIABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{
+        chats, vars, PromptRegistry,
+        Role::{Human, System},
+    };
+
+    fn concierge() -> Persona {
+        Persona::new("Ava, the hotel concierge")
+            .with_style("warm and concise")
+            .with_constraint("Never quote prices.")
+            .with_example("Happy to help with that!")
+    }
+
+    #[test]
+    fn test_render_block() {
+        assert_eq!(
+            concierge().render(),
+            "You are Ava, the hotel concierge.\nStyle: warm and concise\nConstraints:\n- Never \
+             quote prices.\nExample phrases:\n- \"Happy to help with that!\""
+        );
+        assert_eq!(
+            Persona::new("a {tone} bot").render(),
+            "You are a {tone} bot."
+        );
+    }
+
+    #[test]
+    fn test_inject_into_templates() {
+        let template = Template::new("{persona}\n\nAnswer: {question}")
+            .unwrap()
+            .with_persona(&Persona::new("a {tone} bot"));
+        assert_eq!(template.input_variables(), vec!["question"]);
+        assert_eq!(
+            template.format(&vars!(question = "Hi?")).unwrap(),
+            "You are a {tone} bot.\n\nAnswer: Hi?"
+        );
+
+        let chat = ChatTemplate::from_messages(chats!(System = "{persona}", Human = "{question}",))
+            .unwrap()
+            .with_persona(&concierge());
+        let messages = chat
+            .format_messages(&vars!(question = "Late checkout?"))
+            .unwrap();
+        assert!(messages[0]
+            .content()
+            .starts_with("You are Ava, the hotel concierge."));
+        assert_eq!(messages[1].content(), "Late checkout?");
+    }
+
+    #[test]
+    fn test_registry_and_serde() {
+        let registry = PromptRegistry::new()
+            .with("personas/concierge", concierge())
+            .unwrap();
+        assert_eq!(
+            *registry.persona("personas/concierge").unwrap(),
+            concierge()
+        );
+        assert!(registry.template("personas/concierge").is_none());
+        assert!(registry.format("personas/concierge", &vars!()).is_err());
+
+        let json = serde_json::to_string(&Persona::new("Bo")).unwrap();
+        assert_eq!(json, r#"{"name":"Bo"}"#);
+    }
+}
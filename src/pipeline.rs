@@ -0,0 +1,213 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, Formattable, TemplateError, Variables};
+
+type Stage = Arc<dyn Formattable + Send + Sync>;
+
+#[derive(Clone)]
+pub struct PipelineTemplate<F> {
+    final_template: F,
+    stages: Vec<(String, Stage)>,
+}
+
+impl<F: Formattable> PipelineTemplate<F> {
+    pub fn new(final_template: F) -> Self {
+        Self {
+            final_template,
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn with_stage(
+        mut self,
+        name: impl Into<String>,
+        template: impl Formattable + Send + Sync + 'static,
+    ) -> Self {
+        let name = name.into();
+        let stage: Stage = Arc::new(template);
+        match self
+            .stages
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some((_, existing)) => *existing = stage,
+            None => self.stages.push((name, stage)),
+        }
+        self
+    }
+
+    pub fn final_template(&self) -> &F {
+        &self.final_template
+    }
+
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub fn stage_outputs(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<Vec<(String, String)>, TemplateError> {
+        let variables = variables.as_map();
+        let mut outputs: Vec<(String, String)> = Vec::with_capacity(self.stages.len());
+
+        for (name, stage) in &self.stages {
+            let output = {
+                let mut scoped = variables.clone().into_owned();
+                for (prior, value) in &outputs {
+                    scoped.insert(prior.as_str(), value.as_str());
+                }
+                stage.format(&scoped).map_err(|e| stage_error(name, e))?
+            };
+            outputs.push((name.clone(), output));
+        }
+
+        Ok(outputs)
+    }
+
+    fn with_stage_outputs<T>(
+        &self,
+        variables: &(impl Variables + ?Sized),
+        render: impl FnOnce(&HashMap<&str, &str>) -> Result<T, TemplateError>,
+    ) -> Result<T, TemplateError> {
+        let outputs = self.stage_outputs(variables)?;
+        let mut merged = variables.as_map().into_owned();
+        for (name, output) in &outputs {
+            merged.insert(name.as_str(), output.as_str());
+        }
+        render(&merged)
+    }
+}
+
+fn stage_error(name: &str, error: TemplateError) -> TemplateError {
+    match error {
+        TemplateError::MissingVariable(msg) => {
+            TemplateError::MissingVariable(format!("stage '{}': {}", name, msg))
+        }
+        TemplateError::MalformedTemplate(msg) => {
+            TemplateError::MalformedTemplate(format!("stage '{}': {}", name, msg))
+        }
+        other => other,
+    }
+}
+
+impl<F: Formattable> Formattable for PipelineTemplate<F> {
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError> {
+        self.with_stage_outputs(variables, |variables| self.final_template.format(variables))
+    }
+}
+
+impl PipelineTemplate<ChatTemplate> {
+    pub fn format_messages(
+        &self,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.with_stage_outputs(variables, |variables| {
+            self.final_template.format_messages(variables)
+        })
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for PipelineTemplate<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineTemplate")
+            .field("final_template", &self.final_template)
+            .field(
+                "stages",
+                &self
+                    .stages
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{
+        chats, vars, FewShotTemplate, PromptVars,
+        Role::{Human, System},
+        Template,
+    };
+
+    #[test]
+    fn test_pipeline_composes_stages() {
+        let pipeline =
+            PipelineTemplate::new(Template::new("{persona}\n\n{examples}\n\n{task}").unwrap())
+                .with_stage(
+                    "persona",
+                    Template::new("You are {name}, a {style} assistant.").unwrap(),
+                )
+                .with_stage(
+                    "examples",
+                    FewShotTemplate::from_example_sets(
+                        &Template::new("Q: {q}\nA: {a}").unwrap(),
+                        vec![vars!(q = "2+2?", a = "4")],
+                    ),
+                )
+                .with_stage(
+                    "task",
+                    Template::new("As {name}, answer: {question}").unwrap(),
+                );
+
+        assert_eq!(pipeline.stage_names(), vec!["persona", "examples", "task"]);
+        let formatted = pipeline
+            .format(&vars!(name = "Ada", style = "terse", question = "3+3?"))
+            .unwrap();
+        assert_eq!(
+            formatted,
+            "You are Ada, a terse assistant.\n\nQ: 2+2?\nA: 4\n\nAs Ada, answer: 3+3?"
+        );
+
+        let owned = PromptVars::new()
+            .with("name", "Bo")
+            .with("style", "warm")
+            .with("question", "1+1?");
+        let stage: &dyn Formattable = &pipeline;
+        assert!(stage
+            .format(&owned)
+            .unwrap()
+            .ends_with("As Bo, answer: 1+1?"));
+    }
+
+    #[test]
+    fn test_later_stages_see_earlier_outputs() {
+        let pipeline = PipelineTemplate::new(Template::new("{summary}").unwrap())
+            .with_stage("intro", Template::new("Hi {name}.").unwrap())
+            .with_stage("summary", Template::new("[{intro}]").unwrap())
+            .with_stage("intro", Template::new("Hello {name}.").unwrap());
+
+        assert_eq!(pipeline.stage_names(), vec!["intro", "summary"]);
+        assert_eq!(pipeline.format(&vars!(name = "Bo")).unwrap(), "[Hello Bo.]");
+
+        let err = pipeline.format(&vars!()).unwrap_err();
+        assert!(
+            matches!(err, TemplateError::MissingVariable(msg) if msg.starts_with("stage 'intro'"))
+        );
+    }
+
+    #[test]
+    fn test_chat_template_pipeline() {
+        let pipeline = PipelineTemplate::new(
+            ChatTemplate::from_messages(chats!(System = "{system}", Human = "{question}")).unwrap(),
+        )
+        .with_stage(
+            "system",
+            Template::new("You are {name}. Be {tone}.").unwrap(),
+        );
+
+        let messages = pipeline
+            .format_messages(&vars!(name = "Ada", tone = "brief", question = "Hi?"))
+            .unwrap();
+        assert_eq!(messages[0].content(), "You are Ada. Be brief.");
+        assert_eq!(messages[1].content(), "Hi?");
+        assert!(format!("{:?}", pipeline).contains("[\"system\"]"));
+    }
+}
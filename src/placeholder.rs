@@ -1,4 +1,11 @@
-use crate::{braces::has_multiple_words_between_braces, TemplateError};
+use crate::{
+    braces::{scan, BraceKind},
+    conditionals::{parse_directive, Directive},
+    filters::split_filters,
+    macros::{macro_definitions, parse_macro_header, MACRO_BLOCK},
+    mustache_expr::{parse_mustache_expr, MustacheTag},
+    TemplateError,
+};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashSet;
@@ -12,17 +19,65 @@ pub fn is_valid_identifier(s: &str) -> bool {
 }
 
 pub fn extract_variables(template: &str) -> Vec<&str> {
-    let re = Regex::new(r"\{{1,2}([^}]+)\}{1,2}").unwrap();
     let mut unique_vars = HashSet::new();
     let mut result = Vec::new();
-
-    for cap in re.captures_iter(template) {
-        let var = cap.get(1).unwrap().as_str().trim();
-        if is_valid_identifier(var)
-            && !has_multiple_words_between_braces(var)
-            && unique_vars.insert(var)
-        {
-            result.push(var);
+    let mut blocks = Vec::new();
+    let mut conditionals = 0;
+    let macros = macro_definitions(template);
+    let mut macro_params = Vec::new();
+
+    for slot in scan(template) {
+        let vars = match slot.kind {
+            BraceKind::Single => match parse_directive(slot.inner(template), conditionals) {
+                Some(Directive::If(var)) => {
+                    conditionals += 1;
+                    vec![var]
+                }
+                Some(Directive::Else) => continue,
+                Some(Directive::End) => {
+                    conditionals -= 1;
+                    continue;
+                }
+                None => vec![split_filters(slot.inner(template)).0],
+            },
+            BraceKind::Double => match parse_mustache_expr(slot.inner(template)) {
+                Some(expr) => {
+                    let in_context = blocks.iter().any(|&changes_context| changes_context);
+                    match expr.tag {
+                        MustacheTag::Open => {
+                            if let Some(def) = parse_macro_header(slot.inner(template)) {
+                                macro_params = def.params;
+                            }
+                            blocks.push(expr.changes_context())
+                        }
+                        MustacheTag::Close => {
+                            if expr.name == MACRO_BLOCK {
+                                macro_params.clear();
+                            }
+                            blocks.pop();
+                        }
+                        _ => {}
+                    }
+                    if in_context {
+                        continue;
+                    }
+                    let is_macro = |var: &&str| {
+                        macro_params.contains(var) || macros.iter().any(|def| def.name == *var)
+                    };
+                    expr.variables
+                        .into_iter()
+                        .filter(|var| !is_macro(var))
+                        .collect()
+                }
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        for var in vars {
+            if is_valid_identifier(var) && unique_vars.insert(var) {
+                result.push(var);
+            }
         }
     }
 
@@ -100,4 +155,24 @@ mod tests {
         check_variables("{var_123}", vec!["var_123"]);
         check_variables("{var123}", vec!["var123"]);
     }
+
+    #[test]
+    fn test_extract_mustache_expression_variables() {
+        check_variables("{{upper name}}", vec!["name"]);
+        check_variables("{{user.name}} and {{user.email}}", vec!["user"]);
+        check_variables("{{#if urgent}}Now!{{else}}Later{{/if}}", vec!["urgent"]);
+        check_variables(
+            "{{#each items}}{{title}} by {{../author}}{{/each}} {{footer}}",
+            vec!["items", "footer"],
+        );
+        check_variables("{{! note }}{{> header}}{{ hello, world! }}", vec![]);
+    }
+
+    #[test]
+    fn test_extract_filtered_variables() {
+        check_variables("{doc|xml:context}", vec!["doc"]);
+        check_variables("{ doc | trim }", vec!["doc"]);
+        check_variables("{doc} and {doc|xml:context}", vec!["doc"]);
+        check_variables("{|xml:context}", vec![]);
+    }
 }
@@ -0,0 +1,193 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use messageforge::MessageEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, TemplateError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataClassification {
+    #[default]
+    Public,
+    Internal,
+    Confidential,
+    Restricted,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptPolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub classification: DataClassification,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub allowed_models: BTreeSet<String>,
+}
+
+impl PromptPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn team(mut self, team: impl Into<String>) -> Self {
+        self.team = Some(team.into());
+        self
+    }
+
+    pub fn contact(mut self, contact: impl Into<String>) -> Self {
+        self.contact = Some(contact.into());
+        self
+    }
+
+    pub fn license(mut self, license: impl Into<String>) -> Self {
+        self.license = Some(license.into());
+        self
+    }
+
+    pub fn classification(mut self, classification: DataClassification) -> Self {
+        self.classification = classification;
+        self
+    }
+
+    pub fn allow_model(mut self, model: impl Into<String>) -> Self {
+        self.allowed_models.insert(model.into());
+        self
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.classification == DataClassification::Restricted
+    }
+
+    pub fn allows(&self, provider: &str) -> bool {
+        if self.allowed_models.is_empty() {
+            return !self.is_restricted();
+        }
+
+        self.allowed_models.iter().any(|allowed| {
+            provider == allowed
+                || provider
+                    .strip_prefix(allowed.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyChecker {
+    provider: String,
+}
+
+impl PolicyChecker {
+    pub fn new(provider: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+        }
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub fn check(&self, template: &ChatTemplate) -> Result<(), TemplateError> {
+        let Some(policy) = template.policy() else {
+            return Ok(());
+        };
+
+        if policy.allows(&self.provider) {
+            return Ok(());
+        }
+
+        let owner = match (&policy.team, &policy.contact) {
+            (Some(team), Some(contact)) => format!(" (owned by {}, {})", team, contact),
+            (Some(owner), None) | (None, Some(owner)) => format!(" (owned by {})", owner),
+            (None, None) => String::new(),
+        };
+        Err(TemplateError::PolicyViolation(format!(
+            "{:?} template may not be rendered for '{}'{}",
+            policy.classification, self.provider, owner
+        )))
+    }
+
+    pub fn format_messages(
+        &self,
+        template: &ChatTemplate,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        self.check(template)?;
+        template.format_messages(variables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{chats, vars, Role::System};
+
+    fn restricted_template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(System = "Patient record for {name}."))
+            .unwrap()
+            .with_policy(
+                PromptPolicy::new()
+                    .team("clinical")
+                    .contact("clinical@example.com")
+                    .classification(DataClassification::Restricted)
+                    .allow_model("azure-openai"),
+            )
+    }
+
+    #[test]
+    fn test_restricted_template_refused_for_disallowed_provider() {
+        let template = restricted_template();
+
+        let err = PolicyChecker::new("openai")
+            .format_messages(&template, &vars!(name = "Ada"))
+            .unwrap_err();
+        assert!(err.matches(&TemplateError::PolicyViolation(
+            "Restricted template may not be rendered for 'openai' (owned by clinical, clinical@example.com)"
+                .to_string()
+        )));
+
+        let messages = PolicyChecker::new("azure-openai/gpt-4o")
+            .format_messages(&template, &vars!(name = "Ada"))
+            .unwrap();
+        assert_eq!(messages[0].content(), "Patient record for Ada.");
+    }
+
+    #[test]
+    fn test_policy_allows() {
+        let open = PromptPolicy::new();
+        assert!(open.allows("anything"));
+
+        let restricted = PromptPolicy::new().classification(DataClassification::Restricted);
+        assert!(!restricted.allows("openai"));
+
+        let pinned = PromptPolicy::new().allow_model("anthropic");
+        assert!(pinned.allows("anthropic"));
+        assert!(pinned.allows("anthropic/claude"));
+        assert!(!pinned.allows("anthropic-proxy"));
+
+        let unlabelled = ChatTemplate::from_messages(chats!(System = "Hi")).unwrap();
+        assert!(PolicyChecker::new("openai").check(&unlabelled).is_ok());
+    }
+
+    #[test]
+    fn test_policy_round_trips_through_json() {
+        let template = restricted_template();
+        let json = serde_json::to_string(&template).unwrap();
+        assert!(json.contains(r#""classification":"restricted""#));
+
+        let parsed: ChatTemplate = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.policy(), template.policy());
+        assert!(PolicyChecker::new("openai").check(&parsed).is_err());
+    }
+}
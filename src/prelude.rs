@@ -0,0 +1,26 @@
+pub use crate::{chats, examples, var_values, vars};
+pub use crate::{
+    ArcMessageEnumExt, ChatTemplate, FewShotChatTemplate, FewShotTemplate, Formattable,
+    MessageLike, MessagesPlaceholder, PartialLibrary, PromptSet, Role, Templatable, Template,
+    TemplateError, TemplateFormat, VarValue,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Role::{Human, System};
+
+    #[test]
+    fn test_prelude_exports() {
+        let chat_template =
+            ChatTemplate::from_messages(chats!(System = "You are {name}.", Human = "Hello!",))
+                .unwrap();
+
+        let formatted = chat_template.format(&vars!(name = "Ava")).unwrap();
+        assert_eq!(formatted, "system: You are Ava.\nhuman: Hello!");
+
+        let template = Template::new("{greeting}").unwrap();
+        assert_eq!(template.template_format(), TemplateFormat::FmtString);
+        assert_eq!(template.input_variables(), vec!["greeting"]);
+    }
+}
@@ -0,0 +1,530 @@
+use std::{
+    collections::HashMap,
+    fs::Metadata,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use lazy_static::lazy_static;
+use messageforge::MessageEnum;
+#[cfg(feature = "async")]
+use tokio::fs;
+
+use crate::{
+    lineage::{fingerprint, variables_fingerprint},
+    ChatTemplate, MessageLike, TemplateError,
+};
+
+lazy_static! {
+    static ref GLOBAL_CACHE: PromptCache<ChatTemplate> =
+        PromptCache::new().with_max_entries(PromptCache::GLOBAL_MAX_ENTRIES);
+}
+
+pub type CacheKey = (String, String);
+
+pub type RenderMemo = PromptCache<Vec<Arc<MessageEnum>>>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub expirations: u64,
+    pub invalidations: u64,
+    pub evictions: u64,
+}
+
+impl CacheMetrics {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry<T> {
+    value: Arc<T>,
+    inserted_at: Instant,
+    last_used: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct PromptCache<T = ChatTemplate> {
+    entries: RwLock<HashMap<CacheKey, CacheEntry<T>>>,
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    expirations: AtomicU64,
+    invalidations: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<T> Default for PromptCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            ttl: None,
+            max_entries: None,
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            expirations: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+}
+
+impl PromptCache<ChatTemplate> {
+    pub const GLOBAL_MAX_ENTRIES: usize = 256;
+
+    pub fn global() -> &'static PromptCache<ChatTemplate> {
+        &GLOBAL_CACHE
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn load_toml_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Arc<ChatTemplate>, TemplateError> {
+        let path = path.as_ref();
+        let version = fs::metadata(path)
+            .await
+            .and_then(|metadata| file_version(&metadata))
+            .map_err(|e| {
+                TemplateError::TomlDeserializationError(format!("Failed to read TOML file: {}", e))
+            })?;
+        let name = path.to_string_lossy();
+
+        if let Some(template) = self.get(&name, &version) {
+            return Ok(template);
+        }
+
+        let template = ChatTemplate::from_toml_file(path).await?;
+        Ok(self.insert(name, version, template))
+    }
+}
+
+impl<T> PromptCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries.max(1));
+        self
+    }
+
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    pub fn max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn is_expired(&self, entry: &CacheEntry<T>) -> bool {
+        self.ttl
+            .is_some_and(|ttl| entry.inserted_at.elapsed() >= ttl)
+    }
+
+    fn key(name: &str, version: &str) -> CacheKey {
+        (name.to_string(), version.to_string())
+    }
+
+    pub fn get(&self, name: &str, version: &str) -> Option<Arc<T>> {
+        let key = Self::key(name, version);
+        let expired = {
+            let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+            match entries.get(&key) {
+                Some(entry) if !self.is_expired(entry) => {
+                    entry.last_used.store(self.tick(), Ordering::Relaxed);
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(entry.value.clone());
+                }
+                Some(_) => true,
+                None => false,
+            }
+        };
+
+        if expired {
+            let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+            if entries
+                .get(&key)
+                .is_some_and(|entry| self.is_expired(entry))
+            {
+                entries.remove(&key);
+                self.expirations.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    pub fn insert(&self, name: impl Into<String>, version: impl Into<String>, value: T) -> Arc<T> {
+        let value = Arc::new(value);
+        let key = (name.into(), version.into());
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(max_entries) = self.max_entries {
+            while entries.len() >= max_entries && !entries.contains_key(&key) {
+                let least_recent = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                    .map(|(key, _)| key.clone());
+                match least_recent {
+                    Some(least_recent) => {
+                        entries.remove(&least_recent);
+                        self.evictions.fetch_add(1, Ordering::Relaxed);
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        entries.insert(
+            key,
+            CacheEntry {
+                value: value.clone(),
+                inserted_at: Instant::now(),
+                last_used: AtomicU64::new(self.tick()),
+            },
+        );
+        value
+    }
+
+    pub fn load_file<P, F>(&self, path: P, load: F) -> Result<Arc<T>, TemplateError>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Path) -> Result<T, TemplateError>,
+    {
+        let path = path.as_ref();
+        let version = std::fs::metadata(path)
+            .and_then(|metadata| file_version(&metadata))
+            .map_err(|e| {
+                TemplateError::MalformedTemplate(format!(
+                    "Failed to read prompt file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        self.get_or_try_insert_with(&path.to_string_lossy(), &version, || load(path))
+    }
+
+    pub fn get_or_try_insert_with<F>(
+        &self,
+        name: &str,
+        version: &str,
+        load: F,
+    ) -> Result<Arc<T>, TemplateError>
+    where
+        F: FnOnce() -> Result<T, TemplateError>,
+    {
+        match self.get(name, version) {
+            Some(value) => Ok(value),
+            None => Ok(self.insert(name, version, load()?)),
+        }
+    }
+
+    pub fn invalidate(&self, name: &str, version: &str) -> bool {
+        let removed = self
+            .entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&Self::key(name, version))
+            .is_some();
+        if removed {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    pub fn invalidate_name(&self, name: &str) -> usize {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        entries.retain(|(entry_name, _), _| entry_name != name);
+        let removed = before - entries.len();
+        self.invalidations
+            .fetch_add(removed as u64, Ordering::Relaxed);
+        removed
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        self.invalidations
+            .fetch_add(entries.len() as u64, Ordering::Relaxed);
+        entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            expirations: self.expirations.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) fn file_version(metadata: &Metadata) -> std::io::Result<String> {
+    Ok(metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos().to_string())
+        .unwrap_or_default())
+}
+
+impl ChatTemplate {
+    pub fn render_fingerprint(&self) -> Result<String, TemplateError> {
+        let mut bytes = serde_json::to_vec(self).map_err(|e| {
+            TemplateError::MalformedTemplate(format!("Failed to serialize template: {}", e))
+        })?;
+
+        for message_like in &self.messages {
+            if let MessageLike::RolePromptTemplate(_, template) = message_like {
+                let mut partials: Vec<_> = template.partial_vars().iter().collect();
+                partials.sort();
+                for (name, value) in partials {
+                    bytes.push(0);
+                    bytes.extend_from_slice(name.as_bytes());
+                    bytes.push(0);
+                    bytes.extend_from_slice(value.as_bytes());
+                }
+            }
+        }
+
+        Ok(fingerprint(&bytes))
+    }
+
+    pub fn format_messages_memoized(
+        &self,
+        memo: &RenderMemo,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Arc<Vec<Arc<MessageEnum>>>, TemplateError> {
+        let template = self.render_fingerprint()?;
+        let variables_hash = variables_fingerprint(variables);
+        memo.get_or_try_insert_with(&template, &variables_hash, || {
+            self.format_messages(variables)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chats, Role::Human};
+    use std::thread;
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(Human = "Hello, {name}!")).unwrap()
+    }
+
+    #[test]
+    fn test_hits_and_misses() {
+        let cache = PromptCache::new();
+        assert!(cache.get("greet", "v1").is_none());
+
+        cache.insert("greet", "v1", template());
+        assert!(cache.get("greet", "v1").is_some());
+        assert!(cache.get("greet", "v2").is_none());
+
+        let metrics = cache.metrics();
+        assert_eq!(metrics.hits, 1);
+        assert_eq!(metrics.misses, 2);
+        assert!((metrics.hit_rate() - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_get_or_try_insert_with_loads_once() {
+        let cache: PromptCache<String> = PromptCache::new();
+        let mut loads = 0;
+
+        for _ in 0..3 {
+            let value = cache
+                .get_or_try_insert_with("greet", "v1", || {
+                    loads += 1;
+                    Ok("Hello".to_string())
+                })
+                .unwrap();
+            assert_eq!(value.as_str(), "Hello");
+        }
+        assert_eq!(loads, 1);
+
+        let result = cache.get_or_try_insert_with("broken", "v1", || {
+            Err(TemplateError::MalformedTemplate("bad".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_ttl_expiration() {
+        let cache: PromptCache<String> = PromptCache::with_ttl(Duration::from_millis(10));
+        cache.insert("greet", "v1", "Hello".to_string());
+        assert!(cache.get("greet", "v1").is_some());
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(cache.get("greet", "v1").is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.metrics().expirations, 1);
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let cache: PromptCache<u32> = PromptCache::new();
+        cache.insert("a", "v1", 1);
+        cache.insert("a", "v2", 2);
+        cache.insert("b", "v1", 3);
+
+        assert!(cache.invalidate("b", "v1"));
+        assert!(!cache.invalidate("b", "v1"));
+        assert_eq!(cache.invalidate_name("a"), 2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.metrics().invalidations, 3);
+    }
+
+    #[test]
+    fn test_max_entries_evicts_least_recently_used() {
+        let cache: PromptCache<u32> = PromptCache::new().with_max_entries(2);
+        cache.insert("a", "v1", 1);
+        cache.insert("b", "v1", 2);
+        assert!(cache.get("a", "v1").is_some());
+
+        cache.insert("c", "v1", 3);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b", "v1").is_none());
+        assert!(cache.get("a", "v1").is_some());
+        assert!(cache.get("c", "v1").is_some());
+
+        cache.insert("c", "v1", 4);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.metrics().evictions, 1);
+        assert_eq!(cache.max_entries(), Some(2));
+    }
+
+    #[test]
+    fn test_concurrent_access() {
+        let cache: Arc<PromptCache<u32>> = Arc::new(PromptCache::new());
+        cache.insert("shared", "v1", 7);
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        assert_eq!(cache.get("shared", "v1").as_deref(), Some(&7));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(cache.metrics().hits, 800);
+    }
+
+    #[test]
+    fn test_global_cache_is_shared() {
+        assert_eq!(
+            PromptCache::global().max_entries(),
+            Some(PromptCache::GLOBAL_MAX_ENTRIES)
+        );
+        PromptCache::global().insert("global-test", "v1", template());
+        assert!(PromptCache::global().get("global-test", "v1").is_some());
+        PromptCache::global().invalidate("global-test", "v1");
+    }
+
+    #[cfg(all(feature = "toml", feature = "async"))]
+    #[tokio::test]
+    async fn test_load_toml_file_is_cached() {
+        let cache = PromptCache::new();
+        let path = "tests/data/chat_template.toml";
+
+        let first = cache.load_toml_file(path).await.unwrap();
+        let second = cache.load_toml_file(path).await.unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.metrics().misses, 1);
+        assert_eq!(cache.metrics().hits, 1);
+    }
+
+    #[test]
+    fn test_format_messages_memoized() {
+        use crate::vars;
+        use messageforge::BaseMessage;
+
+        let memo = RenderMemo::with_ttl(Duration::from_secs(60));
+        let template = template();
+
+        let first = template
+            .format_messages_memoized(&memo, &vars!(name = "Ada"))
+            .unwrap();
+        let second = template
+            .format_messages_memoized(&memo, &vars!(name = "Ada"))
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first[0].content(), "Hello, Ada!");
+
+        let other = template
+            .format_messages_memoized(&memo, &vars!(name = "Grace"))
+            .unwrap();
+        assert_eq!(other[0].content(), "Hello, Grace!");
+
+        let changed = ChatTemplate::from_messages(chats!(Human = "Hi, {name}!")).unwrap();
+        let changed = changed
+            .format_messages_memoized(&memo, &vars!(name = "Ada"))
+            .unwrap();
+        assert_eq!(changed[0].content(), "Hi, Ada!");
+
+        assert_eq!(memo.len(), 3);
+        assert_eq!(memo.metrics().hits, 1);
+        assert!(template.format_messages_memoized(&memo, &vars!()).is_err());
+        assert_eq!(memo.len(), 3);
+    }
+
+    #[test]
+    fn test_render_fingerprint_includes_partials() {
+        use crate::{MessageLike, Role, Template};
+
+        let mut with_partial = Template::new("Hello, {name}!").unwrap();
+        with_partial.partial("name", "friend");
+        let mut partial_template = template();
+        partial_template.messages[0] = MessageLike::role_prompt_template(Role::Human, with_partial);
+
+        assert_ne!(
+            template().render_fingerprint().unwrap(),
+            partial_template.render_fingerprint().unwrap()
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{ChatTemplate, PromptCache, PromptRegistry, TemplateError};
+
+const PROMPT_EXTENSIONS: &[&str] = &["toml", "json", "yaml", "yml"];
+
+pub type LoadErrors = Vec<(PathBuf, TemplateError)>;
+
+#[derive(Debug, Clone)]
+pub struct PromptLoader {
+    root: PathBuf,
+    registry: Arc<PromptRegistry>,
+}
+
+impl PromptLoader {
+    pub fn new(root: impl Into<PathBuf>, registry: Arc<PromptRegistry>) -> Self {
+        Self {
+            root: root.into(),
+            registry,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn registry(&self) -> &Arc<PromptRegistry> {
+        &self.registry
+    }
+
+    pub fn load_all(&self) -> Result<Vec<String>, TemplateError> {
+        let mut files = Vec::new();
+        collect_prompt_files(&self.root, &mut files)?;
+        files.sort();
+
+        let parsed = files
+            .iter()
+            .map(|path| Ok((self.prompt_name(path)?, load_prompt_file(path)?)))
+            .collect::<Result<Vec<_>, TemplateError>>()?;
+
+        let mut names = Vec::new();
+        for (name, template) in parsed {
+            self.registry.register(name.clone(), template)?;
+            names.push(name);
+        }
+        Ok(names)
+    }
+
+    pub fn reload(&self, path: &Path) -> Result<Option<String>, TemplateError> {
+        if !is_prompt_file(path) {
+            return Ok(None);
+        }
+
+        let name = self.prompt_name(path)?;
+        PromptCache::global().invalidate_name(&path.to_string_lossy());
+        if path.exists() {
+            let template = load_prompt_file(path)?;
+            self.registry.register(name.clone(), template)?;
+        } else {
+            self.registry.remove(&name);
+        }
+        Ok(Some(name))
+    }
+
+    pub fn watch(self) -> Result<PromptWatcher, TemplateError> {
+        let errors: Arc<Mutex<LoadErrors>> = Arc::default();
+        let loader = self.clone();
+        let sink = Arc::clone(&errors);
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    let path = err.paths.first().cloned().unwrap_or_default();
+                    sink.lock()
+                        .unwrap()
+                        .push((path, watch_error(&loader.root, err)));
+                    return;
+                }
+            };
+            if matches!(event.kind, EventKind::Access(_) | EventKind::Other) {
+                return;
+            }
+            for path in event.paths {
+                if let Err(err) = loader.reload(&path) {
+                    sink.lock().unwrap().push((path, err));
+                }
+            }
+        })
+        .map_err(|err| watch_error(&self.root, err))?;
+
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .map_err(|err| watch_error(&self.root, err))?;
+
+        Ok(PromptWatcher {
+            loader: self,
+            errors,
+            _watcher: watcher,
+        })
+    }
+
+    fn prompt_name(&self, path: &Path) -> Result<String, TemplateError> {
+        let canonical_root = fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        let relative = path
+            .strip_prefix(&self.root)
+            .or_else(|_| path.strip_prefix(&canonical_root))
+            .map_err(|_| outside_root(&self.root, path))?
+            .with_extension("");
+
+        let segments = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| outside_root(&self.root, path))?;
+        Ok(segments.join("/"))
+    }
+}
+
+pub struct PromptWatcher {
+    loader: PromptLoader,
+    errors: Arc<Mutex<LoadErrors>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl PromptWatcher {
+    pub fn loader(&self) -> &PromptLoader {
+        &self.loader
+    }
+
+    pub fn registry(&self) -> &Arc<PromptRegistry> {
+        self.loader.registry()
+    }
+
+    pub fn take_errors(&self) -> LoadErrors {
+        std::mem::take(&mut *self.errors.lock().unwrap())
+    }
+}
+
+impl std::fmt::Debug for PromptWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PromptWatcher")
+            .field("loader", &self.loader)
+            .finish_non_exhaustive()
+    }
+}
+
+fn is_prompt_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| PROMPT_EXTENSIONS.contains(&extension))
+}
+
+fn collect_prompt_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), TemplateError> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        TemplateError::MalformedTemplate(format!(
+            "Failed to read prompt directory '{}': {}",
+            dir.display(),
+            e
+        ))
+    })?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_prompt_files(&path, files)?;
+        } else if is_prompt_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn load_prompt_file(path: &Path) -> Result<Arc<ChatTemplate>, TemplateError> {
+    PromptCache::global().load_file(path, parse_prompt_file)
+}
+
+fn parse_prompt_file(path: &Path) -> Result<ChatTemplate, TemplateError> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        TemplateError::MalformedTemplate(format!(
+            "Failed to read prompt file '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        #[cfg(feature = "yaml")]
+        Some("yaml" | "yml") => ChatTemplate::from_yaml_str(&content),
+        #[cfg(not(feature = "yaml"))]
+        Some("yaml" | "yml") => Err(TemplateError::UnsupportedFormat(
+            "YAML support is disabled; enable the `yaml` feature".to_string(),
+        )),
+        _ => ChatTemplate::try_from(content),
+    }
+}
+
+fn outside_root(root: &Path, path: &Path) -> TemplateError {
+    TemplateError::MalformedTemplate(format!(
+        "Prompt file '{}' is not under '{}'",
+        path.display(),
+        root.display()
+    ))
+}
+
+fn watch_error(root: &Path, err: notify::Error) -> TemplateError {
+    TemplateError::MalformedTemplate(format!("Failed to watch '{}': {}", root.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{chats, vars, Role::Human};
+
+    fn prompt_json(content: &str) -> String {
+        serde_json::to_string(&ChatTemplate::from_messages(chats!(Human = content)).unwrap())
+            .unwrap()
+    }
+
+    fn scratch(test: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "promptforge-loader-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("support")).unwrap();
+        root
+    }
+
+    fn rendered(registry: &PromptRegistry, name: &str) -> Option<String> {
+        registry
+            .format_messages(name, &vars!(name = "Ada"))
+            .ok()
+            .map(|messages| messages[0].content().to_string())
+    }
+
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(25));
+        }
+        false
+    }
+
+    #[test]
+    fn test_load_all_namespaces_by_directory() {
+        let root = scratch("load");
+        fs::write(
+            root.join("support/greeting.json"),
+            prompt_json("Hello {name}."),
+        )
+        .unwrap();
+        fs::write(root.join("farewell.json"), prompt_json("Bye {name}.")).unwrap();
+        fs::write(root.join("notes.txt"), "ignored").unwrap();
+
+        let registry = Arc::new(PromptRegistry::new());
+        let loader = PromptLoader::new(&root, Arc::clone(&registry));
+        let names = loader.load_all().unwrap();
+
+        assert_eq!(names, vec!["farewell", "support/greeting"]);
+        let farewell = root.join("farewell.json");
+        let first = load_prompt_file(&farewell).unwrap();
+        assert!(Arc::ptr_eq(&first, &load_prompt_file(&farewell).unwrap()));
+        assert_eq!(
+            rendered(&registry, "support/greeting").as_deref(),
+            Some("Hello Ada.")
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_load_all_keeps_registry_on_parse_error() {
+        let root = scratch("broken");
+        fs::write(
+            root.join("support/greeting.json"),
+            prompt_json("Hello {name}."),
+        )
+        .unwrap();
+        let registry = Arc::new(PromptRegistry::new());
+        let loader = PromptLoader::new(&root, Arc::clone(&registry));
+        loader.load_all().unwrap();
+
+        fs::write(root.join("support/greeting.json"), "{ not json").unwrap();
+        assert!(loader.load_all().is_err());
+        assert!(loader.reload(&root.join("support/greeting.json")).is_err());
+        assert_eq!(
+            rendered(&registry, "support/greeting").as_deref(),
+            Some("Hello Ada.")
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_reload_invalidates_global_cache() {
+        let root = scratch("invalidate");
+        let greeting = root.join("support/greeting.json");
+        fs::write(&greeting, prompt_json("Hello {name}.")).unwrap();
+        let key = greeting.to_string_lossy();
+        PromptCache::global().insert(key.as_ref(), "stale", ChatTemplate::default());
+
+        let registry = Arc::new(PromptRegistry::new());
+        let loader = PromptLoader::new(&root, Arc::clone(&registry));
+        assert_eq!(
+            loader.reload(&greeting).unwrap().as_deref(),
+            Some("support/greeting")
+        );
+        assert!(PromptCache::global().get(&key, "stale").is_none());
+        assert_eq!(
+            rendered(&registry, "support/greeting").as_deref(),
+            Some("Hello Ada.")
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_watch_swaps_updated_prompts() {
+        let root = scratch("watch");
+        let greeting = root.join("support/greeting.json");
+        fs::write(&greeting, prompt_json("Hello {name}.")).unwrap();
+
+        let registry = Arc::new(PromptRegistry::new());
+        let loader = PromptLoader::new(&root, Arc::clone(&registry));
+        loader.load_all().unwrap();
+        let watcher = loader.watch().unwrap();
+
+        fs::write(&greeting, prompt_json("Bye {name}.")).unwrap();
+        assert!(wait_for(|| rendered(&registry, "support/greeting")
+            .as_deref()
+            == Some("Bye Ada.")));
+
+        fs::remove_file(&greeting).unwrap();
+        assert!(wait_for(|| !registry.contains("support/greeting")));
+
+        drop(watcher);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
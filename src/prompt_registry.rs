@@ -0,0 +1,301 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, Defaults, Persona, Template, TemplateError, Variables};
+
+#[derive(Debug, Clone)]
+pub enum RegisteredPrompt {
+    Text(Arc<Template>),
+    Chat(Arc<ChatTemplate>),
+    Persona(Arc<Persona>),
+}
+
+impl From<Template> for RegisteredPrompt {
+    fn from(template: Template) -> Self {
+        RegisteredPrompt::Text(Arc::new(template))
+    }
+}
+
+impl From<ChatTemplate> for RegisteredPrompt {
+    fn from(template: ChatTemplate) -> Self {
+        RegisteredPrompt::Chat(Arc::new(template))
+    }
+}
+
+impl From<Arc<ChatTemplate>> for RegisteredPrompt {
+    fn from(template: Arc<ChatTemplate>) -> Self {
+        RegisteredPrompt::Chat(template)
+    }
+}
+
+impl From<Persona> for RegisteredPrompt {
+    fn from(persona: Persona) -> Self {
+        RegisteredPrompt::Persona(Arc::new(persona))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PromptRegistry {
+    prompts: RwLock<BTreeMap<String, RegisteredPrompt>>,
+    defaults: RwLock<Defaults>,
+}
+
+impl PromptRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        &self,
+        name: impl Into<String>,
+        prompt: impl Into<RegisteredPrompt>,
+    ) -> Result<Option<RegisteredPrompt>, TemplateError> {
+        let name = name.into();
+        validate_name(&name)?;
+
+        Ok(self.prompts.write().unwrap().insert(name, prompt.into()))
+    }
+
+    pub fn with(
+        self,
+        name: impl Into<String>,
+        prompt: impl Into<RegisteredPrompt>,
+    ) -> Result<Self, TemplateError> {
+        self.register(name, prompt)?;
+        Ok(self)
+    }
+
+    pub fn with_defaults(self, defaults: Defaults) -> Self {
+        *self.defaults.write().unwrap() = defaults;
+        self
+    }
+
+    pub fn defaults(&self) -> Defaults {
+        self.defaults.read().unwrap().clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<RegisteredPrompt> {
+        let mut prompt = self.prompts.read().unwrap().get(name).cloned()?;
+        prompt.layer_defaults(&self.defaults.read().unwrap());
+        Some(prompt)
+    }
+
+    pub fn template(&self, name: &str) -> Option<Arc<Template>> {
+        match self.get(name)? {
+            RegisteredPrompt::Text(template) => Some(template),
+            _ => None,
+        }
+    }
+
+    pub fn chat_template(&self, name: &str) -> Option<Arc<ChatTemplate>> {
+        match self.get(name)? {
+            RegisteredPrompt::Chat(template) => Some(template),
+            _ => None,
+        }
+    }
+
+    pub fn persona(&self, name: &str) -> Option<Arc<Persona>> {
+        match self.get(name)? {
+            RegisteredPrompt::Persona(persona) => Some(persona),
+            _ => None,
+        }
+    }
+
+    pub fn remove(&self, name: &str) -> Option<RegisteredPrompt> {
+        self.prompts.write().unwrap().remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prompts.read().unwrap().contains_key(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.prompts.read().unwrap().keys().cloned().collect()
+    }
+
+    pub fn namespace(&self, prefix: &str) -> Vec<String> {
+        let prefix = format!("{}/", prefix.trim_end_matches('/'));
+        self.prompts
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| name.starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.prompts.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prompts.read().unwrap().is_empty()
+    }
+
+    pub fn format(
+        &self,
+        name: &str,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<String, TemplateError> {
+        match self.lookup(name)? {
+            RegisteredPrompt::Text(template) => template.format(variables),
+            _ => Err(TemplateError::MalformedTemplate(format!(
+                "Prompt '{}' is not a text template",
+                name
+            ))),
+        }
+    }
+
+    pub fn format_messages(
+        &self,
+        name: &str,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        match self.lookup(name)? {
+            RegisteredPrompt::Chat(template) => template.format_messages(variables),
+            _ => Err(TemplateError::MalformedTemplate(format!(
+                "Prompt '{}' is not a chat template",
+                name
+            ))),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Result<RegisteredPrompt, TemplateError> {
+        self.get(name)
+            .ok_or_else(|| TemplateError::UnknownPrompt(name.to_string()))
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), TemplateError> {
+    let valid = name.split('/').all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(TemplateError::MalformedTemplate(format!(
+            "Invalid prompt name '{}'",
+            name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{chats, vars, Role::System};
+
+    #[test]
+    fn test_register_and_lookup() {
+        let registry = PromptRegistry::new()
+            .with("support/greeting", Template::new("Hello {name}").unwrap())
+            .unwrap()
+            .with(
+                "support/escalation",
+                ChatTemplate::from_messages(chats!(System = "Escalate for {name}.")).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(registry.len(), 2);
+        assert!(registry.contains("support/greeting"));
+        assert!(registry.template("support/greeting").is_some());
+        assert!(registry.chat_template("support/greeting").is_none());
+        assert!(registry.chat_template("support/escalation").is_some());
+
+        assert_eq!(
+            registry
+                .format("support/greeting", &vars!(name = "Ada"))
+                .unwrap(),
+            "Hello Ada"
+        );
+        let messages = registry
+            .format_messages("support/escalation", &vars!(name = "Ada"))
+            .unwrap();
+        assert_eq!(messages[0].content(), "Escalate for Ada.");
+    }
+
+    #[test]
+    fn test_override_returns_previous() {
+        let registry = PromptRegistry::new();
+        assert!(registry
+            .register("greeting", Template::new("Hi").unwrap())
+            .unwrap()
+            .is_none());
+
+        let previous = registry
+            .register("greeting", Template::new("Hello").unwrap())
+            .unwrap();
+        assert!(matches!(previous, Some(RegisteredPrompt::Text(_))));
+        assert_eq!(
+            registry.format("greeting", &HashMap::new()).unwrap(),
+            "Hello"
+        );
+
+        assert!(registry.remove("greeting").is_some());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_names_and_namespace() {
+        let registry = PromptRegistry::new();
+        for name in [
+            "support/greeting",
+            "sales/pitch",
+            "support/farewell",
+            "supportive",
+        ] {
+            registry
+                .register(name, Template::new("text").unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(
+            registry.names(),
+            vec![
+                "sales/pitch",
+                "support/farewell",
+                "support/greeting",
+                "supportive"
+            ]
+        );
+        assert_eq!(
+            registry.namespace("support"),
+            vec!["support/farewell", "support/greeting"]
+        );
+        assert_eq!(
+            registry.namespace("support/"),
+            registry.namespace("support")
+        );
+    }
+
+    #[test]
+    fn test_invalid_and_unknown_names() {
+        let registry = PromptRegistry::new();
+        for name in ["", "support/", "/greeting", "support//greeting", "bad name"] {
+            let result = registry.register(name, Template::new("text").unwrap());
+            assert!(matches!(result, Err(TemplateError::MalformedTemplate(_))));
+        }
+
+        let err = registry.format("missing", &HashMap::new()).unwrap_err();
+        assert!(err.matches(&TemplateError::UnknownPrompt("missing".to_string())));
+
+        registry
+            .register("greeting", Template::new("Hi").unwrap())
+            .unwrap();
+        assert!(registry
+            .format_messages("greeting", &HashMap::new())
+            .is_err());
+    }
+}
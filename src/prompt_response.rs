@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    hf_chat_template::{hf_role, to_hf_messages, HfMessage},
+    ChatTemplate, Role, Template, TemplateError,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseParser {
+    #[default]
+    Text,
+    Lines,
+    Json {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        required_keys: Vec<String>,
+    },
+    Regex {
+        pattern: String,
+    },
+}
+
+impl ResponseParser {
+    pub fn parse(&self, response: &str) -> Result<Value, TemplateError> {
+        match self {
+            ResponseParser::Text => Ok(Value::String(response.trim().to_string())),
+            ResponseParser::Lines => Ok(Value::Array(
+                response
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(|line| Value::String(line.to_string()))
+                    .collect(),
+            )),
+            ResponseParser::Json { required_keys } => {
+                let value: Value = serde_json::from_str(response.trim())
+                    .map_err(|e| TemplateError::InvalidResponse(e.to_string()))?;
+                for key in required_keys {
+                    if value.get(key).is_none() {
+                        return Err(TemplateError::InvalidResponse(format!(
+                            "Missing required key '{}'.",
+                            key
+                        )));
+                    }
+                }
+                Ok(value)
+            }
+            ResponseParser::Regex { pattern } => {
+                let re = Regex::new(pattern)
+                    .map_err(|e| TemplateError::InvalidResponse(e.to_string()))?;
+                let captures = re.captures(response).ok_or_else(|| {
+                    TemplateError::InvalidResponse(format!(
+                        "Response does not match '{}'.",
+                        pattern
+                    ))
+                })?;
+
+                let fields: Map<String, Value> = re
+                    .capture_names()
+                    .flatten()
+                    .filter_map(|name| {
+                        captures
+                            .name(name)
+                            .map(|m| (name.to_string(), Value::String(m.as_str().to_string())))
+                    })
+                    .collect();
+                Ok(Value::Object(fields))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptResponsePair {
+    pub prompt: ChatTemplate,
+    pub response: Template,
+    #[serde(default)]
+    pub parser: ResponseParser,
+}
+
+impl PromptResponsePair {
+    pub fn new(prompt: ChatTemplate, response: Template) -> Self {
+        Self {
+            prompt,
+            response,
+            parser: ResponseParser::default(),
+        }
+    }
+
+    pub fn with_parser(mut self, parser: ResponseParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    pub fn expected_response(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<String, TemplateError> {
+        self.response.format(variables)
+    }
+
+    pub fn parse_response(&self, response: &str) -> Result<Value, TemplateError> {
+        self.parser.parse(response)
+    }
+
+    pub fn check(
+        &self,
+        variables: &HashMap<&str, &str>,
+        response: &str,
+    ) -> Result<bool, TemplateError> {
+        let expected = self.parser.parse(&self.expected_response(variables)?)?;
+        Ok(self.parser.parse(response)? == expected)
+    }
+
+    pub fn to_training_messages(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<HfMessage>, TemplateError> {
+        let mut messages = to_hf_messages(&self.prompt.format_messages(variables)?);
+        messages.push(HfMessage::new(
+            hf_role(Role::Ai),
+            self.expected_response(variables)?,
+        ));
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, System},
+    };
+
+    fn pair() -> PromptResponsePair {
+        let prompt = ChatTemplate::from_messages(chats!(
+            System = "Classify the sentiment of the review.",
+            Human = "{review}",
+        ))
+        .unwrap();
+        let response = Template::new(r#"{"label": "{label}"}"#).unwrap();
+
+        PromptResponsePair::new(prompt, response).with_parser(ResponseParser::Json {
+            required_keys: vec!["label".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_training_messages_append_expected_response() {
+        let messages = pair()
+            .to_training_messages(&vars!(review = "Loved it", label = "positive"))
+            .unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1], HfMessage::new("user", "Loved it"));
+        assert_eq!(
+            messages[2],
+            HfMessage::new("assistant", r#"{"label": "positive"}"#)
+        );
+    }
+
+    #[test]
+    fn test_check_compares_parsed_responses() {
+        let pair = pair();
+        let variables = vars!(review = "Meh", label = "neutral");
+
+        assert!(pair.check(&variables, r#" {"label":"neutral"} "#).unwrap());
+        assert!(!pair.check(&variables, r#"{"label": "negative"}"#).unwrap());
+        assert!(matches!(
+            pair.check(&variables, r#"{"score": 1}"#),
+            Err(TemplateError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_regex_and_lines_parsers() {
+        let parser = ResponseParser::Regex {
+            pattern: r"Answer: (?P<answer>\w+)".to_string(),
+        };
+        assert_eq!(
+            parser.parse("Reasoning...\nAnswer: yes").unwrap(),
+            serde_json::json!({ "answer": "yes" })
+        );
+        assert!(parser.parse("no answer").is_err());
+
+        assert_eq!(
+            ResponseParser::Lines.parse("a\n\n b \n").unwrap(),
+            serde_json::json!(["a", "b"])
+        );
+    }
+
+    #[test]
+    fn test_pair_round_trips_through_json() {
+        let json = serde_json::to_string(&pair()).unwrap();
+        let restored: PromptResponsePair = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.parser, pair().parser);
+        assert_eq!(
+            restored
+                .expected_response(&vars!(label = "positive"))
+                .unwrap(),
+            r#"{"label": "positive"}"#
+        );
+    }
+}
@@ -0,0 +1,550 @@
+#[cfg(feature = "async")]
+use std::path::Path;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "async")]
+use tokio::fs;
+
+use crate::{
+    events::EventBus,
+    few_shot_chat_template_config::MessageConfig,
+    history::PromptRevision,
+    rollout::Rollout,
+    semantic_search::{Embedder, EmbeddingIndex, SemanticMatch},
+    template_format::parse_toml,
+    ChatTemplate, PromptEvent, PromptTestCase, TemplateError,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct PromptSetConfig {
+    pub prompts: HashMap<String, PromptConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptConfig {
+    pub messages: Vec<MessageConfig>,
+    #[serde(default)]
+    pub tests: Vec<PromptTestCase>,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptMetadata {
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl PromptMetadata {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.labels.get(key).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptSet {
+    prompts: HashMap<String, ChatTemplate>,
+    metadata: HashMap<String, PromptMetadata>,
+    revisions: HashMap<String, u64>,
+    pub(crate) history: HashMap<String, Vec<PromptRevision>>,
+    pub(crate) history_limit: Option<usize>,
+    pub(crate) rollouts: HashMap<String, Rollout>,
+    embeddings: Option<EmbeddingIndex>,
+    pub(crate) events: EventBus,
+}
+
+impl PromptSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, template: ChatTemplate) -> &mut Self {
+        self.insert_at(name, template, SystemTime::now())
+    }
+
+    pub fn insert_at(
+        &mut self,
+        name: impl Into<String>,
+        template: ChatTemplate,
+        recorded_at: SystemTime,
+    ) -> &mut Self {
+        let name = name.into();
+        let revision = self.revisions.entry(name.clone()).or_default();
+        *revision += 1;
+        let revision = *revision;
+        self.record(
+            &name,
+            PromptRevision {
+                revision,
+                recorded_at,
+                template: Some(template.clone()),
+            },
+        );
+
+        let event = match self.prompts.insert(name.clone(), template) {
+            Some(_) => PromptEvent::Updated { name, revision },
+            None => PromptEvent::Created { name, revision },
+        };
+        self.events.publish(event);
+        self
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<ChatTemplate> {
+        let template = self.prompts.remove(name)?;
+        let revision = self.revisions.entry(name.to_string()).or_default();
+        *revision += 1;
+        let revision = *revision;
+        self.record(
+            name,
+            PromptRevision {
+                revision,
+                recorded_at: SystemTime::now(),
+                template: None,
+            },
+        );
+        self.metadata.remove(name);
+        self.rollouts.remove(name);
+        self.events.publish(PromptEvent::Deleted {
+            name: name.to_string(),
+        });
+        Some(template)
+    }
+
+    pub fn revision(&self, name: &str) -> Option<u64> {
+        self.prompts
+            .contains_key(name)
+            .then(|| self.revisions.get(name).copied().unwrap_or_default())
+    }
+
+    pub fn put(
+        &mut self,
+        name: impl Into<String>,
+        template: ChatTemplate,
+        expected: Option<u64>,
+    ) -> Result<u64, TemplateError> {
+        let name = name.into();
+        let actual = self.revision(&name);
+        if actual != expected {
+            let describe = |revision: Option<u64>| {
+                revision.map_or_else(|| "absent".to_string(), |r| format!("revision {}", r))
+            };
+            return Err(TemplateError::VersionConflict(format!(
+                "'{}' expected {} but found {}",
+                name,
+                describe(expected),
+                describe(actual)
+            )));
+        }
+
+        self.insert(name.clone(), template);
+        Ok(self.revisions[&name])
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ChatTemplate> {
+        self.prompts.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prompts.contains_key(name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.prompts.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn tag(&mut self, name: &str, tag: impl Into<String>) -> &mut Self {
+        if self.prompts.contains_key(name) {
+            self.metadata
+                .entry(name.to_string())
+                .or_default()
+                .tags
+                .insert(tag.into());
+        }
+        self
+    }
+
+    pub fn label(
+        &mut self,
+        name: &str,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        if self.prompts.contains_key(name) {
+            self.metadata
+                .entry(name.to_string())
+                .or_default()
+                .labels
+                .insert(key.into(), value.into());
+        }
+        self
+    }
+
+    pub fn metadata(&self, name: &str) -> Option<&PromptMetadata> {
+        self.metadata.get(name)
+    }
+
+    pub fn by_tag(&self, tag: &str) -> Vec<&str> {
+        self.filter(|metadata| metadata.has_tag(tag))
+    }
+
+    pub fn where_label(&self, key: &str, value: &str) -> Vec<&str> {
+        self.filter(|metadata| metadata.label(key) == Some(value))
+    }
+
+    pub fn filter<F>(&self, predicate: F) -> Vec<&str>
+    where
+        F: Fn(&PromptMetadata) -> bool,
+    {
+        let empty = PromptMetadata::default();
+        self.names()
+            .into_iter()
+            .filter(|name| predicate(self.metadata.get(*name).unwrap_or(&empty)))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.prompts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty()
+    }
+
+    pub fn with_embedder(mut self, embedder: impl Embedder + 'static) -> Self {
+        self.embeddings = Some(EmbeddingIndex::new(Arc::new(embedder)));
+        self
+    }
+
+    pub fn search_semantic(&self, query: &str, k: usize) -> Vec<SemanticMatch> {
+        match &self.embeddings {
+            Some(index) => index.search(&self.prompts, query, k),
+            None => Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
+        let toml_content = fs::read_to_string(path).await.map_err(|e| {
+            TemplateError::TomlDeserializationError(format!("Failed to read TOML file: {}", e))
+        })?;
+
+        PromptSet::try_from(toml_content)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, TemplateError> {
+        let config: PromptSetConfig = serde_yaml::from_str(yaml)?;
+        PromptSet::try_from(config)
+    }
+
+    #[cfg(all(feature = "yaml", feature = "async"))]
+    pub async fn from_yaml<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
+        let yaml_content = fs::read_to_string(path).await.map_err(|e| {
+            TemplateError::YamlDeserializationError(format!("Failed to read YAML file: {}", e))
+        })?;
+
+        PromptSet::from_yaml_str(&yaml_content)
+    }
+}
+
+impl TryFrom<PromptSetConfig> for PromptSet {
+    type Error = TemplateError;
+
+    fn try_from(config: PromptSetConfig) -> Result<Self, Self::Error> {
+        let mut prompts: Vec<(String, PromptConfig)> = config.prompts.into_iter().collect();
+        prompts.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut set = PromptSet::new();
+        for (name, prompt) in prompts {
+            let template = ChatTemplate::try_from(prompt.messages).map_err(|e| {
+                TemplateError::MalformedTemplate(format!(
+                    "Failed to parse prompt '{}': {}",
+                    name, e
+                ))
+            })?;
+            set.insert(name.clone(), template.with_tests(prompt.tests));
+            if !prompt.tags.is_empty() || !prompt.labels.is_empty() {
+                set.metadata.insert(
+                    name,
+                    PromptMetadata {
+                        tags: prompt.tags,
+                        labels: prompt.labels,
+                    },
+                );
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+impl TryFrom<String> for PromptSet {
+    type Error = TemplateError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let config: PromptSetConfig = if value.trim().starts_with('{') {
+            serde_json::from_str(&value).map_err(|e| {
+                TemplateError::MalformedTemplate(format!("Failed to parse JSON: {}", e))
+            })?
+        } else {
+            parse_toml(&value).map_err(|e| {
+                TemplateError::MalformedTemplate(format!("Failed to parse TOML: {}", e))
+            })?
+        };
+
+        PromptSet::try_from(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats,
+        Role::{Human, System},
+    };
+
+    #[cfg(feature = "toml")]
+    const PROMPTS_TOML: &str = r#"
+        [[prompts.summarize.messages]]
+        type = "BaseMessage"
+        [prompts.summarize.messages.value]
+        role = "system"
+        content = "You summarize documents."
+
+        [[prompts.summarize.messages]]
+        type = "BaseMessage"
+        [prompts.summarize.messages.value]
+        role = "human"
+        content = "Summarize: {document}"
+
+        [[prompts.classify.messages]]
+        type = "BaseMessage"
+        [prompts.classify.messages.value]
+        role = "human"
+        content = "Classify '{text}' as one of {labels}."
+    "#;
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_prompt_set_from_toml() {
+        use crate::vars;
+        use messageforge::BaseMessage;
+
+        let prompt_set = PromptSet::try_from(PROMPTS_TOML.to_string()).unwrap();
+
+        assert_eq!(prompt_set.len(), 2);
+        assert_eq!(prompt_set.names(), vec!["classify", "summarize"]);
+
+        let summarize = prompt_set.get("summarize").unwrap();
+        let messages = summarize
+            .format_messages(&vars!(document = "The report."))
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content(), "You summarize documents.");
+        assert_eq!(messages[1].content(), "Summarize: The report.");
+
+        let classify = prompt_set.get("classify").unwrap();
+        let messages = classify
+            .format_messages(&vars!(text = "great!", labels = "positive, negative"))
+            .unwrap();
+        assert_eq!(
+            messages[0].content(),
+            "Classify 'great!' as one of positive, negative."
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_prompt_set_from_yaml() {
+        use crate::vars;
+        use messageforge::BaseMessage;
+
+        let yaml = r#"
+prompts:
+  summarize:
+    tags: [docs]
+    messages:
+      - type: BaseMessage
+        value:
+          role: system
+          content: You summarize documents.
+      - type: BaseMessage
+        value:
+          role: human
+          content: "Summarize: {document}"
+"#;
+        let prompt_set = PromptSet::from_yaml_str(yaml).unwrap();
+        assert_eq!(prompt_set.names(), vec!["summarize"]);
+        assert_eq!(prompt_set.by_tag("docs"), vec!["summarize"]);
+        let messages = prompt_set
+            .get("summarize")
+            .unwrap()
+            .format_messages(&vars!(document = "The report."))
+            .unwrap();
+        assert_eq!(messages[1].content(), "Summarize: The report.");
+
+        assert!(matches!(
+            PromptSet::from_yaml_str("prompts: [not, a, map]"),
+            Err(TemplateError::YamlDeserializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_prompt_set_from_json() {
+        let json = r#"{
+            "prompts": {
+                "greet": {
+                    "messages": [
+                        { "type": "BaseMessage", "value": { "role": "human", "content": "Hi {name}" } }
+                    ]
+                }
+            }
+        }"#;
+
+        let prompt_set = PromptSet::try_from(json.to_string()).unwrap();
+        assert!(prompt_set.contains("greet"));
+        assert!(!prompt_set.contains("missing"));
+        assert_eq!(prompt_set.revision("greet"), Some(1));
+        assert_eq!(prompt_set.history("greet").len(), 1);
+        assert!(prompt_set.get_as_of("greet", SystemTime::now()).is_some());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_prompt_set_get_missing() {
+        let prompt_set = PromptSet::try_from(PROMPTS_TOML.to_string()).unwrap();
+        assert!(prompt_set.get("translate").is_none());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_prompt_set_invalid_role() {
+        let toml = r#"
+            [[prompts.broken.messages]]
+            type = "BaseMessage"
+            [prompts.broken.messages.value]
+            role = "narrator"
+            content = "Once upon a time."
+        "#;
+
+        let err = PromptSet::try_from(toml.to_string()).unwrap_err();
+        match err {
+            TemplateError::MalformedTemplate(msg) => {
+                assert!(msg.contains("Failed to parse prompt 'broken'"))
+            }
+            e => panic!("Expected MalformedTemplate error. Got error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_prompt_set_invalid_toml() {
+        let result = PromptSet::try_from("prompts = 3".to_string());
+        assert!(matches!(result, Err(TemplateError::MalformedTemplate(_))));
+    }
+
+    #[test]
+    fn test_prompt_set_insert() {
+        let mut prompt_set = PromptSet::new();
+        assert!(prompt_set.is_empty());
+
+        let template = ChatTemplate::from_messages(chats!(System = "Be brief.")).unwrap();
+        prompt_set.insert("brief", template);
+
+        assert_eq!(prompt_set.len(), 1);
+        assert_eq!(prompt_set.get("brief").unwrap().messages.len(), 1);
+    }
+
+    #[test]
+    fn test_prompt_set_tags_and_labels() {
+        let mut prompt_set = PromptSet::new();
+        for name in ["billing/refund", "billing/invoice", "support/greeting"] {
+            let template = ChatTemplate::from_messages(chats!(System = "Be brief.")).unwrap();
+            prompt_set.insert(name, template);
+        }
+        prompt_set
+            .tag("billing/refund", "billing")
+            .tag("billing/invoice", "billing")
+            .label("billing/refund", "model", "gpt-4o")
+            .label("support/greeting", "model", "gpt-4o")
+            .label("billing/invoice", "model", "llama")
+            .tag("unknown", "billing");
+
+        assert_eq!(
+            prompt_set.by_tag("billing"),
+            vec!["billing/invoice", "billing/refund"]
+        );
+        assert_eq!(
+            prompt_set.where_label("model", "gpt-4o"),
+            vec!["billing/refund", "support/greeting"]
+        );
+        assert_eq!(
+            prompt_set.filter(|m| m.has_tag("billing") && m.label("model") == Some("llama")),
+            vec!["billing/invoice"]
+        );
+        assert!(prompt_set.by_tag("legal").is_empty());
+        assert!(prompt_set.metadata("unknown").is_none());
+    }
+
+    #[test]
+    fn test_prompt_set_metadata_from_json() {
+        let json = r#"{
+            "prompts": {
+                "greet": {
+                    "tags": ["support"],
+                    "labels": { "model": "gpt-4o" },
+                    "messages": [
+                        { "type": "BaseMessage", "value": { "role": "human", "content": "Hi {name}" } }
+                    ]
+                }
+            }
+        }"#;
+
+        let prompt_set = PromptSet::try_from(json.to_string()).unwrap();
+        assert_eq!(prompt_set.by_tag("support"), vec!["greet"]);
+        assert_eq!(
+            prompt_set.metadata("greet").unwrap().label("model"),
+            Some("gpt-4o")
+        );
+    }
+
+    #[test]
+    fn test_put_compare_and_swap() {
+        let mut prompts = PromptSet::new();
+        let template = || ChatTemplate::from_messages(chats!(Human = "{text}")).unwrap();
+
+        assert_eq!(prompts.revision("echo"), None);
+        assert_eq!(prompts.put("echo", template(), None).unwrap(), 1);
+        assert_eq!(prompts.put("echo", template(), Some(1)).unwrap(), 2);
+
+        let err = prompts.put("echo", template(), Some(1)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Version conflict: 'echo' expected revision 1 but found revision 2"
+        );
+        let err = prompts.put("echo", template(), None).unwrap_err();
+        assert!(matches!(err, TemplateError::VersionConflict(_)));
+        let err = prompts.put("new", template(), Some(3)).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("expected revision 3 but found absent"));
+
+        prompts.insert("echo", template());
+        assert_eq!(prompts.revision("echo"), Some(3));
+    }
+}
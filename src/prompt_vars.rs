@@ -0,0 +1,178 @@
+use std::{
+    borrow::Cow,
+    collections::{btree_map, BTreeMap, HashMap},
+};
+
+use serde::{Deserialize, Serialize};
+
+pub trait Variables {
+    fn as_map(&self) -> Cow<'_, HashMap<&str, &str>>;
+}
+
+impl Variables for HashMap<&str, &str> {
+    fn as_map(&self) -> Cow<'_, HashMap<&str, &str>> {
+        Cow::Borrowed(self)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PromptVars {
+    values: BTreeMap<String, String>,
+}
+
+impl PromptVars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.values.insert(name.into(), value.into())
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn merge(&mut self, other: PromptVars) -> &mut Self {
+        self.values.extend(other.values);
+        self
+    }
+
+    pub fn merged(mut self, other: PromptVars) -> Self {
+        self.merge(other);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.values.remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+impl Variables for PromptVars {
+    fn as_map(&self) -> Cow<'_, HashMap<&str, &str>> {
+        Cow::Owned(self.iter().collect())
+    }
+}
+
+impl From<&HashMap<&str, &str>> for PromptVars {
+    fn from(variables: &HashMap<&str, &str>) -> Self {
+        variables
+            .iter()
+            .map(|(name, value)| (*name, *value))
+            .collect()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for PromptVars {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut vars = PromptVars::new();
+        vars.extend(iter);
+        vars
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for PromptVars {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            self.insert(name, value);
+        }
+    }
+}
+
+impl IntoIterator for PromptVars {
+    type Item = (String, String);
+    type IntoIter = btree_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{
+        chats, vars, ChatTemplate, PromptRegistry,
+        Role::{Human, System},
+        Template,
+    };
+
+    fn row() -> HashMap<String, String> {
+        HashMap::from([
+            ("name".to_string(), "Ada".to_string()),
+            ("plan".to_string(), "pro".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_builder_and_merge() {
+        let mut vars = PromptVars::new().with("name", "Ada").with("plan", "free");
+        assert_eq!(vars.insert("plan", "pro"), Some("free".to_string()));
+
+        vars.merge(PromptVars::from(&vars!(team = "Core", name = "Bo")));
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars.get("name"), Some("Bo"));
+        assert_eq!(
+            vars.iter().collect::<Vec<_>>(),
+            vec![("name", "Bo"), ("plan", "pro"), ("team", "Core")]
+        );
+
+        let collected: PromptVars = row().into_iter().collect();
+        assert_eq!(collected.get("plan"), Some("pro"));
+        assert_eq!(
+            serde_json::to_string(&collected).unwrap(),
+            r#"{"name":"Ada","plan":"pro"}"#
+        );
+    }
+
+    #[test]
+    fn test_accepted_by_format_functions() {
+        let vars: PromptVars = row().into_iter().collect();
+
+        let template = Template::new("Hi {name}, you are on {plan}.").unwrap();
+        assert_eq!(template.format(&vars).unwrap(), "Hi Ada, you are on pro.");
+        assert_eq!(
+            template.format(&vars!(name = "Bo", plan = "free")).unwrap(),
+            "Hi Bo, you are on free."
+        );
+
+        let chat =
+            ChatTemplate::from_messages(chats!(System = "Plan: {plan}.", Human = "I am {name}.",))
+                .unwrap();
+        let messages = chat.format_messages(&vars).unwrap();
+        assert_eq!(messages[1].content(), "I am Ada.");
+        assert_eq!(chat.render(&vars).unwrap().messages.len(), 2);
+
+        let registry = PromptRegistry::new().with("greeting", template).unwrap();
+        assert_eq!(
+            registry.format("greeting", &vars).unwrap(),
+            "Hi Ada, you are on pro."
+        );
+    }
+}
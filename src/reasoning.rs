@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReasoningHints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<Verbosity>,
+}
+
+impl ReasoningHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn effort(mut self, effort: ReasoningEffort) -> Self {
+        self.effort = Some(effort);
+        self
+    }
+
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reasoning_hints_serialization() {
+        let hints = ReasoningHints::new().effort(ReasoningEffort::High);
+        let json = serde_json::to_string(&hints).unwrap();
+        assert_eq!(json, r#"{"effort":"high"}"#);
+
+        let parsed: ReasoningHints =
+            serde_json::from_str(r#"{"effort":"minimal","verbosity":"low"}"#).unwrap();
+        assert_eq!(parsed.effort, Some(ReasoningEffort::Minimal));
+        assert_eq!(parsed.verbosity, Some(Verbosity::Low));
+    }
+}
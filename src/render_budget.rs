@@ -0,0 +1,305 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use messageforge::MessageEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, MessageLike, TemplateError};
+
+type SlowRenderHook = Arc<dyn Fn(&SlowRender) + Send + Sync>;
+
+lazy_static! {
+    static ref SLOW_RENDER_HOOKS: RwLock<Vec<(u64, SlowRenderHook)>> = RwLock::new(Vec::new());
+}
+
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderStage {
+    Parse,
+    VariableResolution,
+    TemplateRendering,
+    PlaceholderDecode,
+    PostProcess,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderTimings {
+    pub stages: BTreeMap<RenderStage, Duration>,
+    pub entries: Vec<Duration>,
+}
+
+impl RenderTimings {
+    pub fn total(&self) -> Duration {
+        self.stages.values().sum()
+    }
+
+    pub fn stage(&self, stage: RenderStage) -> Duration {
+        self.stages.get(&stage).copied().unwrap_or_default()
+    }
+
+    pub fn slowest_entry(&self) -> Option<(usize, Duration)> {
+        self.entries
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by_key(|(_, elapsed)| *elapsed)
+    }
+
+    fn record(&mut self, stage: RenderStage, elapsed: Duration) {
+        *self.stages.entry(stage).or_default() += elapsed;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowRender {
+    pub budget: Duration,
+    pub timings: RenderTimings,
+}
+
+impl SlowRender {
+    pub fn elapsed(&self) -> Duration {
+        self.timings.total()
+    }
+
+    pub fn overrun(&self) -> Duration {
+        self.elapsed().saturating_sub(self.budget)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[must_use = "dropping the handle keeps the hook registered; call `remove` to unregister it"]
+pub struct SlowRenderHandle {
+    id: u64,
+}
+
+impl SlowRenderHandle {
+    pub fn remove(self) -> bool {
+        match SLOW_RENDER_HOOKS.write() {
+            Ok(mut hooks) => {
+                let before = hooks.len();
+                hooks.retain(|(id, _)| *id != self.id);
+                hooks.len() != before
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+pub fn on_slow_render(hook: impl Fn(&SlowRender) + Send + Sync + 'static) -> SlowRenderHandle {
+    let id = NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut hooks) = SLOW_RENDER_HOOKS.write() {
+        hooks.push((id, Arc::new(hook)));
+    }
+    SlowRenderHandle { id }
+}
+
+fn emit_slow_render(event: &SlowRender) {
+    let hooks = match SLOW_RENDER_HOOKS.read() {
+        Ok(hooks) => hooks.clone(),
+        Err(_) => return,
+    };
+    for (_, hook) in hooks {
+        hook(event);
+    }
+}
+
+fn entry_stage(message_like: &MessageLike) -> RenderStage {
+    match message_like {
+        MessageLike::RolePromptTemplate(..) | MessageLike::BaseMessage(_) => {
+            RenderStage::TemplateRendering
+        }
+        MessageLike::Placeholder(_) | MessageLike::FewShotPrompt(_) => {
+            RenderStage::PlaceholderDecode
+        }
+    }
+}
+
+impl ChatTemplate {
+    pub fn with_render_budget(mut self, budget: Duration) -> Self {
+        self.meta.render_budget = Some(budget);
+        self
+    }
+
+    pub fn render_timed(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<(Vec<Arc<MessageEnum>>, RenderTimings), TemplateError> {
+        self.render_slice_timed(&self.messages, variables)
+    }
+
+    pub(crate) fn render_slice_timed(
+        &self,
+        messages: &[MessageLike],
+        variables: &HashMap<&str, &str>,
+    ) -> Result<(Vec<Arc<MessageEnum>>, RenderTimings), TemplateError> {
+        let mut timings = RenderTimings::default();
+
+        let parse_start = Instant::now();
+        let mut parsed = false;
+        for message_like in messages {
+            if let MessageLike::RolePromptTemplate(_, template) = message_like {
+                if !template.is_validated() {
+                    template.prepare()?;
+                    parsed = true;
+                }
+            }
+        }
+        if parsed {
+            timings.record(RenderStage::Parse, parse_start.elapsed());
+        }
+
+        let start = Instant::now();
+
+        let results = self.with_render_variables(variables, |variables| {
+            timings.record(RenderStage::VariableResolution, start.elapsed());
+
+            let mut results = Vec::new();
+            for message_like in messages {
+                let entry_start = Instant::now();
+                let groups =
+                    self.render_groups(std::slice::from_ref(message_like), variables, None)?;
+                let elapsed = entry_start.elapsed();
+                timings.record(entry_stage(message_like), elapsed);
+                timings.entries.push(elapsed);
+                results.extend(groups.into_iter().flatten());
+            }
+
+            let finish_start = Instant::now();
+            let results = self.finish_render(results, None)?;
+            timings.record(RenderStage::PostProcess, finish_start.elapsed());
+            Ok(results)
+        })?;
+
+        if let Some(budget) = self.meta.render_budget {
+            if timings.total() > budget {
+                emit_slow_render(&SlowRender {
+                    budget,
+                    timings: timings.clone(),
+                });
+            }
+        }
+
+        Ok((results, timings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, Placeholder, System},
+    };
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(
+            System = "You are {persona}.",
+            Placeholder = "{history|optional}",
+            Human = "{question}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_timed_breakdown() {
+        let template = template();
+        let variables = vars!(persona = "a pirate", question = "Where?");
+
+        let (messages, timings) = template.render_timed(&variables).unwrap();
+        assert_eq!(messages, template.format_messages(&variables).unwrap());
+        assert_eq!(timings.entries.len(), 3);
+        assert_eq!(
+            timings.stages.keys().copied().collect::<Vec<_>>(),
+            vec![
+                RenderStage::VariableResolution,
+                RenderStage::TemplateRendering,
+                RenderStage::PlaceholderDecode,
+                RenderStage::PostProcess,
+            ]
+        );
+        assert!(timings.total() >= timings.stage(RenderStage::TemplateRendering));
+        assert!(timings.slowest_entry().is_some());
+    }
+
+    #[test]
+    fn test_slow_render_hook_fires_when_budget_is_exceeded() {
+        let seen: Arc<Mutex<Vec<SlowRender>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let handle = on_slow_render(move |event| {
+            if event.budget == Duration::ZERO {
+                sink.lock().unwrap().push(event.clone());
+            }
+        });
+
+        let variables = vars!(persona = "a pirate", question = "Where?");
+        template()
+            .with_render_budget(Duration::from_secs(60))
+            .format_messages(&variables)
+            .unwrap();
+        assert!(seen.lock().unwrap().is_empty());
+
+        let messages = template()
+            .with_render_budget(Duration::ZERO)
+            .format_messages(&variables)
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+
+        {
+            let seen = seen.lock().unwrap();
+            assert_eq!(seen.len(), 1);
+            assert_eq!(seen[0].overrun(), seen[0].elapsed());
+            assert_eq!(seen[0].timings.entries.len(), 3);
+        }
+
+        assert!(handle.remove());
+        template()
+            .with_render_budget(Duration::ZERO)
+            .format_messages(&variables)
+            .unwrap();
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_removing_a_handle_keeps_other_hooks() {
+        let first = on_slow_render(|_| {});
+        let second = on_slow_render(|_| {});
+        let second_id = second.id;
+
+        assert!(first.remove());
+        assert!(SLOW_RENDER_HOOKS
+            .read()
+            .unwrap()
+            .iter()
+            .any(|(id, _)| *id == second_id));
+        assert!(second.remove());
+        assert!(!SlowRenderHandle { id: second_id }.remove());
+    }
+
+    #[test]
+    fn test_render_timed_records_parse_for_unchecked_templates() {
+        let template = ChatTemplate::from_messages_unchecked(chats!(
+            System = "You are {persona}.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        let variables = vars!(persona = "a pirate", question = "Where?");
+
+        let (messages, timings) = template.render_timed(&variables).unwrap();
+        assert_eq!(messages, template.format_messages(&variables).unwrap());
+        assert!(timings.stages.contains_key(&RenderStage::Parse));
+
+        let (_, checked) = self::template().render_timed(&variables).unwrap();
+        assert!(!checked.stages.contains_key(&RenderStage::Parse));
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use messageforge::MessageEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{lineage::Lineage, source_map::SourceMap, ChatTemplate};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderOutput {
+    pub messages: Vec<Arc<MessageEnum>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_map: Option<SourceMap>,
+}
+
+impl RenderOutput {
+    pub fn new(messages: Vec<Arc<MessageEnum>>) -> Self {
+        Self {
+            messages,
+            lineage: None,
+            source_map: None,
+        }
+    }
+
+    pub fn with_lineage(mut self, lineage: Lineage) -> Self {
+        self.lineage = Some(lineage);
+        self
+    }
+
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    pub fn transcript(&self) -> String {
+        ChatTemplate::transcript(&self.messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Role;
+
+    #[test]
+    fn test_render_output_serialization() {
+        let output = RenderOutput::new(vec![Role::Human.to_message("Hi.").unwrap()]);
+        let json = serde_json::to_string(&output).unwrap();
+        assert!(!json.contains("lineage"));
+
+        let deserialized: RenderOutput = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, output);
+        assert_eq!(deserialized.transcript(), "human: Hi.");
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "async")]
+use futures::Stream;
+use messageforge::MessageEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{batch::Row, ChatTemplate, TemplateError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_second: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch: Option<usize>,
+}
+
+impl RateLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn requests_per_second(mut self, max: u32) -> Self {
+        self.requests_per_second = Some(max);
+        self
+    }
+
+    pub fn tokens_per_second(mut self, max: u32) -> Self {
+        self.tokens_per_second = Some(max);
+        self
+    }
+
+    pub fn max_batch(mut self, max: usize) -> Self {
+        self.max_batch = Some(max);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedRender {
+    pub sequence: usize,
+    pub messages: Vec<Arc<MessageEnum>>,
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(per_second: u32, now: Instant) -> Self {
+        Self {
+            capacity: per_second as f64,
+            available: per_second as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.capacity).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn can_spend(&self, cost: f64) -> bool {
+        self.available >= cost.min(self.capacity)
+    }
+
+    fn wait_for(&self, cost: f64) -> Duration {
+        let missing = cost.min(self.capacity) - self.available;
+        if missing <= 0.0 || self.capacity <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.capacity)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderQueue {
+    template: Arc<ChatTemplate>,
+    provider: String,
+    limits: RateLimits,
+    pending: VecDeque<(usize, Row)>,
+    head: Option<Result<QueuedRender, TemplateError>>,
+    next_sequence: usize,
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+impl RenderQueue {
+    pub fn new(template: ChatTemplate, provider: impl Into<String>, limits: RateLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            template: Arc::new(template),
+            provider: provider.into(),
+            limits,
+            pending: VecDeque::new(),
+            head: None,
+            next_sequence: 0,
+            requests: limits
+                .requests_per_second
+                .map(|rate| Bucket::new(rate, now)),
+            tokens: limits.tokens_per_second.map(|rate| Bucket::new(rate, now)),
+        }
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits
+    }
+
+    pub fn push(&mut self, row: Row) -> usize {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.pending.push_back((sequence, row));
+        sequence
+    }
+
+    pub fn extend<I: IntoIterator<Item = Row>>(&mut self, rows: I) {
+        for row in rows {
+            self.push(row);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len() + usize::from(self.head.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn next_batch(&mut self, now: Instant) -> Vec<Result<QueuedRender, TemplateError>> {
+        self.refill(now);
+
+        let max_batch = self.limits.max_batch.unwrap_or(usize::MAX).max(1);
+        let mut batch = Vec::new();
+
+        while batch.len() < max_batch {
+            let render = match self.head.take().or_else(|| self.render_next()) {
+                Some(Ok(render)) => render,
+                Some(Err(e)) => {
+                    batch.push(Err(e));
+                    continue;
+                }
+                None => break,
+            };
+
+            if !self.can_send(&render) {
+                self.head = Some(Ok(render));
+                break;
+            }
+            self.spend(&render);
+            batch.push(Ok(render));
+        }
+
+        batch
+    }
+
+    pub fn ready_in(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+
+        if self.head.is_none() {
+            self.head = Some(self.render_next()?);
+        }
+        let Some(Ok(render)) = &self.head else {
+            return Some(Duration::ZERO);
+        };
+
+        let request_wait = self
+            .requests
+            .as_ref()
+            .map_or(Duration::ZERO, |bucket| bucket.wait_for(1.0));
+        let token_wait = self.tokens.as_ref().map_or(Duration::ZERO, |bucket| {
+            bucket.wait_for(render.tokens as f64)
+        });
+        Some(request_wait.max(token_wait))
+    }
+
+    #[cfg(feature = "async")]
+    pub fn drain(self) -> impl Stream<Item = Vec<Result<QueuedRender, TemplateError>>> {
+        futures::stream::unfold(self, |mut queue| async move {
+            let wait = queue.ready_in(Instant::now())?;
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+            let batch = queue.next_batch(Instant::now());
+            Some((batch, queue))
+        })
+    }
+
+    fn render_next(&mut self) -> Option<Result<QueuedRender, TemplateError>> {
+        let (sequence, row) = self.pending.pop_front()?;
+        let variables: HashMap<&str, &str> = row
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        Some(
+            self.template
+                .format_messages(&variables)
+                .map(|messages| QueuedRender {
+                    sequence,
+                    tokens: self.template.counter().count_messages(&messages),
+                    messages,
+                }),
+        )
+    }
+
+    fn refill(&mut self, now: Instant) {
+        for bucket in [&mut self.requests, &mut self.tokens].into_iter().flatten() {
+            bucket.refill(now);
+        }
+    }
+
+    fn can_send(&self, render: &QueuedRender) -> bool {
+        self.requests
+            .as_ref()
+            .map_or(true, |bucket| bucket.can_spend(1.0))
+            && self
+                .tokens
+                .as_ref()
+                .map_or(true, |bucket| bucket.can_spend(render.tokens as f64))
+    }
+
+    fn spend(&mut self, render: &QueuedRender) {
+        if let Some(bucket) = &mut self.requests {
+            bucket.available -= 1.0;
+        }
+        if let Some(bucket) = &mut self.tokens {
+            bucket.available -= render.tokens as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{chats, Role::Human};
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(Human = "Summarize ticket {id}."))
+            .unwrap()
+            .with_token_counter(|_: &str| 10)
+    }
+
+    fn row(id: usize) -> Row {
+        Row::from([("id".to_string(), id.to_string())])
+    }
+
+    #[test]
+    fn test_request_budget_paces_batches() {
+        let mut queue = RenderQueue::new(
+            template(),
+            "openai",
+            RateLimits::new().requests_per_second(2),
+        );
+        queue.extend((0..5).map(row));
+        let start = Instant::now();
+
+        let first = queue.next_batch(start);
+        assert_eq!(first.len(), 2);
+        assert_eq!(
+            first[1].as_ref().unwrap().messages[0].content(),
+            "Summarize ticket 1."
+        );
+        assert!(queue.next_batch(start).is_empty());
+        assert_eq!(queue.ready_in(start), Some(Duration::from_millis(500)));
+
+        let second = queue.next_batch(start + Duration::from_millis(500));
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].as_ref().unwrap().sequence, 2);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_token_budget_and_max_batch() {
+        let mut queue = RenderQueue::new(
+            template(),
+            "anthropic",
+            RateLimits::new().tokens_per_second(30).max_batch(2),
+        );
+        queue.extend((0..4).map(row));
+        let start = Instant::now();
+
+        let first = queue.next_batch(start);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].as_ref().unwrap().tokens, 10);
+
+        assert_eq!(queue.next_batch(start).len(), 1);
+        assert!(queue.next_batch(start).is_empty());
+        assert!(queue.ready_in(start).unwrap() > Duration::from_millis(300));
+
+        let later = start + Duration::from_secs(1);
+        assert_eq!(queue.next_batch(later).len(), 1);
+        assert!(queue.is_empty());
+        assert_eq!(queue.ready_in(later), None);
+    }
+
+    #[test]
+    fn test_render_errors_are_emitted_without_spending_budget() {
+        let mut queue = RenderQueue::new(
+            template(),
+            "openai",
+            RateLimits::new().requests_per_second(1),
+        );
+        queue.push(Row::new());
+        queue.push(row(1));
+
+        let batch = queue.next_batch(Instant::now());
+        assert_eq!(batch.len(), 2);
+        assert!(batch[0].is_err());
+        assert_eq!(batch[1].as_ref().unwrap().sequence, 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_drain_emits_every_render() {
+        use futures::StreamExt;
+
+        let mut queue = RenderQueue::new(
+            template(),
+            "openai",
+            RateLimits::new().requests_per_second(100),
+        );
+        queue.extend((0..150).map(row));
+
+        let batches: Vec<_> = queue.drain().collect().await;
+        assert!(batches.len() >= 2);
+        let sequences: Vec<usize> = batches
+            .into_iter()
+            .flatten()
+            .map(|render| render.unwrap().sequence)
+            .collect();
+        assert_eq!(sequences, (0..150).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+};
+
+use futures::{stream, stream::BoxStream, Stream, StreamExt};
+use messageforge::MessageEnum;
+
+use crate::{ChatTemplate, MessageLike, Templatable, TemplateError};
+
+pub type ResolveFuture = Pin<Box<dyn Future<Output = Result<String, TemplateError>> + Send>>;
+pub type Resolver = Arc<dyn Fn() -> ResolveFuture + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Resolvers {
+    resolvers: BTreeMap<String, Resolver>,
+    concurrency: usize,
+}
+
+impl fmt::Debug for Resolvers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resolvers")
+            .field("variables", &self.resolvers.keys().collect::<Vec<_>>())
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
+
+impl Default for Resolvers {
+    fn default() -> Self {
+        Self {
+            resolvers: BTreeMap::new(),
+            concurrency: Self::DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl Resolvers {
+    pub const DEFAULT_CONCURRENCY: usize = 8;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<F, Fut>(mut self, variable: impl Into<String>, resolver: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, TemplateError>> + Send + 'static,
+    {
+        self.resolvers.insert(
+            variable.into(),
+            Arc::new(move || Box::pin(resolver()) as ResolveFuture),
+        );
+        self
+    }
+
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    pub async fn resolve(
+        &self,
+        template: &ChatTemplate,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<HashMap<String, String>, TemplateError> {
+        let pending: Vec<(String, Resolver)> = template
+            .referenced_variables()
+            .into_iter()
+            .filter(|name| !variables.contains_key(name.as_str()))
+            .filter_map(|name| {
+                let resolver = self.resolvers.get(&name)?.clone();
+                Some((name, resolver))
+            })
+            .collect();
+
+        let results: Vec<(String, Result<String, TemplateError>)> = stream::iter(pending)
+            .map(|(name, resolver)| async move { (name, resolver().await) })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut resolved = HashMap::new();
+        let mut failures = Vec::new();
+        for (name, result) in results {
+            match result {
+                Ok(value) => {
+                    resolved.insert(name, value);
+                }
+                Err(e) => failures.push((name, e.to_string())),
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(resolved)
+        } else {
+            failures.sort();
+            Err(TemplateError::ResolutionFailed(failures))
+        }
+    }
+
+    pub async fn format_messages(
+        &self,
+        template: &ChatTemplate,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<Arc<MessageEnum>>, TemplateError> {
+        let resolved = self.resolve(template, variables).await?;
+        let mut merged = variables.clone();
+        merged.extend(
+            resolved
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+        template.format_messages(&merged)
+    }
+
+    pub fn format_messages_stream<'a>(
+        &'a self,
+        template: &'a ChatTemplate,
+        variables: &'a HashMap<&'a str, &'a str>,
+    ) -> impl Stream<Item = Result<Arc<MessageEnum>, TemplateError>> + Send + 'a {
+        let pending: Vec<(String, Resolver)> = template
+            .referenced_variables()
+            .into_iter()
+            .filter(|name| !variables.contains_key(name.as_str()))
+            .filter_map(|name| Some((name.clone(), self.resolvers.get(&name)?.clone())))
+            .collect();
+        let unresolved = pending.iter().map(|(name, _)| name.clone()).collect();
+
+        let state = StreamState {
+            template,
+            variables,
+            resolving: stream::iter(pending)
+                .map(|(name, resolver)| async move { (name, resolver().await) })
+                .buffer_unordered(self.concurrency)
+                .boxed(),
+            unresolved,
+            resolved: HashMap::new(),
+            next: 0,
+            ready: VecDeque::new(),
+            emitted: Vec::new(),
+            failed: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let item = state.advance().await?;
+            Some((item, state))
+        })
+    }
+}
+
+impl ChatTemplate {
+    pub fn format_messages_stream<'a>(
+        &'a self,
+        resolvers: &'a Resolvers,
+        variables: &'a HashMap<&'a str, &'a str>,
+    ) -> impl Stream<Item = Result<Arc<MessageEnum>, TemplateError>> + Send + 'a {
+        resolvers.format_messages_stream(self, variables)
+    }
+}
+
+struct StreamState<'a> {
+    template: &'a ChatTemplate,
+    variables: &'a HashMap<&'a str, &'a str>,
+    resolving: BoxStream<'a, (String, Result<String, TemplateError>)>,
+    unresolved: Vec<String>,
+    resolved: HashMap<String, String>,
+    next: usize,
+    ready: VecDeque<Arc<MessageEnum>>,
+    emitted: Vec<Arc<MessageEnum>>,
+    failed: bool,
+}
+
+impl StreamState<'_> {
+    async fn advance(&mut self) -> Option<Result<Arc<MessageEnum>, TemplateError>> {
+        if let Some(message) = self.ready.pop_front() {
+            return Some(Ok(message));
+        }
+        if self.failed || self.next >= self.template.messages.len() {
+            return None;
+        }
+
+        let result = self.render_next().await;
+        if let Err(e) = result {
+            self.failed = true;
+            return Some(Err(e));
+        }
+        self.ready.pop_front().map(Ok)
+    }
+
+    async fn render_next(&mut self) -> Result<(), TemplateError> {
+        let (range, needed) = if self.template.meta.profile.is_some() {
+            let all = 0..self.template.messages.len();
+            (all, self.unresolved.clone())
+        } else {
+            let needed = message_variables(&self.template.messages[self.next])
+                .into_iter()
+                .filter(|name| self.unresolved.contains(name))
+                .collect();
+            (self.next..self.next + 1, needed)
+        };
+
+        self.await_variables(&needed).await?;
+
+        let merged: HashMap<&str, &str> = self
+            .variables
+            .iter()
+            .map(|(name, value)| (*name, *value))
+            .chain(
+                self.resolved
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            )
+            .collect();
+
+        let messages = if self.template.meta.profile.is_some() {
+            self.template.format_messages(&merged)?
+        } else {
+            let slice = &self.template.messages[range.clone()];
+            let groups = self.template.with_render_variables(&merged, |variables| {
+                self.template.render_groups(slice, variables, None)
+            })?;
+            let messages: Vec<_> = groups.into_iter().flatten().collect();
+            if let Some(limits) = &self.template.meta.limits {
+                self.emitted.extend(messages.iter().cloned());
+                limits.check_total(&self.emitted)?;
+            }
+            messages
+        };
+
+        self.next = range.end;
+        self.ready.extend(messages);
+        Ok(())
+    }
+
+    async fn await_variables(&mut self, needed: &[String]) -> Result<(), TemplateError> {
+        let mut failures = Vec::new();
+
+        while needed.iter().any(|name| self.unresolved.contains(name)) {
+            let Some((name, result)) = self.resolving.next().await else {
+                break;
+            };
+            self.unresolved.retain(|pending| pending != &name);
+            match result {
+                Ok(value) => {
+                    self.resolved.insert(name, value);
+                }
+                Err(e) if needed.contains(&name) => failures.push((name, e.to_string())),
+                Err(e) => {
+                    return Err(TemplateError::ResolutionFailed(vec![(name, e.to_string())]));
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            failures.sort();
+            Err(TemplateError::ResolutionFailed(failures))
+        }
+    }
+}
+
+fn message_variables(message_like: &MessageLike) -> Vec<String> {
+    match message_like {
+        MessageLike::RolePromptTemplate(_, template) => template.input_variables(),
+        MessageLike::Placeholder(placeholder) => vec![placeholder.variable_name().to_string()],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{
+        chats, vars,
+        Role::{Human, System},
+    };
+
+    fn template() -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(
+            System = "Profile: {profile}. Orders: {orders}.",
+            Human = "{question} (docs: {docs})",
+        ))
+        .unwrap()
+    }
+
+    fn tracked(
+        value: &'static str,
+        in_flight: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    ) -> impl Fn() -> ResolveFuture + Send + Sync {
+        move || {
+            let in_flight = in_flight.clone();
+            let peak = peak.clone();
+            Box::pin(async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(value.to_string())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolvers_run_concurrently_within_limit() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let resolvers = Resolvers::new()
+            .with("profile", tracked("gold", in_flight.clone(), peak.clone()))
+            .with("orders", tracked("3", in_flight.clone(), peak.clone()))
+            .with("docs", tracked("faq", in_flight.clone(), peak.clone()))
+            .concurrency(2);
+
+        let messages = resolvers
+            .format_messages(&template(), &vars!(question = "Where is my order?"))
+            .await
+            .unwrap();
+
+        assert_eq!(messages[0].content(), "Profile: gold. Orders: 3.");
+        assert_eq!(messages[1].content(), "Where is my order? (docs: faq)");
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_provided_variables_skip_resolvers() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let resolvers = Resolvers::new().with("docs", move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+            async { Ok("resolved".to_string()) }
+        });
+
+        let resolved = resolvers
+            .resolve(&template(), &vars!(docs = "given"))
+            .await
+            .unwrap();
+        assert!(resolved.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_yields_messages_before_slow_resolvers_finish() {
+        let resolvers = Resolvers::new()
+            .with("profile", || async { Ok("gold".to_string()) })
+            .with("orders", || async { Ok("3".to_string()) })
+            .with("docs", || async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok("faq".to_string())
+            });
+        let template = template();
+        let variables = vars!(question = "Where is my order?");
+
+        let mut stream = Box::pin(template.format_messages_stream(&resolvers, &variables));
+        let first = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("system message should not wait for docs")
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.content(), "Profile: gold. Orders: 3.");
+    }
+
+    #[tokio::test]
+    async fn test_stream_matches_format_messages() {
+        let resolvers = Resolvers::new()
+            .with("profile", || async { Ok("gold".to_string()) })
+            .with("orders", || async { Ok("3".to_string()) })
+            .with("docs", || async { Ok("faq".to_string()) });
+        let template = template();
+        let variables = vars!(question = "Hi");
+
+        let streamed: Vec<String> = resolvers
+            .format_messages_stream(&template, &variables)
+            .map(|message| message.unwrap().content().to_string())
+            .collect()
+            .await;
+        let formatted: Vec<String> = resolvers
+            .format_messages(&template, &variables)
+            .await
+            .unwrap()
+            .iter()
+            .map(|message| message.content().to_string())
+            .collect();
+        assert_eq!(streamed, formatted);
+    }
+
+    #[tokio::test]
+    async fn test_stream_stops_at_failed_resolver() {
+        let resolvers = Resolvers::new()
+            .with("profile", || async { Ok("gold".to_string()) })
+            .with("orders", || async { Ok("3".to_string()) })
+            .with("docs", || async {
+                Err(TemplateError::MalformedTemplate("index offline".into()))
+            })
+            .concurrency(1);
+        let template = template();
+        let variables = vars!(question = "Hi");
+
+        let results: Vec<_> = resolvers
+            .format_messages_stream(&template, &variables)
+            .collect()
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            &results[1],
+            Err(TemplateError::ResolutionFailed(failures)) if failures[0].0 == "docs"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_aggregated() {
+        let resolvers = Resolvers::new()
+            .with("profile", || async {
+                Err(TemplateError::MissingVariable("crm timeout".into()))
+            })
+            .with("orders", || async { Ok("3".to_string()) })
+            .with("docs", || async {
+                Err(TemplateError::MalformedTemplate("index offline".into()))
+            });
+
+        let err = resolvers
+            .format_messages(&template(), &vars!(question = "Hi"))
+            .await
+            .unwrap_err();
+        match err {
+            TemplateError::ResolutionFailed(failures) => {
+                let names: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+                assert_eq!(names, vec!["docs", "profile"]);
+                assert!(failures[1].1.contains("crm timeout"));
+            }
+            e => panic!("Expected ResolutionFailed, got {:?}", e),
+        }
+    }
+}
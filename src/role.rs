@@ -3,9 +3,14 @@ use std::{convert::TryFrom, fmt, sync::Arc};
 use messageforge::{AiMessage, HumanMessage, MessageEnum, SystemMessage};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+use crate::TemplateError;
+
+pub(crate) const ROLE_KWARG: &str = "role";
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum Role {
     System,
+    Developer,
     Human,
     Ai,
     Tool,
@@ -30,6 +35,7 @@ impl TryFrom<&str> for Role {
     fn try_from(role: &str) -> Result<Self, Self::Error> {
         match role.to_lowercase().as_str() {
             "system" => Ok(Role::System),
+            "developer" => Ok(Role::Developer),
             "human" => Ok(Role::Human),
             "ai" => Ok(Role::Ai),
             "tool" => Ok(Role::Tool),
@@ -44,6 +50,7 @@ impl Role {
     pub fn as_str(&self) -> &str {
         match self {
             Role::System => "system",
+            Role::Developer => "developer",
             Role::Human => "human",
             Role::Ai => "ai",
             Role::Tool => "tool",
@@ -55,6 +62,14 @@ impl Role {
     pub fn to_message(self, content: &str) -> Result<Arc<MessageEnum>, InvalidRoleError> {
         let message_enum = match self {
             Role::System => MessageEnum::System(SystemMessage::new(content)),
+            Role::Developer => {
+                let mut message = SystemMessage::new(content);
+                message
+                    .base
+                    .additional_kwargs
+                    .insert(ROLE_KWARG.to_string(), self.to_string());
+                MessageEnum::System(message)
+            }
             Role::Human => MessageEnum::Human(HumanMessage::new(content)),
             Role::Ai => MessageEnum::Ai(AiMessage::new(content)),
             _ => return Err(InvalidRoleError),
@@ -64,6 +79,36 @@ impl Role {
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolePolicy {
+    #[default]
+    Fail,
+    Skip,
+    Substitute(Role),
+}
+
+impl RolePolicy {
+    pub fn is_fail(&self) -> bool {
+        *self == RolePolicy::Fail
+    }
+
+    pub(crate) fn resolve(
+        &self,
+        role: &str,
+        content: &str,
+    ) -> Result<Option<Arc<MessageEnum>>, TemplateError> {
+        match self {
+            RolePolicy::Fail => Err(TemplateError::UnknownRole(role.to_string())),
+            RolePolicy::Skip => Ok(None),
+            RolePolicy::Substitute(substitute) => substitute
+                .to_message(content)
+                .map(Some)
+                .map_err(|_| TemplateError::UnknownRole(substitute.as_str().to_string())),
+        }
+    }
+}
+
 impl fmt::Display for Role {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -73,6 +118,7 @@ impl fmt::Display for Role {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assertions::{declared_role, message_role};
     use messageforge::BaseMessage;
 
     fn test_message_creation(role: Role, content: &str) {
@@ -88,6 +134,7 @@ mod tests {
     #[test]
     fn test_role_to_string() {
         assert_eq!(Role::System.to_string(), "system");
+        assert_eq!(Role::Developer.to_string(), "developer");
         assert_eq!(Role::Human.to_string(), "human");
         assert_eq!(Role::Ai.to_string(), "ai");
         assert_eq!(Role::Tool.to_string(), "tool");
@@ -98,6 +145,7 @@ mod tests {
     #[test]
     fn test_try_from_str() {
         assert_eq!(Role::try_from("system").unwrap(), Role::System);
+        assert_eq!(Role::try_from("developer").unwrap(), Role::Developer);
         assert_eq!(Role::try_from("human").unwrap(), Role::Human);
         assert_eq!(Role::try_from("ai").unwrap(), Role::Ai);
         assert_eq!(Role::try_from("tool").unwrap(), Role::Tool);
@@ -109,6 +157,16 @@ mod tests {
         assert!(Role::try_from("invalid").is_err());
     }
 
+    #[test]
+    fn test_developer_messages_keep_their_role() {
+        let developer = Role::Developer.to_message("Follow the guide.").unwrap();
+        assert_eq!(message_role(&developer), Some(Role::System));
+        assert_eq!(declared_role(&developer), Some(Role::Developer));
+
+        let system = Role::System.to_message("Be terse.").unwrap();
+        assert_eq!(declared_role(&system), Some(Role::System));
+    }
+
     #[test]
     fn test_system_message_creation() {
         test_message_creation(Role::System, "This is a system message.");
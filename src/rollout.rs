@@ -0,0 +1,155 @@
+use crate::{lineage::fnv1a, ChatTemplate, PromptEvent, PromptSet, TemplateError};
+
+#[derive(Debug, Clone)]
+pub struct Rollout {
+    pub candidate: ChatTemplate,
+    percent: u8,
+}
+
+impl Rollout {
+    pub fn new(candidate: ChatTemplate, percent: u8) -> Self {
+        Self {
+            candidate,
+            percent: percent.min(100),
+        }
+    }
+
+    pub fn percent(&self) -> u8 {
+        self.percent
+    }
+
+    pub fn bucket(name: &str, user_id: &str) -> u8 {
+        let key = format!("{}\u{0}{}", name, user_id);
+        (fnv1a(key.as_bytes()) % 100) as u8
+    }
+
+    pub fn serves_candidate(&self, name: &str, user_id: &str) -> bool {
+        Self::bucket(name, user_id) < self.percent
+    }
+}
+
+impl PromptSet {
+    pub fn start_rollout(
+        &mut self,
+        name: &str,
+        candidate: ChatTemplate,
+        percent: u8,
+    ) -> Result<&mut Self, TemplateError> {
+        if !self.contains(name) {
+            return Err(TemplateError::UnknownPrompt(name.to_string()));
+        }
+        self.rollouts
+            .insert(name.to_string(), Rollout::new(candidate, percent));
+        Ok(self)
+    }
+
+    pub fn set_rollout_percent(&mut self, name: &str, percent: u8) -> Option<&Rollout> {
+        let rollout = self.rollouts.get_mut(name)?;
+        rollout.percent = percent.min(100);
+        Some(rollout)
+    }
+
+    pub fn rollout(&self, name: &str) -> Option<&Rollout> {
+        self.rollouts.get(name)
+    }
+
+    pub fn rollback(&mut self, name: &str) -> Option<Rollout> {
+        let rollout = self.rollouts.remove(name)?;
+        self.events.publish(PromptEvent::RolledBack {
+            name: name.to_string(),
+        });
+        Some(rollout)
+    }
+
+    pub fn promote(&mut self, name: &str) -> Option<&ChatTemplate> {
+        let rollout = self.rollouts.remove(name)?;
+        self.insert(name, rollout.candidate);
+        self.get(name)
+    }
+
+    pub fn resolve(&self, name: &str, user_id: &str) -> Option<&ChatTemplate> {
+        match self.rollouts.get(name) {
+            Some(rollout) if rollout.serves_candidate(name, user_id) => Some(&rollout.candidate),
+            _ => self.get(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chats, vars, Role::System};
+    use messageforge::BaseMessage;
+
+    fn prompt(content: &str) -> ChatTemplate {
+        ChatTemplate::from_messages(chats!(System = content)).unwrap()
+    }
+
+    fn serves_v2(set: &PromptSet, user_id: &str) -> bool {
+        let messages = set
+            .resolve("greeting", user_id)
+            .unwrap()
+            .format_messages(&vars!())
+            .unwrap();
+        messages[0].content() == "v2"
+    }
+
+    fn set_with_rollout(percent: u8) -> PromptSet {
+        let mut set = PromptSet::new();
+        set.insert("greeting", prompt("v1"));
+        set.start_rollout("greeting", prompt("v2"), percent)
+            .unwrap();
+        set
+    }
+
+    #[test]
+    fn test_resolve_splits_traffic_by_percent() {
+        let set = set_with_rollout(30);
+        let users: Vec<String> = (0..1000).map(|i| format!("user-{}", i)).collect();
+        let on_candidate = users.iter().filter(|user| serves_v2(&set, user)).count();
+
+        assert!((250..350).contains(&on_candidate), "{}", on_candidate);
+        assert_eq!(serves_v2(&set, "user-7"), serves_v2(&set, "user-7"));
+    }
+
+    #[test]
+    fn test_raising_percent_keeps_sticky_users() {
+        let mut set = set_with_rollout(20);
+        let users: Vec<String> = (0..200).map(|i| format!("user-{}", i)).collect();
+        let early: Vec<&String> = users.iter().filter(|user| serves_v2(&set, user)).collect();
+
+        set.set_rollout_percent("greeting", 60);
+        assert!(early.iter().all(|user| serves_v2(&set, user)));
+
+        assert_eq!(
+            set.set_rollout_percent("greeting", 150).unwrap().percent(),
+            100
+        );
+        assert!(users.iter().all(|user| serves_v2(&set, user)));
+    }
+
+    #[test]
+    fn test_rollback_and_promote() {
+        let mut set = set_with_rollout(100);
+        assert!(serves_v2(&set, "ada"));
+
+        let rolled_back = set.rollback("greeting").unwrap();
+        assert_eq!(rolled_back.percent(), 100);
+        assert!(!serves_v2(&set, "ada"));
+        assert!(set.rollout("greeting").is_none());
+
+        set.start_rollout("greeting", prompt("v2"), 0).unwrap();
+        assert!(!serves_v2(&set, "ada"));
+        set.promote("greeting");
+        assert!(serves_v2(&set, "ada"));
+        assert!(set.rollout("greeting").is_none());
+    }
+
+    #[test]
+    fn test_rollout_requires_known_prompt() {
+        let mut set = PromptSet::new();
+        let result = set.start_rollout("missing", prompt("v2"), 10);
+        assert!(matches!(result, Err(TemplateError::UnknownPrompt(name)) if name == "missing"));
+        assert!(set.resolve("missing", "ada").is_none());
+    }
+}
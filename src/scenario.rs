@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use crate::{ChatTemplate, MessageLike, Role, Templatable, Template, TemplateError};
+
+pub const AGENT_VARIABLE: &str = "agent";
+pub const PARTICIPANTS_VARIABLE: &str = "participants";
+
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    world: BTreeMap<String, String>,
+    participants: BTreeMap<String, Template>,
+    shared: Vec<MessageLike>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_world(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.world.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_participant(mut self, name: impl Into<String>, system: Template) -> Self {
+        self.participants.insert(name.into(), system);
+        self
+    }
+
+    pub fn with_shared(mut self, message: MessageLike) -> Self {
+        self.shared.push(message);
+        self
+    }
+
+    pub fn set_world(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.world.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn world(&self) -> &BTreeMap<String, String> {
+        &self.world
+    }
+
+    pub fn participants(&self) -> Vec<&str> {
+        self.participants.keys().map(String::as_str).collect()
+    }
+
+    pub fn agent(&self, name: &str) -> Result<ChatTemplate, TemplateError> {
+        let system = self.participants.get(name).ok_or_else(|| {
+            TemplateError::MalformedTemplate(format!("Unknown participant '{}'", name))
+        })?;
+
+        let mut messages = Vec::with_capacity(self.shared.len() + 1);
+        messages.push(MessageLike::role_prompt_template(
+            Role::System,
+            self.bind(system, name),
+        ));
+        for message in &self.shared {
+            messages.push(match message {
+                MessageLike::RolePromptTemplate(role, template) => {
+                    MessageLike::role_prompt_template(*role, self.bind(template, name))
+                }
+                other => other.clone(),
+            });
+        }
+
+        Ok(ChatTemplate {
+            messages,
+            ..Default::default()
+        })
+    }
+
+    pub fn agents(&self) -> Result<BTreeMap<String, ChatTemplate>, TemplateError> {
+        self.participants
+            .keys()
+            .map(|name| Ok((name.clone(), self.agent(name)?)))
+            .collect()
+    }
+
+    fn bind(&self, template: &Template, agent: &str) -> Template {
+        let participants = self.participants().join(", ");
+        let builtins = [
+            (AGENT_VARIABLE, agent),
+            (PARTICIPANTS_VARIABLE, participants.as_str()),
+        ];
+        let used = template.input_variables();
+
+        builtins
+            .into_iter()
+            .chain(
+                self.world
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            )
+            .filter(|(name, _)| used.iter().any(|var| var == name))
+            .fold(template.clone(), |bound, (name, value)| {
+                bound.with_partial(name, value)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::vars;
+
+    fn scenario() -> Scenario {
+        Scenario::new()
+            .with_world("setting", "a used car lot")
+            .with_world("budget", "$8,000")
+            .with_participant(
+                "buyer",
+                Template::new("You are {agent}, shopping at {setting} with {budget}.").unwrap(),
+            )
+            .with_participant(
+                "seller",
+                Template::new("You are {agent} at {setting}. Never go below {floor}.").unwrap(),
+            )
+            .with_shared(MessageLike::role_prompt_template(
+                Role::Human,
+                Template::new("Present: {participants}. Opening line: {opening}").unwrap(),
+            ))
+    }
+
+    #[test]
+    fn test_agents_share_world_variables() {
+        let scenario = scenario();
+        assert_eq!(scenario.participants(), vec!["buyer", "seller"]);
+
+        let buyer = scenario
+            .agent("buyer")
+            .unwrap()
+            .format_messages(&vars!(opening = "Hello."))
+            .unwrap();
+        assert_eq!(
+            buyer[0].content(),
+            "You are buyer, shopping at a used car lot with $8,000."
+        );
+        assert_eq!(
+            buyer[1].content(),
+            "Present: buyer, seller. Opening line: Hello."
+        );
+
+        let seller = scenario.agent("seller").unwrap();
+        let required = seller.input_variables().required;
+        assert_eq!(
+            required.iter().map(String::as_str).collect::<Vec<_>>(),
+            vec!["floor", "opening"]
+        );
+    }
+
+    #[test]
+    fn test_world_changes_reach_every_agent() {
+        let mut scenario = scenario();
+        scenario.set_world("setting", "an auction");
+
+        let agents = scenario.agents().unwrap();
+        assert_eq!(agents.len(), 2);
+        let seller = agents["seller"]
+            .format_messages(&vars!(floor = "$7,500", opening = "Hi."))
+            .unwrap();
+        assert_eq!(
+            seller[0].content(),
+            "You are seller at an auction. Never go below $7,500."
+        );
+    }
+
+    #[test]
+    fn test_unknown_participant() {
+        assert!(matches!(
+            scenario().agent("auctioneer"),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+#[cfg(feature = "mustache")]
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::{
+    braces::{scan, BraceKind},
+    mustache_expr::{parse_mustache_expr, MustacheTag},
+    Templatable, Template, TemplateFormat,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub variables: Vec<String>,
+    pub inverted: bool,
+    pub has_else: bool,
+    pub depth: usize,
+    pub span: Range<usize>,
+}
+
+impl Template {
+    pub fn sections(&self) -> Vec<Section> {
+        if self.template_format() != TemplateFormat::Mustache {
+            return Vec::new();
+        }
+
+        let source = self.template();
+        let mut open: Vec<Section> = Vec::new();
+        let mut sections = Vec::new();
+
+        for token in scan(source) {
+            if token.kind != BraceKind::Double {
+                continue;
+            }
+            let inner = token.inner(source);
+            let Some(expr) = parse_mustache_expr(inner) else {
+                continue;
+            };
+
+            match expr.tag {
+                MustacheTag::Open => open.push(Section {
+                    name: expr.name.to_string(),
+                    variables: expr.variables.iter().map(|v| v.to_string()).collect(),
+                    inverted: inner.trim_start().starts_with('^'),
+                    has_else: false,
+                    depth: open.len(),
+                    span: token.span.start..token.span.end,
+                }),
+                MustacheTag::Else => {
+                    if let Some(section) = open.last_mut() {
+                        section.has_else = true;
+                    }
+                }
+                MustacheTag::Close => {
+                    if let Some(mut section) = open.pop() {
+                        section.span.end = token.span.end;
+                        sections.push(section);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        sections.sort_by_key(|section| section.span.start);
+        sections
+    }
+}
+
+#[cfg(feature = "mustache")]
+pub(crate) fn to_handlebars(source: &str) -> Cow<'_, str> {
+    let mut result = String::with_capacity(source.len());
+    let mut inverted = Vec::new();
+    let mut last = 0;
+
+    for token in scan(source) {
+        if token.kind != BraceKind::Double {
+            continue;
+        }
+        let inner = token.inner(source);
+        let replacement = match parse_mustache_expr(inner) {
+            Some(expr) if expr.tag == MustacheTag::Open => {
+                let is_inverted = inner.trim_start().starts_with('^');
+                inverted.push(is_inverted);
+                is_inverted.then(|| format!("{{{{#unless {}}}}}", expr.name))
+            }
+            Some(expr) if expr.tag == MustacheTag::Close => inverted
+                .pop()
+                .unwrap_or_default()
+                .then(|| "{{/unless}}".to_string()),
+            _ => None,
+        };
+
+        if let Some(replacement) = replacement {
+            result.push_str(&source[last..token.span.start]);
+            result.push_str(&replacement);
+            last = token.span.end;
+        }
+    }
+
+    if last == 0 {
+        return Cow::Borrowed(source);
+    }
+    result.push_str(&source[last..]);
+    Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections() {
+        let template = Template::new(
+            "{{#if premium}}Priority{{else}}Standard{{/if}} \
+             {{#each orders as |order|}}{{order.id}}{{#if order.late}}!{{/if}}{{/each}}\
+             {{^notes}}No notes.{{/notes}}",
+        )
+        .unwrap();
+        assert_eq!(template.template_format(), TemplateFormat::Mustache);
+        assert_eq!(
+            template.input_variables(),
+            vec!["premium", "orders", "notes"]
+        );
+
+        let sections = template.sections();
+        let summary: Vec<_> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.inverted, s.has_else, s.depth))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("if", false, true, 0),
+                ("each", false, false, 0),
+                ("if", false, false, 1),
+                ("notes", true, false, 0),
+            ]
+        );
+        assert_eq!(sections[0].variables, vec!["premium"]);
+        assert_eq!(
+            &template.template()[sections[3].span.clone()],
+            "{{^notes}}No notes.{{/notes}}"
+        );
+        assert!(Template::new("{name}").unwrap().sections().is_empty());
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_render_sections() {
+        use crate::{var_values, vars};
+
+        let template = Template::new(
+            "{{#if premium}}Priority{{else}}Standard{{/if}} support.\
+             {{^notes}} No notes.{{/notes}}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            template
+                .format(&vars!(premium = "yes", notes = ""))
+                .unwrap(),
+            "Priority support. No notes."
+        );
+        assert_eq!(
+            template
+                .format(&vars!(premium = "", notes = "call back"))
+                .unwrap(),
+            "Standard support."
+        );
+
+        let orders = Template::new(
+            "{{#each orders as |order|}}#{{order.id}}{{#if order.late}} (late){{/if}}\n{{/each}}",
+        )
+        .unwrap();
+        let values = var_values!(
+            orders = serde_json::json!([
+                { "id": 1, "late": false },
+                { "id": 2, "late": true }
+            ])
+        );
+        assert_eq!(orders.format_values(&values).unwrap(), "#1\n#2 (late)\n");
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_to_handlebars() {
+        assert!(matches!(
+            to_handlebars("{{#if a}}x{{/if}}"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            to_handlebars("{{#a}}{{^b}}x{{/b}}{{/a}}"),
+            "{{#a}}{{#unless b}}x{{/unless}}{{/a}}"
+        );
+    }
+}
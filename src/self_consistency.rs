@@ -0,0 +1,164 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Formattable, Mutant, Mutation, TemplateError};
+
+pub const BASE_VARIANT: &str = "base";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampledRender {
+    pub variant_id: String,
+    pub output: String,
+}
+
+pub struct SelfConsistency<T> {
+    strategies: Vec<Box<dyn Mutation<T> + Send + Sync>>,
+    include_base: bool,
+    max_variants: Option<usize>,
+}
+
+impl<T> fmt::Debug for SelfConsistency<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelfConsistency")
+            .field("strategies", &self.strategies.len())
+            .field("include_base", &self.include_base)
+            .field("max_variants", &self.max_variants)
+            .finish()
+    }
+}
+
+impl<T> Default for SelfConsistency<T> {
+    fn default() -> Self {
+        Self {
+            strategies: Vec::new(),
+            include_base: true,
+            max_variants: None,
+        }
+    }
+}
+
+impl<T: Formattable + Clone> SelfConsistency<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_strategy(mut self, strategy: impl Mutation<T> + Send + Sync + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    pub fn without_base(mut self) -> Self {
+        self.include_base = false;
+        self
+    }
+
+    pub fn with_max_variants(mut self, max: usize) -> Self {
+        self.max_variants = Some(max);
+        self
+    }
+
+    pub fn variants(&self, base: &T) -> Result<Vec<Mutant<T>>, TemplateError> {
+        let mut variants = Vec::new();
+        if self.include_base {
+            variants.push(Mutant::new(BASE_VARIANT, base.clone()));
+        }
+        for strategy in &self.strategies {
+            variants.extend(strategy.mutate(base)?);
+        }
+
+        if let Some(max) = self.max_variants {
+            variants.truncate(max);
+        }
+        Ok(variants)
+    }
+
+    pub fn expand(
+        &self,
+        base: &T,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<Vec<SampledRender>, TemplateError> {
+        self.variants(base)?
+            .into_iter()
+            .map(|variant| {
+                Ok(SampledRender {
+                    output: variant.value.format(variables)?,
+                    variant_id: variant.label,
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn majority_vote<'a, I>(answers: I) -> Option<(&'a str, usize)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for answer in answers {
+        let answer = answer.trim();
+        match counts.iter_mut().find(|(seen, _)| *seen == answer) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((answer, 1)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .max_by(|(i, (_, a)), (j, (_, b))| a.cmp(b).then(j.cmp(i)))
+        .map(|(_, winner)| winner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mutate::{ReorderExamples, SwapSynonyms},
+        vars, FewShotTemplate, Template,
+    };
+
+    #[test]
+    fn test_expand_tags_each_render_with_variant_id() {
+        let base = Template::new("Give a short answer: {question}").unwrap();
+        let expander = SelfConsistency::new()
+            .with_strategy(SwapSynonyms::new().synonyms("short", ["brief", "concise"]));
+
+        let renders = expander
+            .expand(&base, &vars!(question = "What is 6 x 7?"))
+            .unwrap();
+        let ids: Vec<&str> = renders.iter().map(|r| r.variant_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["base", "synonym:short->brief", "synonym:short->concise"]
+        );
+        assert_eq!(renders[1].output, "Give a brief answer: What is 6 x 7?");
+    }
+
+    #[test]
+    fn test_expand_few_shot_orders() {
+        let examples = vec![
+            Template::new("Q: 1+1 A: 2").unwrap(),
+            Template::new("Q: 2+2 A: 4").unwrap(),
+            Template::new("Q: 3+3 A: 6").unwrap(),
+        ];
+        let base = FewShotTemplate::new(examples);
+        let expander = SelfConsistency::new()
+            .without_base()
+            .with_strategy(ReorderExamples::new(3, 7))
+            .with_max_variants(2);
+
+        let renders = expander.expand(&base, &vars!()).unwrap();
+        assert_eq!(renders.len(), 2);
+        assert!(renders.iter().all(|r| r.variant_id.starts_with("reorder:")));
+        assert_ne!(renders[0].output, renders[1].output);
+    }
+
+    #[test]
+    fn test_majority_vote() {
+        assert_eq!(
+            majority_vote(["42", " 41", "42 ", "41", "42"]),
+            Some(("42", 3))
+        );
+        assert_eq!(majority_vote(["a", "b"]), Some(("a", 1)));
+        assert_eq!(majority_vote([]), None);
+    }
+}
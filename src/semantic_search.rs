@@ -0,0 +1,220 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use messageforge::BaseMessage;
+
+use crate::{lineage::fingerprint, ChatTemplate, MessageLike, Templatable};
+
+pub trait Embedder: Send + Sync {
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+impl<F> Embedder for F
+where
+    F: Fn(&str) -> Vec<f32> + Send + Sync,
+{
+    fn embed(&self, text: &str) -> Vec<f32> {
+        self(text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticMatch {
+    pub name: String,
+    pub score: f32,
+}
+
+#[derive(Clone)]
+pub(crate) struct EmbeddingIndex {
+    embedder: Arc<dyn Embedder>,
+    cache: Arc<RwLock<HashMap<String, Arc<Vec<f32>>>>>,
+}
+
+impl fmt::Debug for EmbeddingIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddingIndex")
+            .field("cached", &self.cached())
+            .finish_non_exhaustive()
+    }
+}
+
+impl EmbeddingIndex {
+    pub(crate) fn new(embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            embedder,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub(crate) fn cached(&self) -> usize {
+        self.cache.read().map_or(0, |cache| cache.len())
+    }
+
+    pub(crate) fn search<'a, I>(&self, prompts: I, query: &str, k: usize) -> Vec<SemanticMatch>
+    where
+        I: IntoIterator<Item = (&'a String, &'a ChatTemplate)>,
+    {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let query = self.embedder.embed(query);
+        let mut matches: Vec<SemanticMatch> = prompts
+            .into_iter()
+            .map(|(name, template)| SemanticMatch {
+                name: name.clone(),
+                score: cosine_similarity(&query, &self.embedding(template)),
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        matches.truncate(k);
+        matches
+    }
+
+    fn embedding(&self, template: &ChatTemplate) -> Arc<Vec<f32>> {
+        let text = embedding_text(template);
+        let key = fingerprint(text.as_bytes());
+
+        if let Some(embedding) = self.cache.read().ok().and_then(|c| c.get(&key).cloned()) {
+            return embedding;
+        }
+
+        let embedding = Arc::new(self.embedder.embed(&text));
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(key, Arc::clone(&embedding));
+        }
+        embedding
+    }
+}
+
+pub(crate) fn embedding_text(template: &ChatTemplate) -> String {
+    template
+        .messages
+        .iter()
+        .filter_map(|message_like| match message_like {
+            MessageLike::BaseMessage(message) => Some(message.content().to_string()),
+            MessageLike::RolePromptTemplate(_, template) => Some(template.template().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, PromptSet,
+        Role::{Human, System},
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const TOPICS: [&str; 3] = ["refund", "weather", "poem"];
+
+    struct KeywordEmbedder {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Embedder for KeywordEmbedder {
+        fn embed(&self, text: &str) -> Vec<f32> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let text = text.to_lowercase();
+            TOPICS
+                .iter()
+                .map(|topic| text.matches(topic).count() as f32)
+                .collect()
+        }
+    }
+
+    fn catalog(calls: &Arc<AtomicUsize>) -> PromptSet {
+        let mut prompts = PromptSet::new();
+        prompts
+            .insert(
+                "billing/refunds",
+                ChatTemplate::from_messages(chats!(
+                    System = "You process refund requests.",
+                    Human = "Refund order {order_id}.",
+                ))
+                .unwrap(),
+            )
+            .insert(
+                "assistant/weather",
+                ChatTemplate::from_messages(chats!(Human = "What is the weather in {city}?"))
+                    .unwrap(),
+            )
+            .insert(
+                "creative/poem",
+                ChatTemplate::from_messages(chats!(Human = "Write a poem about {topic}.")).unwrap(),
+            );
+        prompts.with_embedder(KeywordEmbedder {
+            calls: Arc::clone(calls),
+        })
+    }
+
+    #[test]
+    fn test_search_semantic_ranks_by_meaning() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let prompts = catalog(&calls);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let matches = prompts.search_semantic("I want a refund", 2);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].name, "billing/refunds");
+        assert!((matches[0].score - 1.0).abs() < 1e-6);
+        assert_eq!(matches[1].score, 0.0);
+
+        let matches = prompts.search_semantic("will the weather hold", 1);
+        assert_eq!(matches[0].name, "assistant/weather");
+    }
+
+    #[test]
+    fn test_search_semantic_caches_embeddings() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut prompts = catalog(&calls);
+
+        prompts.search_semantic("refund", 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        prompts.search_semantic("poem", 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+        prompts.insert(
+            "creative/poem",
+            ChatTemplate::from_messages(chats!(Human = "Write a haiku poem.")).unwrap(),
+        );
+        prompts.search_semantic("poem", 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 7);
+    }
+
+    #[test]
+    fn test_search_semantic_without_embedder() {
+        let prompts = PromptSet::new();
+        assert!(prompts.search_semantic("refund", 3).is_empty());
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+use messageforge::{BaseMessage, MessageEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    braces::{scan, BraceKind},
+    conditionals::{is_truthy, Branches},
+    filters::{apply_filters, split_filters},
+    MissingVariables, Templatable, Template, TemplateError, TemplateFormat,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SourceOrigin {
+    Template { span: Range<usize> },
+    Variable { name: String },
+    Literal,
+    FewShot,
+}
+
+pub(crate) type Segments = Vec<(Range<usize>, SourceOrigin)>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMapEntry {
+    pub message: usize,
+    pub range: Range<usize>,
+    pub template_message: usize,
+    pub origin: SourceOrigin,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMap {
+    pub entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    pub fn lookup(&self, message: usize, offset: usize) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.message == message && entry.range.contains(&offset))
+    }
+
+    pub fn variable_ranges(&self, name: &str) -> Vec<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .filter(
+                |entry| matches!(&entry.origin, SourceOrigin::Variable { name: n } if n == name),
+            )
+            .collect()
+    }
+
+    pub(crate) fn push_message(
+        &mut self,
+        message: usize,
+        template_message: usize,
+        segments: Segments,
+    ) {
+        self.entries
+            .extend(segments.into_iter().map(|(range, origin)| SourceMapEntry {
+                message,
+                range,
+                template_message,
+                origin,
+            }));
+    }
+
+    pub(crate) fn remap(
+        self,
+        rendered: &[Arc<MessageEnum>],
+        output: &[Arc<MessageEnum>],
+    ) -> SourceMap {
+        let mut cursor = 0;
+        let targets: Vec<Option<(usize, usize)>> = rendered
+            .iter()
+            .map(|message| {
+                let content = message.content();
+                let found = (cursor..output.len())
+                    .chain(0..cursor)
+                    .find_map(|j| output[j].content().find(content).map(|offset| (j, offset)));
+                if let Some((j, _)) = found {
+                    cursor = j;
+                }
+                found
+            })
+            .collect();
+
+        let entries = self
+            .entries
+            .into_iter()
+            .filter_map(|entry| {
+                let (message, offset) = targets.get(entry.message).copied().flatten()?;
+                Some(SourceMapEntry {
+                    message,
+                    range: entry.range.start + offset..entry.range.end + offset,
+                    ..entry
+                })
+            })
+            .collect();
+
+        SourceMap { entries }
+    }
+}
+
+pub(crate) fn whole_message(content: &str, origin: SourceOrigin) -> Segments {
+    vec![(0..content.len(), origin)]
+}
+
+impl Template {
+    pub(crate) fn format_with_source_map(
+        &self,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<(String, Segments), TemplateError> {
+        let formatted = self.format(variables)?;
+        let variables = &self.merged_variables(variables);
+        let source = self.template();
+
+        if self.template_format() != TemplateFormat::FmtString {
+            let segments = whole_message(
+                &formatted,
+                SourceOrigin::Template {
+                    span: 0..source.len(),
+                },
+            );
+            return Ok((formatted, segments));
+        }
+
+        let mut segments = Vec::new();
+        let mut output = String::with_capacity(formatted.len());
+        let mut branches = Branches::default();
+
+        for token in scan(source) {
+            if token.kind == BraceKind::Single
+                && branches.step(token.inner(source), |var| {
+                    is_truthy(variables.get(var).copied())
+                })
+            {
+                continue;
+            }
+            if !branches.is_rendering() {
+                continue;
+            }
+
+            let start = output.len();
+            let origin = match token.kind {
+                BraceKind::Single => {
+                    let (var, filters) = split_filters(token.inner(source));
+                    let value = variables
+                        .get(var)
+                        .copied()
+                        .or_else(|| self.partial_vars().get(var).map(String::as_str));
+                    match value {
+                        Some(value) if self.substitutes(var) => {
+                            output.push_str(&apply_filters(&filters, value)?);
+                            SourceOrigin::Variable {
+                                name: var.to_string(),
+                            }
+                        }
+                        None if self.substitutes(var)
+                            && self.format_options().missing_vars == MissingVariables::Empty =>
+                        {
+                            output.push_str(&apply_filters(&filters, "")?);
+                            SourceOrigin::Variable {
+                                name: var.to_string(),
+                            }
+                        }
+                        _ => {
+                            output.push_str(token.literal(source));
+                            SourceOrigin::Template { span: token.span }
+                        }
+                    }
+                }
+                _ => {
+                    output.push_str(token.literal(source));
+                    SourceOrigin::Template { span: token.span }
+                }
+            };
+
+            if output.len() > start {
+                segments.push((start..output.len(), origin));
+            }
+        }
+
+        Ok((output, segments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars;
+
+    #[test]
+    fn test_fmtstring_segments() {
+        let template = Template::new("Hi {name}, you owe {amount|trim}.").unwrap();
+        let (output, segments) = template
+            .format_with_source_map(&vars!(name = "Ada", amount = " 5 "))
+            .unwrap();
+
+        assert_eq!(output, "Hi Ada, you owe 5.");
+        let pieces: Vec<(&str, &SourceOrigin)> = segments
+            .iter()
+            .map(|(range, origin)| (&output[range.clone()], origin))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                ("Hi ", &SourceOrigin::Template { span: 0..3 }),
+                (
+                    "Ada",
+                    &SourceOrigin::Variable {
+                        name: "name".into()
+                    }
+                ),
+                (", you owe ", &SourceOrigin::Template { span: 9..19 }),
+                (
+                    "5",
+                    &SourceOrigin::Variable {
+                        name: "amount".into()
+                    }
+                ),
+                (".", &SourceOrigin::Template { span: 32..33 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_and_variable_ranges() {
+        let mut map = SourceMap::default();
+        map.push_message(
+            0,
+            2,
+            vec![
+                (0..3, SourceOrigin::Template { span: 0..3 }),
+                (
+                    3..6,
+                    SourceOrigin::Variable {
+                        name: "name".into(),
+                    },
+                ),
+            ],
+        );
+
+        assert_eq!(map.lookup(0, 4).unwrap().template_message, 2);
+        assert!(map.lookup(1, 0).is_none());
+        assert_eq!(map.variable_ranges("name")[0].range, 3..6);
+    }
+
+    #[test]
+    fn test_chat_template_source_map() {
+        use crate::{
+            chats, ChatTemplate, MessageLike, ModelProfile, Role,
+            Role::{Human, System},
+        };
+
+        let mut template = ChatTemplate::from_messages(chats!(
+            System = "Answer as {persona}.",
+            Human = "{question}",
+        ))
+        .unwrap();
+        template.messages.push(MessageLike::BaseMessage(
+            Role::Ai.to_message("Sure.").unwrap(),
+        ));
+        let variables = vars!(persona = "a pirate", question = "Where is the gold?");
+
+        let output = template.render_with_source_map(&variables).unwrap();
+        let map = output.source_map.unwrap();
+        let entry = map.lookup(0, 12).unwrap();
+        assert_eq!(
+            entry.origin,
+            SourceOrigin::Variable {
+                name: "persona".into()
+            }
+        );
+        assert_eq!(
+            &output.messages[0].content()[entry.range.clone()],
+            "a pirate"
+        );
+        assert_eq!(map.lookup(2, 0).unwrap().origin, SourceOrigin::Literal);
+
+        let merged = template
+            .specialize(ModelProfile::llama())
+            .render_with_source_map(&variables)
+            .unwrap();
+        let content = merged.messages[0].content();
+        let source_map = merged.source_map.unwrap();
+        let entry = source_map.variable_ranges("question")[0];
+        assert_eq!(entry.template_message, 1);
+        assert_eq!(&content[entry.range.clone()], "Where is the gold?");
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use messageforge::BaseMessage;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{assertions::message_role, ChatTemplate, MessageLike, Role, Templatable};
+
+lazy_static! {
+    static ref SUMMARY_SLOT_RE: Regex = Regex::new(r"\{{1,2}[^{}]*\}{1,2}").unwrap();
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatTemplateSummary {
+    pub role_counts: BTreeMap<Role, usize>,
+    pub templated_messages: usize,
+    pub literal_messages: usize,
+    pub placeholders: usize,
+    pub few_shot_prompts: usize,
+    pub static_chars: usize,
+}
+
+impl ChatTemplateSummary {
+    pub fn total_messages(&self) -> usize {
+        self.templated_messages + self.literal_messages
+    }
+}
+
+impl ChatTemplate {
+    pub fn summary(&self) -> ChatTemplateSummary {
+        let mut summary = ChatTemplateSummary::default();
+
+        for message_like in &self.messages {
+            match message_like {
+                MessageLike::BaseMessage(message) => {
+                    summary.literal_messages += 1;
+                    summary.static_chars += message.content().chars().count();
+                    if let Some(role) = message_role(message) {
+                        *summary.role_counts.entry(role).or_default() += 1;
+                    }
+                }
+                MessageLike::RolePromptTemplate(role, template) => {
+                    summary.templated_messages += 1;
+                    summary.static_chars += SUMMARY_SLOT_RE
+                        .replace_all(template.template(), "")
+                        .chars()
+                        .count();
+                    *summary.role_counts.entry(*role).or_default() += 1;
+                }
+                MessageLike::Placeholder(_) => summary.placeholders += 1,
+                MessageLike::FewShotPrompt(few_shot) => {
+                    summary.few_shot_prompts += 1;
+                    if let Ok(examples) = few_shot.format_examples() {
+                        summary.static_chars += examples.chars().count();
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats,
+        Role::{Ai, Human, Placeholder, System},
+    };
+
+    #[test]
+    fn test_summary_counts() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You are helpful.",
+            Placeholder = "{history}",
+            Human = "Hi, I am {name}.",
+            Ai = "Hello!",
+            Human = "{question}",
+        ))
+        .unwrap();
+
+        let summary = template.summary();
+
+        assert_eq!(summary.literal_messages, 2);
+        assert_eq!(summary.templated_messages, 2);
+        assert_eq!(summary.total_messages(), 4);
+        assert_eq!(summary.placeholders, 1);
+        assert_eq!(summary.few_shot_prompts, 0);
+        assert_eq!(
+            summary.role_counts,
+            BTreeMap::from([(Role::System, 1), (Role::Human, 2), (Role::Ai, 1)])
+        );
+        assert_eq!(
+            summary.static_chars,
+            "You are helpful.".len() + "Hi, I am .".len() + "Hello!".len()
+        );
+    }
+
+    #[test]
+    fn test_empty_summary() {
+        let summary = ChatTemplate::default().summary();
+        assert_eq!(summary, ChatTemplateSummary::default());
+    }
+
+    #[test]
+    fn test_summary_serialization() {
+        let template = ChatTemplate::from_messages(chats!(Human = "{{question}}")).unwrap();
+        let json = serde_json::to_value(template.summary()).unwrap();
+
+        assert_eq!(json["role_counts"]["Human"], 1);
+        assert_eq!(json["templated_messages"], 1);
+        assert_eq!(json["static_chars"], 0);
+    }
+}
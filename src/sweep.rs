@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{placeholder::is_valid_identifier, Template, TemplateError};
+
+lazy_static! {
+    static ref SLOT_RE: Regex = Regex::new(r"(?s)\[\[(.*?)\]\]").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Slot(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SweepSlot {
+    pub name: String,
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepVariant {
+    pub label: String,
+    pub choices: Vec<(String, String)>,
+    pub template: Template,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamSweep {
+    segments: Vec<Segment>,
+    slots: Vec<SweepSlot>,
+}
+
+impl ParamSweep {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut slots: Vec<SweepSlot> = Vec::new();
+        let mut last = 0;
+
+        for cap in SLOT_RE.captures_iter(source) {
+            let whole = cap.get(0).unwrap();
+            if whole.start() > last {
+                segments.push(Segment::Text(source[last..whole.start()].to_string()));
+            }
+            last = whole.end();
+
+            let body = cap.get(1).unwrap().as_str();
+            let (name, alternatives) = match body.split_once('=') {
+                Some((name, rest)) if is_valid_identifier(name) => (name.to_string(), rest),
+                _ => (format!("slot{}", slots.len()), body),
+            };
+
+            if slots.iter().any(|slot| slot.name == name) {
+                return Err(TemplateError::MalformedTemplate(format!(
+                    "Duplicate sweep slot '{}'.",
+                    name
+                )));
+            }
+
+            segments.push(Segment::Slot(slots.len()));
+            slots.push(SweepSlot {
+                name,
+                options: alternatives.split('|').map(str::to_string).collect(),
+            });
+        }
+
+        if last < source.len() {
+            segments.push(Segment::Text(source[last..].to_string()));
+        }
+
+        Ok(Self { segments, slots })
+    }
+
+    pub fn slots(&self) -> &[SweepSlot] {
+        &self.slots
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().fold(1usize, |total, slot| {
+            total.saturating_mul(slot.options.len())
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn variant(&self, index: usize) -> Result<SweepVariant, TemplateError> {
+        if index >= self.len() {
+            return Err(TemplateError::MalformedTemplate(format!(
+                "Sweep variant {} is out of range ({} variants).",
+                index,
+                self.len()
+            )));
+        }
+
+        let mut remainder = index;
+        let mut picks = vec![0; self.slots.len()];
+        for (pick, slot) in picks.iter_mut().zip(&self.slots).rev() {
+            *pick = remainder % slot.options.len();
+            remainder /= slot.options.len();
+        }
+
+        let source: String = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                Segment::Slot(slot) => self.slots[*slot].options[picks[*slot]].as_str(),
+            })
+            .collect();
+
+        let label = self
+            .slots
+            .iter()
+            .zip(&picks)
+            .map(|(slot, pick)| format!("{}={}", slot.name, pick))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let choices = self
+            .slots
+            .iter()
+            .zip(&picks)
+            .map(|(slot, pick)| (slot.name.clone(), slot.options[*pick].clone()))
+            .collect();
+
+        Ok(SweepVariant {
+            label,
+            choices,
+            template: Template::new(&source)?,
+        })
+    }
+
+    pub fn variants(&self) -> Result<Vec<SweepVariant>, TemplateError> {
+        (0..self.len()).map(|index| self.variant(index)).collect()
+    }
+
+    pub fn sample(&self, n: usize, seed: u64) -> Result<Vec<SweepVariant>, TemplateError> {
+        let total = self.len();
+        if n >= total {
+            return self.variants();
+        }
+
+        let mut state = seed;
+        let mut seen = HashSet::new();
+        let mut indices = Vec::with_capacity(n);
+        while indices.len() < n {
+            let index = (splitmix64(&mut state) % total as u64) as usize;
+            if seen.insert(index) {
+                indices.push(index);
+            }
+        }
+        indices.sort_unstable();
+
+        indices
+            .into_iter()
+            .map(|index| self.variant(index))
+            .collect()
+    }
+}
+
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vars, Templatable};
+
+    const SOURCE: &str =
+        "[[tone=Please|Kindly]] summarize {doc}[[length=| in one sentence| in three bullets]].";
+
+    #[test]
+    fn test_parse_slots() {
+        let sweep = ParamSweep::parse(SOURCE).unwrap();
+
+        assert_eq!(sweep.slots().len(), 2);
+        assert_eq!(sweep.slots()[0].name, "tone");
+        assert_eq!(sweep.slots()[0].options, vec!["Please", "Kindly"]);
+        assert_eq!(
+            sweep.slots()[1].options,
+            vec!["", " in one sentence", " in three bullets"]
+        );
+        assert_eq!(sweep.len(), 6);
+    }
+
+    #[test]
+    fn test_unnamed_slots_get_positional_names() {
+        let sweep = ParamSweep::parse("[[Hi|Hello]] there, [[x = 1|y]]").unwrap();
+        assert_eq!(sweep.slots()[0].name, "slot0");
+        assert_eq!(sweep.slots()[1].name, "slot1");
+        assert_eq!(sweep.slots()[1].options, vec!["x = 1", "y"]);
+    }
+
+    #[test]
+    fn test_duplicate_slot_names_fail() {
+        let result = ParamSweep::parse("[[a=x|y]] [[a=z|w]]");
+        assert!(matches!(result, Err(TemplateError::MalformedTemplate(_))));
+    }
+
+    #[test]
+    fn test_variants_enumerate_all_combinations() {
+        let sweep = ParamSweep::parse(SOURCE).unwrap();
+        let variants = sweep.variants().unwrap();
+
+        let labels: Vec<&str> = variants.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "tone=0,length=0",
+                "tone=0,length=1",
+                "tone=0,length=2",
+                "tone=1,length=0",
+                "tone=1,length=1",
+                "tone=1,length=2"
+            ]
+        );
+
+        let last = &variants[5];
+        assert_eq!(
+            last.choices,
+            vec![
+                ("tone".to_string(), "Kindly".to_string()),
+                ("length".to_string(), " in three bullets".to_string())
+            ]
+        );
+        assert_eq!(last.template.input_variables(), vec!["doc"]);
+        assert_eq!(
+            last.template.format(&vars!(doc = "the memo")).unwrap(),
+            "Kindly summarize the memo in three bullets."
+        );
+    }
+
+    #[test]
+    fn test_template_without_slots_has_one_variant() {
+        let sweep = ParamSweep::parse("Hello, {name}!").unwrap();
+        let variants = sweep.variants().unwrap();
+
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].label, "");
+        assert_eq!(variants[0].template.template(), "Hello, {name}!");
+    }
+
+    #[test]
+    fn test_sample_is_deterministic_subset() {
+        let sweep = ParamSweep::parse(SOURCE).unwrap();
+
+        let first = sweep.sample(3, 42).unwrap();
+        let second = sweep.sample(3, 42).unwrap();
+        assert_eq!(first.len(), 3);
+
+        let labels = |variants: &[SweepVariant]| {
+            variants.iter().map(|v| v.label.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(labels(&first), labels(&second));
+
+        let unique: HashSet<_> = labels(&first).into_iter().collect();
+        assert_eq!(unique.len(), 3);
+
+        assert_eq!(sweep.sample(100, 1).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_variant_out_of_range() {
+        let sweep = ParamSweep::parse(SOURCE).unwrap();
+        assert!(sweep.variant(6).is_err());
+    }
+}
@@ -1,22 +1,76 @@
-use handlebars::Handlebars;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
+#[cfg(feature = "mustache")]
+use handlebars::{handlebars_helper, Handlebars};
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use crate::braces::{scan, BraceKind};
+use crate::conditionals::{is_truthy, Branches};
+use crate::defaults::{apply_defaults, Defaults};
+#[cfg(feature = "mustache")]
+use crate::expression_helpers::register_expression_helpers;
+use crate::filters::{apply_filters, split_filters};
+use crate::format_options::{FormatOptions, MissingVariables};
 use crate::formatting::{Formattable, Templatable};
+#[cfg(feature = "mustache")]
+use crate::macros::expand_macros;
 use crate::placeholder::extract_variables;
+use crate::prompt_vars::Variables;
+#[cfg(feature = "mustache")]
+use crate::sections::to_handlebars;
 use crate::template_format::{
-    detect_template, merge_vars, validate_template, TemplateError, TemplateFormat,
+    detect_template, detect_template_strict, is_jinja2, merge_vars, resolve_aliases,
+    validate_template, TemplateError, TemplateFormat,
 };
+use crate::var_value::{stringify_values, VarValue};
+
+#[cfg(feature = "jinja")]
+lazy_static::lazy_static! {
+    static ref JINJA: minijinja::Environment<'static> = minijinja::Environment::new();
+}
+
+#[cfg(feature = "jinja")]
+fn jinja_error(e: minijinja::Error) -> TemplateError {
+    TemplateError::MalformedTemplate(format!("Jinja error: {}", e))
+}
+
+fn serialize_sorted<S: Serializer>(
+    partials: &HashMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    partials
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Template {
     template: String,
     template_format: TemplateFormat,
     input_variables: Vec<String>,
+    #[cfg(feature = "mustache")]
     #[serde(skip, default)]
     handlebars: Option<Handlebars<'static>>,
-    #[serde(skip)]
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
     partials: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    aliases: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bound: Vec<String>,
+    #[serde(default, skip_serializing_if = "FormatOptions::is_strict")]
+    format_options: FormatOptions,
+    #[serde(default, skip_serializing_if = "Defaults::is_empty")]
+    defaults: Defaults,
+    #[serde(skip)]
+    unchecked: bool,
+    #[serde(skip)]
+    checked: OnceLock<Box<Template>>,
 }
 
 impl Template {
@@ -31,20 +85,27 @@ impl Template {
         template_format: Option<TemplateFormat>,
         input_variables: Option<Vec<String>>,
     ) -> Result<Self, TemplateError> {
-        validate_template(tmpl)?;
+        let jinja = template_format == Some(TemplateFormat::Jinja2)
+            || (template_format.is_none() && is_jinja2(tmpl));
+        if !jinja {
+            validate_template(tmpl)?;
+        }
 
         let template_format = template_format
             .or_else(|| detect_template(tmpl).ok())
             .ok_or_else(|| {
                 TemplateError::UnsupportedFormat("Unable to detect template format".into())
             })?;
-        let input_variables = input_variables.unwrap_or_else(|| {
-            extract_variables(tmpl)
+        let input_variables = match input_variables {
+            Some(input_variables) => input_variables,
+            None if jinja => Self::jinja_variables(tmpl)?,
+            None => extract_variables(tmpl)
                 .into_iter()
                 .map(|var| var.to_string())
-                .collect()
-        });
+                .collect(),
+        };
 
+        #[cfg(feature = "mustache")]
         let handlebars = if template_format == TemplateFormat::Mustache {
             let handle = Self::initialize_handlebars(tmpl)?;
             Some(handle)
@@ -56,22 +117,136 @@ impl Template {
             template: tmpl.to_string(),
             template_format,
             input_variables,
+            #[cfg(feature = "mustache")]
             handlebars,
             partials: HashMap::new(),
+            aliases: BTreeMap::new(),
+            bound: Vec::new(),
+            format_options: FormatOptions::default(),
+            defaults: Defaults::default(),
+            unchecked: false,
+            checked: OnceLock::new(),
         })
     }
 
+    pub fn from_template_unchecked(tmpl: &str) -> Self {
+        Template {
+            template: tmpl.to_string(),
+            template_format: TemplateFormat::PlainText,
+            input_variables: Vec::new(),
+            #[cfg(feature = "mustache")]
+            handlebars: None,
+            partials: HashMap::new(),
+            aliases: BTreeMap::new(),
+            bound: Vec::new(),
+            format_options: FormatOptions::default(),
+            defaults: Defaults::default(),
+            unchecked: true,
+            checked: OnceLock::new(),
+        }
+    }
+
+    pub fn is_validated(&self) -> bool {
+        !self.unchecked
+    }
+
+    pub fn validate(&mut self) -> Result<&mut Self, TemplateError> {
+        if let Some(validated) = self.checked.take() {
+            *self = *validated;
+        }
+        if self.unchecked {
+            let mut validated = Template::new(&self.template)?;
+            validated.partials = std::mem::take(&mut self.partials);
+            validated.aliases = std::mem::take(&mut self.aliases);
+            validated.bind_variables(std::mem::take(&mut self.bound));
+            validated.format_options = self.format_options;
+            validated.defaults = std::mem::take(&mut self.defaults);
+            *self = validated;
+        }
+        Ok(self)
+    }
+
+    pub(crate) fn prepare(&self) -> Result<(), TemplateError> {
+        if self.unchecked {
+            self.validated()?;
+        }
+        Ok(())
+    }
+
+    fn validated(&self) -> Result<&Template, TemplateError> {
+        if let Some(validated) = self.checked.get() {
+            return Ok(validated);
+        }
+        let mut validated = Template::new(&self.template)?;
+        validated.partials = self.partials.clone();
+        validated.aliases = self.aliases.clone();
+        validated.bind_variables(self.bound.clone());
+        validated.format_options = self.format_options;
+        validated.defaults = self.defaults.clone();
+        Ok(self.checked.get_or_init(|| Box::new(validated)))
+    }
+
+    pub fn new_strict(tmpl: &str) -> Result<Self, TemplateError> {
+        let template_format = detect_template_strict(tmpl)?;
+        Self::new_with_config(tmpl, Some(template_format), None)
+    }
+
     pub fn from_template(tmpl: &str) -> Result<Self, TemplateError> {
         Self::new(tmpl)
     }
 
     pub fn partial(&mut self, var: &str, value: &str) -> &mut Self {
+        self.checked.take();
         self.partials.insert(var.to_string(), value.to_string());
         self
     }
 
+    pub fn with_partial(mut self, var: &str, value: &str) -> Self {
+        self.partials.insert(var.to_string(), value.to_string());
+        self.bind_variables(vec![var.to_string()]);
+        self
+    }
+
+    pub fn with_partials(mut self, variables: &(impl Variables + ?Sized)) -> Self {
+        let variables = variables.as_map();
+        let mut names: Vec<String> = variables.keys().map(|name| name.to_string()).collect();
+        names.sort();
+        for (name, value) in variables.iter() {
+            self.partials.insert(name.to_string(), value.to_string());
+        }
+        self.bind_variables(names);
+        self
+    }
+
+    fn bind_variables(&mut self, names: Vec<String>) {
+        self.checked.take();
+        for name in names {
+            self.input_variables.retain(|var| *var != name);
+            if !self.bound.contains(&name) {
+                self.bound.push(name);
+            }
+        }
+    }
+
     pub fn clear_partials(&mut self) -> &mut Self {
+        self.checked.take();
         self.partials.clear();
+        if !self.bound.is_empty() && !self.unchecked {
+            let bound = std::mem::take(&mut self.bound);
+            let detected = if self.template_format == TemplateFormat::Jinja2 {
+                Self::jinja_variables(&self.template).unwrap_or_default()
+            } else {
+                extract_variables(&self.template)
+                    .into_iter()
+                    .map(|var| var.to_string())
+                    .collect()
+            };
+            self.input_variables = detected
+                .into_iter()
+                .filter(|var| self.input_variables.contains(var) || bound.contains(var))
+                .collect();
+        }
+        self.bound.clear();
         self
     }
 
@@ -79,16 +254,132 @@ impl Template {
         &self.partials
     }
 
+    pub(crate) fn bound_variables(&self) -> &[String] {
+        &self.bound
+    }
+
+    pub(crate) fn map_partials(&mut self, map: impl Fn(&str) -> String) {
+        self.checked.take();
+        for value in self.partials.values_mut() {
+            *value = map(value);
+        }
+    }
+
+    pub(crate) fn with_source(&self, source: &str) -> Result<Template, TemplateError> {
+        let mut template = if self.unchecked {
+            Template::from_template_unchecked(source)
+        } else {
+            Template::new_with_config(
+                source,
+                Some(self.template_format.clone()),
+                Some(self.input_variables.clone()),
+            )?
+        };
+        template.partials = self.partials.clone();
+        template.aliases = self.aliases.clone();
+        template.bound = self.bound.clone();
+        template.format_options = self.format_options;
+        template.defaults = self.defaults.clone();
+        Ok(template)
+    }
+
+    pub fn with_alias(mut self, alias: &str, canonical: &str) -> Self {
+        self.checked.take();
+        self.aliases
+            .insert(alias.to_string(), canonical.to_string());
+        self
+    }
+
+    pub fn aliases(&self) -> &BTreeMap<String, String> {
+        &self.aliases
+    }
+
+    pub fn with_format_options(mut self, options: FormatOptions) -> Self {
+        if let Some(checked) = self.checked.get_mut() {
+            checked.format_options = options;
+        }
+        self.format_options = options;
+        self
+    }
+
+    pub fn format_options(&self) -> FormatOptions {
+        self.format_options
+    }
+
+    pub fn set_defaults(&mut self, defaults: Defaults) -> &mut Self {
+        self.checked.take();
+        self.defaults = defaults;
+        self
+    }
+
+    pub fn with_defaults(mut self, defaults: Defaults) -> Self {
+        self.set_defaults(defaults);
+        self
+    }
+
+    pub fn defaults(&self) -> &Defaults {
+        &self.defaults
+    }
+
+    pub(crate) fn layer_defaults(&mut self, fallback: &Defaults) {
+        self.checked.take();
+        self.defaults = self.defaults.or(fallback);
+    }
+
+    pub(crate) fn merged_variables<'a>(
+        &'a self,
+        variables: &HashMap<&'a str, &'a str>,
+    ) -> HashMap<&'a str, &'a str> {
+        let mut merged = merge_vars(&self.partials, variables);
+        resolve_aliases(&self.aliases, &mut merged);
+        apply_defaults(&self.defaults, &mut merged);
+        merged
+    }
+
+    #[cfg(feature = "mustache")]
     fn initialize_handlebars(tmpl: &str) -> Result<Handlebars<'static>, TemplateError> {
+        handlebars_helper!(upper: |value: str| value.to_uppercase());
+        handlebars_helper!(lower: |value: str| value.to_lowercase());
+        handlebars_helper!(trim: |value: str| value.trim().to_string());
+
         let mut handlebars = Handlebars::new();
+        handlebars.register_helper("upper", Box::new(upper));
+        handlebars.register_helper("lower", Box::new(lower));
+        handlebars.register_helper("trim", Box::new(trim));
+        register_expression_helpers(&mut handlebars);
         handlebars
-            .register_template_string(Self::MUSTACHE_TEMPLATE, tmpl)
+            .register_template_string(Self::MUSTACHE_TEMPLATE, expand_macros(&to_handlebars(tmpl)))
             .map_err(|e| {
                 TemplateError::MalformedTemplate(format!("Failed to register template: {}", e))
             })?;
         Ok(handlebars)
     }
 
+    #[cfg(feature = "jinja")]
+    fn jinja_variables(tmpl: &str) -> Result<Vec<String>, TemplateError> {
+        let template = JINJA.template_from_str(tmpl).map_err(jinja_error)?;
+        let mut variables: Vec<String> = template.undeclared_variables(false).into_iter().collect();
+        variables.sort();
+        Ok(variables)
+    }
+
+    #[cfg(not(feature = "jinja"))]
+    fn jinja_variables(_tmpl: &str) -> Result<Vec<String>, TemplateError> {
+        Err(TemplateError::UnsupportedFormat(
+            "Jinja2 templates require the `jinja` feature".to_string(),
+        ))
+    }
+
+    pub(crate) fn substitutes(&self, name: &str) -> bool {
+        if self.bound.iter().any(|v| v == name) {
+            return true;
+        }
+        if self.unchecked {
+            return self.input_variables().iter().any(|v| v == name);
+        }
+        self.input_variables.iter().any(|v| v == name)
+    }
+
     fn validate_variables(
         &self,
         variables: &std::collections::HashMap<&str, &str>,
@@ -107,44 +398,236 @@ impl Template {
         Ok(())
     }
 
-    fn format_fmtstring(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError> {
-        let mut result = self.template.clone();
-
+    fn fill_missing<'a>(
+        &'a self,
+        variables: &HashMap<&'a str, &'a str>,
+        missing_vars: MissingVariables,
+        (open, close): (&str, &str),
+    ) -> HashMap<&'a str, Cow<'a, str>> {
+        let mut filled: HashMap<&str, Cow<str>> = variables
+            .iter()
+            .map(|(name, value)| (*name, Cow::Borrowed(*value)))
+            .collect();
         for var in &self.input_variables {
-            let placeholder = format!("{{{}}}", var);
+            filled
+                .entry(var.as_str())
+                .or_insert_with(|| match missing_vars {
+                    MissingVariables::KeepPlaceholder => {
+                        Cow::Owned(format!("{}{}{}", open, var, close))
+                    }
+                    _ => Cow::Borrowed(""),
+                });
+        }
+        filled
+    }
 
-            if let Some(value) = variables.get(var.as_str()) {
-                result = result.replace(&placeholder, value);
-            } else {
-                return Err(TemplateError::MissingVariable(var.clone()));
+    fn format_fmtstring(
+        &self,
+        variables: &HashMap<&str, &str>,
+        missing_vars: MissingVariables,
+    ) -> Result<String, TemplateError> {
+        let mut result = String::with_capacity(self.template.len());
+        let mut branches = Branches::default();
+
+        for token in scan(&self.template) {
+            if token.kind == BraceKind::Single
+                && branches.step(token.inner(&self.template), |var| {
+                    is_truthy(variables.get(var).copied())
+                })
+            {
+                continue;
+            }
+            if !branches.is_rendering() {
+                continue;
+            }
+
+            let (var, filters) = match token.kind {
+                BraceKind::Single => split_filters(token.inner(&self.template)),
+                _ => {
+                    result.push_str(token.literal(&self.template));
+                    continue;
+                }
+            };
+
+            if !self.substitutes(var) {
+                result.push_str(token.literal(&self.template));
+                continue;
+            }
+
+            match (variables.get(var), missing_vars) {
+                (Some(value), _) => result.push_str(&apply_filters(&filters, value)?),
+                (None, MissingVariables::Error) => {
+                    return Err(TemplateError::MissingVariable(var.to_string()))
+                }
+                (None, MissingVariables::Empty) => result.push_str(&apply_filters(&filters, "")?),
+                (None, MissingVariables::KeepPlaceholder) => {
+                    result.push_str(token.literal(&self.template))
+                }
             }
         }
 
         Ok(result)
     }
 
-    fn format_mustache(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError> {
-        match &self.handlebars {
-            None => Err(TemplateError::UnsupportedFormat(
-                "Handlebars not initialized".to_string(),
+    pub fn format(&self, variables: &(impl Variables + ?Sized)) -> Result<String, TemplateError> {
+        self.format_with_options(variables, self.format_options)
+    }
+
+    pub fn format_as(
+        &self,
+        template_format: TemplateFormat,
+        variables: &(impl Variables + ?Sized),
+    ) -> Result<String, TemplateError> {
+        self.render_as(template_format, &variables.as_map(), self.format_options)
+    }
+
+    pub fn format_with_options(
+        &self,
+        variables: &(impl Variables + ?Sized),
+        options: FormatOptions,
+    ) -> Result<String, TemplateError> {
+        if self.unchecked {
+            return self.validated()?.format_with_options(variables, options);
+        }
+        self.render_as(self.template_format.clone(), &variables.as_map(), options)
+    }
+
+    fn render_as(
+        &self,
+        template_format: TemplateFormat,
+        variables: &HashMap<&str, &str>,
+        options: FormatOptions,
+    ) -> Result<String, TemplateError> {
+        if self.unchecked {
+            return self
+                .validated()?
+                .render_as(template_format, variables, options);
+        }
+
+        let merged_variables = self.merged_variables(variables);
+        let missing_vars = options.missing_vars;
+        if template_format != TemplateFormat::PlainText && options.is_strict() {
+            self.validate_variables(&merged_variables)?;
+        }
+
+        match template_format {
+            TemplateFormat::PlainText => Ok(self.template.clone()),
+            TemplateFormat::FmtString => self.format_fmtstring(&merged_variables, missing_vars),
+            TemplateFormat::Mustache if options.is_strict() => {
+                self.format_mustache(&merged_variables)
+            }
+            TemplateFormat::Mustache => self.format_mustache(&self.fill_missing(
+                &merged_variables,
+                missing_vars,
+                ("{{", "}}"),
             )),
+            TemplateFormat::Jinja2 if options.is_strict() => self.format_jinja(&merged_variables),
+            TemplateFormat::Jinja2 => self.format_jinja(&self.fill_missing(
+                &merged_variables,
+                missing_vars,
+                ("{{ ", " }}"),
+            )),
+        }
+    }
+
+    pub fn format_values(&self, values: &HashMap<&str, VarValue>) -> Result<String, TemplateError> {
+        if self.unchecked {
+            return self.validated()?.format_values(values);
+        }
+
+        let strings = stringify_values(values);
+        let strings: HashMap<&str, &str> = strings
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+
+        if !matches!(
+            self.template_format,
+            TemplateFormat::Mustache | TemplateFormat::Jinja2
+        ) {
+            return self.format(&strings);
+        }
+
+        let merged_variables = self.merged_variables(&strings);
+        if self.format_options.is_strict() {
+            self.validate_variables(&merged_variables)?;
+        }
+        let mut data: HashMap<&str, VarValue> = self
+            .partials
+            .iter()
+            .map(|(name, value)| (name.as_str(), VarValue::from(value)))
+            .chain(values.iter().map(|(name, value)| (*name, value.clone())))
+            .collect();
+        for (alias, canonical) in &self.aliases {
+            if !data.contains_key(canonical.as_str()) {
+                if let Some(value) = values.get(alias.as_str()) {
+                    data.insert(canonical, value.clone());
+                }
+            }
+        }
+        for (name, value) in self.defaults.iter() {
+            data.entry(name).or_insert_with(|| VarValue::from(value));
+        }
+        if !self.format_options.is_strict() {
+            let delimiters = match self.template_format {
+                TemplateFormat::Mustache => ("{{", "}}"),
+                _ => ("{{ ", " }}"),
+            };
+            let filled = self.fill_missing(
+                &merged_variables,
+                self.format_options.missing_vars,
+                delimiters,
+            );
+            for (name, value) in filled {
+                if !merged_variables.contains_key(name) {
+                    data.insert(name, VarValue::from(value.as_ref()));
+                }
+            }
+        }
+
+        match self.template_format {
+            TemplateFormat::Mustache => self.format_mustache(&data),
+            _ => self.format_jinja(&data),
+        }
+    }
+
+    #[cfg(feature = "mustache")]
+    fn format_mustache<T: Serialize>(&self, variables: &T) -> Result<String, TemplateError> {
+        match &self.handlebars {
+            None => Self::initialize_handlebars(&self.template)?
+                .render(Self::MUSTACHE_TEMPLATE, variables)
+                .map_err(TemplateError::from),
             Some(handlebars) => handlebars
                 .render(Self::MUSTACHE_TEMPLATE, variables)
-                .map_err(TemplateError::RuntimeError),
+                .map_err(TemplateError::from),
         }
     }
+
+    #[cfg(not(feature = "mustache"))]
+    fn format_mustache<T: Serialize>(&self, _variables: &T) -> Result<String, TemplateError> {
+        Err(TemplateError::UnsupportedFormat(
+            "Mustache rendering requires the `mustache` feature".to_string(),
+        ))
+    }
+
+    #[cfg(feature = "jinja")]
+    fn format_jinja<T: Serialize>(&self, variables: &T) -> Result<String, TemplateError> {
+        JINJA
+            .render_str(&self.template, variables)
+            .map_err(jinja_error)
+    }
+
+    #[cfg(not(feature = "jinja"))]
+    fn format_jinja<T: Serialize>(&self, _variables: &T) -> Result<String, TemplateError> {
+        Err(TemplateError::UnsupportedFormat(
+            "Jinja2 rendering requires the `jinja` feature".to_string(),
+        ))
+    }
 }
 
 impl Formattable for Template {
-    fn format(&self, variables: &HashMap<&str, &str>) -> Result<String, TemplateError> {
-        let merged_variables = merge_vars(&self.partials, variables);
-        self.validate_variables(&merged_variables)?;
-
-        match self.template_format {
-            TemplateFormat::FmtString => self.format_fmtstring(&merged_variables),
-            TemplateFormat::Mustache => self.format_mustache(&merged_variables),
-            TemplateFormat::PlainText => Ok(self.template.clone()),
-        }
+    fn format(&self, variables: &dyn Variables) -> Result<String, TemplateError> {
+        Template::format(self, variables)
     }
 }
 
@@ -154,10 +637,25 @@ impl Templatable for Template {
     }
 
     fn template_format(&self) -> TemplateFormat {
+        if self.unchecked {
+            return detect_template(&self.template).unwrap_or(TemplateFormat::PlainText);
+        }
         self.template_format.clone()
     }
 
     fn input_variables(&self) -> Vec<String> {
+        if self.unchecked && is_jinja2(&self.template) {
+            let mut variables = Self::jinja_variables(&self.template).unwrap_or_default();
+            variables.retain(|var| !self.bound.contains(var));
+            return variables;
+        }
+        if self.unchecked {
+            return extract_variables(&self.template)
+                .into_iter()
+                .map(|var| var.to_string())
+                .filter(|var| !self.bound.contains(var))
+                .collect();
+        }
         self.input_variables.clone()
     }
 }
@@ -173,7 +671,7 @@ impl TryFrom<String> for Template {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::vars;
+    use crate::{var_values, vars};
 
     #[test]
     fn test_prompt_template_new_success() {
@@ -217,6 +715,19 @@ mod tests {
         assert!(matches!(tmpl_err, TemplateError::MalformedTemplate(_)));
     }
 
+    #[test]
+    fn test_prompt_template_new_strict() {
+        let tmpl = Template::new_strict("Tell me a {adjective} joke.").unwrap();
+        assert_eq!(tmpl.template_format(), TemplateFormat::FmtString);
+
+        let tmpl_err =
+            Template::new_strict("Tell me a {adjective} joke about {{content}}.").unwrap_err();
+        assert!(matches!(tmpl_err, TemplateError::AmbiguousFormat(spans) if spans.len() == 2));
+
+        let tmpl_err = Template::new_strict("Format as {json} with {\"key\": 1}.").unwrap_err();
+        assert!(matches!(tmpl_err, TemplateError::AmbiguousFormat(_)));
+    }
+
     #[test]
     fn test_fmtstring_formatting() {
         let tmpl = Template::new("Hello, {name}!").unwrap();
@@ -250,6 +761,55 @@ mod tests {
         assert!(matches!(result, TemplateError::MissingVariable(_)));
     }
 
+    #[test]
+    fn test_fmtstring_formatting_with_xml_filter() {
+        let tmpl = Template::new("Use this:\n{doc|xml:context}\nThanks, {name}.").unwrap();
+        assert_eq!(tmpl.input_variables, vec!["doc", "name"]);
+
+        let variables = &vars!(doc = "a < b & </context>", name = "Ann");
+        let formatted = tmpl.format(variables).unwrap();
+        assert_eq!(
+            formatted,
+            "Use this:\n<context>\na &lt; b &amp; &lt;/context&gt;\n</context>\nThanks, Ann."
+        );
+
+        let err = Template::new("{doc|xml:1bad}").unwrap_err();
+        assert!(matches!(err, TemplateError::MalformedTemplate(_)));
+
+        let tmpl = Template::new("{doc|unknown}").unwrap();
+        let err = tmpl.format(&vars!(doc = "x")).unwrap_err();
+        assert!(matches!(err, TemplateError::UnsupportedFormat(_)));
+    }
+
+    #[test]
+    fn test_fmtstring_formatting_with_code_filter() {
+        let tmpl = Template::new("Review this:\n{snippet|code:rust}").unwrap();
+        let variables = &vars!(snippet = "let s = format!(\"{x}\");");
+        let formatted = tmpl.format(variables).unwrap();
+        assert_eq!(
+            formatted,
+            "Review this:\n```rust\nlet s = format!(\"{x}\");\n```"
+        );
+    }
+
+    #[test]
+    fn test_fmtstring_values_are_not_reformatted() {
+        let tmpl = Template::new("{first} {second}").unwrap();
+        let variables = &vars!(first = "{second}", second = "two");
+        let formatted = tmpl.format(variables).unwrap();
+        assert_eq!(formatted, "{second} two");
+    }
+
+    #[test]
+    fn test_fmtstring_formatting_with_nested_and_escaped_braces() {
+        let tmpl = Template::new(r#"Reply {"answer": {answer}} using \{ and \}"#).unwrap();
+        assert_eq!(tmpl.input_variables(), vec!["answer".to_string()]);
+
+        let formatted = tmpl.format(&vars!(answer = "42")).unwrap();
+        assert_eq!(formatted, r#"Reply {"answer": 42} using { and }"#);
+    }
+
+    #[cfg(feature = "mustache")]
     #[test]
     fn test_format_mustache_success() {
         let tmpl = Template::new("Hello, {{name}}!").unwrap();
@@ -273,6 +833,70 @@ mod tests {
         assert_eq!(result, "Hello, John! Hello, again!");
     }
 
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_format_mustache_helpers_and_sections() {
+        let tmpl =
+            Template::new("{{#if urgent}}URGENT: {{/if}}{{upper name}}, {{lower tone}}").unwrap();
+        assert_eq!(tmpl.template_format(), TemplateFormat::Mustache);
+        assert_eq!(
+            tmpl.input_variables(),
+            vec!["urgent".to_string(), "name".to_string(), "tone".to_string()]
+        );
+
+        let formatted = tmpl
+            .format(&vars!(urgent = "yes", name = "ada", tone = "CALM"))
+            .unwrap();
+        assert_eq!(formatted, "URGENT: ADA, calm");
+    }
+
+    #[test]
+    fn test_format_as_plaintext_returns_source() {
+        let tmpl = Template::new("Hello, {name}!").unwrap();
+        assert_eq!(
+            tmpl.format_as(TemplateFormat::PlainText, &vars!()).unwrap(),
+            "Hello, {name}!"
+        );
+        assert_eq!(
+            tmpl.format_as(TemplateFormat::FmtString, &vars!(name = "Ada"))
+                .unwrap(),
+            "Hello, Ada!"
+        );
+        assert_eq!(tmpl.template_format(), TemplateFormat::FmtString);
+    }
+
+    #[test]
+    fn test_format_as_fmtstring_leaves_double_braces() {
+        let tmpl = Template::new("Hello, {{name}}!").unwrap();
+        assert_eq!(
+            tmpl.format_as(TemplateFormat::FmtString, &vars!(name = "Ada"))
+                .unwrap(),
+            "Hello, {{name}}!"
+        );
+        assert!(matches!(
+            tmpl.format_as(TemplateFormat::FmtString, &vars!()),
+            Err(TemplateError::MissingVariable(_))
+        ));
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_format_as_mustache_on_plain_text() {
+        let tmpl =
+            Template::new_with_config("Hello, {{name}}!", Some(TemplateFormat::PlainText), None)
+                .unwrap();
+        assert_eq!(
+            tmpl.format(&vars!(name = "Ada")).unwrap(),
+            "Hello, {{name}}!"
+        );
+        assert_eq!(
+            tmpl.format_as(TemplateFormat::Mustache, &vars!(name = "Ada"))
+                .unwrap(),
+            "Hello, Ada!"
+        );
+    }
+
+    #[cfg(feature = "mustache")]
     #[test]
     fn test_format_mustache_error() {
         let tmpl_missing_var = Template::new("Hello, {{name}}!").unwrap();
@@ -345,6 +969,94 @@ mod tests {
         assert_eq!(formatted, "Hello, Jill. You are feeling excited.");
     }
 
+    #[test]
+    fn test_with_partial_binds_variables() {
+        let template = Template::new("{persona} on {date}: {question}")
+            .unwrap()
+            .with_partial("persona", "Ada")
+            .with_partial("date", "2024-05-01");
+
+        assert_eq!(template.input_variables(), vec!["question"]);
+        assert_eq!(
+            template.format(&vars!(question = "Hi?")).unwrap(),
+            "Ada on 2024-05-01: Hi?"
+        );
+        assert_eq!(
+            template
+                .format(&vars!(question = "Hi?", persona = "Grace"))
+                .unwrap(),
+            "Grace on 2024-05-01: Hi?"
+        );
+
+        #[cfg(feature = "mustache")]
+        {
+            let mustache = Template::new("{{persona}} says {{line}}")
+                .unwrap()
+                .with_partial("persona", "Ada");
+            assert_eq!(mustache.input_variables(), vec!["line"]);
+            assert_eq!(
+                mustache.format(&vars!(line = "hello")).unwrap(),
+                "Ada says hello"
+            );
+        }
+
+        let unchecked = Template::from_template_unchecked("{persona}: {question}")
+            .with_partial("persona", "Ada");
+        assert_eq!(unchecked.input_variables(), vec!["question"]);
+        assert_eq!(
+            unchecked.format(&vars!(question = "Hi?")).unwrap(),
+            "Ada: Hi?"
+        );
+
+        let mut cleared = template.clone();
+        cleared.clear_partials();
+        assert_eq!(
+            cleared.input_variables(),
+            vec!["persona", "date", "question"]
+        );
+    }
+
+    #[test]
+    fn test_with_partials_binds_subset() {
+        let template = Template::new("{persona} on {date}: {question}")
+            .unwrap()
+            .with_partials(&vars!(persona = "Ada", date = "2024-05-01"));
+
+        assert_eq!(template.input_variables(), vec!["question"]);
+        assert_eq!(
+            template.format(&vars!(question = "Hi?")).unwrap(),
+            "Ada on 2024-05-01: Hi?"
+        );
+    }
+
+    #[test]
+    fn test_partials_survive_json_round_trip() {
+        let template = Template::new("{persona} on {date}: {question}")
+            .unwrap()
+            .with_partial("persona", "Ada")
+            .with_partial("date", "2024-05-01");
+
+        let json = serde_json::to_string(&template).unwrap();
+        let restored: Template = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.input_variables(), vec!["question"]);
+        assert_eq!(
+            restored.format(&vars!(question = "Hi?")).unwrap(),
+            "Ada on 2024-05-01: Hi?"
+        );
+
+        let mut cleared = restored.clone();
+        cleared.clear_partials();
+        assert_eq!(
+            cleared.input_variables(),
+            vec!["persona", "date", "question"]
+        );
+
+        let plain = serde_json::to_value(Template::new("{question}").unwrap()).unwrap();
+        assert!(plain.get("partials").is_none());
+        assert!(plain.get("bound").is_none());
+    }
+
     #[test]
     fn test_clear_partials() {
         let mut template = Template::new("Hello, {name}.").unwrap();
@@ -511,4 +1223,288 @@ mod tests {
             panic!("Expected TemplateError::MalformedTemplate");
         }
     }
+
+    #[test]
+    fn test_unchecked_template_defers_validation() {
+        let mut template = Template::from_template_unchecked("Hello, {name}!");
+        assert!(!template.is_validated());
+        assert_eq!(template.template_format(), TemplateFormat::FmtString);
+        assert_eq!(template.input_variables(), vec!["name".to_string()]);
+        assert!(template.checked.get().is_none());
+        assert_eq!(
+            template.format(&vars!(name = "Ada")).unwrap(),
+            "Hello, Ada!"
+        );
+        let cached: *const Template = &**template.checked.get().unwrap();
+        template.format(&vars!(name = "Bo")).unwrap();
+        assert!(std::ptr::eq(cached, &**template.checked.get().unwrap()));
+
+        template.partial("name", "friend");
+        assert!(template.checked.get().is_none());
+        assert_eq!(template.format(&vars!()).unwrap(), "Hello, friend!");
+        template.validate().unwrap();
+        assert!(template.is_validated());
+        assert_eq!(template.format(&vars!()).unwrap(), "Hello, friend!");
+    }
+
+    #[test]
+    fn test_unchecked_template_reports_errors_on_use() {
+        let mut template = Template::from_template_unchecked("Hello, {name}} and {{other}}");
+        assert!(template.format(&vars!(name = "Ada")).is_err());
+        assert!(template.validate().is_err());
+        assert!(!template.is_validated());
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_jinja2_template() {
+        let tmpl = Template::from_template(
+            "{# support prompt #}Hi {{ name | title }}!\n\
+             {% if vip == \"yes\" %}Priority queue.\n{% endif %}\
+             {% for tag in tags | split(\",\") %}- {{ tag | trim }}\n{% endfor %}",
+        )
+        .unwrap();
+
+        assert_eq!(tmpl.template_format(), TemplateFormat::Jinja2);
+        assert_eq!(tmpl.input_variables(), vec!["name", "tags", "vip"]);
+        assert_eq!(
+            tmpl.format(&vars!(
+                name = "ada lovelace",
+                vip = "yes",
+                tags = "math, engines"
+            ))
+            .unwrap(),
+            "Hi Ada Lovelace!\nPriority queue.\n- math\n- engines\n"
+        );
+        assert_eq!(
+            tmpl.format(&vars!(name = "bob", vip = "no", tags = "x"))
+                .unwrap(),
+            "Hi Bob!\n- x\n"
+        );
+        assert!(matches!(
+            tmpl.format(&vars!(name = "bob")),
+            Err(TemplateError::MissingVariable(_))
+        ));
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_jinja2_detection_and_errors() {
+        let filtered = Template::new("Dear {{ name | upper }}").unwrap();
+        assert_eq!(filtered.template_format(), TemplateFormat::Jinja2);
+        assert_eq!(filtered.format(&vars!(name = "ada")).unwrap(), "Dear ADA");
+
+        let mustache = Template::new("Dear {{name}}").unwrap();
+        assert_eq!(mustache.template_format(), TemplateFormat::Mustache);
+
+        let loop_vars = Template::new("{% for x in items %}{{ x }}{{ sep }}{% endfor %}").unwrap();
+        assert_eq!(loop_vars.input_variables(), vec!["items", "sep"]);
+
+        let err = Template::new("{% if open %}unterminated").unwrap_err();
+        assert!(
+            matches!(err, TemplateError::MalformedTemplate(msg) if msg.starts_with("Jinja error"))
+        );
+
+        let unchecked = Template::from_template_unchecked("{% if a %}{{ b }}{% endif %}");
+        assert_eq!(unchecked.input_variables(), vec!["a", "b"]);
+        assert_eq!(unchecked.template_format(), TemplateFormat::Jinja2);
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_format_values_mustache_sections() {
+        let tmpl = Template::new(
+            "{{#if premium}}Priority. {{/if}}Items for {{name}}:{{#each items}} [{{this}}]{{/each}}",
+        )
+        .unwrap();
+
+        let values = var_values!(name = "Ada", premium = true, items = vec!["tea", "cake"]);
+        assert_eq!(
+            tmpl.format_values(&values).unwrap(),
+            "Priority. Items for Ada: [tea] [cake]"
+        );
+
+        let values = var_values!(name = "Bob", premium = false, items = Vec::<&str>::new());
+        assert_eq!(tmpl.format_values(&values).unwrap(), "Items for Bob:");
+
+        assert!(matches!(
+            tmpl.format_values(&var_values!(name = "Bob")),
+            Err(TemplateError::MissingVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_values_fmtstring_stringifies() {
+        let tmpl = Template::new("{name} is {age}, tags: {tags}").unwrap();
+        let values = var_values!(name = "Ada", age = 36, tags = vec!["math"]);
+        assert_eq!(
+            tmpl.format_values(&values).unwrap(),
+            r#"Ada is 36, tags: ["math"]"#
+        );
+    }
+
+    #[cfg(feature = "jinja")]
+    #[test]
+    fn test_format_values_jinja_structured() {
+        let tmpl = Template::new(
+            "{% for item in order.items %}{{ item.qty }}x {{ item.name }}\n{% endfor %}",
+        )
+        .unwrap();
+        let order = VarValue::from(serde_json::json!({
+            "items": [{ "name": "tea", "qty": 2 }, { "name": "cake", "qty": 1 }]
+        }));
+
+        let mut values = HashMap::new();
+        values.insert("order", order);
+        assert_eq!(tmpl.format_values(&values).unwrap(), "2x tea\n1x cake\n");
+    }
+
+    #[test]
+    fn test_aliases() {
+        let tmpl = Template::new("Hello {user_name}, welcome to {team}.")
+            .unwrap()
+            .with_alias("username", "user_name")
+            .with_alias("legacy_team", "team");
+
+        assert_eq!(
+            tmpl.format(&vars!(user_name = "Ada", team = "Core"))
+                .unwrap(),
+            "Hello Ada, welcome to Core."
+        );
+        assert_eq!(
+            tmpl.format(&vars!(username = "Ada", legacy_team = "Core"))
+                .unwrap(),
+            "Hello Ada, welcome to Core."
+        );
+        assert_eq!(
+            tmpl.format(&vars!(username = "Old", user_name = "New", team = "Core"))
+                .unwrap(),
+            "Hello New, welcome to Core."
+        );
+        assert!(matches!(
+            tmpl.format(&vars!(name = "Ada", team = "Core")),
+            Err(TemplateError::MissingVariable(_))
+        ));
+        assert_eq!(tmpl.input_variables(), vec!["user_name", "team"]);
+
+        let json = serde_json::to_string(&tmpl).unwrap();
+        let restored: Template = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.aliases(), tmpl.aliases());
+        assert!(!serde_json::to_string(&Template::new("{a}").unwrap())
+            .unwrap()
+            .contains("aliases"));
+    }
+
+    #[test]
+    fn test_fmtstring_conditionals() {
+        let tmpl = Template::new(
+            "Hi {name}.{if premium} You have priority support{if agent} from {agent}{end}.{else} Replies take a day.{end}",
+        )
+        .unwrap();
+        assert_eq!(tmpl.template_format(), TemplateFormat::FmtString);
+        assert_eq!(tmpl.input_variables(), vec!["name", "premium", "agent"]);
+
+        assert_eq!(
+            tmpl.format(&vars!(name = "Ada", premium = "yes", agent = "Bo"))
+                .unwrap(),
+            "Hi Ada. You have priority support from Bo."
+        );
+        assert_eq!(
+            tmpl.format(&vars!(name = "Ada", premium = "yes", agent = ""))
+                .unwrap(),
+            "Hi Ada. You have priority support."
+        );
+        assert_eq!(
+            tmpl.format(&vars!(name = "Ada", premium = "", agent = "Bo"))
+                .unwrap(),
+            "Hi Ada. Replies take a day."
+        );
+        assert!(matches!(
+            tmpl.format(&vars!(name = "Ada", agent = "Bo")),
+            Err(TemplateError::MissingVariable(_))
+        ));
+
+        assert_eq!(
+            detect_template_strict("{if premium}*{end}").unwrap(),
+            TemplateFormat::FmtString
+        );
+        assert_eq!(
+            Template::new("{start} to {end}").unwrap().input_variables(),
+            vec!["start", "end"]
+        );
+        assert!(matches!(
+            Template::new("{if premium}Priority"),
+            Err(TemplateError::MalformedTemplate(msg)) if msg == "Unclosed '{if premium}' block"
+        ));
+    }
+
+    #[test]
+    fn test_fenced_placeholders_are_substituted() {
+        let tmpl = Template::new("```\n{snippet}\n```\nHi {name}").unwrap();
+        assert_eq!(tmpl.template_format(), TemplateFormat::FmtString);
+        assert_eq!(
+            tmpl.format(&vars!(snippet = "let x = 1;", name = "Ada"))
+                .unwrap(),
+            "```\nlet x = 1;\n```\nHi Ada"
+        );
+
+        let fence_only = Template::new("```\n{snippet}\n```").unwrap();
+        assert_eq!(fence_only.input_variables(), vec!["snippet"]);
+    }
+
+    #[test]
+    fn test_missing_variable_policies() {
+        use crate::{FormatOptions, MissingVariables};
+
+        let tmpl = Template::new("Hi {name|trim}, your plan is {plan}.").unwrap();
+        assert!(matches!(
+            tmpl.format(&vars!(plan = "pro")),
+            Err(TemplateError::MissingVariable(_))
+        ));
+
+        let empty = FormatOptions::new().missing_vars(MissingVariables::Empty);
+        assert_eq!(
+            tmpl.format_with_options(&vars!(plan = "pro"), empty)
+                .unwrap(),
+            "Hi , your plan is pro."
+        );
+
+        let keep = tmpl.clone().with_format_options(
+            FormatOptions::new().missing_vars(MissingVariables::KeepPlaceholder),
+        );
+        assert_eq!(
+            keep.format(&vars!(plan = "pro")).unwrap(),
+            "Hi {name|trim}, your plan is pro."
+        );
+        assert_eq!(
+            keep.format(&vars!(name = " ada ", plan = "pro")).unwrap(),
+            "Hi ada, your plan is pro."
+        );
+
+        let json = serde_json::to_string(&keep).unwrap();
+        let restored: Template = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.format_options(), keep.format_options());
+        assert!(!serde_json::to_string(&tmpl)
+            .unwrap()
+            .contains("format_options"));
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_missing_variable_policies_mustache() {
+        use crate::{FormatOptions, MissingVariables};
+
+        let tmpl = Template::new("Hello {{name}} from {{team}}!").unwrap();
+        let keep = FormatOptions::new().missing_vars(MissingVariables::KeepPlaceholder);
+        assert_eq!(
+            tmpl.format_with_options(&vars!(team = "Core"), keep)
+                .unwrap(),
+            "Hello {{name}} from Core!"
+        );
+        assert_eq!(
+            tmpl.format_with_options(&vars!(team = "Core"), FormatOptions::lenient())
+                .unwrap(),
+            "Hello  from Core!"
+        );
+    }
 }
@@ -1,14 +1,28 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+};
+
+#[cfg(feature = "toml")]
 use toml::de::Error as TomlError;
 
+#[cfg(feature = "yaml")]
+use serde_yaml::Error as YamlError;
+
+#[cfg(feature = "mustache")]
 use handlebars::RenderError;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
     braces::{
-        count_left_braces, count_right_braces, has_multiple_words_between_braces, has_no_braces,
-        has_only_double_braces, has_only_single_braces,
+        has_no_braces, has_only_double_braces, has_only_single_braces, has_stray_braces, scan,
+        BraceKind,
     },
+    conditionals::{check_conditionals, parse_directive},
+    filters::{check_filters, split_filters},
+    macros::check_macros,
+    mustache_expr::{parse_mustache_expr, MustacheTag},
+    placeholder::is_valid_identifier,
     role::InvalidRoleError,
 };
 
@@ -17,9 +31,22 @@ pub enum TemplateError {
     MalformedTemplate(String),
     UnsupportedFormat(String),
     MissingVariable(String),
-    RuntimeError(RenderError),
+    RuntimeError(String),
     InvalidRoleError,
     TomlDeserializationError(String),
+    YamlDeserializationError(String),
+    ConflictingVariable(String),
+    LimitExceeded(String),
+    AmbiguousFormat(Vec<Range<usize>>),
+    InvalidResponse(String),
+    UnresolvedPlaceholder(String),
+    InvalidVariable(String),
+    ResolutionFailed(Vec<(String, String)>),
+    DependencyCycle(Vec<String>),
+    VersionConflict(String),
+    UnknownRole(String),
+    PolicyViolation(String),
+    UnknownPrompt(String),
 }
 
 impl From<InvalidRoleError> for TemplateError {
@@ -28,29 +55,73 @@ impl From<InvalidRoleError> for TemplateError {
     }
 }
 
+#[cfg(feature = "mustache")]
 impl From<RenderError> for TemplateError {
     fn from(err: RenderError) -> Self {
-        TemplateError::RuntimeError(err)
+        TemplateError::RuntimeError(err.to_string())
     }
 }
 
+#[cfg(feature = "toml")]
 impl From<TomlError> for TemplateError {
     fn from(err: TomlError) -> Self {
         TemplateError::TomlDeserializationError(err.to_string())
     }
 }
 
+#[cfg(feature = "yaml")]
+impl From<YamlError> for TemplateError {
+    fn from(err: YamlError) -> Self {
+        TemplateError::YamlDeserializationError(err.to_string())
+    }
+}
+
 impl std::fmt::Display for TemplateError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TemplateError::MalformedTemplate(msg) => write!(f, "Malformed template: {}", msg),
             TemplateError::UnsupportedFormat(msg) => write!(f, "Unsupported format: {}", msg),
             TemplateError::MissingVariable(msg) => write!(f, "Missing variable: {}", msg),
-            TemplateError::RuntimeError(err) => write!(f, "Render error: {}", err),
+            TemplateError::RuntimeError(msg) => write!(f, "Render error: {}", msg),
             TemplateError::InvalidRoleError => write!(f, "Invalid role error"),
             TemplateError::TomlDeserializationError(msg) => {
                 write!(f, "TOML deserialization error: {}", msg)
             }
+            TemplateError::YamlDeserializationError(msg) => {
+                write!(f, "YAML deserialization error: {}", msg)
+            }
+            TemplateError::ConflictingVariable(name) => write!(
+                f,
+                "Conflicting variable: '{}' is used both as a text variable and a messages placeholder",
+                name
+            ),
+            TemplateError::LimitExceeded(msg) => write!(f, "Limit exceeded: {}", msg),
+            TemplateError::AmbiguousFormat(spans) => {
+                let spans: Vec<String> = spans
+                    .iter()
+                    .map(|span| format!("{}..{}", span.start, span.end))
+                    .collect();
+                write!(f, "Ambiguous template format at {}", spans.join(", "))
+            }
+            TemplateError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
+            TemplateError::UnresolvedPlaceholder(msg) => {
+                write!(f, "Unresolved placeholder: {}", msg)
+            }
+            TemplateError::InvalidVariable(msg) => write!(f, "Invalid variable: {}", msg),
+            TemplateError::ResolutionFailed(failures) => {
+                let failures: Vec<String> = failures
+                    .iter()
+                    .map(|(name, error)| format!("{}: {}", name, error))
+                    .collect();
+                write!(f, "Failed to resolve variables: {}", failures.join("; "))
+            }
+            TemplateError::DependencyCycle(cycle) => {
+                write!(f, "Dependency cycle: {}", cycle.join(" -> "))
+            }
+            TemplateError::VersionConflict(msg) => write!(f, "Version conflict: {}", msg),
+            TemplateError::UnknownRole(role) => write!(f, "Unknown role: '{}'", role),
+            TemplateError::PolicyViolation(msg) => write!(f, "Policy violation: {}", msg),
+            TemplateError::UnknownPrompt(name) => write!(f, "Unknown prompt: '{}'", name),
         }
     }
 }
@@ -63,12 +134,32 @@ impl TemplateError {
             (TemplateError::MissingVariable(a), TemplateError::MissingVariable(b)) => a == b,
             (TemplateError::MalformedTemplate(a), TemplateError::MalformedTemplate(b)) => a == b,
             (TemplateError::UnsupportedFormat(a), TemplateError::UnsupportedFormat(b)) => a == b,
-            (TemplateError::RuntimeError(_), TemplateError::RuntimeError(_)) => true,
+            (TemplateError::RuntimeError(a), TemplateError::RuntimeError(b)) => a == b,
             (TemplateError::InvalidRoleError, TemplateError::InvalidRoleError) => true,
             (
                 TemplateError::TomlDeserializationError(a),
                 TemplateError::TomlDeserializationError(b),
             ) => a == b,
+            (
+                TemplateError::YamlDeserializationError(a),
+                TemplateError::YamlDeserializationError(b),
+            ) => a == b,
+            (TemplateError::ConflictingVariable(a), TemplateError::ConflictingVariable(b)) => {
+                a == b
+            }
+            (TemplateError::LimitExceeded(a), TemplateError::LimitExceeded(b)) => a == b,
+            (TemplateError::AmbiguousFormat(a), TemplateError::AmbiguousFormat(b)) => a == b,
+            (TemplateError::InvalidResponse(a), TemplateError::InvalidResponse(b)) => a == b,
+            (TemplateError::UnresolvedPlaceholder(a), TemplateError::UnresolvedPlaceholder(b)) => {
+                a == b
+            }
+            (TemplateError::InvalidVariable(a), TemplateError::InvalidVariable(b)) => a == b,
+            (TemplateError::ResolutionFailed(a), TemplateError::ResolutionFailed(b)) => a == b,
+            (TemplateError::DependencyCycle(a), TemplateError::DependencyCycle(b)) => a == b,
+            (TemplateError::VersionConflict(a), TemplateError::VersionConflict(b)) => a == b,
+            (TemplateError::UnknownRole(a), TemplateError::UnknownRole(b)) => a == b,
+            (TemplateError::PolicyViolation(a), TemplateError::PolicyViolation(b)) => a == b,
+            (TemplateError::UnknownPrompt(a), TemplateError::UnknownPrompt(b)) => a == b,
             _ => false,
         }
     }
@@ -79,6 +170,7 @@ pub enum TemplateFormat {
     PlainText,
     FmtString,
     Mustache,
+    Jinja2,
 }
 
 impl TemplateFormat {
@@ -87,9 +179,14 @@ impl TemplateFormat {
             TemplateFormat::FmtString => "FmtString",
             TemplateFormat::Mustache => "Mustache",
             TemplateFormat::PlainText => "PlainText",
+            TemplateFormat::Jinja2 => "Jinja2",
         }
     }
     pub fn from_template(template: &str) -> Result<Self, TemplateError> {
+        if is_jinja2(template) {
+            return Ok(TemplateFormat::Jinja2);
+        }
+
         if !is_valid_template(template) {
             return Err(TemplateError::MalformedTemplate(
                 "Malformed template".to_string(),
@@ -118,6 +215,7 @@ impl TryFrom<&str> for TemplateFormat {
             "fmtstring" => Ok(TemplateFormat::FmtString),
             "mustache" => Ok(TemplateFormat::Mustache),
             "plaintext" => Ok(TemplateFormat::PlainText),
+            "jinja2" | "jinja" => Ok(TemplateFormat::Jinja2),
             _ => Err(TemplateError::UnsupportedFormat(
                 "Unsupported template format".to_string(),
             )),
@@ -130,11 +228,51 @@ pub fn is_plain_text(s: &str) -> bool {
 }
 
 pub fn is_mustache(s: &str) -> bool {
-    has_only_double_braces(s) && !has_multiple_words_between_braces(s)
+    has_only_double_braces(s) && has_balanced_sections(s)
+}
+
+fn has_balanced_sections(s: &str) -> bool {
+    let mut sections = Vec::new();
+
+    for slot in scan(s)
+        .into_iter()
+        .filter(|token| token.kind == BraceKind::Double)
+    {
+        match parse_mustache_expr(slot.inner(s)) {
+            Some(expr) if expr.tag == MustacheTag::Open => sections.push(expr.name),
+            Some(expr) if expr.tag == MustacheTag::Close => {
+                if sections.pop() != Some(expr.name) {
+                    return false;
+                }
+            }
+            Some(_) => {}
+            None => return false,
+        }
+    }
+
+    sections.is_empty()
+}
+
+fn has_tag(s: &str, open: &str, close: &str) -> bool {
+    s.find(open)
+        .is_some_and(|start| s[start + open.len()..].contains(close))
+}
+
+pub fn is_jinja2(s: &str) -> bool {
+    has_tag(s, "{%", "%}")
+        || has_tag(s, "{#", "#}")
+        || scan(s).iter().any(|token| {
+            let inner = token.inner(s);
+            token.kind == BraceKind::Double && inner.contains('|') && !inner.contains(" as |")
+        })
 }
 
 pub fn is_fmtstring(s: &str) -> bool {
-    has_only_single_braces(s) && !has_multiple_words_between_braces(s)
+    has_only_single_braces(s)
+        && scan(s).iter().filter(|token| token.is_slot()).all(|slot| {
+            let inner = slot.inner(s);
+            inner.split_whitespace().count() <= 1 || parse_directive(inner, 0).is_some()
+        })
 }
 
 pub fn is_valid_template(s: &str) -> bool {
@@ -142,8 +280,7 @@ pub fn is_valid_template(s: &str) -> bool {
         return true;
     }
 
-    count_left_braces(s) == count_right_braces(s)
-        && (has_only_double_braces(s) || has_only_single_braces(s))
+    !has_stray_braces(s) && (has_only_double_braces(s) || has_only_single_braces(s))
 }
 
 pub fn validate_template(s: &str) -> Result<(), TemplateError> {
@@ -151,12 +288,16 @@ pub fn validate_template(s: &str) -> Result<(), TemplateError> {
         return Err(TemplateError::MalformedTemplate(s.to_string()));
     }
 
-    Ok(())
+    check_conditionals(s)?;
+    check_filters(s)?;
+    check_macros(s)
 }
 
 pub fn detect_template(s: &str) -> Result<TemplateFormat, TemplateError> {
     if is_plain_text(s) {
         Ok(TemplateFormat::PlainText)
+    } else if is_jinja2(s) {
+        Ok(TemplateFormat::Jinja2)
     } else if is_mustache(s) {
         Ok(TemplateFormat::Mustache)
     } else if is_fmtstring(s) {
@@ -166,6 +307,64 @@ pub fn detect_template(s: &str) -> Result<TemplateFormat, TemplateError> {
     }
 }
 
+pub fn detect_template_strict(s: &str) -> Result<TemplateFormat, TemplateError> {
+    if is_jinja2(s) {
+        return Ok(TemplateFormat::Jinja2);
+    }
+
+    let mut ambiguous = Vec::new();
+    let mut single: Option<Range<usize>> = None;
+    let mut double: Option<Range<usize>> = None;
+
+    for token in scan(s) {
+        match token.kind {
+            BraceKind::Single
+                if !is_valid_identifier(split_filters(token.inner(s)).0)
+                    && parse_directive(token.inner(s), 0).is_none() =>
+            {
+                ambiguous.push(token.span)
+            }
+            BraceKind::Double if parse_mustache_expr(token.inner(s)).is_none() => {
+                ambiguous.push(token.span)
+            }
+            BraceKind::Single => {
+                single.get_or_insert(token.span);
+            }
+            BraceKind::Double => {
+                double.get_or_insert(token.span);
+            }
+            BraceKind::Open | BraceKind::Close | BraceKind::Stray => ambiguous.push(token.span),
+            BraceKind::Text | BraceKind::Escaped => {}
+        }
+    }
+
+    if let (Some(single), Some(double)) = (&single, &double) {
+        ambiguous.push(single.clone());
+        ambiguous.push(double.clone());
+    }
+
+    if !ambiguous.is_empty() {
+        ambiguous.sort_by_key(|span| span.start);
+        return Err(TemplateError::AmbiguousFormat(ambiguous));
+    }
+
+    Ok(match (single, double) {
+        (Some(_), _) => TemplateFormat::FmtString,
+        (_, Some(_)) => TemplateFormat::Mustache,
+        _ => TemplateFormat::PlainText,
+    })
+}
+
+#[cfg(feature = "toml")]
+pub(crate) fn parse_toml<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    toml::from_str(value).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "toml"))]
+pub(crate) fn parse_toml<T: DeserializeOwned>(_value: &str) -> Result<T, String> {
+    Err("TOML support is disabled; enable the `toml` feature".to_string())
+}
+
 pub fn merge_vars<'a>(
     partials: &'a HashMap<String, String>,
     runtime_vars: &HashMap<&'a str, &'a str>,
@@ -177,6 +376,20 @@ pub fn merge_vars<'a>(
         .collect()
 }
 
+pub(crate) fn resolve_aliases<'a>(
+    aliases: &'a BTreeMap<String, String>,
+    variables: &mut HashMap<&'a str, &'a str>,
+) {
+    for (alias, canonical) in aliases {
+        if variables.contains_key(canonical.as_str()) {
+            continue;
+        }
+        if let Some(value) = variables.get(alias.as_str()).copied() {
+            variables.insert(canonical, value);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -203,7 +416,28 @@ mod tests {
         assert!(!is_mustache("{{var"));
         assert!(!is_mustache("var}}"));
         assert!(!is_mustache("{var} words {{another}}"));
-        assert!(!is_mustache("{{ hello world }}"));
+        assert!(!is_mustache("{{ hello, world! }}"));
+    }
+
+    #[test]
+    fn test_is_mustache_accepts_helpers_sections_and_paths() {
+        assert!(is_mustache("{{upper name}}"));
+        assert!(is_mustache("{{ truncate summary 20 ellipsis=\"...\" }}"));
+        assert!(is_mustache("{{user.name}} <{{user.email}}>"));
+        assert!(is_mustache(
+            "{{#if urgent}}Now{{else}}Later{{/if}} {{#each items}}- {{this}}{{/each}}"
+        ));
+        assert!(is_mustache("{{! reviewer note }}{{> footer}}"));
+
+        assert!(!is_mustache("{{#if urgent}}Now"));
+        assert!(!is_mustache("{{#if urgent}}Now{{/each}}"));
+        assert!(!is_mustache("{{upper \"name}}"));
+        assert!(!is_fmtstring("{upper name}"));
+        assert_eq!(
+            detect_template("{{#each items}}{{title}}{{/each}}").unwrap(),
+            TemplateFormat::Mustache
+        );
+        assert!(detect_template("{upper name}").is_err());
     }
 
     #[test]
@@ -442,4 +676,87 @@ mod tests {
         assert_eq!(merged.get("day"), Some(&"Sunday"));
         assert_eq!(merged.len(), 2);
     }
+
+    #[test]
+    fn test_detect_template_strict_accepts_clean_templates() {
+        assert_eq!(
+            detect_template_strict("Hello there.").unwrap(),
+            TemplateFormat::PlainText
+        );
+        assert_eq!(
+            detect_template_strict("Hello {name}, {doc|trim}.").unwrap(),
+            TemplateFormat::FmtString
+        );
+        assert_eq!(
+            detect_template_strict("Hello {{ name }}!").unwrap(),
+            TemplateFormat::Mustache
+        );
+    }
+
+    #[test]
+    fn test_detect_template_strict_reports_stray_braces() {
+        let result = detect_template_strict("Use { to open a block for {name}.");
+        assert!(
+            matches!(result, Err(TemplateError::AmbiguousFormat(spans)) if spans.len() == 1 && spans[0] == (4..5))
+        );
+
+        let result = detect_template_strict(r#"Return {"ok": true}"#);
+        assert!(
+            matches!(result, Err(TemplateError::AmbiguousFormat(spans)) if spans.len() == 1 && spans[0] == (7..19))
+        );
+    }
+
+    #[test]
+    fn test_detect_template_strict_reports_mixed_contexts() {
+        let template = "Hi {name}, welcome to {{place}}.";
+        let err = detect_template_strict(template).unwrap_err();
+        assert!(err.matches(&TemplateError::AmbiguousFormat(vec![3..9, 22..31])));
+        assert_eq!(err.to_string(), "Ambiguous template format at 3..9, 22..31");
+    }
+
+    #[test]
+    fn test_detection_uses_brace_scanner() {
+        assert_eq!(
+            detect_template(r#"Reply as {"answer": {answer}}"#).unwrap(),
+            TemplateFormat::FmtString
+        );
+        assert_eq!(
+            detect_template(r"Literal \{braces\} only").unwrap(),
+            TemplateFormat::PlainText
+        );
+        assert_eq!(
+            detect_template("Review {{lang}}:\n```\nfn main() {\n```").unwrap(),
+            TemplateFormat::Mustache
+        );
+        assert!(is_valid_template("Type '{' to start a block, {name}."));
+        assert!(!is_valid_template("Type { to start a block, {name}."));
+    }
+
+    #[test]
+    fn test_lenient_detection_is_unchanged() {
+        assert!(detect_template("Hi {name}, welcome to {{place}}.").is_err());
+        assert_eq!(
+            detect_template("Hi {name}.").unwrap(),
+            TemplateFormat::FmtString
+        );
+    }
+
+    #[test]
+    fn test_is_jinja2() {
+        assert!(is_jinja2("{% if vip %}VIP{% endif %}"));
+        assert!(is_jinja2("{# note #}Hello"));
+        assert!(is_jinja2("Hello {{ name | upper }}"));
+        assert!(!is_jinja2("Hello {{name}}"));
+        assert!(!is_jinja2("Hello {name|trim}"));
+        assert!(!is_jinja2("Discount {%} applies"));
+
+        assert_eq!(
+            detect_template("{% for x in xs %}{{ x }}{% endfor %}").unwrap(),
+            TemplateFormat::Jinja2
+        );
+        assert_eq!(
+            TemplateFormat::try_from("jinja2").unwrap(),
+            TemplateFormat::Jinja2
+        );
+    }
 }
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatTemplate, MessageLike, TemplateError, TokenCounter};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenAnnotations {
+    pub tokenizer: String,
+    pub messages: Vec<usize>,
+    pub total: usize,
+}
+
+impl ChatTemplate {
+    pub fn annotate_tokens<C: TokenCounter + ?Sized>(
+        &self,
+        tokenizer: &str,
+        counter: &C,
+    ) -> TokenAnnotations {
+        let messages: Vec<usize> = self
+            .messages
+            .iter()
+            .map(|message_like| match message_like {
+                MessageLike::BaseMessage(message) => counter.count_message(message),
+                MessageLike::RolePromptTemplate(_, template) => {
+                    counter.count(&template.static_text())
+                }
+                MessageLike::Placeholder(_) => 0,
+                MessageLike::FewShotPrompt(few_shot) => few_shot
+                    .format_examples()
+                    .map(|examples| counter.count(&examples))
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        TokenAnnotations {
+            tokenizer: tokenizer.to_string(),
+            total: messages.iter().sum(),
+            messages,
+        }
+    }
+
+    pub fn with_token_annotations<C: TokenCounter + ?Sized>(
+        mut self,
+        tokenizer: &str,
+        counter: &C,
+    ) -> Self {
+        self.meta.token_annotations = Some(self.annotate_tokens(tokenizer, counter));
+        self
+    }
+
+    pub fn to_json(&self) -> Result<String, TemplateError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            TemplateError::MalformedTemplate(format!("Failed to serialize template: {}", e))
+        })
+    }
+
+    pub fn to_json_with_tokens<C: TokenCounter + ?Sized>(
+        &self,
+        tokenizer: &str,
+        counter: &C,
+    ) -> Result<String, TemplateError> {
+        self.clone()
+            .with_token_annotations(tokenizer, counter)
+            .to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, TemplateError> {
+        serde_json::from_str(json).map_err(|e| {
+            TemplateError::MalformedTemplate(format!("Failed to deserialize template: {}", e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chats, HeuristicCounter,
+        Role::{Ai, Human, Placeholder, System},
+    };
+
+    fn words(text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+
+    #[test]
+    fn test_annotate_tokens() {
+        let template = ChatTemplate::from_messages(chats!(
+            System = "You are a helpful assistant.",
+            Placeholder = "{history}",
+            Human = "Please answer {question} briefly.",
+            Ai = "Sure thing.",
+        ))
+        .unwrap();
+
+        let annotations = template.annotate_tokens("words", &words);
+        assert_eq!(annotations.tokenizer, "words");
+        assert_eq!(annotations.messages, vec![5, 0, 3, 2]);
+        assert_eq!(annotations.total, 10);
+    }
+
+    #[test]
+    fn test_json_round_trip_with_tokens() {
+        let template =
+            ChatTemplate::from_messages(chats!(System = "Be brief.", Human = "{question}"))
+                .unwrap();
+
+        let plain = template.to_json().unwrap();
+        assert!(!plain.contains("token_annotations"));
+
+        let json = template
+            .to_json_with_tokens("heuristic", &HeuristicCounter)
+            .unwrap();
+        let restored = ChatTemplate::from_json(&json).unwrap();
+        let annotations = restored.token_annotations().unwrap();
+        assert_eq!(annotations.tokenizer, "heuristic");
+        assert_eq!(annotations.messages.len(), 2);
+        assert_eq!(annotations.messages[1], 0);
+        assert_eq!(restored.messages.len(), 2);
+
+        assert!(matches!(
+            ChatTemplate::from_json("{"),
+            Err(TemplateError::MalformedTemplate(_))
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use messageforge::{BaseMessage, MessageEnum};
+#[cfg(feature = "tiktoken")]
+use tiktoken_rs::CoreBPE;
+
+use crate::{
+    braces::{scan, BraceKind},
+    truncate::CHARS_PER_TOKEN,
+    ChatTemplate, Templatable, Template, TemplateError,
+};
+
+pub fn estimate_tokens(text: &str) -> usize {
+    (text.chars().count() + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN
+}
+
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+
+    fn count_message(&self, message: &MessageEnum) -> usize {
+        self.count(message.content())
+    }
+
+    fn count_messages(&self, messages: &[Arc<MessageEnum>]) -> usize {
+        messages
+            .iter()
+            .map(|message| self.count_message(message))
+            .sum()
+    }
+}
+
+impl fmt::Debug for dyn TokenCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenCounter")
+    }
+}
+
+impl<F> TokenCounter for F
+where
+    F: Fn(&str) -> usize + Send + Sync,
+{
+    fn count(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeuristicCounter;
+
+impl TokenCounter for HeuristicCounter {
+    fn count(&self, text: &str) -> usize {
+        estimate_tokens(text)
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+#[derive(Clone)]
+pub struct BpeCounter {
+    bpe: Arc<CoreBPE>,
+    tokens_per_message: usize,
+}
+
+#[cfg(feature = "tiktoken")]
+impl BpeCounter {
+    const TOKENS_PER_MESSAGE: usize = 4;
+
+    fn from_bpe<E: fmt::Display>(
+        bpe: Result<CoreBPE, E>,
+        name: &str,
+    ) -> Result<Self, TemplateError> {
+        bpe.map(|bpe| Self {
+            bpe: Arc::new(bpe),
+            tokens_per_message: Self::TOKENS_PER_MESSAGE,
+        })
+        .map_err(|e| {
+            TemplateError::UnsupportedFormat(format!("No BPE encoding for '{}': {}", name, e))
+        })
+    }
+
+    pub fn cl100k() -> Result<Self, TemplateError> {
+        Self::from_bpe(tiktoken_rs::cl100k_base(), "cl100k_base")
+    }
+
+    pub fn o200k() -> Result<Self, TemplateError> {
+        Self::from_bpe(tiktoken_rs::o200k_base(), "o200k_base")
+    }
+
+    pub fn for_model(model: &str) -> Result<Self, TemplateError> {
+        Self::from_bpe(tiktoken_rs::get_bpe_from_model(model), model)
+    }
+
+    pub fn with_tokens_per_message(mut self, tokens_per_message: usize) -> Self {
+        self.tokens_per_message = tokens_per_message;
+        self
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for BpeCounter {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+
+    fn count_message(&self, message: &MessageEnum) -> usize {
+        self.tokens_per_message + self.count(message.content())
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl fmt::Debug for BpeCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BpeCounter")
+            .field("tokens_per_message", &self.tokens_per_message)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Template {
+    pub(crate) fn static_text(&self) -> String {
+        let source = self.template();
+        scan(source)
+            .iter()
+            .filter(|token| matches!(token.kind, BraceKind::Text | BraceKind::Escaped))
+            .map(|token| token.literal(source))
+            .collect()
+    }
+
+    pub fn estimate_tokens(&self) -> usize {
+        estimate_tokens(&self.static_text())
+    }
+
+    pub fn count_tokens<C: TokenCounter + ?Sized>(
+        &self,
+        variables: &HashMap<&str, &str>,
+        counter: &C,
+    ) -> Result<usize, TemplateError> {
+        Ok(counter.count(&self.format(variables)?))
+    }
+}
+
+impl ChatTemplate {
+    pub fn with_token_counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.meta.token_counter = Some(Arc::new(counter));
+        self
+    }
+
+    pub fn counter(&self) -> &dyn TokenCounter {
+        self.meta
+            .token_counter
+            .as_deref()
+            .unwrap_or(&HeuristicCounter as &dyn TokenCounter)
+    }
+
+    pub fn count_tokens<C: TokenCounter + ?Sized>(
+        &self,
+        variables: &HashMap<&str, &str>,
+        counter: &C,
+    ) -> Result<usize, TemplateError> {
+        Ok(counter.count_messages(&self.format_messages(variables)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chats, vars, Role::Human, Role::System};
+
+    #[test]
+    fn test_template_estimate_tokens() {
+        let template = Template::new("Summarize {text} in {count} bullets.").unwrap();
+        assert_eq!(
+            template.estimate_tokens(),
+            estimate_tokens("Summarize  in  bullets.")
+        );
+        assert_eq!(Template::new("{a}{b}").unwrap().estimate_tokens(), 0);
+    }
+
+    #[test]
+    fn test_count_tokens_with_custom_counter() {
+        let words = |text: &str| text.split_whitespace().count();
+        let template = Template::new("Summarize {text} briefly.").unwrap();
+        assert_eq!(
+            template
+                .count_tokens(&vars!(text = "the quarterly report"), &words)
+                .unwrap(),
+            5
+        );
+
+        let chat =
+            ChatTemplate::from_messages(chats!(System = "You are terse.", Human = "{question}",))
+                .unwrap();
+        assert_eq!(
+            chat.count_tokens(&vars!(question = "Why is the sky blue?"), &words)
+                .unwrap(),
+            8
+        );
+        assert_eq!(
+            chat.count_tokens(&vars!(question = "Hi"), &HeuristicCounter)
+                .unwrap(),
+            estimate_tokens("You are terse.") + estimate_tokens("Hi")
+        );
+        assert!(chat.count_tokens(&vars!(), &words).is_err());
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_bpe_counter() {
+        let counter = BpeCounter::cl100k().unwrap();
+        assert_eq!(counter.count("hello world"), 2);
+
+        let chat = ChatTemplate::from_messages(chats!(Human = "{question}")).unwrap();
+        assert_eq!(
+            chat.count_tokens(&vars!(question = "hello world"), &counter)
+                .unwrap(),
+            6
+        );
+        assert_eq!(
+            chat.count_tokens(
+                &vars!(question = "hello world"),
+                &counter.clone().with_tokens_per_message(0)
+            )
+            .unwrap(),
+            2
+        );
+        assert!(BpeCounter::for_model("gpt-4o").is_ok());
+        assert!(matches!(
+            BpeCounter::for_model("not-a-model"),
+            Err(TemplateError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::TokenCounter;
+
+pub const CHARS_PER_TOKEN: usize = 4;
+
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((index, _)) => &s[..index],
+        None => s,
+    }
+}
+
+pub fn truncate_graphemes(s: &str, max_graphemes: usize) -> &str {
+    match s.grapheme_indices(true).nth(max_graphemes) {
+        Some((index, _)) => &s[..index],
+        None => s,
+    }
+}
+
+pub fn truncate_tokens<'a>(s: &'a str, max_tokens: usize, counter: &dyn TokenCounter) -> &'a str {
+    if counter.count(s) <= max_tokens {
+        return s;
+    }
+
+    let boundaries: Vec<usize> = s
+        .grapheme_indices(true)
+        .map(|(index, _)| index)
+        .skip(1)
+        .collect();
+    let fits = boundaries.partition_point(|&end| counter.count(&s[..end]) <= max_tokens);
+    match fits {
+        0 => "",
+        fits => &s[..boundaries[fits - 1]],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{token_counter::estimate_tokens, HeuristicCounter};
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("hello", 3), "hel");
+        assert_eq!(truncate_chars("hello", 10), "hello");
+        assert_eq!(truncate_chars("héllo wörld", 7), "héllo w");
+        assert_eq!(truncate_chars("日本語テキスト", 3), "日本語");
+        assert_eq!(truncate_chars("abc", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_graphemes_keeps_clusters_whole() {
+        let family = "👨‍👩‍👧‍👦";
+        let text = format!("{}{}ok", family, family);
+
+        assert_eq!(truncate_graphemes(&text, 1), family);
+        assert_eq!(
+            truncate_graphemes(&text, 3),
+            format!("{}{}o", family, family)
+        );
+        assert_eq!(truncate_graphemes("e\u{301}te\u{301}", 1), "e\u{301}");
+        assert_eq!(truncate_graphemes("short", 10), "short");
+    }
+
+    #[test]
+    fn test_truncate_chars_can_split_clusters_but_not_chars() {
+        assert_eq!(truncate_chars("e\u{301}x", 1), "e");
+    }
+
+    #[test]
+    fn test_truncate_tokens() {
+        let text = "abcdefghijkl";
+        assert_eq!(truncate_tokens(text, 3, &HeuristicCounter), text);
+        assert_eq!(truncate_tokens(text, 2, &HeuristicCounter), "abcdefgh");
+        assert_eq!(
+            estimate_tokens(truncate_tokens(text, 1, &HeuristicCounter)),
+            1
+        );
+
+        let accented = "aaae\u{301}bbb";
+        assert_eq!(truncate_tokens(accented, 1, &HeuristicCounter), "aaa");
+        assert_eq!(truncate_tokens("", 0, &HeuristicCounter), "");
+    }
+
+    #[test]
+    fn test_truncate_tokens_with_custom_counter() {
+        let words = |text: &str| text.split_whitespace().count();
+        assert_eq!(truncate_tokens("one two three four", 2, &words), "one two ");
+        assert_eq!(truncate_tokens("one two", 5, &words), "one two");
+        assert_eq!(truncate_tokens("one two", 0, &words), "");
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VarValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<VarValue>),
+    Map(BTreeMap<String, VarValue>),
+}
+
+impl VarValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VarValue::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn is_structured(&self) -> bool {
+        matches!(self, VarValue::List(_) | VarValue::Map(_))
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+impl fmt::Display for VarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarValue::Null => Ok(()),
+            VarValue::Bool(value) => write!(f, "{}", value),
+            VarValue::Int(value) => write!(f, "{}", value),
+            VarValue::Float(value) => write!(f, "{}", value),
+            VarValue::Str(value) => f.write_str(value),
+            VarValue::List(_) | VarValue::Map(_) => write!(f, "{}", self.to_json()),
+        }
+    }
+}
+
+impl From<&str> for VarValue {
+    fn from(value: &str) -> Self {
+        VarValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for VarValue {
+    fn from(value: String) -> Self {
+        VarValue::Str(value)
+    }
+}
+
+impl From<&String> for VarValue {
+    fn from(value: &String) -> Self {
+        VarValue::Str(value.clone())
+    }
+}
+
+impl From<bool> for VarValue {
+    fn from(value: bool) -> Self {
+        VarValue::Bool(value)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty),+) => {
+        $(
+            impl From<$ty> for VarValue {
+                fn from(value: $ty) -> Self {
+                    VarValue::Int(value as i64)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32, usize);
+
+impl From<f32> for VarValue {
+    fn from(value: f32) -> Self {
+        VarValue::Float(value as f64)
+    }
+}
+
+impl From<f64> for VarValue {
+    fn from(value: f64) -> Self {
+        VarValue::Float(value)
+    }
+}
+
+impl<T: Into<VarValue>> From<Option<T>> for VarValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(VarValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<VarValue>> From<Vec<T>> for VarValue {
+    fn from(values: Vec<T>) -> Self {
+        VarValue::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<VarValue>> From<BTreeMap<K, V>> for VarValue {
+    fn from(values: BTreeMap<K, V>) -> Self {
+        VarValue::Map(
+            values
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl From<serde_json::Value> for VarValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => VarValue::Null,
+            serde_json::Value::Bool(value) => VarValue::Bool(value),
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(value) => VarValue::Int(value),
+                None => VarValue::Float(number.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(value) => VarValue::Str(value),
+            serde_json::Value::Array(values) => {
+                VarValue::List(values.into_iter().map(Into::into).collect())
+            }
+            serde_json::Value::Object(values) => VarValue::Map(
+                values
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+pub(crate) fn stringify_values<'a>(
+    values: &HashMap<&'a str, VarValue>,
+) -> HashMap<&'a str, String> {
+    values
+        .iter()
+        .map(|(name, value)| (*name, value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(VarValue::from("plain").to_string(), "plain");
+        assert_eq!(VarValue::from(3).to_string(), "3");
+        assert_eq!(VarValue::from(2.5).to_string(), "2.5");
+        assert_eq!(VarValue::from(true).to_string(), "true");
+        assert_eq!(VarValue::from(None::<i32>).to_string(), "");
+        assert_eq!(VarValue::from(vec!["a", "b"]).to_string(), r#"["a","b"]"#);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let value = json!({ "name": "Ada", "tags": ["x", 1, 1.5, null, false] });
+        let var = VarValue::from(value.clone());
+        assert!(var.is_structured());
+        assert_eq!(var.to_json(), value);
+        assert_eq!(serde_json::from_value::<VarValue>(value).unwrap(), var);
+        assert_eq!(VarValue::from("x").as_str(), Some("x"));
+        assert_eq!(VarValue::from(1).as_str(), None);
+    }
+}
@@ -1,14 +1,84 @@
+use std::borrow::Cow;
+
+pub trait VarStr<'a> {
+    fn var_str(self) -> &'a str;
+}
+
+impl<'a> VarStr<'a> for &&'a str {
+    fn var_str(self) -> &'a str {
+        self
+    }
+}
+
+impl<'a> VarStr<'a> for &'a String {
+    fn var_str(self) -> &'a str {
+        self
+    }
+}
+
+impl<'a> VarStr<'a> for &&'a String {
+    fn var_str(self) -> &'a str {
+        self
+    }
+}
+
+impl<'a> VarStr<'a> for &'a Cow<'_, str> {
+    fn var_str(self) -> &'a str {
+        self
+    }
+}
+
 #[macro_export]
 macro_rules! vars {
+    (@key $key:ident) => {
+        stringify!($key)
+    };
+
+    (@key $key:literal) => {
+        $key
+    };
+
     () => {
         std::collections::HashMap::<&str, &str>::new()
     };
 
-    ($($key:ident = $value:expr),+ $(,)?) => {
+    ($($key:tt = $value:expr),+ $(,)?) => {
+        std::collections::HashMap::<&str, &str>::from([
+            $(
+                ($crate::vars!(@key $key), $crate::vars::VarStr::var_str(&$value)),
+            )+
+        ])
+    };
+}
+
+#[macro_export]
+macro_rules! prompt_vars {
+    () => {
+        $crate::PromptVars::new()
+    };
+
+    ($($key:tt = $value:expr),+ $(,)?) => {
+        {
+            let mut vars = $crate::PromptVars::new();
+            $(
+                vars.insert($crate::vars!(@key $key), $crate::vars::VarStr::var_str(&$value));
+            )+
+            vars
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! var_values {
+    () => {
+        std::collections::HashMap::<&str, $crate::VarValue>::new()
+    };
+
+    ($($key:tt = $value:expr),+ $(,)?) => {
         {
             let mut map = std::collections::HashMap::new();
             $(
-                map.insert(stringify!($key), $value);
+                map.insert($crate::vars!(@key $key), $crate::VarValue::from($value));
             )+
             map
         }
@@ -19,6 +89,8 @@ macro_rules! vars {
 mod tests {
     use std::collections::HashMap;
 
+    use crate::{PromptVars, Template, VarValue};
+
     #[test]
     fn test_empty_prompt_vars() {
         let vars: HashMap<&str, &str> = vars!();
@@ -56,4 +128,68 @@ mod tests {
         assert_eq!(vars.len(), 1);
         assert_eq!(vars.get("name"), Some(&"jerry"));
     }
+
+    #[test]
+    fn test_var_values() {
+        let empty: HashMap<&str, VarValue> = var_values!();
+        assert!(empty.is_empty());
+
+        let values = var_values!(name = "tom", age = 7, tags = vec!["a", "b"],);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get("name"), Some(&VarValue::Str("tom".to_string())));
+        assert_eq!(values.get("age"), Some(&VarValue::Int(7)));
+        assert!(values["tags"].is_structured());
+    }
+
+    #[test]
+    fn test_string_literal_keys() {
+        let vars = vars!("user-name" = "tom", plan = "pro", "x.y" = "z");
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars.get("user-name"), Some(&"tom"));
+        assert_eq!(vars.get("x.y"), Some(&"z"));
+
+        let values = var_values!("retry-count" = 3);
+        assert_eq!(values.get("retry-count"), Some(&VarValue::Int(3)));
+    }
+
+    #[test]
+    fn test_owned_values() {
+        let name = String::from("tom");
+        let vars = vars!(name = name, borrowed = &name, literal = "x");
+        assert_eq!(vars.get("name"), Some(&"tom"));
+        assert_eq!(vars.get("borrowed"), Some(&"tom"));
+
+        let template = Template::new("Hi {user_name}, you have {count} messages.")
+            .unwrap()
+            .with_alias("user-name", "user_name");
+        let rendered = template
+            .format(&vars!(
+                "user-name" = name.to_uppercase(),
+                count = 3.to_string()
+            ))
+            .unwrap();
+        assert_eq!(rendered, "Hi TOM, you have 3 messages.");
+    }
+
+    #[test]
+    fn test_prompt_vars_outlive_temporaries() {
+        let empty: PromptVars = prompt_vars!();
+        assert!(empty.is_empty());
+
+        let name = String::from("tom");
+        let vars = prompt_vars!(
+            name = name.to_uppercase(),
+            "x.y" = "z",
+            count = 3.to_string()
+        );
+        assert_eq!(vars.get("name"), Some("TOM"));
+        assert_eq!(vars.get("x.y"), Some("z"));
+        assert_eq!(vars.get("count"), Some("3"));
+
+        let rendered = Template::new("Hi {name}, you have {count} messages.")
+            .unwrap()
+            .format(&vars)
+            .unwrap();
+        assert_eq!(rendered, "Hi TOM, you have 3 messages.");
+    }
 }
@@ -0,0 +1,214 @@
+#[cfg(feature = "async")]
+use std::path::Path;
+
+use serde::Deserialize;
+#[cfg(feature = "async")]
+use tokio::fs;
+
+use crate::{dsl::dsl_role, ChatTemplate, Role, TemplateError};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct YamlChatTemplate {
+    pub messages: Vec<YamlMessage>,
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct YamlMessage {
+    pub role: String,
+    #[serde(default, alias = "content")]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub keep: Option<String>,
+}
+
+impl YamlMessage {
+    fn into_entry(self, index: usize) -> Result<(Role, String), TemplateError> {
+        let error = |msg: String| {
+            TemplateError::YamlDeserializationError(format!("messages[{}]: {}", index, msg))
+        };
+        let role =
+            dsl_role(&self.role).ok_or_else(|| error(format!("unknown role '{}'", self.role)))?;
+
+        if role != Role::Placeholder {
+            if self.name.is_some() || self.optional || self.limit.is_some() || self.keep.is_some() {
+                return Err(error(
+                    "only placeholders accept name, optional, limit and keep".to_string(),
+                ));
+            }
+            let template = self
+                .template
+                .ok_or_else(|| error("missing 'template'".to_string()))?;
+            return Ok((role, template));
+        }
+
+        if self.template.is_some() {
+            return Err(error(
+                "placeholders take a 'name', not a 'template'".to_string(),
+            ));
+        }
+        let mut slot = self
+            .name
+            .ok_or_else(|| error("placeholder is missing 'name'".to_string()))?;
+        if self.optional {
+            slot.push_str("|optional");
+        }
+        if let Some(limit) = self.limit {
+            slot.push_str(&format!("|limit:{}", limit));
+        }
+        if let Some(keep) = self.keep {
+            slot.push_str(&format!("|keep:{}", keep));
+        }
+
+        Ok((role, format!("{{{}}}", slot)))
+    }
+}
+
+impl TryFrom<YamlChatTemplate> for ChatTemplate {
+    type Error = TemplateError;
+
+    fn try_from(config: YamlChatTemplate) -> Result<Self, Self::Error> {
+        let messages = config
+            .messages
+            .into_iter()
+            .enumerate()
+            .map(|(index, message)| message.into_entry(index))
+            .collect::<Result<Vec<_>, TemplateError>>()?;
+
+        let template = ChatTemplate::from_messages(messages)?;
+        Ok(if config.strict {
+            template.with_strict_placeholders()
+        } else {
+            template
+        })
+    }
+}
+
+impl ChatTemplate {
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, TemplateError> {
+        let config: YamlChatTemplate = serde_yaml::from_str(yaml)?;
+        ChatTemplate::try_from(config)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn from_yaml<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
+        let yaml_content = fs::read_to_string(path).await.map_err(|e| {
+            TemplateError::YamlDeserializationError(format!("Failed to read YAML file: {}", e))
+        })?;
+
+        ChatTemplate::from_yaml_str(&yaml_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use messageforge::BaseMessage;
+
+    use super::*;
+    use crate::{vars, MessageLike, TrimStrategy};
+
+    const SUPPORT_YAML: &str = r#"
+strict: true
+messages:
+  - role: system
+    template: "You are a support agent for {company}."
+  - role: placeholder
+    name: history
+    optional: true
+    limit: 5
+    keep: last
+  - role: user
+    content: |-
+      Order {order_id}:
+      {question}
+"#;
+
+    #[test]
+    fn test_from_yaml_str() {
+        let template = ChatTemplate::from_yaml_str(SUPPORT_YAML).unwrap();
+        assert!(template.strict_placeholders());
+        assert_eq!(template.messages.len(), 3);
+
+        match &template.messages[1] {
+            MessageLike::Placeholder(placeholder) => {
+                assert_eq!(placeholder.variable_name(), "history");
+                assert!(placeholder.optional());
+                assert_eq!(placeholder.n_messages(), 5);
+                assert_eq!(placeholder.trim_strategy(), TrimStrategy::KeepLast);
+            }
+            other => panic!("Expected a placeholder, got {:?}", other),
+        }
+
+        let messages = template
+            .format_messages(&vars!(
+                company = "Acme",
+                order_id = "42",
+                question = "Where?"
+            ))
+            .unwrap();
+        assert_eq!(messages[0].content(), "You are a support agent for Acme.");
+        assert_eq!(messages[1].content(), "Order 42:\nWhere?");
+    }
+
+    #[test]
+    fn test_from_yaml_str_errors() {
+        let cases = [
+            (
+                "messages:\n  - role: narrator\n    template: hi",
+                "messages[0]: unknown role 'narrator'",
+            ),
+            (
+                "messages:\n  - role: user\n    template: hi\n  - role: system",
+                "messages[1]: missing 'template'",
+            ),
+            (
+                "messages:\n  - role: placeholder\n    optional: true",
+                "messages[0]: placeholder is missing 'name'",
+            ),
+            (
+                "messages:\n  - role: user\n    template: hi\n    limit: 3",
+                "messages[0]: only placeholders accept",
+            ),
+        ];
+
+        for (yaml, expected) in cases {
+            match ChatTemplate::from_yaml_str(yaml) {
+                Err(TemplateError::YamlDeserializationError(msg)) => {
+                    assert!(msg.starts_with(expected), "{:?} -> {}", yaml, msg)
+                }
+                other => panic!("Expected a YAML error for {:?}, got {:?}", yaml, other),
+            }
+        }
+
+        assert!(matches!(
+            ChatTemplate::from_yaml_str("messages:\n  - role: user\n    tempalte: hi"),
+            Err(TemplateError::YamlDeserializationError(msg)) if msg.contains("tempalte")
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_from_yaml_file() {
+        let path =
+            std::env::temp_dir().join(format!("promptforge-chat-{}.yaml", std::process::id()));
+        fs::write(&path, SUPPORT_YAML).await.unwrap();
+
+        let template = ChatTemplate::from_yaml(&path).await.unwrap();
+        assert_eq!(template.messages.len(), 3);
+        fs::remove_file(&path).await.unwrap();
+
+        assert!(matches!(
+            ChatTemplate::from_yaml(&path).await,
+            Err(TemplateError::YamlDeserializationError(_))
+        ));
+    }
+}
@@ -1,8 +1,10 @@
+#![cfg(feature = "async")]
+
 use messageforge::BaseMessage;
 use std::collections::HashMap;
 use std::path::Path;
 
-use promptforge::{ChatTemplate, Formattable, MessageLike};
+use promptforge::{ChatTemplate, MessageLike};
 
 #[tokio::test]
 async fn test_chat_template_from_toml_file() {
@@ -1,3 +1,5 @@
+#![cfg(feature = "async")]
+
 use promptforge::{FewShotChatTemplate, MessageLike, Role, Templatable};
 use std::path::Path;
 
@@ -1,3 +1,5 @@
+#![cfg(feature = "async")]
+
 use promptforge::{FewShotTemplate, Formattable, Template};
 use std::collections::HashMap;
 use std::path::Path;
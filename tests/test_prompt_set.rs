@@ -0,0 +1,46 @@
+#![cfg(feature = "async")]
+
+use std::path::Path;
+
+use promptforge::{vars, PromptSet};
+
+#[tokio::test]
+async fn test_prompt_set_from_toml_file() {
+    let toml_file_path = Path::new("tests/data/prompt_set.toml");
+
+    let prompt_set = PromptSet::from_toml_file(toml_file_path).await.unwrap();
+
+    assert_eq!(prompt_set.names(), vec!["classify", "summarize"]);
+
+    let summarize = prompt_set.get("summarize").unwrap();
+    let formatted_output = summarize.format(&vars!(text = "Rust is fast.")).unwrap();
+
+    let expected_output = "\
+system: You are a concise summarizer.
+human: Summarize the following text: Rust is fast.";
+
+    assert_eq!(formatted_output, expected_output);
+
+    let classify = prompt_set.get("classify").unwrap();
+    let formatted_output = classify.format(&vars!(ticket = "Refund please")).unwrap();
+
+    let expected_output = "\
+system: You classify support tickets.
+human: Ticket: Refund please";
+
+    assert_eq!(formatted_output, expected_output);
+}
+
+#[tokio::test]
+async fn test_prompt_set_embedded_tests() {
+    let toml_file_path = Path::new("tests/data/prompt_set.toml");
+
+    let prompt_set = PromptSet::from_toml_file(toml_file_path).await.unwrap();
+
+    let report = prompt_set.get("classify").unwrap().run_embedded_tests();
+    assert!(report.is_success(), "{:?}", report.failures);
+    assert_eq!(report.passed, 1);
+
+    let report = prompt_set.get("summarize").unwrap().run_embedded_tests();
+    assert_eq!(report.passed, 0);
+}